@@ -1,14 +1,38 @@
 use std::env;
 
 fn main() {
+    declare_cfgs();
     enable_simd_optimizations();
+    enable_portable_simd();
     enable_libc();
 }
 
+// Tell rustc about every cfg this build script might set, so that it doesn't
+// warn about unexpected cfgs when some of them aren't set. This uses the
+// single colon syntax, which older versions of Cargo ignore.
+fn declare_cfgs() {
+    const CFGS: &'static [&'static str] = &[
+        "memchr_disable_auto_simd",
+        "memchr_runtime_simd",
+        "memchr_runtime_sse2",
+        "memchr_runtime_sse42",
+        "memchr_runtime_avx",
+        "memchr_portable_simd",
+        "memchr_libc",
+        "memchr_libc_memchr",
+    ];
+    for cfg in CFGS {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+}
+
 // This adds various simd cfgs if this compiler and target support it.
 //
-// This can be disabled with RUSTFLAGS="--cfg memchr_disable_auto_simd", but
-// this is generally only intended for testing.
+// This can be disabled with the 'force-fallback' feature (which is also
+// implied by the 'safe-only' feature), or with
+// RUSTFLAGS="--cfg memchr_disable_auto_simd". The latter is generally only
+// intended for testing. The AVX routines alone can be disabled with the
+// 'no-avx' feature.
 //
 // On targets which don't feature SSE2, this is disabled, as LLVM wouln't know
 // how to work with SSE2 operands. Enabling SSE4.2 and AVX on SSE2-only targets
@@ -16,6 +40,7 @@ fn main() {
 // runtime.
 fn enable_simd_optimizations() {
     if is_env_set("CARGO_CFG_MEMCHR_DISABLE_AUTO_SIMD")
+        || is_feature_set("FORCE_FALLBACK")
         || !target_has_feature("sse2")
     {
         return;
@@ -23,24 +48,43 @@ fn enable_simd_optimizations() {
     println!("cargo:rustc-cfg=memchr_runtime_simd");
     println!("cargo:rustc-cfg=memchr_runtime_sse2");
     println!("cargo:rustc-cfg=memchr_runtime_sse42");
-    println!("cargo:rustc-cfg=memchr_runtime_avx");
+    if !is_feature_set("NO_AVX") {
+        println!("cargo:rustc-cfg=memchr_runtime_avx");
+    }
+}
+
+// This adds a `memchr_portable_simd` cfg if the 'nightly' feature is enabled,
+// which permits the use of core::simd on targets without a hand written SIMD
+// implementation.
+//
+// This is suppressed by the 'force-fallback' feature. We can't turn off the
+// 'nightly' feature itself, so all uses of core::simd are gated on this cfg
+// instead of on the feature.
+fn enable_portable_simd() {
+    if !is_feature_set("NIGHTLY") || is_feature_set("FORCE_FALLBACK") {
+        return;
+    }
+    println!("cargo:rustc-cfg=memchr_portable_simd");
 }
 
 // This adds a `memchr_libc` cfg if and only if libc can be used, if no other
-// better option is available.
+// better option is available. It also adds a `memchr_libc_memchr` cfg if
+// libc's memchr should be used. (libc's memrchr is always used when
+// `memchr_libc` is set, if the platform has it.)
 //
 // This could be performed in the source code, but it's simpler to do it once
-// here and consolidate it into one cfg knob.
+// here and consolidate it into a couple of cfg knobs.
 //
 // Basically, we use libc only if its enabled and if we aren't targeting a
 // known bad platform. For example, wasm32 doesn't have a libc and the
 // performance of memchr on Windows is seemingly worse than the fallback
-// implementation.
+// implementation. The 'force-fallback' feature disables libc entirely, while
+// the 'libc-memrchr-only' feature uses it for memrchr but not for memchr.
 fn enable_libc() {
     const NO_ARCH: &'static [&'static str] = &["wasm32", "windows"];
     const NO_ENV: &'static [&'static str] = &["sgx"];
 
-    if !is_feature_set("LIBC") {
+    if !is_feature_set("LIBC") || is_feature_set("FORCE_FALLBACK") {
         return;
     }
 
@@ -57,6 +101,9 @@ fn enable_libc() {
     }
 
     println!("cargo:rustc-cfg=memchr_libc");
+    if !is_feature_set("LIBC_MEMRCHR_ONLY") {
+        println!("cargo:rustc-cfg=memchr_libc_memchr");
+    }
 }
 
 fn is_feature_set(name: &str) -> bool {
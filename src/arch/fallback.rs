@@ -0,0 +1,58 @@
+/*!
+The fallback backend, which is written in portable Rust and is available on
+every target.
+
+These routines still examine multiple bytes at a time where possible, but
+don't use any vector instructions. Since this backend is always available,
+these routines return their results directly.
+*/
+
+use crate::memchr::fallback;
+
+/// Like [`memchr`](crate::memchr), but always uses the fallback backend.
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    fallback::memchr(n1, haystack)
+}
+
+/// Like [`memchr2`](crate::memchr2), but always uses the fallback backend.
+pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    fallback::memchr2(n1, n2, haystack)
+}
+
+/// Like [`memchr3`](crate::memchr3), but always uses the fallback backend.
+pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    fallback::memchr3(n1, n2, n3, haystack)
+}
+
+/// Like [`memrchr`](crate::memrchr), but always uses the fallback backend.
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    fallback::memrchr(n1, haystack)
+}
+
+/// Like [`memrchr2`](crate::memrchr2), but always uses the fallback backend.
+pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    fallback::memrchr2(n1, n2, haystack)
+}
+
+/// Like [`memrchr3`](crate::memrchr3), but always uses the fallback backend.
+pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    fallback::memrchr3(n1, n2, n3, haystack)
+}
@@ -0,0 +1,51 @@
+/*!
+The libc backend, which calls your platform's libc.
+
+This is only available when the `libc` feature is enabled, and only on
+platforms where this crate is willing to use libc. (For example, it's never
+used on `wasm32` or Windows.) When it isn't available, these routines return
+`None`. The `force-fallback` feature also makes it unavailable.
+
+When the `libc-memrchr-only` feature is enabled, [`memchr`](crate::memchr)
+never uses libc, but [`memchr`] in this module still does.
+*/
+
+/// Returns true if and only if the libc backend is available in the current
+/// environment.
+///
+/// Note that even when this returns true, `memrchr` may still be unavailable,
+/// since it isn't provided by every libc.
+pub fn is_available() -> bool {
+    cfg!(memchr_libc)
+}
+
+/// Like [`memchr`](crate::memchr), but always uses your platform's libc.
+///
+/// If the libc backend isn't available, then this returns `None`.
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<Option<usize>> {
+    #[cfg(memchr_libc)]
+    {
+        Some(crate::memchr::c::memchr(n1, haystack))
+    }
+    #[cfg(not(memchr_libc))]
+    {
+        let _ = (n1, haystack);
+        None
+    }
+}
+
+/// Like [`memrchr`](crate::memrchr), but always uses your platform's libc.
+///
+/// Since `memrchr` is a GNU extension, this is only available on Linux. If
+/// it isn't available, then this returns `None`.
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<Option<usize>> {
+    #[cfg(all(memchr_libc, target_os = "linux"))]
+    {
+        Some(crate::memchr::c::memrchr(n1, haystack))
+    }
+    #[cfg(not(all(memchr_libc, target_os = "linux")))]
+    {
+        let _ = (n1, haystack);
+        None
+    }
+}
@@ -0,0 +1,251 @@
+/*!
+Introspection and control over which implementation is used for searching.
+
+Most routines in this crate pick an implementation ("backend") automatically,
+based on the target, the enabled crate features and, on `x86_64`, on which
+CPU features are available at runtime. This module makes that choice
+observable and controllable:
+
+* [`selected`] reports which backend is used by `memchr` and friends, and
+  [`Finder::backend`](crate::memmem::Finder::backend) reports which backend a
+  substring searcher picked.
+* Each backend has a module with explicitly named entry points, such as
+  [`x86_64::avx2::memchr`]. Backends that may not be available in the current
+  environment return `None` instead of searching.
+* [`force`] overrides the automatic choice for the entire process.
+
+This is mostly useful for benchmarking backends against one another and for
+testing them against one another. In general, the automatic choice is the
+right one.
+
+# Example
+
+This shows how to check that the AVX2 and fallback backends agree, if AVX2
+is available.
+
+```
+use memchr::arch;
+
+let haystack = b"the quick brown fox";
+let expected = arch::fallback::memchr(b'q', haystack);
+assert_eq!(expected, Some(4));
+if let Some(got) = arch::x86_64::avx2::memchr(b'q', haystack) {
+    assert_eq!(expected, got);
+}
+```
+*/
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+pub mod fallback;
+pub mod libc;
+pub mod naive;
+#[cfg(all(memchr_portable_simd, not(miri)))]
+pub mod portable;
+pub mod x86_64;
+
+/// An implementation of the routines in this crate.
+///
+/// Not every backend is available in every environment. For example, the
+/// AVX2 backend is only available on `x86_64` CPUs that support AVX2, and the
+/// portable backend is only available when the `nightly` feature is enabled.
+/// The `no-avx` and `force-fallback` crate features make the vector backends
+/// unavailable regardless of what the CPU supports.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Backend {
+    /// Routines written with AVX2 vector instructions on `x86_64`.
+    Avx2,
+    /// Routines written with SSE2 vector instructions on `x86_64`.
+    Sse2,
+    /// Routines written with the portable SIMD API in `core::simd`. This
+    /// requires the `nightly` feature.
+    Portable,
+    /// Substring search that treats a `u64` as a vector of 8 bytes. This is
+    /// only used for substring search.
+    Swar,
+    /// Your platform's libc. This only provides `memchr` (and `memrchr` on
+    /// Linux), and requires the `libc` feature.
+    Libc,
+    /// Routines written in portable Rust that work on every target. These
+    /// still examine multiple bytes at a time where possible.
+    Fallback,
+    /// The simplest possible routines that examine one byte at a time.
+    Naive,
+}
+
+impl Backend {
+    fn to_usize(self) -> usize {
+        match self {
+            Backend::Avx2 => 1,
+            Backend::Sse2 => 2,
+            Backend::Portable => 3,
+            Backend::Swar => 4,
+            Backend::Libc => 5,
+            Backend::Fallback => 6,
+            Backend::Naive => 7,
+        }
+    }
+
+    fn from_usize(n: usize) -> Option<Backend> {
+        match n {
+            1 => Some(Backend::Avx2),
+            2 => Some(Backend::Sse2),
+            3 => Some(Backend::Portable),
+            4 => Some(Backend::Swar),
+            5 => Some(Backend::Libc),
+            6 => Some(Backend::Fallback),
+            7 => Some(Backend::Naive),
+            _ => None,
+        }
+    }
+}
+
+/// The backend forced via `force`, or `0` if none has been forced.
+static FORCED: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the backend used by `memchr`, `memchr2`, `memchr3` and their
+/// variants in the current environment.
+///
+/// If a backend has been forced via [`force`], then that backend is
+/// returned. Otherwise, this returns the backend that is picked
+/// automatically.
+///
+/// Note that when this returns [`Backend::Libc`], only `memchr` (and `memrchr`
+/// on Linux) use libc. All other routines use [`Backend::Fallback`].
+///
+/// # Example
+///
+/// ```
+/// use memchr::arch::{self, Backend};
+///
+/// // Whichever backend is selected, it's never one that only works for
+/// // substring search.
+/// assert_ne!(arch::selected(), Backend::Swar);
+/// ```
+pub fn selected() -> Backend {
+    forced().unwrap_or_else(detected)
+}
+
+/// Force every search in this process to use the given backend, or restore
+/// the automatic choice if `None` is given.
+///
+/// This returns `true` if the backend given was forced. If the backend
+/// isn't available in the current environment, or if it can't be forced,
+/// then this returns `false` and nothing changes.
+///
+/// Currently, a backend can only be forced on `x86_64` when CPU features are
+/// detected at runtime (which is the default, unless AVX2 is enabled at
+/// compile time). In that case, any of [`Backend::Avx2`] (if the CPU supports
+/// it), [`Backend::Sse2`], [`Backend::Fallback`] and [`Backend::Naive`] may be
+/// forced. In every other environment, the backend is chosen at compile time,
+/// and only that backend can be "forced."
+///
+/// Forcing a backend applies to `memchr` and friends, to
+/// [`ByteSet`](crate::ByteSet), and to substring searchers that are built
+/// after this call. Substring searchers that already exist continue to use
+/// whichever backend they picked when they were built. (Since `ByteSet` has
+/// no SSE2 routines, forcing [`Backend::Sse2`] makes it use its SSSE3
+/// routines if they are available.)
+///
+/// # Example
+///
+/// ```
+/// use memchr::arch::{self, Backend};
+///
+/// if arch::force(Some(Backend::Fallback)) {
+///     assert_eq!(arch::selected(), Backend::Fallback);
+/// }
+/// assert_eq!(Some(3), memchr::memchr(b'z', b"abcz"));
+/// // Go back to picking a backend automatically.
+/// assert!(arch::force(None));
+/// ```
+pub fn force(backend: Option<Backend>) -> bool {
+    let n = match backend {
+        None => 0,
+        Some(backend) if is_forceable(backend) => backend.to_usize(),
+        Some(_) => return false,
+    };
+    FORCED.store(n, Ordering::SeqCst);
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    crate::memchr::x86::reset_ifuncs();
+    true
+}
+
+/// Returns the backend forced via `force`, if one has been forced.
+pub(crate) fn forced() -> Option<Backend> {
+    Backend::from_usize(FORCED.load(Ordering::SeqCst))
+}
+
+/// Returns true if and only if searching may use the given backend, i.e.,
+/// either no backend has been forced or the given backend has been forced.
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+pub(crate) fn allows(backend: Backend) -> bool {
+    match forced() {
+        None => true,
+        Some(forced) => forced == backend,
+    }
+}
+
+/// Returns true if and only if the given backend can be forced in the current
+/// environment.
+fn is_forceable(backend: Backend) -> bool {
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    {
+        use crate::memchr::x86;
+
+        if x86::is_runtime_dispatch() {
+            return match backend {
+                Backend::Avx2 => x86::is_avx2_available(),
+                Backend::Sse2 => cfg!(memchr_runtime_sse2),
+                Backend::Fallback | Backend::Naive => true,
+                _ => false,
+            };
+        }
+    }
+    backend == detected()
+}
+
+/// Returns the backend that `memchr` and friends pick automatically.
+fn detected() -> Backend {
+    #[cfg(miri)]
+    {
+        Backend::Naive
+    }
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    {
+        if crate::memchr::x86::is_avx2_available() {
+            Backend::Avx2
+        } else if cfg!(memchr_runtime_sse2) {
+            Backend::Sse2
+        } else {
+            Backend::Fallback
+        }
+    }
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    {
+        Backend::Portable
+    }
+    #[cfg(all(
+        memchr_libc_memchr,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    {
+        Backend::Libc
+    }
+    #[cfg(all(
+        not(memchr_libc_memchr),
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    {
+        Backend::Fallback
+    }
+}
@@ -0,0 +1,57 @@
+/*!
+The naive backend, which examines one byte at a time.
+
+This is mostly useful as a reference implementation to test other backends
+against. Since this backend is always available, these routines return their
+results directly.
+*/
+
+use crate::memchr::naive;
+
+/// Like [`memchr`](crate::memchr), but always uses the naive backend.
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    naive::memchr(n1, haystack)
+}
+
+/// Like [`memchr2`](crate::memchr2), but always uses the naive backend.
+pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    naive::memchr2(n1, n2, haystack)
+}
+
+/// Like [`memchr3`](crate::memchr3), but always uses the naive backend.
+pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    naive::memchr3(n1, n2, n3, haystack)
+}
+
+/// Like [`memrchr`](crate::memrchr), but always uses the naive backend.
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    naive::memrchr(n1, haystack)
+}
+
+/// Like [`memrchr2`](crate::memrchr2), but always uses the naive backend.
+pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    naive::memrchr2(n1, n2, haystack)
+}
+
+/// Like [`memrchr3`](crate::memrchr3), but always uses the naive backend.
+pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    naive::memrchr3(n1, n2, n3, haystack)
+}
@@ -0,0 +1,58 @@
+/*!
+The portable backend, which is written with the portable SIMD API in
+`core::simd`.
+
+This is only available when the `nightly` feature is enabled. Since
+`core::simd` works on every target, these routines return their results
+directly.
+*/
+
+use crate::memchr::portable;
+
+/// Like [`memchr`](crate::memchr), but always uses the portable backend.
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    portable::memchr(n1, haystack)
+}
+
+/// Like [`memchr2`](crate::memchr2), but always uses the portable backend.
+pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    portable::memchr2(n1, n2, haystack)
+}
+
+/// Like [`memchr3`](crate::memchr3), but always uses the portable backend.
+pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    portable::memchr3(n1, n2, n3, haystack)
+}
+
+/// Like [`memrchr`](crate::memrchr), but always uses the portable backend.
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    portable::memrchr(n1, haystack)
+}
+
+/// Like [`memrchr2`](crate::memrchr2), but always uses the portable backend.
+pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    portable::memrchr2(n1, n2, haystack)
+}
+
+/// Like [`memrchr3`](crate::memrchr3), but always uses the portable backend.
+pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    if haystack.is_empty() {
+        return None;
+    }
+    portable::memrchr3(n1, n2, n3, haystack)
+}
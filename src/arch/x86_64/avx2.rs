@@ -0,0 +1,24 @@
+/*!
+The AVX2 backend, which uses 256-bit vector instructions.
+
+This is available on `x86_64` CPUs that support AVX2. When it isn't
+available, these routines return `None`.
+*/
+
+/// Returns true if and only if the AVX2 backend is available in the current
+/// environment.
+pub fn is_available() -> bool {
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    {
+        crate::memchr::x86::is_avx2_available()
+    }
+    #[cfg(not(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)))]
+    {
+        false
+    }
+}
+
+checked_routines!(
+    cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)),
+    crate::memchr::x86::avx
+);
@@ -0,0 +1,87 @@
+/*!
+Backends that are specific to `x86_64`.
+
+These modules are available on every target, so that code using them doesn't
+need to be conditionally compiled. On targets other than `x86_64`, the
+backends are never available and every routine returns `None`.
+*/
+
+/// Defines safe entry points for `memchr`, `memchr2`, `memchr3` and their
+/// reverse variants in a backend. Each returns `None` when the backend isn't
+/// available.
+///
+/// A routine is only called when the given cfg predicate holds and when
+/// `is_available()` (which must be defined in the calling module) returns
+/// true. Since the routines are only compiled when the cfg predicate holds,
+/// the path to them is given as a `use` path.
+macro_rules! checked_routines {
+    (cfg($($cfg:tt)*), $($imp:ident)::+) => {
+        #[cfg($($cfg)*)]
+        use $($imp)::+ as imp;
+
+        checked_routines! {
+            @one cfg($($cfg)*),
+            /// Like [`memchr`](crate::memchr), but only uses this backend.
+            fn memchr(n1)
+        }
+        checked_routines! {
+            @one cfg($($cfg)*),
+            /// Like [`memchr2`](crate::memchr2), but only uses this backend.
+            fn memchr2(n1, n2)
+        }
+        checked_routines! {
+            @one cfg($($cfg)*),
+            /// Like [`memchr3`](crate::memchr3), but only uses this backend.
+            fn memchr3(n1, n2, n3)
+        }
+        checked_routines! {
+            @one cfg($($cfg)*),
+            /// Like [`memrchr`](crate::memrchr), but only uses this backend.
+            fn memrchr(n1)
+        }
+        checked_routines! {
+            @one cfg($($cfg)*),
+            /// Like [`memrchr2`](crate::memrchr2), but only uses this backend.
+            fn memrchr2(n1, n2)
+        }
+        checked_routines! {
+            @one cfg($($cfg)*),
+            /// Like [`memrchr3`](crate::memrchr3), but only uses this backend.
+            fn memrchr3(n1, n2, n3)
+        }
+    };
+    (
+        @one cfg($($cfg:tt)*),
+        $(#[$attr:meta])*
+        fn $name:ident($($needle:ident),+)
+    ) => {
+        $(#[$attr])*
+        ///
+        /// If this backend isn't available, then this returns `None`.
+        pub fn $name(
+            $($needle: u8),+,
+            haystack: &[u8],
+        ) -> Option<Option<usize>> {
+            #[cfg($($cfg)*)]
+            {
+                if !is_available() {
+                    return None;
+                }
+                if haystack.is_empty() {
+                    return Some(None);
+                }
+                // SAFETY: We just checked that this backend is available,
+                // which is the only safety requirement of its routines.
+                Some(unsafe { imp::$name($($needle),+, haystack) })
+            }
+            #[cfg(not($($cfg)*))]
+            {
+                let _ = ($($needle),+, haystack);
+                None
+            }
+        }
+    };
+}
+
+pub mod avx2;
+pub mod sse2;
@@ -0,0 +1,23 @@
+/*!
+The SSE2 backend, which uses 128-bit vector instructions.
+
+SSE2 is supported by every `x86_64` CPU, so this is available on `x86_64`
+unless vector instructions have been disabled, e.g., by targeting `x86_64`
+without SSE2. When it isn't available, these routines return `None`.
+*/
+
+/// Returns true if and only if the SSE2 backend is available in the current
+/// environment.
+pub fn is_available() -> bool {
+    cfg!(all(
+        not(miri),
+        target_arch = "x86_64",
+        memchr_runtime_simd,
+        memchr_runtime_sse2
+    ))
+}
+
+checked_routines!(
+    cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)),
+    crate::memchr::x86::sse2
+);
@@ -5,9 +5,21 @@ This section gives a brief high level overview of what this crate offers.
 * The top-level module provides routines for searching for 1, 2 or 3 bytes
   in the forward or reverse direction. When searching for more than one byte,
   positions are considered a match if the byte at that position matches any
-  of the bytes.
+  of the bytes. When searching for more than 3 bytes, a [`ByteSet`] can be
+  used to search for any subset of bytes in a single pass. Each of the
+  `memchr` routines also has a negated `_not` variant that finds bytes that
+  do *not* match any of the given bytes, and the `memchr2` and `memchr3`
+  routines have `_which` variants that also report which byte matched.
+  Finally, [`memchr_count`] and [`memchr_nth`] count occurrences of a byte
+  and find its `n`th occurrence without searching for each occurrence
+  individually. [`lines`] and [`memchr_split`] (along with its variants)
+  split a haystack into subslices, and [`replace`] rewrites every occurrence
+  of a byte in place.
 * The [`memmem`] sub-module provides forward and reverse substring search
   routines.
+* The [`arch`] sub-module reports which implementation is used for searching,
+  provides explicitly named entry points for each implementation and permits
+  forcing one of them for the entire process.
 In all such cases, routines operate on `&[u8]` without regard to encoding. This
 is exactly what you want when searching either UTF-8 or arbitrary bytes.
 # Example: using `memchr`
@@ -100,24 +112,60 @@ library haven't quite been worked out yet.
 implementations of substring search. For `memchr`, all targets have
 somewhat-accelerated implementations, while only `x86_64` targets have highly
 accelerated implementations. This limitation is expected to be lifted once the
-standard library exposes a platform independent SIMD API.
+standard library exposes a platform independent SIMD API. In the meantime, the
+`nightly` feature can be used to accelerate both on other targets via the
+unstable `core::simd` API.
 # Crate features
 * **std** - When enabled (the default), this will permit this crate to use
   features specific to the standard library. Currently, the only thing used
-  from the standard library is runtime SIMD CPU feature detection. This means
-  that this feature must be enabled to get AVX accelerated routines. When
-  `std` is not enabled, this crate will still attempt to use SSE2 accelerated
-  routines on `x86_64`.
+  from the standard library is runtime SIMD CPU feature detection. When `std`
+  is not enabled, this crate detects AVX2 support on `x86_64` by executing the
+  `cpuid` instruction directly, so AVX accelerated routines are used either
+  way. If AVX2 is enabled at compile time (e.g., with `-C target-cpu=native`),
+  then the AVX2 routines are called directly without any runtime detection.
 * **libc** - When enabled (**not** the default), this library will use your
   platform's libc implementation of `memchr` (and `memrchr` on Linux). This
   can be useful on non-`x86_64` targets where the fallback implementation in
   this crate is not as good as the one found in your libc. All other routines
   (e.g., `memchr[23]` and substring search) unconditionally use the
   implementation in this crate.
+* **nightly** - When enabled (**not** the default), this library will use the
+  unstable portable SIMD API in `core::simd` to accelerate `memchr`,
+  `memchr2`, `memchr3` (along with their reverse variants) and substring
+  search on targets that don't have a hand written vectorized implementation.
+  Currently, that's every target except `x86_64`, which continues to use its
+  own SSE2 and AVX2 routines. This requires a nightly compiler, and takes
+  precedence over the `libc` feature.
+* **no-avx** - When enabled (**not** the default), this library will never
+  use its AVX2 routines, even if the CPU supports them. On `x86_64`, SSE2
+  routines are used instead. This is useful in environments where AVX is
+  undesirable, e.g., because of its effect on CPU frequency scaling.
+* **force-fallback** - When enabled (**not** the default), this library
+  will never use vector instructions or libc, and instead always uses its own
+  portable fallback implementation. This overrides the `nightly` and `libc`
+  features. It is equivalent to setting
+  `RUSTFLAGS="--cfg memchr_disable_auto_simd"` and not enabling `libc`, but
+  doesn't require control over `RUSTFLAGS`.
+* **libc-memrchr-only** - When enabled (**not** the default), this library
+  will use your platform's libc implementation of `memrchr` (on Linux), but
+  not its `memchr`. This implies the `libc` feature.
+* **safe-only** - When enabled (**not** the default), this library doesn't
+  use any `unsafe` code, and is compiled with `#![forbid(unsafe_code)]`. This
+  implies `force-fallback`. (Tests may still use `unsafe`.) In addition, the
+  fallback routines that would otherwise read memory through raw pointers
+  are replaced with equivalents built on `chunks_exact`, and substring search
+  uses Two-Way and Rabin-Karp (along with a prefilter built on `memchr`)
+  instead of vectorized routines. Searching is slower as a result, but still
+  much faster than a naive search.
 */
 
 #![deny(missing_docs)]
+// The test harnesses for prefilters are shared with the vectorized
+// prefilters, and are thus written in terms of unsafe functions. So unsafe
+// code is only forbidden outside of tests.
+#![cfg_attr(all(feature = "safe-only", not(test)), forbid(unsafe_code))]
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(memchr_portable_simd, feature(portable_simd))]
 // It's not worth trying to gate all code on just miri, so turn off relevant
 // dead code warnings.
 #![cfg_attr(miri, allow(dead_code, unused_macros))]
@@ -132,446 +180,34 @@ standard library exposes a platform independent SIMD API.
 compile_error!("memchr currently not supported on non-{16,32,64}");
 
 pub use crate::memchr::{
-    memchr, memchr2, memchr2_iter, memchr3, memchr3_iter, memchr_iter,
-    memrchr, memrchr2, memrchr2_iter, memrchr3, memrchr3_iter, memrchr_iter,
-    Memchr, Memchr2, Memchr3,
+    lines, lines_with, memchr, memchr2, memchr2_count, memchr2_iter,
+    memchr2_not, memchr2_not_iter, memchr2_rsplit, memchr2_split,
+    memchr2_split_inclusive, memchr2_splitn, memchr2_which,
+    memchr2_which_iter, memchr3, memchr3_count, memchr3_iter, memchr3_not,
+    memchr3_not_iter, memchr3_rsplit, memchr3_split, memchr3_split_inclusive,
+    memchr3_splitn, memchr3_which, memchr3_which_iter, memchr_ascii_nocase,
+    memchr_ascii_nocase_iter, memchr_count, memchr_iter, memchr_not,
+    memchr_not_iter, memchr_nth, memchr_rsplit, memchr_split,
+    memchr_split_inclusive, memchr_splitn, memrchr, memrchr2, memrchr2_iter,
+    memrchr2_not, memrchr2_not_iter, memrchr2_which, memrchr2_which_iter,
+    memrchr3, memrchr3_iter, memrchr3_not, memrchr3_not_iter, memrchr3_which,
+    memrchr3_which_iter, memrchr_ascii_nocase, memrchr_ascii_nocase_iter,
+    memrchr_iter, memrchr_not, memrchr_not_iter, replace, replace2, replace3,
+    ByteSet, ByteSetIter, LineTerminator, Lines, Memchr, Memchr2, Memchr2Not,
+    Memchr2Which, Memchr3, Memchr3Not, Memchr3Which, MemchrAsciiNocase,
+    MemchrNot, Split, SplitInclusive, SplitN,
 };
 
-<<<<<<< HEAD
+pub mod arch;
 mod cow;
 mod memchr;
 pub mod memmem;
 #[cfg(test)]
 mod tests;
-=======
-#[cfg(test)]
-#[macro_use]
-extern crate quickcheck;
-
-use core::iter::Rev;
-
-pub use iter::{Memchr, Memchr2, Memchr3};
-
-// N.B. If you're looking for the cfg knobs for libc, see build.rs.
-#[cfg(memchr_libc)]
-mod c;
-#[allow(dead_code)]
-mod fallback;
-mod iter;
-mod naive;
-#[cfg(all(
-    not(all(target_arch = "x86_64", memchr_runtime_simd)),
-    feature = "nightly"
-))]
-mod simd;
-#[cfg(test)]
-mod tests;
-#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
-mod x86;
-
-/// An iterator over all occurrences of the needle in a haystack.
-#[inline]
-pub fn memchr_iter(needle: u8, haystack: &[u8]) -> Memchr {
-    Memchr::new(needle, haystack)
-}
-
-/// An iterator over all occurrences of the needles in a haystack.
-#[inline]
-pub fn memchr2_iter(needle1: u8, needle2: u8, haystack: &[u8]) -> Memchr2 {
-    Memchr2::new(needle1, needle2, haystack)
-}
-
-/// An iterator over all occurrences of the needles in a haystack.
-#[inline]
-pub fn memchr3_iter(
-    needle1: u8,
-    needle2: u8,
-    needle3: u8,
-    haystack: &[u8],
-) -> Memchr3 {
-    Memchr3::new(needle1, needle2, needle3, haystack)
-}
-
-/// An iterator over all occurrences of the needle in a haystack, in reverse.
-#[inline]
-pub fn memrchr_iter(needle: u8, haystack: &[u8]) -> Rev<Memchr> {
-    Memchr::new(needle, haystack).rev()
-}
-
-/// An iterator over all occurrences of the needles in a haystack, in reverse.
-#[inline]
-pub fn memrchr2_iter(
-    needle1: u8,
-    needle2: u8,
-    haystack: &[u8],
-) -> Rev<Memchr2> {
-    Memchr2::new(needle1, needle2, haystack).rev()
-}
-
-/// An iterator over all occurrences of the needles in a haystack, in reverse.
-#[inline]
-pub fn memrchr3_iter(
-    needle1: u8,
-    needle2: u8,
-    needle3: u8,
-    haystack: &[u8],
-) -> Rev<Memchr3> {
-    Memchr3::new(needle1, needle2, needle3, haystack).rev()
-}
-
-/// Search for the first occurrence of a byte in a slice.
-///
-/// This returns the index corresponding to the first occurrence of `needle` in
-/// `haystack`, or `None` if one is not found.
-///
-/// While this is operationally the same as something like
-/// `haystack.iter().position(|&b| b == needle)`, `memchr` will use a highly
-/// optimized routine that can be up to an order of magnitude faster in some
-/// cases.
-///
-/// # Example
-///
-/// This shows how to find the first position of a byte in a byte string.
-///
-/// ```
-/// use memchr::memchr;
-///
-/// let haystack = b"the quick brown fox";
-/// assert_eq!(memchr(b'k', haystack), Some(8));
-/// ```
-#[inline]
-pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        x86::memchr(n1, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        feature = "nightly"
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        simd::memchr(n1, haystack)
-    }
-
-    #[cfg(all(
-        memchr_libc,
-        not(all(target_arch = "x86_64", memchr_runtime_simd))
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        c::memchr(n1, haystack)
-    }
-
-    #[cfg(all(
-        not(memchr_libc),
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        not(feature = "nightly")
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        fallback::memchr(n1, haystack)
-    }
-
-    if haystack.is_empty() {
-        None
-    } else {
-        imp(needle, haystack)
-    }
-}
-
-/// Like `memchr`, but searches for either of two bytes instead of just one.
-///
-/// This returns the index corresponding to the first occurrence of `needle1`
-/// or the first occurrence of `needle2` in `haystack` (whichever occurs
-/// earlier), or `None` if neither one is found.
-///
-/// While this is operationally the same as something like
-/// `haystack.iter().position(|&b| b == needle1 || b == needle2)`, `memchr2`
-/// will use a highly optimized routine that can be up to an order of magnitude
-/// faster in some cases.
-///
-/// # Example
-///
-/// This shows how to find the first position of either of two bytes in a byte
-/// string.
-///
-/// ```
-/// use memchr::memchr2;
-///
-/// let haystack = b"the quick brown fox";
-/// assert_eq!(memchr2(b'k', b'q', haystack), Some(4));
-/// ```
-#[inline]
-pub fn memchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
-        x86::memchr2(n1, n2, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        feature = "nightly"
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
-        simd::memchr2(n1, n2, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        not(feature = "nightly")
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
-        fallback::memchr2(n1, n2, haystack)
-    }
-
-    if haystack.is_empty() {
-        None
-    } else {
-        imp(needle1, needle2, haystack)
-    }
-}
-
-/// Like `memchr`, but searches for any of three bytes instead of just one.
-///
-/// This returns the index corresponding to the first occurrence of `needle1`,
-/// the first occurrence of `needle2`, or the first occurence of `needle3` in
-/// `haystack` (whichever occurs earliest), or `None` if none are found.
-///
-/// While this is operationally the same as something like
-/// `haystack.iter().position(|&b| b == needle1 || b == needle2 ||
-/// b == needle3)`, `memchr3` will use a highly optimized routine that can be
-/// up to an order of magnitude faster in some cases.
-///
-/// # Example
-///
-/// This shows how to find the first position of any of three bytes in a byte
-/// string.
-///
-/// ```
-/// use memchr::memchr3;
-///
-/// let haystack = b"the quick brown fox";
-/// assert_eq!(memchr3(b'k', b'q', b'e', haystack), Some(2));
-/// ```
-#[inline]
-pub fn memchr3(
-    needle1: u8,
-    needle2: u8,
-    needle3: u8,
-    haystack: &[u8],
-) -> Option<usize> {
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
-        x86::memchr3(n1, n2, n3, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        feature = "nightly"
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
-        simd::memchr3(n1, n2, n3, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        not(feature = "nightly")
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
-        fallback::memchr3(n1, n2, n3, haystack)
-    }
-
-    if haystack.is_empty() {
-        None
-    } else {
-        imp(needle1, needle2, needle3, haystack)
-    }
-}
-
-/// Search for the last occurrence of a byte in a slice.
-///
-/// This returns the index corresponding to the last occurrence of `needle` in
-/// `haystack`, or `None` if one is not found.
-///
-/// While this is operationally the same as something like
-/// `haystack.iter().rposition(|&b| b == needle)`, `memrchr` will use a highly
-/// optimized routine that can be up to an order of magnitude faster in some
-/// cases.
-///
-/// # Example
-///
-/// This shows how to find the last position of a byte in a byte string.
-///
-/// ```
-/// use memchr::memrchr;
-///
-/// let haystack = b"the quick brown fox";
-/// assert_eq!(memrchr(b'o', haystack), Some(17));
-/// ```
-#[inline]
-pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        x86::memrchr(n1, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        feature = "nightly"
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        simd::memrchr(n1, haystack)
-    }
-
-    #[cfg(all(
-        all(memchr_libc, target_os = "linux"),
-        not(all(target_arch = "x86_64", memchr_runtime_simd))
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        c::memrchr(n1, haystack)
-    }
-
-    #[cfg(all(
-        not(all(memchr_libc, target_os = "linux")),
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        not(feature = "nightly")
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
-        fallback::memrchr(n1, haystack)
-    }
-
-    if haystack.is_empty() {
-        None
-    } else {
-        imp(needle, haystack)
-    }
-}
-
-/// Like `memrchr`, but searches for either of two bytes instead of just one.
-///
-/// This returns the index corresponding to the last occurrence of `needle1`
-/// or the last occurrence of `needle2` in `haystack` (whichever occurs later),
-/// or `None` if neither one is found.
-///
-/// While this is operationally the same as something like
-/// `haystack.iter().rposition(|&b| b == needle1 || b == needle2)`, `memrchr2`
-/// will use a highly optimized routine that can be up to an order of magnitude
-/// faster in some cases.
-///
-/// # Example
-///
-/// This shows how to find the last position of either of two bytes in a byte
-/// string.
-///
-/// ```
-/// use memchr::memrchr2;
-///
-/// let haystack = b"the quick brown fox";
-/// assert_eq!(memrchr2(b'k', b'q', haystack), Some(8));
-/// ```
-#[inline]
-pub fn memrchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
-        x86::memrchr2(n1, n2, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        feature = "nightly"
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
-        simd::memrchr2(n1, n2, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        not(feature = "nightly")
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
-        fallback::memrchr2(n1, n2, haystack)
-    }
-
-    if haystack.is_empty() {
-        None
-    } else {
-        imp(needle1, needle2, haystack)
-    }
-}
-
-/// Like `memrchr`, but searches for any of three bytes instead of just one.
-///
-/// This returns the index corresponding to the last occurrence of `needle1`,
-/// the last occurrence of `needle2`, or the last occurence of `needle3` in
-/// `haystack` (whichever occurs later), or `None` if none are found.
-///
-/// While this is operationally the same as something like
-/// `haystack.iter().rposition(|&b| b == needle1 || b == needle2 ||
-/// b == needle3)`, `memrchr3` will use a highly optimized routine that can be
-/// up to an order of magnitude faster in some cases.
-///
-/// # Example
-///
-/// This shows how to find the last position of any of three bytes in a byte
-/// string.
-///
-/// ```
-/// use memchr::memrchr3;
-///
-/// let haystack = b"the quick brown fox";
-/// assert_eq!(memrchr3(b'k', b'q', b'e', haystack), Some(8));
-/// ```
-#[inline]
-pub fn memrchr3(
-    needle1: u8,
-    needle2: u8,
-    needle3: u8,
-    haystack: &[u8],
-) -> Option<usize> {
-    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
-        x86::memrchr3(n1, n2, n3, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        feature = "nightly"
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
-        simd::memrchr3(n1, n2, n3, haystack)
-    }
-
-    #[cfg(all(
-        not(all(target_arch = "x86_64", memchr_runtime_simd)),
-        not(feature = "nightly")
-    ))]
-    #[inline(always)]
-    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
-        fallback::memrchr3(n1, n2, n3, haystack)
-    }
-
-    if haystack.is_empty() {
-        None
-    } else {
-        imp(needle1, needle2, needle3, haystack)
-    }
-}
->>>>>>> Accidentally deleted naive path.
+// Vector instructions are only used on x86_64 currently, unless the 'nightly'
+// feature is enabled (see the memchr_portable_simd cfg in build.rs), in which
+// case core::simd is used everywhere else. But the SWAR implementation of the
+// Vector trait is available on every target, except when the 'safe-only'
+// feature is enabled.
+#[cfg(not(feature = "safe-only"))]
+mod vector;
@@ -0,0 +1,125 @@
+// This module defines pure Rust platform independent implementations of
+// searching for any byte in a byte set. When the set is small, we search a
+// word at a time by testing the word against each member of the set, just
+// like memchr2 and memchr3 do. When the set gets bigger, that stops paying
+// off, and we instead still skip a word at a time, but test each word by
+// looking up each of its bytes in a 256 entry table and combining the results
+// without branching.
+
+use super::ByteSet;
+use crate::memchr::fallback::{
+    contains_zero_byte, forward_search_words, repeat_byte,
+    reverse_search_words, USIZE_BYTES,
+};
+
+// The maximum number of bytes in a set for which we compare each word against
+// every member of the set. Each member costs a handful of operations per
+// word, so beyond this point, looking up every byte of the word in a table is
+// faster.
+const SWAR_MAX: usize = 8;
+
+pub fn find(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    let mut vns = [0usize; SWAR_MAX];
+    match splat_members(set, &mut vns) {
+        Some(vns) => forward_search_words(
+            haystack,
+            |chunk| contains_any(chunk, vns),
+            |byte| set.contains(byte),
+        ),
+        None => {
+            let table = Table::new(set);
+            forward_search_words(
+                haystack,
+                |chunk| table.contains_any(chunk),
+                |byte| table.contains(byte),
+            )
+        }
+    }
+}
+
+pub fn rfind(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    let mut vns = [0usize; SWAR_MAX];
+    match splat_members(set, &mut vns) {
+        Some(vns) => reverse_search_words(
+            haystack,
+            |chunk| contains_any(chunk, vns),
+            |byte| set.contains(byte),
+        ),
+        None => {
+            let table = Table::new(set);
+            reverse_search_words(
+                haystack,
+                |chunk| table.contains_any(chunk),
+                |byte| table.contains(byte),
+            )
+        }
+    }
+}
+
+/// A byte set flattened into a table with one entry per byte value, which is
+/// `1` for members of the set and `0` otherwise. This makes testing a byte
+/// for membership a single load, which in turn lets us test every byte in a
+/// word for membership without any branches.
+struct Table([u8; 256]);
+
+impl Table {
+    #[inline(always)]
+    fn new(set: &ByteSet) -> Table {
+        let mut table = [0; 256];
+        for b in set.iter() {
+            table[b as usize] = 1;
+        }
+        Table(table)
+    }
+
+    /// Return true if and only if the given byte is in this table.
+    #[inline(always)]
+    fn contains(&self, byte: u8) -> bool {
+        self.0[byte as usize] != 0
+    }
+
+    /// Return true if and only if any byte in `chunk` is in this table. The
+    /// order in which the bytes in `chunk` are visited doesn't matter.
+    #[inline(always)]
+    fn contains_any(&self, chunk: usize) -> bool {
+        let mut any = 0;
+        for i in 0..USIZE_BYTES {
+            any |= self.0[(chunk >> (i * 8)) & 0xFF];
+        }
+        any != 0
+    }
+}
+
+/// Write each member of the given set, repeated into a word, into `vns` and
+/// return the prefix of `vns` that was written. If the set has more than
+/// `SWAR_MAX` members, then this returns `None`.
+#[inline(always)]
+fn splat_members<'a>(
+    set: &ByteSet,
+    vns: &'a mut [usize; SWAR_MAX],
+) -> Option<&'a [usize]> {
+    let (lo, hi) = set.tables();
+    let mut len = 0;
+    for (base, table) in [(0x00u8, lo), (0x80u8, hi)].iter() {
+        for (nibble, &bits) in table.iter().enumerate() {
+            let mut bits = bits;
+            while bits != 0 {
+                if len == SWAR_MAX {
+                    return None;
+                }
+                let high = bits.trailing_zeros() as u8;
+                vns[len] = repeat_byte(base | (high << 4) | nibble as u8);
+                len += 1;
+                bits &= bits - 1;
+            }
+        }
+    }
+    Some(&vns[..len])
+}
+
+/// Return true if and only if any byte in `chunk` is equivalent to any byte
+/// in any of the given repeated bytes.
+#[inline(always)]
+fn contains_any(chunk: usize, vns: &[usize]) -> bool {
+    vns.iter().any(|&vn| contains_zero_byte(chunk ^ vn))
+}
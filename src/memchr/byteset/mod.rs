@@ -0,0 +1,290 @@
+use core::{fmt, iter::Rev};
+
+use crate::memchr::iter::ByteSetIter;
+
+#[allow(dead_code)]
+pub mod fallback;
+pub mod naive;
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+pub(crate) mod x86;
+
+/// A set of bytes that can be searched for in a haystack.
+///
+/// A byte set generalizes `memchr2` and `memchr3` to any subset of the 256
+/// possible byte values. A position in a haystack is considered a match if
+/// the byte at that position is a member of the set. This is useful when the
+/// number of bytes to search for exceeds three, since searching for each byte
+/// separately requires scanning the haystack several times.
+///
+/// On `x86_64`, searching uses a vectorized routine that classifies every
+/// byte in a vector using two table lookups (via `PSHUFB`), and thus costs
+/// the same regardless of the number of bytes in the set. On other targets,
+/// a word-at-a-time routine is used, which compares each word against every
+/// member of small sets and looks up each byte of the word in a table for
+/// bigger sets.
+///
+/// # Example
+///
+/// This shows how to find the first and last delimiters in a haystack.
+///
+/// ```
+/// use memchr::ByteSet;
+///
+/// let set = ByteSet::new(b",;:|\t");
+/// let haystack = b"name:value|other;thing";
+/// assert_eq!(Some(4), set.find(haystack));
+/// assert_eq!(Some(16), set.rfind(haystack));
+/// ```
+#[derive(Clone, Copy, Default, Eq, PartialEq)]
+pub struct ByteSet {
+    // The set is represented as a 256-bit bitmap split into two tables, both
+    // indexed by the low nibble of a byte. `lo` records bytes less than 0x80
+    // and `hi` records bytes greater than or equal to 0x80. In each case, the
+    // bit set corresponds to the high nibble of the byte (modulo 8).
+    //
+    // This is an odd representation for a bitmap, but it's precisely the one
+    // needed by the vectorized routines, which can then load each table
+    // directly into a vector and use it as a shuffle table.
+    lo: [u8; 16],
+    hi: [u8; 16],
+}
+
+impl ByteSet {
+    /// Create a new byte set containing each of the given bytes.
+    ///
+    /// Duplicate bytes are permitted and ignored.
+    #[inline]
+    pub fn new(bytes: &[u8]) -> ByteSet {
+        let mut set = ByteSet::empty();
+        for &b in bytes {
+            set.insert(b);
+        }
+        set
+    }
+
+    /// Create a new empty byte set. Searching with an empty set never
+    /// reports a match.
+    #[inline]
+    pub fn empty() -> ByteSet {
+        ByteSet { lo: [0; 16], hi: [0; 16] }
+    }
+
+    /// Add the given byte to this set.
+    #[inline]
+    pub fn insert(&mut self, byte: u8) {
+        let (table, bit) = self.slot_mut(byte);
+        *table |= bit;
+    }
+
+    /// Remove the given byte from this set.
+    #[inline]
+    pub fn remove(&mut self, byte: u8) {
+        let (table, bit) = self.slot_mut(byte);
+        *table &= !bit;
+    }
+
+    /// Returns true if and only if the given byte is in this set.
+    #[inline]
+    pub fn contains(&self, byte: u8) -> bool {
+        let nibble = (byte & 0xF) as usize;
+        let bit = 1 << ((byte >> 4) & 0x7);
+        if byte < 0x80 {
+            self.lo[nibble] & bit != 0
+        } else {
+            self.hi[nibble] & bit != 0
+        }
+    }
+
+    /// Returns the number of distinct bytes in this set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        let lo =
+            self.lo.iter().map(|b| b.count_ones() as usize).sum::<usize>();
+        let hi =
+            self.hi.iter().map(|b| b.count_ones() as usize).sum::<usize>();
+        lo + hi
+    }
+
+    /// Returns true if and only if this set contains no bytes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.lo == [0; 16] && self.hi == [0; 16]
+    }
+
+    /// Returns an iterator over the bytes in this set, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..=255u8).filter(move |&b| self.contains(b))
+    }
+
+    /// Search for the first occurrence of any byte in this set in the given
+    /// haystack.
+    ///
+    /// This returns the index corresponding to the first position in
+    /// `haystack` whose byte is in this set, or `None` if no such position
+    /// exists. If an index is returned, it is guaranteed to be less than
+    /// `usize::MAX`.
+    ///
+    /// While this is operationally the same as something like
+    /// `haystack.iter().position(|&b| set.contains(b))`, this routine will
+    /// use a highly optimized routine when possible.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::ByteSet;
+    ///
+    /// let set = ByteSet::new(b"aeiou");
+    /// assert_eq!(Some(2), set.find(b"the quick brown fox"));
+    /// assert_eq!(None, set.find(b"rhythm"));
+    /// ```
+    #[inline]
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        #[cfg(miri)]
+        #[inline(always)]
+        fn imp(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+            naive::find(set, haystack)
+        }
+
+        #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+        #[inline(always)]
+        fn imp(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+            x86::find(set, haystack)
+        }
+
+        #[cfg(all(
+            not(all(target_arch = "x86_64", memchr_runtime_simd)),
+            not(miri),
+        ))]
+        #[inline(always)]
+        fn imp(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+            fallback::find(set, haystack)
+        }
+
+        if haystack.is_empty() || self.is_empty() {
+            None
+        } else {
+            imp(self, haystack)
+        }
+    }
+
+    /// Search for the last occurrence of any byte in this set in the given
+    /// haystack.
+    ///
+    /// This returns the index corresponding to the last position in
+    /// `haystack` whose byte is in this set, or `None` if no such position
+    /// exists. If an index is returned, it is guaranteed to be less than
+    /// `usize::MAX`.
+    ///
+    /// While this is operationally the same as something like
+    /// `haystack.iter().rposition(|&b| set.contains(b))`, this routine will
+    /// use a highly optimized routine when possible.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::ByteSet;
+    ///
+    /// let set = ByteSet::new(b"aeiou");
+    /// assert_eq!(Some(17), set.rfind(b"the quick brown fox"));
+    /// assert_eq!(None, set.rfind(b"rhythm"));
+    /// ```
+    #[inline]
+    pub fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+        #[cfg(miri)]
+        #[inline(always)]
+        fn imp(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+            naive::rfind(set, haystack)
+        }
+
+        #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+        #[inline(always)]
+        fn imp(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+            x86::rfind(set, haystack)
+        }
+
+        #[cfg(all(
+            not(all(target_arch = "x86_64", memchr_runtime_simd)),
+            not(miri),
+        ))]
+        #[inline(always)]
+        fn imp(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+            fallback::rfind(set, haystack)
+        }
+
+        if haystack.is_empty() || self.is_empty() {
+            None
+        } else {
+            imp(self, haystack)
+        }
+    }
+
+    /// Returns an iterator over all positions in the haystack whose byte is
+    /// in this set.
+    ///
+    /// The iterator returned implements `DoubleEndedIterator`, so it can be
+    /// consumed from both ends.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::ByteSet;
+    ///
+    /// let set = ByteSet::new(b",;\t");
+    /// let positions: Vec<usize> = set.find_iter(b"a,b;c\td").collect();
+    /// assert_eq!(positions, vec![1, 3, 5]);
+    /// ```
+    #[inline]
+    pub fn find_iter<'h>(&self, haystack: &'h [u8]) -> ByteSetIter<'h> {
+        ByteSetIter::new(*self, haystack)
+    }
+
+    /// Returns an iterator over all positions in the haystack whose byte is
+    /// in this set, in reverse.
+    #[inline]
+    pub fn rfind_iter<'h>(&self, haystack: &'h [u8]) -> Rev<ByteSetIter<'h>> {
+        ByteSetIter::new(*self, haystack).rev()
+    }
+
+    /// Return the raw tables used to represent this set. The first table
+    /// corresponds to bytes less than `0x80` and the second to all other
+    /// bytes.
+    #[inline(always)]
+    pub(crate) fn tables(&self) -> (&[u8; 16], &[u8; 16]) {
+        (&self.lo, &self.hi)
+    }
+
+    /// Return the table entry and bit corresponding to the given byte.
+    #[inline(always)]
+    fn slot_mut(&mut self, byte: u8) -> (&mut u8, u8) {
+        let nibble = (byte & 0xF) as usize;
+        let bit = 1 << ((byte >> 4) & 0x7);
+        if byte < 0x80 {
+            (&mut self.lo[nibble], bit)
+        } else {
+            (&mut self.hi[nibble], bit)
+        }
+    }
+}
+
+impl fmt::Debug for ByteSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl core::iter::FromIterator<u8> for ByteSet {
+    fn from_iter<I: IntoIterator<Item = u8>>(it: I) -> ByteSet {
+        let mut set = ByteSet::empty();
+        set.extend(it);
+        set
+    }
+}
+
+impl core::iter::Extend<u8> for ByteSet {
+    fn extend<I: IntoIterator<Item = u8>>(&mut self, it: I) {
+        for b in it {
+            self.insert(b);
+        }
+    }
+}
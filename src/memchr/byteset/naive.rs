@@ -0,0 +1,11 @@
+#![allow(dead_code)]
+
+use super::ByteSet;
+
+pub fn find(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| set.contains(b))
+}
+
+pub fn rfind(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|&b| set.contains(b))
+}
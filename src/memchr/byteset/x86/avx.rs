@@ -0,0 +1,235 @@
+use core::{arch::x86_64::*, cmp, mem::size_of};
+
+use super::ssse3;
+use crate::memchr::byteset::ByteSet;
+
+const VECTOR_SIZE: usize = size_of::<__m256i>();
+const VECTOR_ALIGN: usize = VECTOR_SIZE - 1;
+
+// The number of bytes to loop at in one iteration of find/rfind.
+const LOOP_SIZE: usize = 2 * VECTOR_SIZE;
+
+/// The vectors required to classify every byte in a vector as either a member
+/// of a byte set or not. See the SSSE3 implementation for a description of
+/// the algorithm. The only wrinkle with AVX2 is that `VPSHUFB` shuffles within
+/// each 128-bit lane, so every table is repeated in both lanes.
+#[derive(Clone, Copy, Debug)]
+struct Tables {
+    lo: __m256i,
+    hi: __m256i,
+    bits: __m256i,
+    highbit: __m256i,
+    low3: __m256i,
+}
+
+impl Tables {
+    #[inline(always)]
+    unsafe fn new(set: &ByteSet) -> Tables {
+        let (lo, hi) = set.tables();
+        let lo = _mm_loadu_si128(lo.as_ptr() as *const __m128i);
+        let hi = _mm_loadu_si128(hi.as_ptr() as *const __m128i);
+        let bits = _mm_setr_epi8(
+            1, 2, 4, 8, 16, 32, 64, -128, 1, 2, 4, 8, 16, 32, 64, -128,
+        );
+        Tables {
+            lo: _mm256_broadcastsi128_si256(lo),
+            hi: _mm256_broadcastsi128_si256(hi),
+            bits: _mm256_broadcastsi128_si256(bits),
+            highbit: _mm256_set1_epi8(0x80u8 as i8),
+            low3: _mm256_set1_epi8(0x07),
+        }
+    }
+
+    /// Return a vector where each lane is 0xFF if the corresponding byte in
+    /// `chunk` is a member of the set, and 0x00 otherwise.
+    #[inline(always)]
+    unsafe fn classify(&self, chunk: __m256i) -> __m256i {
+        let lo = _mm256_shuffle_epi8(self.lo, chunk);
+        let flipped = _mm256_xor_si256(chunk, self.highbit);
+        let hi = _mm256_shuffle_epi8(self.hi, flipped);
+        let nibbles = _mm256_and_si256(_mm256_srli_epi16(chunk, 4), self.low3);
+        let bits = _mm256_shuffle_epi8(self.bits, nibbles);
+        let found = _mm256_and_si256(_mm256_or_si256(lo, hi), bits);
+        _mm256_cmpeq_epi8(found, bits)
+    }
+}
+
+#[target_feature(enable = "avx2")]
+pub unsafe fn find(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    // For a high level explanation for how this algorithm works, see the
+    // SSSE3 implementation. The avx implementation here is the same, but with
+    // 256-bit vectors instead of 128-bit vectors.
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_SIZE, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < VECTOR_SIZE {
+        // For small haystacks, defer to the SSSE3 implementation. AVX2
+        // implies SSSE3, so this is always safe to call here.
+        return ssse3::find(set, haystack);
+    }
+
+    let tables = Tables::new(set);
+    if let Some(i) = forward_search(start_ptr, end_ptr, ptr, &tables) {
+        return Some(i);
+    }
+
+    ptr = ptr.add(VECTOR_SIZE - (start_ptr as usize & VECTOR_ALIGN));
+    debug_assert!(ptr > start_ptr && end_ptr.sub(VECTOR_SIZE) >= start_ptr);
+    while loop_size == LOOP_SIZE && ptr <= end_ptr.sub(loop_size) {
+        debug_assert_eq!(0, (ptr as usize) % VECTOR_SIZE);
+
+        let a = _mm256_load_si256(ptr as *const __m256i);
+        let b = _mm256_load_si256(ptr.add(VECTOR_SIZE) as *const __m256i);
+        let eqa = tables.classify(a);
+        let eqb = tables.classify(b);
+        if _mm256_movemask_epi8(_mm256_or_si256(eqa, eqb)) != 0 {
+            let mut at = sub(ptr, start_ptr);
+            let mask = _mm256_movemask_epi8(eqa);
+            if mask != 0 {
+                return Some(at + forward_pos(mask));
+            }
+
+            at += VECTOR_SIZE;
+            let mask = _mm256_movemask_epi8(eqb);
+            debug_assert!(mask != 0);
+            return Some(at + forward_pos(mask));
+        }
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(VECTOR_SIZE) {
+        debug_assert!(sub(end_ptr, ptr) >= VECTOR_SIZE);
+
+        if let Some(i) = forward_search(start_ptr, end_ptr, ptr, &tables) {
+            return Some(i);
+        }
+        ptr = ptr.add(VECTOR_SIZE);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < VECTOR_SIZE);
+        ptr = ptr.sub(VECTOR_SIZE - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), VECTOR_SIZE);
+
+        return forward_search(start_ptr, end_ptr, ptr, &tables);
+    }
+    None
+}
+
+#[target_feature(enable = "avx2")]
+pub unsafe fn rfind(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_SIZE, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = end_ptr;
+
+    if haystack.len() < VECTOR_SIZE {
+        return ssse3::rfind(set, haystack);
+    }
+
+    let tables = Tables::new(set);
+    ptr = ptr.sub(VECTOR_SIZE);
+    if let Some(i) = reverse_search(start_ptr, end_ptr, ptr, &tables) {
+        return Some(i);
+    }
+
+    ptr = (end_ptr as usize & !VECTOR_ALIGN) as *const u8;
+    debug_assert!(start_ptr <= ptr && ptr <= end_ptr);
+    while loop_size == LOOP_SIZE && ptr >= start_ptr.add(loop_size) {
+        debug_assert_eq!(0, (ptr as usize) % VECTOR_SIZE);
+
+        ptr = ptr.sub(loop_size);
+        let a = _mm256_load_si256(ptr as *const __m256i);
+        let b = _mm256_load_si256(ptr.add(VECTOR_SIZE) as *const __m256i);
+        let eqa = tables.classify(a);
+        let eqb = tables.classify(b);
+        if _mm256_movemask_epi8(_mm256_or_si256(eqa, eqb)) != 0 {
+            let mut at = sub(ptr.add(VECTOR_SIZE), start_ptr);
+            let mask = _mm256_movemask_epi8(eqb);
+            if mask != 0 {
+                return Some(at + reverse_pos(mask));
+            }
+
+            at -= VECTOR_SIZE;
+            let mask = _mm256_movemask_epi8(eqa);
+            debug_assert!(mask != 0);
+            return Some(at + reverse_pos(mask));
+        }
+    }
+    while ptr >= start_ptr.add(VECTOR_SIZE) {
+        ptr = ptr.sub(VECTOR_SIZE);
+        if let Some(i) = reverse_search(start_ptr, end_ptr, ptr, &tables) {
+            return Some(i);
+        }
+    }
+    if ptr > start_ptr {
+        debug_assert!(sub(ptr, start_ptr) < VECTOR_SIZE);
+        return reverse_search(start_ptr, end_ptr, start_ptr, &tables);
+    }
+    None
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn forward_search(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    tables: &Tables,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= VECTOR_SIZE);
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(VECTOR_SIZE));
+
+    let chunk = _mm256_loadu_si256(ptr as *const __m256i);
+    let mask = _mm256_movemask_epi8(tables.classify(chunk));
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + forward_pos(mask))
+    } else {
+        None
+    }
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn reverse_search(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    tables: &Tables,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= VECTOR_SIZE);
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(VECTOR_SIZE));
+
+    let chunk = _mm256_loadu_si256(ptr as *const __m256i);
+    let mask = _mm256_movemask_epi8(tables.classify(chunk));
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + reverse_pos(mask))
+    } else {
+        None
+    }
+}
+
+/// Compute the position of the first matching byte from the given mask. The
+/// position returned is always in the range [0, 31].
+///
+/// The mask given is expected to be the result of _mm256_movemask_epi8.
+fn forward_pos(mask: i32) -> usize {
+    mask.trailing_zeros() as usize
+}
+
+/// Compute the position of the last matching byte from the given mask. The
+/// position returned is always in the range [0, 31].
+///
+/// The mask given is expected to be the result of _mm256_movemask_epi8.
+fn reverse_pos(mask: i32) -> usize {
+    VECTOR_SIZE - (mask as u32).leading_zeros() as usize - 1
+}
+
+/// Subtract `b` from `a` and return the difference. `a` should be greater than
+/// or equal to `b`.
+fn sub(a: *const u8, b: *const u8) -> usize {
+    debug_assert!(a >= b);
+    (a as usize) - (b as usize)
+}
@@ -0,0 +1,134 @@
+use super::ByteSet;
+
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+use super::{fallback, naive};
+
+pub(crate) mod avx;
+pub(crate) mod ssse3;
+
+/// Returns true if and only if the SSSE3 routines in this module can be
+/// called in the current environment.
+///
+/// Without std, there is no runtime detection for SSSE3, so it has to be
+/// enabled at compile time. (In practice, this rarely matters, since every
+/// CPU that supports AVX2 also supports SSSE3, and the AVX2 routines are
+/// preferred whenever they are available.)
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+#[inline(always)]
+fn is_ssse3_available() -> bool {
+    #[cfg(feature = "std")]
+    {
+        is_x86_feature_detected!("ssse3")
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        cfg!(target_feature = "ssse3")
+    }
+}
+
+/// Like the `unsafe_ifunc` macro used by memchr, this performs CPU feature
+/// detection upon the first call and caches the selected routine in a
+/// function pointer. See that macro for details.
+///
+/// The only real difference is that the routines here take a `&ByteSet`
+/// instead of some number of needle bytes, and that the vectorized routines
+/// require SSSE3 (for `PSHUFB`), which isn't guaranteed to be available on
+/// all `x86_64` targets. AVX2 is detected the same way as for memchr, which
+/// works with or without std.
+///
+/// A backend forced via `crate::arch::force` is honored too, and the function
+/// pointer is registered with memchr's ifuncs so that it's reset whenever a
+/// backend is forced. Since there are no SSE2 routines for byte sets, forcing
+/// the SSE2 backend uses the SSSE3 routines when SSSE3 is available, and the
+/// fallback routines otherwise.
+///
+/// # Safety
+///
+/// Callers must ensure that fnty is function pointer type.
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+macro_rules! unsafe_ifunc {
+    ($fnty:ty, $name:ident, $set:ident, $haystack:ident) => {{
+        use core::{
+            mem,
+            sync::atomic::{AtomicPtr, Ordering},
+        };
+
+        use crate::memchr::x86::{is_avx2_available, Ifunc};
+
+        type FnRaw = *mut ();
+
+        static FN: AtomicPtr<()> = AtomicPtr::new(detect as FnRaw);
+
+        static IFUNC: Ifunc = Ifunc::new(&FN, detect as FnRaw);
+
+        fn detect(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+            use crate::arch::{self, Backend};
+
+            // A forced backend is only ever one that is available in the
+            // current environment. See crate::arch::force.
+            let forced = arch::forced();
+            let fun = match forced {
+                Some(Backend::Avx2) => avx::$name as FnRaw,
+                Some(Backend::Sse2) if is_ssse3_available() => {
+                    ssse3::$name as FnRaw
+                }
+                Some(Backend::Sse2) | Some(Backend::Fallback) => {
+                    fallback::$name as FnRaw
+                }
+                Some(Backend::Naive) => naive::$name as FnRaw,
+                _ if is_avx2_available() => avx::$name as FnRaw,
+                _ if is_ssse3_available() => ssse3::$name as FnRaw,
+                _ => fallback::$name as FnRaw,
+            };
+            FN.store(fun as FnRaw, Ordering::SeqCst);
+            IFUNC.register();
+            // If a different backend was forced while we were busy picking
+            // one, then we might have missed the reset. So make sure the next
+            // call detects again.
+            if arch::forced() != forced {
+                FN.store(detect as FnRaw, Ordering::SeqCst);
+            }
+            // SAFETY: By virtue of the caller contract, $fnty is a function
+            // pointer, which is always safe to transmute with a *mut ().
+            // Also, if 'fun is the AVX or SSSE3 routine, then it is
+            // guaranteed to be supported since we checked the corresponding
+            // feature. (Either above, or when the AVX2 backend was forced.)
+            unsafe { mem::transmute::<FnRaw, $fnty>(fun)(set, haystack) }
+        }
+
+        // SAFETY: By virtue of the caller contract, $fnty is a function
+        // pointer, which is always safe to transmute with a *mut (). Also, if
+        // 'fun is the AVX or SSSE3 routine, then it is guaranteed to be
+        // supported since we checked the corresponding feature.
+        unsafe {
+            let fun = FN.load(Ordering::Relaxed);
+            mem::transmute::<FnRaw, $fnty>(fun)($set, $haystack)
+        }
+    }};
+}
+
+/// When AVX2 is enabled at compile time, then every CPU that this binary
+/// runs on must support AVX2 (and thus SSSE3), and no other backend can be
+/// forced. So we call the AVX2 routines directly, just like memchr does.
+///
+/// # Safety
+///
+/// There are no safety requirements for this definition of the macro. It is
+/// safe for all inputs since the AVX2 routines only require the avx2 target
+/// feature, which is enabled at compile time.
+#[cfg(all(memchr_runtime_avx, target_feature = "avx2"))]
+macro_rules! unsafe_ifunc {
+    ($fnty:ty, $name:ident, $set:ident, $haystack:ident) => {{
+        unsafe { avx::$name($set, $haystack) }
+    }};
+}
+
+#[inline(always)]
+pub fn find(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(fn(&ByteSet, &[u8]) -> Option<usize>, find, set, haystack)
+}
+
+#[inline(always)]
+pub fn rfind(set: &ByteSet, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(fn(&ByteSet, &[u8]) -> Option<usize>, rfind, set, haystack)
+}
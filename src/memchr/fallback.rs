@@ -1,19 +1,25 @@
 // This module defines pure Rust platform independent implementations of all
 // the memchr routines. We do our best to make them fast. Some of them may even
 // get auto-vectorized.
+//
+// When the 'safe-only' feature is enabled, the routines that would otherwise
+// read words through raw pointers read them with chunks_exact instead, so
+// that this module doesn't use any unsafe code.
 
-use core::{cmp, usize};
+#[cfg(not(feature = "safe-only"))]
+use core::cmp;
 
 #[cfg(target_pointer_width = "16")]
-const USIZE_BYTES: usize = 2;
+pub(crate) const USIZE_BYTES: usize = 2;
 
 #[cfg(target_pointer_width = "32")]
-const USIZE_BYTES: usize = 4;
+pub(crate) const USIZE_BYTES: usize = 4;
 
 #[cfg(target_pointer_width = "64")]
-const USIZE_BYTES: usize = 8;
+pub(crate) const USIZE_BYTES: usize = 8;
 
 // The number of bytes to loop at in one iteration of memchr/memrchr.
+#[cfg(not(feature = "safe-only"))]
 const LOOP_SIZE: usize = 2 * USIZE_BYTES;
 
 /// Return `true` if `x` contains any zero byte.
@@ -24,7 +30,7 @@ const LOOP_SIZE: usize = 2 * USIZE_BYTES;
 /// bytes where the borrow propagated all the way to the most significant
 /// bit."
 #[inline(always)]
-fn contains_zero_byte(x: usize) -> bool {
+pub(crate) fn contains_zero_byte(x: usize) -> bool {
     const LO_U64: u64 = 0x0101010101010101;
     const HI_U64: u64 = 0x8080808080808080;
 
@@ -34,15 +40,52 @@ fn contains_zero_byte(x: usize) -> bool {
     x.wrapping_sub(LO_USIZE) & !x & HI_USIZE != 0
 }
 
+/// Return a word where the most significant bit of each byte is set if and
+/// only if the corresponding byte in `x` is zero. All other bits are zero.
+///
+/// Unlike `contains_zero_byte`, the result is exact for every byte in the
+/// word, since no carry can propagate from one byte into the next.
+#[inline(always)]
+fn zero_bytes(x: usize) -> usize {
+    const LO7_U64: u64 = 0x7F7F7F7F7F7F7F7F;
+    const HI_U64: u64 = 0x8080808080808080;
+
+    const LO7_USIZE: usize = LO7_U64 as usize;
+    const HI_USIZE: usize = HI_U64 as usize;
+
+    !(((x & LO7_USIZE) + LO7_USIZE) | x) & HI_USIZE
+}
+
+/// Return a word where the most significant bit of each byte is set if and
+/// only if the corresponding byte in `x` is equal to any of the given
+/// repeated bytes. All other bits are zero.
+#[inline(always)]
+fn eq_bytes(x: usize, vns: &[usize]) -> usize {
+    vns.iter().fold(0, |eq, &vn| eq | zero_bytes(x ^ vn))
+}
+
+/// Return `true` if `x` contains any byte that is not equal to every byte in
+/// any of the given repeated bytes. That is, this returns true if and only if
+/// `x` contains a byte that isn't one of the needles.
+#[inline(always)]
+fn contains_other_byte(x: usize, vns: &[usize]) -> bool {
+    const HI_U64: u64 = 0x8080808080808080;
+    const HI_USIZE: usize = HI_U64 as usize;
+
+    eq_bytes(x, vns) != HI_USIZE
+}
+
 /// Repeat the given byte into a word size number. That is, every 8 bits
 /// is equivalent to the given byte. For example, if `b` is `\x4E` or
 /// `01001110` in binary, then the returned value on a 32-bit system would be:
 /// `01001110_01001110_01001110_01001110`.
 #[inline(always)]
-fn repeat_byte(b: u8) -> usize {
-    (b as usize) * (usize::MAX / 255)
+pub(crate) fn repeat_byte(b: u8) -> usize {
+    (b as usize) * (core::usize::MAX / 255)
 }
 
+/// Return the first index matching the byte `n1` in `haystack`.
+#[cfg(not(feature = "safe-only"))]
 pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = repeat_byte(n1);
     let confirm = |byte| byte == n1;
@@ -81,44 +124,22 @@ pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     }
 }
 
+/// Return the first index matching the byte `n1` in `haystack`.
+#[cfg(feature = "safe-only")]
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    let vn1 = repeat_byte(n1);
+    let has_match = |chunk: usize| contains_zero_byte(chunk ^ vn1);
+    forward_search_words(haystack, has_match, |byte| byte == n1)
+}
+
 /// Like `memchr`, but searches for two bytes instead of one.
 pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = repeat_byte(n1);
     let vn2 = repeat_byte(n2);
-    let confirm = |byte| byte == n1 || byte == n2;
-    let align = USIZE_BYTES - 1;
-    let start_ptr = haystack.as_ptr();
-    let mut ptr = start_ptr;
-
-    unsafe {
-        let end_ptr = start_ptr.add(haystack.len());
-        if haystack.len() < USIZE_BYTES {
-            return forward_search(start_ptr, end_ptr, ptr, confirm);
-        }
-
-        let chunk = (ptr as *const usize).read_unaligned();
-        let eq1 = contains_zero_byte(chunk ^ vn1);
-        let eq2 = contains_zero_byte(chunk ^ vn2);
-        if eq1 || eq2 {
-            return forward_search(start_ptr, end_ptr, ptr, confirm);
-        }
-
-        ptr = ptr.add(USIZE_BYTES - (start_ptr as usize & align));
-        debug_assert!(ptr > start_ptr);
-        debug_assert!(end_ptr.sub(USIZE_BYTES) >= start_ptr);
-        while ptr <= end_ptr.sub(USIZE_BYTES) {
-            debug_assert_eq!(0, (ptr as usize) % USIZE_BYTES);
-
-            let chunk = *(ptr as *const usize);
-            let eq1 = contains_zero_byte(chunk ^ vn1);
-            let eq2 = contains_zero_byte(chunk ^ vn2);
-            if eq1 || eq2 {
-                break;
-            }
-            ptr = ptr.add(USIZE_BYTES);
-        }
-        forward_search(start_ptr, end_ptr, ptr, confirm)
-    }
+    let has_match = |chunk: usize| {
+        contains_zero_byte(chunk ^ vn1) || contains_zero_byte(chunk ^ vn2)
+    };
+    forward_search_words(haystack, has_match, |byte| byte == n1 || byte == n2)
 }
 
 /// Like `memchr`, but searches for three bytes instead of one.
@@ -126,45 +147,18 @@ pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = repeat_byte(n1);
     let vn2 = repeat_byte(n2);
     let vn3 = repeat_byte(n3);
-    let confirm = |byte| byte == n1 || byte == n2 || byte == n3;
-    let align = USIZE_BYTES - 1;
-    let start_ptr = haystack.as_ptr();
-    let mut ptr = start_ptr;
-
-    unsafe {
-        let end_ptr = start_ptr.add(haystack.len());
-        if haystack.len() < USIZE_BYTES {
-            return forward_search(start_ptr, end_ptr, ptr, confirm);
-        }
-
-        let chunk = (ptr as *const usize).read_unaligned();
-        let eq1 = contains_zero_byte(chunk ^ vn1);
-        let eq2 = contains_zero_byte(chunk ^ vn2);
-        let eq3 = contains_zero_byte(chunk ^ vn3);
-        if eq1 || eq2 || eq3 {
-            return forward_search(start_ptr, end_ptr, ptr, confirm);
-        }
-
-        ptr = ptr.add(USIZE_BYTES - (start_ptr as usize & align));
-        debug_assert!(ptr > start_ptr);
-        debug_assert!(end_ptr.sub(USIZE_BYTES) >= start_ptr);
-        while ptr <= end_ptr.sub(USIZE_BYTES) {
-            debug_assert_eq!(0, (ptr as usize) % USIZE_BYTES);
-
-            let chunk = *(ptr as *const usize);
-            let eq1 = contains_zero_byte(chunk ^ vn1);
-            let eq2 = contains_zero_byte(chunk ^ vn2);
-            let eq3 = contains_zero_byte(chunk ^ vn3);
-            if eq1 || eq2 || eq3 {
-                break;
-            }
-            ptr = ptr.add(USIZE_BYTES);
-        }
-        forward_search(start_ptr, end_ptr, ptr, confirm)
-    }
+    let has_match = |chunk: usize| {
+        contains_zero_byte(chunk ^ vn1)
+            || contains_zero_byte(chunk ^ vn2)
+            || contains_zero_byte(chunk ^ vn3)
+    };
+    forward_search_words(haystack, has_match, |byte| {
+        byte == n1 || byte == n2 || byte == n3
+    })
 }
 
-/// Return the last index matching the byte `x` in `text`.
+/// Return the last index matching the byte `n1` in `haystack`.
+#[cfg(not(feature = "safe-only"))]
 pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = repeat_byte(n1);
     let confirm = |byte| byte == n1;
@@ -202,51 +196,391 @@ pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
     }
 }
 
+/// Return the last index matching the byte `n1` in `haystack`.
+#[cfg(feature = "safe-only")]
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    let vn1 = repeat_byte(n1);
+    let has_match = |chunk: usize| contains_zero_byte(chunk ^ vn1);
+    reverse_search_words(haystack, has_match, |byte| byte == n1)
+}
+
 /// Like `memrchr`, but searches for two bytes instead of one.
 pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
     let vn1 = repeat_byte(n1);
     let vn2 = repeat_byte(n2);
-    let confirm = |byte| byte == n1 || byte == n2;
+    let has_match = |chunk: usize| {
+        contains_zero_byte(chunk ^ vn1) || contains_zero_byte(chunk ^ vn2)
+    };
+    reverse_search_words(haystack, has_match, |byte| byte == n1 || byte == n2)
+}
+
+/// Like `memrchr`, but searches for three bytes instead of one.
+pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    let vn1 = repeat_byte(n1);
+    let vn2 = repeat_byte(n2);
+    let vn3 = repeat_byte(n3);
+    let has_match = |chunk: usize| {
+        contains_zero_byte(chunk ^ vn1)
+            || contains_zero_byte(chunk ^ vn2)
+            || contains_zero_byte(chunk ^ vn3)
+    };
+    reverse_search_words(haystack, has_match, |byte| {
+        byte == n1 || byte == n2 || byte == n3
+    })
+}
+
+/// Like `memchr`, but ignores ASCII case. `n1` must be a lowercase ASCII
+/// letter.
+///
+/// Setting the ASCII case bit (`0x20`) of every byte in a word folds both
+/// cases of `n1` into `n1`, and no other byte, so a single comparison per
+/// word finds both cases.
+pub fn memchr_ascii_nocase(n1: u8, haystack: &[u8]) -> Option<usize> {
+    debug_assert!(n1.is_ascii_lowercase());
+    let (vn1, vfold) = (repeat_byte(n1), repeat_byte(0x20));
+    let has_match = |chunk: usize| contains_zero_byte((chunk | vfold) ^ vn1);
+    forward_search_words(haystack, has_match, |byte| byte | 0x20 == n1)
+}
+
+/// Like `memrchr`, but ignores ASCII case. `n1` must be a lowercase ASCII
+/// letter.
+pub fn memrchr_ascii_nocase(n1: u8, haystack: &[u8]) -> Option<usize> {
+    debug_assert!(n1.is_ascii_lowercase());
+    let (vn1, vfold) = (repeat_byte(n1), repeat_byte(0x20));
+    let has_match = |chunk: usize| contains_zero_byte((chunk | vfold) ^ vn1);
+    reverse_search_words(haystack, has_match, |byte| byte | 0x20 == n1)
+}
+
+/// Like `memchr`, but searches for the first byte not equal to `n1`.
+pub fn memchr_not(n1: u8, haystack: &[u8]) -> Option<usize> {
+    forward_search_not(haystack, &[repeat_byte(n1)], |byte| byte != n1)
+}
+
+/// Like `memchr2`, but searches for the first byte not equal to either `n1`
+/// or `n2`.
+pub fn memchr2_not(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    let vns = [repeat_byte(n1), repeat_byte(n2)];
+    forward_search_not(haystack, &vns, |byte| byte != n1 && byte != n2)
+}
+
+/// Like `memchr3`, but searches for the first byte not equal to any of `n1`,
+/// `n2` or `n3`.
+pub fn memchr3_not(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    let vns = [repeat_byte(n1), repeat_byte(n2), repeat_byte(n3)];
+    forward_search_not(haystack, &vns, |byte| {
+        byte != n1 && byte != n2 && byte != n3
+    })
+}
+
+/// Like `memrchr`, but searches for the last byte not equal to `n1`.
+pub fn memrchr_not(n1: u8, haystack: &[u8]) -> Option<usize> {
+    reverse_search_not(haystack, &[repeat_byte(n1)], |byte| byte != n1)
+}
+
+/// Like `memrchr2`, but searches for the last byte not equal to either `n1`
+/// or `n2`.
+pub fn memrchr2_not(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    let vns = [repeat_byte(n1), repeat_byte(n2)];
+    reverse_search_not(haystack, &vns, |byte| byte != n1 && byte != n2)
+}
+
+/// Like `memrchr3`, but searches for the last byte not equal to any of `n1`,
+/// `n2` or `n3`.
+pub fn memrchr3_not(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    let vns = [repeat_byte(n1), repeat_byte(n2), repeat_byte(n3)];
+    reverse_search_not(haystack, &vns, |byte| {
+        byte != n1 && byte != n2 && byte != n3
+    })
+}
+
+/// The shared implementation of the negated forward searches. `vns` are the
+/// needles repeated into words and `confirm` returns true for any byte that
+/// is not a needle.
+#[inline(always)]
+fn forward_search_not<F: Fn(u8) -> bool>(
+    haystack: &[u8],
+    vns: &[usize],
+    confirm: F,
+) -> Option<usize> {
+    forward_search_words(
+        haystack,
+        |chunk| contains_other_byte(chunk, vns),
+        confirm,
+    )
+}
+
+/// The shared implementation of the negated reverse searches. `vns` are the
+/// needles repeated into words and `confirm` returns true for any byte that
+/// is not a needle.
+#[inline(always)]
+fn reverse_search_not<F: Fn(u8) -> bool>(
+    haystack: &[u8],
+    vns: &[usize],
+    confirm: F,
+) -> Option<usize> {
+    reverse_search_words(
+        haystack,
+        |chunk| contains_other_byte(chunk, vns),
+        confirm,
+    )
+}
+
+/// Like `memchr2`, but also returns the needle that matched.
+pub fn memchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    memchr2(n1, n2, haystack).map(|i| (i, haystack[i]))
+}
+
+/// Like `memchr3`, but also returns the needle that matched.
+pub fn memchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    memchr3(n1, n2, n3, haystack).map(|i| (i, haystack[i]))
+}
+
+/// Like `memrchr2`, but also returns the needle that matched.
+pub fn memrchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    memrchr2(n1, n2, haystack).map(|i| (i, haystack[i]))
+}
+
+/// Like `memrchr3`, but also returns the needle that matched.
+pub fn memrchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    memrchr3(n1, n2, n3, haystack).map(|i| (i, haystack[i]))
+}
+
+/// Count the number of occurrences of `n1` in `haystack`.
+pub fn memchr_count(n1: u8, haystack: &[u8]) -> usize {
+    count(haystack, &[repeat_byte(n1)], |byte| byte == n1)
+}
+
+/// Count the number of occurrences of either `n1` or `n2` in `haystack`.
+pub fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    let vns = [repeat_byte(n1), repeat_byte(n2)];
+    count(haystack, &vns, |byte| byte == n1 || byte == n2)
+}
+
+/// Count the number of occurrences of any of `n1`, `n2` or `n3` in
+/// `haystack`.
+pub fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    let vns = [repeat_byte(n1), repeat_byte(n2), repeat_byte(n3)];
+    count(haystack, &vns, |byte| byte == n1 || byte == n2 || byte == n3)
+}
+
+/// Return the position of the `n`th (starting at zero) occurrence of `n1` in
+/// `haystack`.
+pub fn memchr_nth(n: usize, n1: u8, haystack: &[u8]) -> Option<usize> {
+    nth(n, haystack, &[repeat_byte(n1)], |byte| byte == n1)
+}
+
+/// Replace every occurrence of `n1` in `haystack` with `to`, and return the
+/// number of bytes replaced.
+pub fn replace(n1: u8, to: u8, haystack: &mut [u8]) -> usize {
+    replace_matches(haystack, to, &[repeat_byte(n1)], |byte| byte == n1)
+}
+
+/// Replace every occurrence of either `n1` or `n2` in `haystack` with `to`,
+/// and return the number of bytes replaced.
+pub fn replace2(n1: u8, n2: u8, to: u8, haystack: &mut [u8]) -> usize {
+    let vns = [repeat_byte(n1), repeat_byte(n2)];
+    replace_matches(haystack, to, &vns, |byte| byte == n1 || byte == n2)
+}
+
+/// Replace every occurrence of any of `n1`, `n2` or `n3` in `haystack` with
+/// `to`, and return the number of bytes replaced.
+pub fn replace3(n1: u8, n2: u8, n3: u8, to: u8, haystack: &mut [u8]) -> usize {
+    let vns = [repeat_byte(n1), repeat_byte(n2), repeat_byte(n3)];
+    replace_matches(haystack, to, &vns, |byte| {
+        byte == n1 || byte == n2 || byte == n3
+    })
+}
+
+/// The shared implementation of all counting routines. `vns` are the needles
+/// repeated into words and `confirm` returns true for any byte that is a
+/// needle.
+///
+/// Since `eq_bytes` is exact, we can count all matches in a word at once by
+/// counting the bits set in its result.
+#[inline(always)]
+fn count<F: Fn(u8) -> bool>(
+    haystack: &[u8],
+    vns: &[usize],
+    confirm: F,
+) -> usize {
+    let mut chunks = haystack.chunks_exact(USIZE_BYTES);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let x = read_word(chunk);
+        count += eq_bytes(x, vns).count_ones() as usize;
+    }
+    count + chunks.remainder().iter().filter(|&&b| confirm(b)).count()
+}
+
+/// The shared implementation of all nth-occurrence routines. This skips
+/// over entire words whose number of matches is at most `n`, and only looks
+/// at individual bytes in the word containing the match.
+#[inline(always)]
+fn nth<F: Fn(u8) -> bool>(
+    mut n: usize,
+    haystack: &[u8],
+    vns: &[usize],
+    confirm: F,
+) -> Option<usize> {
+    let mut chunks = haystack.chunks_exact(USIZE_BYTES);
+    let mut at = 0;
+    for chunk in &mut chunks {
+        let x = read_word(chunk);
+        let found = eq_bytes(x, vns).count_ones() as usize;
+        if n < found {
+            break;
+        }
+        n -= found;
+        at += USIZE_BYTES;
+    }
+    haystack[at..]
+        .iter()
+        .enumerate()
+        .filter(|&(_, &b)| confirm(b))
+        .nth(n)
+        .map(|(i, _)| at + i)
+}
+
+/// The shared implementation of all replacement routines. `vns` and
+/// `confirm` are as for `count`.
+///
+/// Every matching byte in a word is replaced at once by widening the result
+/// of `eq_bytes` into a mask that covers each matching byte entirely. Words
+/// without any matches are never written to.
+#[inline(always)]
+fn replace_matches<F: Fn(u8) -> bool>(
+    haystack: &mut [u8],
+    to: u8,
+    vns: &[usize],
+    confirm: F,
+) -> usize {
+    let vto = repeat_byte(to);
+    let mut chunks = haystack.chunks_exact_mut(USIZE_BYTES);
+    let mut count = 0;
+    for chunk in &mut chunks {
+        let x = read_word(chunk);
+        let eq = eq_bytes(x, vns);
+        if eq != 0 {
+            // Each byte of `eq` is either 0x80 or 0x00, so this turns each
+            // of them into 0xFF or 0x00, respectively.
+            let mask = (eq >> 7) * 0xFF;
+            let replaced = (x & !mask) | (vto & mask);
+            chunk.copy_from_slice(&replaced.to_ne_bytes());
+            count += eq.count_ones() as usize;
+        }
+    }
+    for b in chunks.into_remainder() {
+        if confirm(*b) {
+            *b = to;
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Read a word from the given chunk, which must have exactly `USIZE_BYTES`
+/// bytes.
+#[inline(always)]
+fn read_word(chunk: &[u8]) -> usize {
+    let mut bytes = [0; USIZE_BYTES];
+    bytes.copy_from_slice(chunk);
+    usize::from_ne_bytes(bytes)
+}
+
+/// Return the position of the first byte in `haystack` for which `confirm`
+/// returns true.
+///
+/// This skips over entire words for which `has_match` returns false, so
+/// `has_match` must return true for every word that contains a byte for which
+/// `confirm` returns true. (It may return true for other words too.)
+#[cfg(not(feature = "safe-only"))]
+#[inline(always)]
+pub(crate) fn forward_search_words<P, F>(
+    haystack: &[u8],
+    has_match: P,
+    confirm: F,
+) -> Option<usize>
+where
+    P: Fn(usize) -> bool,
+    F: Fn(u8) -> bool,
+{
     let align = USIZE_BYTES - 1;
     let start_ptr = haystack.as_ptr();
+    let mut ptr = start_ptr;
 
     unsafe {
         let end_ptr = start_ptr.add(haystack.len());
-        let mut ptr = end_ptr;
         if haystack.len() < USIZE_BYTES {
-            return reverse_search(start_ptr, end_ptr, ptr, confirm);
+            return forward_search(start_ptr, end_ptr, ptr, confirm);
         }
 
-        let chunk = (ptr.sub(USIZE_BYTES) as *const usize).read_unaligned();
-        let eq1 = contains_zero_byte(chunk ^ vn1);
-        let eq2 = contains_zero_byte(chunk ^ vn2);
-        if eq1 || eq2 {
-            return reverse_search(start_ptr, end_ptr, ptr, confirm);
+        let chunk = (ptr as *const usize).read_unaligned();
+        if has_match(chunk) {
+            return forward_search(start_ptr, end_ptr, ptr, confirm);
         }
 
-        ptr = (end_ptr as usize & !align) as *const u8;
-        debug_assert!(start_ptr <= ptr && ptr <= end_ptr);
-        while ptr >= start_ptr.add(USIZE_BYTES) {
+        ptr = ptr.add(USIZE_BYTES - (start_ptr as usize & align));
+        debug_assert!(ptr > start_ptr);
+        debug_assert!(end_ptr.sub(USIZE_BYTES) >= start_ptr);
+        while ptr <= end_ptr.sub(USIZE_BYTES) {
             debug_assert_eq!(0, (ptr as usize) % USIZE_BYTES);
 
-            let chunk = *(ptr.sub(USIZE_BYTES) as *const usize);
-            let eq1 = contains_zero_byte(chunk ^ vn1);
-            let eq2 = contains_zero_byte(chunk ^ vn2);
-            if eq1 || eq2 {
+            let chunk = *(ptr as *const usize);
+            if has_match(chunk) {
                 break;
             }
-            ptr = ptr.sub(USIZE_BYTES);
+            ptr = ptr.add(USIZE_BYTES);
         }
-        reverse_search(start_ptr, end_ptr, ptr, confirm)
+        forward_search(start_ptr, end_ptr, ptr, confirm)
     }
 }
 
-/// Like `memrchr`, but searches for three bytes instead of one.
-pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
-    let vn1 = repeat_byte(n1);
-    let vn2 = repeat_byte(n2);
-    let vn3 = repeat_byte(n3);
-    let confirm = |byte| byte == n1 || byte == n2 || byte == n3;
+/// Like `forward_search_words`, but doesn't use any unsafe code. Words are
+/// read with `chunks_exact` instead of aligned pointer reads.
+#[cfg(feature = "safe-only")]
+#[inline(always)]
+pub(crate) fn forward_search_words<P, F>(
+    haystack: &[u8],
+    has_match: P,
+    confirm: F,
+) -> Option<usize>
+where
+    P: Fn(usize) -> bool,
+    F: Fn(u8) -> bool,
+{
+    let mut at = 0;
+    for chunk in haystack.chunks_exact(USIZE_BYTES) {
+        if has_match(read_word(chunk)) {
+            break;
+        }
+        at += USIZE_BYTES;
+    }
+    haystack[at..].iter().position(|&b| confirm(b)).map(|i| at + i)
+}
+
+/// Return the position of the last byte in `haystack` for which `confirm`
+/// returns true. `has_match` is as for `forward_search_words`.
+#[cfg(not(feature = "safe-only"))]
+#[inline(always)]
+pub(crate) fn reverse_search_words<P, F>(
+    haystack: &[u8],
+    has_match: P,
+    confirm: F,
+) -> Option<usize>
+where
+    P: Fn(usize) -> bool,
+    F: Fn(u8) -> bool,
+{
     let align = USIZE_BYTES - 1;
     let start_ptr = haystack.as_ptr();
 
@@ -258,10 +592,7 @@ pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
         }
 
         let chunk = (ptr.sub(USIZE_BYTES) as *const usize).read_unaligned();
-        let eq1 = contains_zero_byte(chunk ^ vn1);
-        let eq2 = contains_zero_byte(chunk ^ vn2);
-        let eq3 = contains_zero_byte(chunk ^ vn3);
-        if eq1 || eq2 || eq3 {
+        if has_match(chunk) {
             return reverse_search(start_ptr, end_ptr, ptr, confirm);
         }
 
@@ -271,10 +602,7 @@ pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
             debug_assert_eq!(0, (ptr as usize) % USIZE_BYTES);
 
             let chunk = *(ptr.sub(USIZE_BYTES) as *const usize);
-            let eq1 = contains_zero_byte(chunk ^ vn1);
-            let eq2 = contains_zero_byte(chunk ^ vn2);
-            let eq3 = contains_zero_byte(chunk ^ vn3);
-            if eq1 || eq2 || eq3 {
+            if has_match(chunk) {
                 break;
             }
             ptr = ptr.sub(USIZE_BYTES);
@@ -283,8 +611,32 @@ pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
     }
 }
 
+/// Like `reverse_search_words`, but doesn't use any unsafe code. Words are
+/// read with `rchunks_exact` instead of aligned pointer reads.
+#[cfg(feature = "safe-only")]
+#[inline(always)]
+pub(crate) fn reverse_search_words<P, F>(
+    haystack: &[u8],
+    has_match: P,
+    confirm: F,
+) -> Option<usize>
+where
+    P: Fn(usize) -> bool,
+    F: Fn(u8) -> bool,
+{
+    let mut end = haystack.len();
+    for chunk in haystack.rchunks_exact(USIZE_BYTES) {
+        if has_match(read_word(chunk)) {
+            break;
+        }
+        end -= USIZE_BYTES;
+    }
+    haystack[..end].iter().rposition(|&b| confirm(b))
+}
+
+#[cfg(not(feature = "safe-only"))]
 #[inline(always)]
-unsafe fn forward_search<F: Fn(u8) -> bool>(
+pub(crate) unsafe fn forward_search<F: Fn(u8) -> bool>(
     start_ptr: *const u8,
     end_ptr: *const u8,
     mut ptr: *const u8,
@@ -302,8 +654,9 @@ unsafe fn forward_search<F: Fn(u8) -> bool>(
     None
 }
 
+#[cfg(not(feature = "safe-only"))]
 #[inline(always)]
-unsafe fn reverse_search<F: Fn(u8) -> bool>(
+pub(crate) unsafe fn reverse_search<F: Fn(u8) -> bool>(
     start_ptr: *const u8,
     end_ptr: *const u8,
     mut ptr: *const u8,
@@ -323,7 +676,8 @@ unsafe fn reverse_search<F: Fn(u8) -> bool>(
 
 /// Subtract `b` from `a` and return the difference. `a` should be greater than
 /// or equal to `b`.
-fn sub(a: *const u8, b: *const u8) -> usize {
+#[cfg(not(feature = "safe-only"))]
+pub(crate) fn sub(a: *const u8, b: *const u8) -> usize {
     debug_assert!(a >= b);
     (a as usize) - (b as usize)
 }
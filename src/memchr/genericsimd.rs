@@ -0,0 +1,1745 @@
+use core::{cmp, mem::size_of};
+
+use crate::vector::Vector;
+
+// The number of vectors to loop at in one iteration of memchr/memrchr.
+const LOOP_VECTORS: usize = 4;
+
+// The number of vectors to loop at in one iteration of memchr2/memrchr2 and
+// memchr3/memrchr3. There was no observable difference between unrolling 2
+// and 4 vectors in benchmarks. memchr3 in particular only gets a very slight
+// speed up from the loop unrolling.
+const LOOP_VECTORS2: usize = 2;
+
+/// Return the first index matching the byte `n1` in `haystack`, using
+/// vectors of type `V`.
+///
+/// Callers must ensure that the target features required by `V` are
+/// enabled, and should generally call this from a function marked with the
+/// appropriate `#[target_feature]` attribute.
+#[inline(always)]
+pub(crate) unsafe fn memchr<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    memchr_imp::<V>(n1, haystack, false)
+}
+
+/// Like `memchr`, but ignores ASCII case. `n1` must be a lowercase ASCII
+/// letter.
+///
+/// This sets the ASCII case bit (`0x20`) of every haystack byte before
+/// comparing it with `n1`. Since the only two bytes that differ from a letter
+/// in just that bit are its lowercase and uppercase variants, a single
+/// comparison per vector finds both of them.
+#[inline(always)]
+pub(crate) unsafe fn memchr_ascii_nocase<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    debug_assert!(n1.is_ascii_lowercase());
+    memchr_imp::<V>(n1, haystack, true)
+}
+
+/// The implementation of `memchr` and `memchr_ascii_nocase`. When `fold` is
+/// true, every haystack byte has its ASCII case bit set before it is
+/// compared with `n1`. Since `fold` is always a constant, this costs nothing
+/// when it's false.
+#[inline(always)]
+unsafe fn memchr_imp<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+    fold: bool,
+) -> Option<usize> {
+    // What follows is a fast vectorized algorithm to detect the position of
+    // `n1` in `haystack` if it exists. From what I know, this is the "classic"
+    // algorithm. I believe it can be found in places like glibc and Go's
+    // standard library. It appears to be well known and is elaborated on in
+    // more detail here: https://gms.tf/stdfind-and-memchr-optimizations.html
+    //
+    // While this routine is very long, the basic idea is actually very simple
+    // and can be expressed straight-forwardly in pseudo code. Using SSE2's
+    // 16 byte vectors as an example:
+    //
+    //     needle = (n1 << 15) | (n1 << 14) | ... | (n1 << 1) | n1
+    //     // Note: shift amount in bytes
+    //
+    //     while i <= haystack.len() - 16:
+    //       // A 16 byte vector. Each byte in chunk corresponds to a byte in
+    //       // the haystack.
+    //       chunk = haystack[i:i+16]
+    //       // Compare bytes in needle with bytes in chunk. The result is a 16
+    //       // byte chunk where each byte is 0xFF if the corresponding bytes
+    //       // in needle and chunk were equal, or 0x00 otherwise.
+    //       eqs = cmpeq(needle, chunk)
+    //       // Return a 32 bit integer where the most significant 16 bits
+    //       // are always 0 and the lower 16 bits correspond to whether the
+    //       // most significant bit in the correspond byte in `eqs` is set.
+    //       // In other words, `mask as u16` has bit i set if and only if
+    //       // needle[i] == chunk[i].
+    //       mask = movemask(eqs)
+    //
+    //       // Mask is 0 if there is no match, and non-zero otherwise.
+    //       if mask != 0:
+    //         // trailing_zeros tells us the position of the least significant
+    //         // bit that is set.
+    //         return i + trailing_zeros(mask)
+    //
+    //     // haystack length may not be a multiple of 16, so search the rest.
+    //     while i < haystack.len():
+    //       if haystack[i] == n1:
+    //         return i
+    //
+    //     // No match found.
+    //     return NULL
+    //
+    // In fact, we could loosely translate the above code to Rust line-for-line
+    // and it would be a pretty fast algorithm. But, we pull out all the stops
+    // to go as fast as possible:
+    //
+    // 1. We use aligned loads. That is, we do some finagling to make sure our
+    //    primary loop not only proceeds in increments of 16 bytes, but that
+    //    the address of haystack's pointer that we dereference is aligned to
+    //    16 bytes. 16 is a magic number here because it is the size of SSE2
+    //    128-bit vector. (For the AVX2 algorithm, 32 is the magic number.)
+    //    Therefore, to get aligned loads, our pointer's address must be evenly
+    //    divisible by 16.
+    // 2. Our primary loop proceeds 64 bytes at a time instead of 16. It's
+    //    kind of like loop unrolling, but we combine the equality comparisons
+    //    using a vector OR such that we only need to extract a single mask to
+    //    determine whether a match exists or not. If so, then we do some
+    //    book-keeping to determine the precise location but otherwise mush on.
+    // 3. We use our "chunk" comparison routine in as many places as possible,
+    //    even if it means using unaligned loads. In particular, if haystack
+    //    starts with an unaligned address, then we do an unaligned load to
+    //    search the first 16 bytes. We then start our primary loop at the
+    //    smallest subsequent aligned address, which will actually overlap with
+    //    previously searched bytes. But we're OK with that. We do a similar
+    //    dance at the end of our primary loop. Finally, to avoid a
+    //    byte-at-a-time loop at the end, we do a final 16 byte unaligned load
+    //    that may overlap with a previous load. This is OK because it converts
+    //    a loop into a small number of very fast vector instructions.
+    //
+    // The primary downside of this algorithm is that it's effectively
+    // completely unsafe. Therefore, we have to be super careful to avoid
+    // undefined behavior:
+    //
+    // 1. We use raw pointers everywhere. Not only does dereferencing a pointer
+    //    require the pointer to be valid, but we actually can't even store the
+    //    address of an invalid pointer (unless it's 1 past the end of
+    //    haystack) without sacrificing performance.
+    // 2. Vector::load_unaligned is used when you don't care about alignment,
+    //    and Vector::load_aligned is used when you do care. You cannot use the
+    //    latter on unaligned pointers.
+    // 3. We make liberal use of debug_assert! to check assumptions.
+    // 4. We make a concerted effort to stick with pointers instead of indices.
+    //    Indices are nicer because there's less to worry about with them (see
+    //    above about pointer offsets), but I could not get the compiler to
+    //    produce as good of code as what the below produces. In any case,
+    //    pointers are what we really care about here, and alignment is
+    //    expressed a bit more naturally with them.
+    //
+    // In general, most of the algorithms in this crate have a similar
+    // structure to what you see below, so this comment applies fairly well to
+    // all of them.
+
+    // This routine is called whenever a match is detected. It is specifically
+    // marked as unlineable because it improves the codegen of the unrolled
+    // loop below. Inlining this seems to cause codegen with some extra adds
+    // and a load that aren't necessary. This seems to result in about a 10%
+    // improvement for the memchr1/crate/huge/never benchmark with AVX2.
+    //
+    // Interestingly, I couldn't observe a similar improvement for memrchr.
+    #[cold]
+    #[inline(never)]
+    fn matched(
+        at: usize,
+        vector_size: usize,
+        maska: u32,
+        maskb: u32,
+        maskc: u32,
+        maskd: u32,
+    ) -> usize {
+        if maska != 0 {
+            return at + forward_pos(maska);
+        }
+        if maskb != 0 {
+            return at + vector_size + forward_pos(maskb);
+        }
+        if maskc != 0 {
+            return at + 2 * vector_size + forward_pos(maskc);
+        }
+        debug_assert!(maskd != 0);
+        at + 3 * vector_size + forward_pos(maskd)
+    }
+
+    let vector_size = size_of::<V>();
+    let vn1 = V::splat(n1);
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr < end_ptr {
+            if fold_byte(*ptr, fold) == n1 {
+                return Some(sub(ptr, start_ptr));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    if let Some(i) = forward_search1(start_ptr, end_ptr, ptr, vn1, fold) {
+        return Some(i);
+    }
+
+    ptr = ptr.add(vector_size - (start_ptr as usize & (vector_size - 1)));
+    debug_assert!(ptr > start_ptr && end_ptr.sub(vector_size) >= start_ptr);
+    while loop_size == LOOP_VECTORS * vector_size
+        && ptr <= end_ptr.sub(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        let a = fold_vector(V::load_aligned(ptr), fold);
+        let b = fold_vector(V::load_aligned(ptr.add(vector_size)), fold);
+        let c = fold_vector(V::load_aligned(ptr.add(2 * vector_size)), fold);
+        let d = fold_vector(V::load_aligned(ptr.add(3 * vector_size)), fold);
+        let eqa = vn1.cmpeq(a);
+        let eqb = vn1.cmpeq(b);
+        let eqc = vn1.cmpeq(c);
+        let eqd = vn1.cmpeq(d);
+        let or1 = eqa.or(eqb);
+        let or2 = eqc.or(eqd);
+        let or3 = or1.or(or2);
+        if or3.movemask_will_have_non_zero() {
+            return Some(matched(
+                sub(ptr, start_ptr),
+                vector_size,
+                eqa.movemask(),
+                eqb.movemask(),
+                eqc.movemask(),
+                eqd.movemask(),
+            ));
+        }
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(vector_size) {
+        debug_assert!(sub(end_ptr, ptr) >= vector_size);
+
+        if let Some(i) = forward_search1(start_ptr, end_ptr, ptr, vn1, fold) {
+            return Some(i);
+        }
+        ptr = ptr.add(vector_size);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < vector_size);
+        ptr = ptr.sub(vector_size - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), vector_size);
+
+        return forward_search1(start_ptr, end_ptr, ptr, vn1, fold);
+    }
+    None
+}
+
+/// Like `memchr`, but searches for either of two bytes instead of just one.
+#[inline(always)]
+pub(crate) unsafe fn memchr2<V: Vector>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let vector_size = size_of::<V>();
+    let vn1 = V::splat(n1);
+    let vn2 = V::splat(n2);
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS2 * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr < end_ptr {
+            if *ptr == n1 || *ptr == n2 {
+                return Some(sub(ptr, start_ptr));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    if let Some(i) = forward_search2(start_ptr, end_ptr, ptr, vn1, vn2) {
+        return Some(i);
+    }
+
+    ptr = ptr.add(vector_size - (start_ptr as usize & (vector_size - 1)));
+    debug_assert!(ptr > start_ptr && end_ptr.sub(vector_size) >= start_ptr);
+    while loop_size == LOOP_VECTORS2 * vector_size
+        && ptr <= end_ptr.sub(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        let a = V::load_aligned(ptr);
+        let b = V::load_aligned(ptr.add(vector_size));
+        let eqa1 = vn1.cmpeq(a);
+        let eqb1 = vn1.cmpeq(b);
+        let eqa2 = vn2.cmpeq(a);
+        let eqb2 = vn2.cmpeq(b);
+        let or1 = eqa1.or(eqb1);
+        let or2 = eqa2.or(eqb2);
+        let or3 = or1.or(or2);
+        if or3.movemask_will_have_non_zero() {
+            let mut at = sub(ptr, start_ptr);
+            let mask = eqa1.or(eqa2).movemask();
+            if mask != 0 {
+                return Some(at + forward_pos(mask));
+            }
+
+            at += vector_size;
+            let mask = eqb1.or(eqb2).movemask();
+            debug_assert!(mask != 0);
+            return Some(at + forward_pos(mask));
+        }
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(vector_size) {
+        if let Some(i) = forward_search2(start_ptr, end_ptr, ptr, vn1, vn2) {
+            return Some(i);
+        }
+        ptr = ptr.add(vector_size);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < vector_size);
+        ptr = ptr.sub(vector_size - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), vector_size);
+
+        return forward_search2(start_ptr, end_ptr, ptr, vn1, vn2);
+    }
+    None
+}
+
+/// Like `memchr`, but searches for any of three bytes instead of just one.
+#[inline(always)]
+pub(crate) unsafe fn memchr3<V: Vector>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let vector_size = size_of::<V>();
+    let vn1 = V::splat(n1);
+    let vn2 = V::splat(n2);
+    let vn3 = V::splat(n3);
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS2 * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr < end_ptr {
+            if *ptr == n1 || *ptr == n2 || *ptr == n3 {
+                return Some(sub(ptr, start_ptr));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    if let Some(i) = forward_search3(start_ptr, end_ptr, ptr, vn1, vn2, vn3) {
+        return Some(i);
+    }
+
+    ptr = ptr.add(vector_size - (start_ptr as usize & (vector_size - 1)));
+    debug_assert!(ptr > start_ptr && end_ptr.sub(vector_size) >= start_ptr);
+    while loop_size == LOOP_VECTORS2 * vector_size
+        && ptr <= end_ptr.sub(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        let a = V::load_aligned(ptr);
+        let b = V::load_aligned(ptr.add(vector_size));
+        let eqa1 = vn1.cmpeq(a);
+        let eqb1 = vn1.cmpeq(b);
+        let eqa2 = vn2.cmpeq(a);
+        let eqb2 = vn2.cmpeq(b);
+        let eqa3 = vn3.cmpeq(a);
+        let eqb3 = vn3.cmpeq(b);
+        let or1 = eqa1.or(eqb1);
+        let or2 = eqa2.or(eqb2);
+        let or3 = eqa3.or(eqb3);
+        let or4 = or1.or(or2);
+        let or5 = or3.or(or4);
+        if or5.movemask_will_have_non_zero() {
+            let mut at = sub(ptr, start_ptr);
+            let mask = eqa1.or(eqa2).or(eqa3).movemask();
+            if mask != 0 {
+                return Some(at + forward_pos(mask));
+            }
+
+            at += vector_size;
+            let mask = eqb1.or(eqb2).or(eqb3).movemask();
+            debug_assert!(mask != 0);
+            return Some(at + forward_pos(mask));
+        }
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(vector_size) {
+        if let Some(i) =
+            forward_search3(start_ptr, end_ptr, ptr, vn1, vn2, vn3)
+        {
+            return Some(i);
+        }
+        ptr = ptr.add(vector_size);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < vector_size);
+        ptr = ptr.sub(vector_size - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), vector_size);
+
+        return forward_search3(start_ptr, end_ptr, ptr, vn1, vn2, vn3);
+    }
+    None
+}
+
+/// Return the last index matching the byte `n1` in `haystack`, using
+/// vectors of type `V`.
+#[inline(always)]
+pub(crate) unsafe fn memrchr<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    memrchr_imp::<V>(n1, haystack, false)
+}
+
+/// Like `memrchr`, but ignores ASCII case. `n1` must be a lowercase ASCII
+/// letter. See `memchr_ascii_nocase` for details.
+#[inline(always)]
+pub(crate) unsafe fn memrchr_ascii_nocase<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    debug_assert!(n1.is_ascii_lowercase());
+    memrchr_imp::<V>(n1, haystack, true)
+}
+
+/// The implementation of `memrchr` and `memrchr_ascii_nocase`. See
+/// `memchr_imp` for what `fold` does.
+#[inline(always)]
+unsafe fn memrchr_imp<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+    fold: bool,
+) -> Option<usize> {
+    let vector_size = size_of::<V>();
+    let vn1 = V::splat(n1);
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = end_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr > start_ptr {
+            ptr = ptr.offset(-1);
+            if fold_byte(*ptr, fold) == n1 {
+                return Some(sub(ptr, start_ptr));
+            }
+        }
+        return None;
+    }
+
+    ptr = ptr.sub(vector_size);
+    if let Some(i) = reverse_search1(start_ptr, end_ptr, ptr, vn1, fold) {
+        return Some(i);
+    }
+
+    ptr = end_ptr.sub(end_ptr as usize & (vector_size - 1));
+    debug_assert!(start_ptr <= ptr && ptr <= end_ptr);
+    while loop_size == LOOP_VECTORS * vector_size
+        && ptr >= start_ptr.add(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        ptr = ptr.sub(loop_size);
+        let a = fold_vector(V::load_aligned(ptr), fold);
+        let b = fold_vector(V::load_aligned(ptr.add(vector_size)), fold);
+        let c = fold_vector(V::load_aligned(ptr.add(2 * vector_size)), fold);
+        let d = fold_vector(V::load_aligned(ptr.add(3 * vector_size)), fold);
+        let eqa = vn1.cmpeq(a);
+        let eqb = vn1.cmpeq(b);
+        let eqc = vn1.cmpeq(c);
+        let eqd = vn1.cmpeq(d);
+        let or1 = eqa.or(eqb);
+        let or2 = eqc.or(eqd);
+        let or3 = or1.or(or2);
+        if or3.movemask_will_have_non_zero() {
+            let mut at = sub(ptr.add(3 * vector_size), start_ptr);
+            let mask = eqd.movemask();
+            if mask != 0 {
+                return Some(at + reverse_pos(mask));
+            }
+
+            at -= vector_size;
+            let mask = eqc.movemask();
+            if mask != 0 {
+                return Some(at + reverse_pos(mask));
+            }
+
+            at -= vector_size;
+            let mask = eqb.movemask();
+            if mask != 0 {
+                return Some(at + reverse_pos(mask));
+            }
+
+            at -= vector_size;
+            let mask = eqa.movemask();
+            debug_assert!(mask != 0);
+            return Some(at + reverse_pos(mask));
+        }
+    }
+    while ptr >= start_ptr.add(vector_size) {
+        ptr = ptr.sub(vector_size);
+        if let Some(i) = reverse_search1(start_ptr, end_ptr, ptr, vn1, fold) {
+            return Some(i);
+        }
+    }
+    if ptr > start_ptr {
+        debug_assert!(sub(ptr, start_ptr) < vector_size);
+        return reverse_search1(start_ptr, end_ptr, start_ptr, vn1, fold);
+    }
+    None
+}
+
+/// Like `memrchr`, but searches for either of two bytes instead of just one.
+#[inline(always)]
+pub(crate) unsafe fn memrchr2<V: Vector>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let vector_size = size_of::<V>();
+    let vn1 = V::splat(n1);
+    let vn2 = V::splat(n2);
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS2 * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = end_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr > start_ptr {
+            ptr = ptr.offset(-1);
+            if *ptr == n1 || *ptr == n2 {
+                return Some(sub(ptr, start_ptr));
+            }
+        }
+        return None;
+    }
+
+    ptr = ptr.sub(vector_size);
+    if let Some(i) = reverse_search2(start_ptr, end_ptr, ptr, vn1, vn2) {
+        return Some(i);
+    }
+
+    ptr = end_ptr.sub(end_ptr as usize & (vector_size - 1));
+    debug_assert!(start_ptr <= ptr && ptr <= end_ptr);
+    while loop_size == LOOP_VECTORS2 * vector_size
+        && ptr >= start_ptr.add(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        ptr = ptr.sub(loop_size);
+        let a = V::load_aligned(ptr);
+        let b = V::load_aligned(ptr.add(vector_size));
+        let eqa1 = vn1.cmpeq(a);
+        let eqb1 = vn1.cmpeq(b);
+        let eqa2 = vn2.cmpeq(a);
+        let eqb2 = vn2.cmpeq(b);
+        let or1 = eqa1.or(eqb1);
+        let or2 = eqa2.or(eqb2);
+        let or3 = or1.or(or2);
+        if or3.movemask_will_have_non_zero() {
+            let mut at = sub(ptr.add(vector_size), start_ptr);
+            let mask = eqb1.or(eqb2).movemask();
+            if mask != 0 {
+                return Some(at + reverse_pos(mask));
+            }
+
+            at -= vector_size;
+            let mask = eqa1.or(eqa2).movemask();
+            debug_assert!(mask != 0);
+            return Some(at + reverse_pos(mask));
+        }
+    }
+    while ptr >= start_ptr.add(vector_size) {
+        ptr = ptr.sub(vector_size);
+        if let Some(i) = reverse_search2(start_ptr, end_ptr, ptr, vn1, vn2) {
+            return Some(i);
+        }
+    }
+    if ptr > start_ptr {
+        debug_assert!(sub(ptr, start_ptr) < vector_size);
+        return reverse_search2(start_ptr, end_ptr, start_ptr, vn1, vn2);
+    }
+    None
+}
+
+/// Like `memrchr`, but searches for any of three bytes instead of just one.
+#[inline(always)]
+pub(crate) unsafe fn memrchr3<V: Vector>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let vector_size = size_of::<V>();
+    let vn1 = V::splat(n1);
+    let vn2 = V::splat(n2);
+    let vn3 = V::splat(n3);
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS2 * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = end_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr > start_ptr {
+            ptr = ptr.offset(-1);
+            if *ptr == n1 || *ptr == n2 || *ptr == n3 {
+                return Some(sub(ptr, start_ptr));
+            }
+        }
+        return None;
+    }
+
+    ptr = ptr.sub(vector_size);
+    if let Some(i) = reverse_search3(start_ptr, end_ptr, ptr, vn1, vn2, vn3) {
+        return Some(i);
+    }
+
+    ptr = end_ptr.sub(end_ptr as usize & (vector_size - 1));
+    debug_assert!(start_ptr <= ptr && ptr <= end_ptr);
+    while loop_size == LOOP_VECTORS2 * vector_size
+        && ptr >= start_ptr.add(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        ptr = ptr.sub(loop_size);
+        let a = V::load_aligned(ptr);
+        let b = V::load_aligned(ptr.add(vector_size));
+        let eqa1 = vn1.cmpeq(a);
+        let eqb1 = vn1.cmpeq(b);
+        let eqa2 = vn2.cmpeq(a);
+        let eqb2 = vn2.cmpeq(b);
+        let eqa3 = vn3.cmpeq(a);
+        let eqb3 = vn3.cmpeq(b);
+        let or1 = eqa1.or(eqb1);
+        let or2 = eqa2.or(eqb2);
+        let or3 = eqa3.or(eqb3);
+        let or4 = or1.or(or2);
+        let or5 = or3.or(or4);
+        if or5.movemask_will_have_non_zero() {
+            let mut at = sub(ptr.add(vector_size), start_ptr);
+            let mask = eqb1.or(eqb2).or(eqb3).movemask();
+            if mask != 0 {
+                return Some(at + reverse_pos(mask));
+            }
+
+            at -= vector_size;
+            let mask = eqa1.or(eqa2).or(eqa3).movemask();
+            debug_assert!(mask != 0);
+            return Some(at + reverse_pos(mask));
+        }
+    }
+    while ptr >= start_ptr.add(vector_size) {
+        ptr = ptr.sub(vector_size);
+        if let Some(i) =
+            reverse_search3(start_ptr, end_ptr, ptr, vn1, vn2, vn3)
+        {
+            return Some(i);
+        }
+    }
+    if ptr > start_ptr {
+        debug_assert!(sub(ptr, start_ptr) < vector_size);
+        return reverse_search3(start_ptr, end_ptr, start_ptr, vn1, vn2, vn3);
+    }
+    None
+}
+
+/// Like `memchr`, but returns the index of the first byte that is *not*
+/// equal to `n1`.
+#[inline(always)]
+pub(crate) unsafe fn memchr_not<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let vn1 = V::splat(n1);
+    forward_search_not(haystack, |b| b != n1, |chunk: V| chunk.cmpeq(vn1))
+}
+
+/// Like `memchr_not`, but skips over bytes equal to either of two needles.
+#[inline(always)]
+pub(crate) unsafe fn memchr2_not<V: Vector>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let (vn1, vn2) = (V::splat(n1), V::splat(n2));
+    forward_search_not(
+        haystack,
+        |b| b != n1 && b != n2,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)),
+    )
+}
+
+/// Like `memchr_not`, but skips over bytes equal to any of three needles.
+#[inline(always)]
+pub(crate) unsafe fn memchr3_not<V: Vector>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let (vn1, vn2, vn3) = (V::splat(n1), V::splat(n2), V::splat(n3));
+    forward_search_not(
+        haystack,
+        |b| b != n1 && b != n2 && b != n3,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)).or(chunk.cmpeq(vn3)),
+    )
+}
+
+/// Like `memrchr`, but returns the index of the last byte that is *not*
+/// equal to `n1`.
+#[inline(always)]
+pub(crate) unsafe fn memrchr_not<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let vn1 = V::splat(n1);
+    reverse_search_not(haystack, |b| b != n1, |chunk: V| chunk.cmpeq(vn1))
+}
+
+/// Like `memrchr_not`, but skips over bytes equal to either of two needles.
+#[inline(always)]
+pub(crate) unsafe fn memrchr2_not<V: Vector>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let (vn1, vn2) = (V::splat(n1), V::splat(n2));
+    reverse_search_not(
+        haystack,
+        |b| b != n1 && b != n2,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)),
+    )
+}
+
+/// Like `memrchr_not`, but skips over bytes equal to any of three needles.
+#[inline(always)]
+pub(crate) unsafe fn memrchr3_not<V: Vector>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let (vn1, vn2, vn3) = (V::splat(n1), V::splat(n2), V::splat(n3));
+    reverse_search_not(
+        haystack,
+        |b| b != n1 && b != n2 && b != n3,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)).or(chunk.cmpeq(vn3)),
+    )
+}
+
+/// The shared implementation of all negated forward searches.
+///
+/// `confirm` should return true for bytes that are *not* any of the needles,
+/// and `eq` should return a vector whose lanes are set if and only if the
+/// corresponding byte in the given chunk is equal to any of the needles. We
+/// report the first position at which a lane is not set.
+///
+/// This follows the same structure as `memchr2`, except that we combine
+/// vectors in the unrolled loop with an AND instead of an OR, since we're
+/// looking for any lane that did *not* match.
+#[inline(always)]
+unsafe fn forward_search_not<V, C, E>(
+    haystack: &[u8],
+    confirm: C,
+    eq: E,
+) -> Option<usize>
+where
+    V: Vector,
+    C: Fn(u8) -> bool,
+    E: Fn(V) -> V,
+{
+    let vector_size = size_of::<V>();
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS2 * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr < end_ptr {
+            if confirm(*ptr) {
+                return Some(sub(ptr, start_ptr));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    let search = |ptr: *const u8| {
+        let mask = not_mask(eq(V::load_unaligned(ptr)));
+        if mask != 0 {
+            Some(sub(ptr, start_ptr) + forward_pos(mask))
+        } else {
+            None
+        }
+    };
+    if let Some(i) = search(ptr) {
+        return Some(i);
+    }
+
+    ptr = ptr.add(vector_size - (start_ptr as usize & (vector_size - 1)));
+    debug_assert!(ptr > start_ptr && end_ptr.sub(vector_size) >= start_ptr);
+    while loop_size == LOOP_VECTORS2 * vector_size
+        && ptr <= end_ptr.sub(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        let eqa = eq(V::load_aligned(ptr));
+        let eqb = eq(V::load_aligned(ptr.add(vector_size)));
+        if not_mask(eqa.and(eqb)) != 0 {
+            let mut at = sub(ptr, start_ptr);
+            let mask = not_mask(eqa);
+            if mask != 0 {
+                return Some(at + forward_pos(mask));
+            }
+
+            at += vector_size;
+            let mask = not_mask(eqb);
+            debug_assert!(mask != 0);
+            return Some(at + forward_pos(mask));
+        }
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(vector_size) {
+        if let Some(i) = search(ptr) {
+            return Some(i);
+        }
+        ptr = ptr.add(vector_size);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < vector_size);
+        ptr = ptr.sub(vector_size - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), vector_size);
+
+        return search(ptr);
+    }
+    None
+}
+
+/// The shared implementation of all negated reverse searches. This is like
+/// `forward_search_not`, but reports the last position at which a lane is
+/// not set.
+#[inline(always)]
+unsafe fn reverse_search_not<V, C, E>(
+    haystack: &[u8],
+    confirm: C,
+    eq: E,
+) -> Option<usize>
+where
+    V: Vector,
+    C: Fn(u8) -> bool,
+    E: Fn(V) -> V,
+{
+    let vector_size = size_of::<V>();
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS2 * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = end_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr > start_ptr {
+            ptr = ptr.offset(-1);
+            if confirm(*ptr) {
+                return Some(sub(ptr, start_ptr));
+            }
+        }
+        return None;
+    }
+
+    let search = |ptr: *const u8| {
+        let mask = not_mask(eq(V::load_unaligned(ptr)));
+        if mask != 0 {
+            Some(sub(ptr, start_ptr) + reverse_pos(mask))
+        } else {
+            None
+        }
+    };
+    ptr = ptr.sub(vector_size);
+    if let Some(i) = search(ptr) {
+        return Some(i);
+    }
+
+    ptr = end_ptr.sub(end_ptr as usize & (vector_size - 1));
+    debug_assert!(start_ptr <= ptr && ptr <= end_ptr);
+    while loop_size == LOOP_VECTORS2 * vector_size
+        && ptr >= start_ptr.add(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        ptr = ptr.sub(loop_size);
+        let eqa = eq(V::load_aligned(ptr));
+        let eqb = eq(V::load_aligned(ptr.add(vector_size)));
+        if not_mask(eqa.and(eqb)) != 0 {
+            let mut at = sub(ptr.add(vector_size), start_ptr);
+            let mask = not_mask(eqb);
+            if mask != 0 {
+                return Some(at + reverse_pos(mask));
+            }
+
+            at -= vector_size;
+            let mask = not_mask(eqa);
+            debug_assert!(mask != 0);
+            return Some(at + reverse_pos(mask));
+        }
+    }
+    while ptr >= start_ptr.add(vector_size) {
+        ptr = ptr.sub(vector_size);
+        if let Some(i) = search(ptr) {
+            return Some(i);
+        }
+    }
+    if ptr > start_ptr {
+        debug_assert!(sub(ptr, start_ptr) < vector_size);
+        return search(start_ptr);
+    }
+    None
+}
+
+/// Like `memchr2`, but also returns the needle that matched.
+#[inline(always)]
+pub(crate) unsafe fn memchr2_which<V: Vector>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    let (vn1, vn2) = (V::splat(n1), V::splat(n2));
+    forward_search_which(
+        haystack,
+        |b| b == n1 || b == n2,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)),
+        |chunk, i| which2(chunk, i, vn1, n1, n2),
+    )
+}
+
+/// Like `memchr3`, but also returns the needle that matched.
+#[inline(always)]
+pub(crate) unsafe fn memchr3_which<V: Vector>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    let (vn1, vn2, vn3) = (V::splat(n1), V::splat(n2), V::splat(n3));
+    forward_search_which(
+        haystack,
+        |b| b == n1 || b == n2 || b == n3,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)).or(chunk.cmpeq(vn3)),
+        |chunk, i| which3(chunk, i, vn1, vn2, n1, n2, n3),
+    )
+}
+
+/// Like `memrchr2`, but also returns the needle that matched.
+#[inline(always)]
+pub(crate) unsafe fn memrchr2_which<V: Vector>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    let (vn1, vn2) = (V::splat(n1), V::splat(n2));
+    reverse_search_which(
+        haystack,
+        |b| b == n1 || b == n2,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)),
+        |chunk, i| which2(chunk, i, vn1, n1, n2),
+    )
+}
+
+/// Like `memrchr3`, but also returns the needle that matched.
+#[inline(always)]
+pub(crate) unsafe fn memrchr3_which<V: Vector>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    let (vn1, vn2, vn3) = (V::splat(n1), V::splat(n2), V::splat(n3));
+    reverse_search_which(
+        haystack,
+        |b| b == n1 || b == n2 || b == n3,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)).or(chunk.cmpeq(vn3)),
+        |chunk, i| which3(chunk, i, vn1, vn2, n1, n2, n3),
+    )
+}
+
+/// The shared implementation of all forward searches that report which
+/// needle matched.
+///
+/// `confirm` should return true for bytes that are equal to any of the
+/// needles, and `eq` should return a vector whose lanes are set if and only
+/// if the corresponding byte in the given chunk is equal to any of the
+/// needles. Once a match is found at position `i` in a chunk, `which` is
+/// called with that chunk and `i`, and should return the needle at that
+/// position.
+///
+/// This follows the same structure as `memchr2`, except it only computes the
+/// masks for each individual needle for the chunk containing the match.
+#[inline(always)]
+unsafe fn forward_search_which<V, C, E, W>(
+    haystack: &[u8],
+    confirm: C,
+    eq: E,
+    which: W,
+) -> Option<(usize, u8)>
+where
+    V: Vector,
+    C: Fn(u8) -> bool,
+    E: Fn(V) -> V,
+    W: Fn(V, usize) -> u8,
+{
+    let vector_size = size_of::<V>();
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS2 * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr < end_ptr {
+            if confirm(*ptr) {
+                return Some((sub(ptr, start_ptr), *ptr));
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    let found = |ptr: *const u8, chunk: V, mask: u32| {
+        let i = forward_pos(mask);
+        (sub(ptr, start_ptr) + i, which(chunk, i))
+    };
+    let search = |ptr: *const u8| {
+        let chunk = V::load_unaligned(ptr);
+        let mask = eq(chunk).movemask();
+        if mask != 0 {
+            Some(found(ptr, chunk, mask))
+        } else {
+            None
+        }
+    };
+    if let Some(m) = search(ptr) {
+        return Some(m);
+    }
+
+    ptr = ptr.add(vector_size - (start_ptr as usize & (vector_size - 1)));
+    debug_assert!(ptr > start_ptr && end_ptr.sub(vector_size) >= start_ptr);
+    while loop_size == LOOP_VECTORS2 * vector_size
+        && ptr <= end_ptr.sub(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        let a = V::load_aligned(ptr);
+        let b = V::load_aligned(ptr.add(vector_size));
+        let eqa = eq(a);
+        let eqb = eq(b);
+        if eqa.or(eqb).movemask_will_have_non_zero() {
+            let mask = eqa.movemask();
+            if mask != 0 {
+                return Some(found(ptr, a, mask));
+            }
+
+            let mask = eqb.movemask();
+            debug_assert!(mask != 0);
+            return Some(found(ptr.add(vector_size), b, mask));
+        }
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(vector_size) {
+        if let Some(m) = search(ptr) {
+            return Some(m);
+        }
+        ptr = ptr.add(vector_size);
+    }
+    if ptr < end_ptr {
+        debug_assert!(sub(end_ptr, ptr) < vector_size);
+        ptr = ptr.sub(vector_size - sub(end_ptr, ptr));
+        debug_assert_eq!(sub(end_ptr, ptr), vector_size);
+
+        return search(ptr);
+    }
+    None
+}
+
+/// The shared implementation of all reverse searches that report which
+/// needle matched. This is like `forward_search_which`, but reports the last
+/// match.
+#[inline(always)]
+unsafe fn reverse_search_which<V, C, E, W>(
+    haystack: &[u8],
+    confirm: C,
+    eq: E,
+    which: W,
+) -> Option<(usize, u8)>
+where
+    V: Vector,
+    C: Fn(u8) -> bool,
+    E: Fn(V) -> V,
+    W: Fn(V, usize) -> u8,
+{
+    let vector_size = size_of::<V>();
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS2 * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = end_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr > start_ptr {
+            ptr = ptr.offset(-1);
+            if confirm(*ptr) {
+                return Some((sub(ptr, start_ptr), *ptr));
+            }
+        }
+        return None;
+    }
+
+    let found = |ptr: *const u8, chunk: V, mask: u32| {
+        let i = reverse_pos(mask);
+        (sub(ptr, start_ptr) + i, which(chunk, i))
+    };
+    let search = |ptr: *const u8| {
+        let chunk = V::load_unaligned(ptr);
+        let mask = eq(chunk).movemask();
+        if mask != 0 {
+            Some(found(ptr, chunk, mask))
+        } else {
+            None
+        }
+    };
+    ptr = ptr.sub(vector_size);
+    if let Some(m) = search(ptr) {
+        return Some(m);
+    }
+
+    ptr = end_ptr.sub(end_ptr as usize & (vector_size - 1));
+    debug_assert!(start_ptr <= ptr && ptr <= end_ptr);
+    while loop_size == LOOP_VECTORS2 * vector_size
+        && ptr >= start_ptr.add(loop_size)
+    {
+        debug_assert_eq!(0, (ptr as usize) % vector_size);
+
+        ptr = ptr.sub(loop_size);
+        let a = V::load_aligned(ptr);
+        let b = V::load_aligned(ptr.add(vector_size));
+        let eqa = eq(a);
+        let eqb = eq(b);
+        if eqa.or(eqb).movemask_will_have_non_zero() {
+            let mask = eqb.movemask();
+            if mask != 0 {
+                return Some(found(ptr.add(vector_size), b, mask));
+            }
+
+            let mask = eqa.movemask();
+            debug_assert!(mask != 0);
+            return Some(found(ptr, a, mask));
+        }
+    }
+    while ptr >= start_ptr.add(vector_size) {
+        ptr = ptr.sub(vector_size);
+        if let Some(m) = search(ptr) {
+            return Some(m);
+        }
+    }
+    if ptr > start_ptr {
+        debug_assert!(sub(ptr, start_ptr) < vector_size);
+        return search(start_ptr);
+    }
+    None
+}
+
+/// Return the number of bytes in `haystack` equal to `n1`.
+#[inline(always)]
+pub(crate) unsafe fn memchr_count<V: Vector>(
+    n1: u8,
+    haystack: &[u8],
+) -> usize {
+    let vn1 = V::splat(n1);
+    count(haystack, |b| b == n1, |chunk: V| chunk.cmpeq(vn1))
+}
+
+/// Like `memchr_count`, but counts bytes equal to either of two needles.
+#[inline(always)]
+pub(crate) unsafe fn memchr2_count<V: Vector>(
+    n1: u8,
+    n2: u8,
+    haystack: &[u8],
+) -> usize {
+    let (vn1, vn2) = (V::splat(n1), V::splat(n2));
+    count(
+        haystack,
+        |b| b == n1 || b == n2,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)),
+    )
+}
+
+/// Like `memchr_count`, but counts bytes equal to any of three needles.
+#[inline(always)]
+pub(crate) unsafe fn memchr3_count<V: Vector>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> usize {
+    let (vn1, vn2, vn3) = (V::splat(n1), V::splat(n2), V::splat(n3));
+    count(
+        haystack,
+        |b| b == n1 || b == n2 || b == n3,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)).or(chunk.cmpeq(vn3)),
+    )
+}
+
+/// Return the index of the `n`th (starting at zero) byte in `haystack` that
+/// is equal to `n1`.
+#[inline(always)]
+pub(crate) unsafe fn memchr_nth<V: Vector>(
+    n: usize,
+    n1: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    let vn1 = V::splat(n1);
+    nth(n, haystack, |b| b == n1, |chunk: V| chunk.cmpeq(vn1))
+}
+
+/// Replace every byte in `haystack` equal to `n1` with `to`, and return the
+/// number of bytes replaced.
+#[inline(always)]
+pub(crate) unsafe fn replace<V: Vector>(
+    n1: u8,
+    to: u8,
+    haystack: &mut [u8],
+) -> usize {
+    let vn1 = V::splat(n1);
+    replace_matches(haystack, to, |b| b == n1, |chunk: V| chunk.cmpeq(vn1))
+}
+
+/// Like `replace`, but replaces bytes equal to either of two needles.
+#[inline(always)]
+pub(crate) unsafe fn replace2<V: Vector>(
+    n1: u8,
+    n2: u8,
+    to: u8,
+    haystack: &mut [u8],
+) -> usize {
+    let (vn1, vn2) = (V::splat(n1), V::splat(n2));
+    replace_matches(
+        haystack,
+        to,
+        |b| b == n1 || b == n2,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)),
+    )
+}
+
+/// Like `replace`, but replaces bytes equal to any of three needles.
+#[inline(always)]
+pub(crate) unsafe fn replace3<V: Vector>(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    to: u8,
+    haystack: &mut [u8],
+) -> usize {
+    let (vn1, vn2, vn3) = (V::splat(n1), V::splat(n2), V::splat(n3));
+    replace_matches(
+        haystack,
+        to,
+        |b| b == n1 || b == n2 || b == n3,
+        |chunk: V| chunk.cmpeq(vn1).or(chunk.cmpeq(vn2)).or(chunk.cmpeq(vn3)),
+    )
+}
+
+/// The shared implementation of all replacement routines. This replaces
+/// every byte in `haystack` that is equal to any of the needles with `to`,
+/// and returns the number of bytes replaced. `confirm` and `eq` are as for
+/// `count`.
+///
+/// Each chunk is rewritten by blending `to` into it using the result of
+/// `eq`. Chunks without any matches are never written to, which avoids
+/// dirtying memory when matches are sparse.
+#[inline(always)]
+unsafe fn replace_matches<V, C, E>(
+    haystack: &mut [u8],
+    to: u8,
+    confirm: C,
+    eq: E,
+) -> usize
+where
+    V: Vector,
+    C: Fn(u8) -> bool,
+    E: Fn(V) -> V,
+{
+    let vector_size = size_of::<V>();
+    let start_ptr = haystack.as_mut_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+    let mut count = 0;
+
+    if haystack.len() < vector_size {
+        while ptr < end_ptr {
+            if confirm(*ptr) {
+                *ptr = to;
+                count += 1;
+            }
+            ptr = ptr.offset(1);
+        }
+        return count;
+    }
+
+    let vto = V::splat(to);
+    // Replace all matches in the chunk at `ptr` and return the mask of
+    // positions that were replaced.
+    let replace = |ptr: *mut u8| {
+        let chunk = V::load_unaligned(ptr);
+        let eq = eq(chunk);
+        let mask = eq.movemask();
+        if mask != 0 {
+            eq.and(vto).or(eq.andnot(chunk)).store_unaligned(ptr);
+        }
+        mask
+    };
+    while ptr <= end_ptr.sub(vector_size) {
+        count += replace(ptr).count_ones() as usize;
+        ptr = ptr.add(vector_size);
+    }
+    if ptr < end_ptr {
+        // The final chunk overlaps with bytes we've already handled. Since
+        // none of them are needles anymore (unless `to` is one of them, in
+        // which case they're rewritten with the same value), replacing them
+        // again is harmless. We just need to avoid counting them twice.
+        let overlap = vector_size - sub(end_ptr, ptr);
+        ptr = ptr.sub(overlap);
+        debug_assert_eq!(sub(end_ptr, ptr), vector_size);
+
+        count += (replace(ptr) >> overlap).count_ones() as usize;
+    }
+    count
+}
+
+/// The shared implementation of all counting routines.
+///
+/// `confirm` should return true for bytes that are equal to any of the
+/// needles, and `eq` should return a vector whose lanes are set if and only
+/// if the corresponding byte in the given chunk is equal to any of the
+/// needles.
+///
+/// Since a set lane is `0xFF`, or `-1`, subtracting the result of `eq` from
+/// an accumulator adds one to each byte counter that matched. We only sum the
+/// counters horizontally once they're at risk of overflowing, which keeps
+/// the main loop to a handful of instructions per vector.
+#[inline(always)]
+unsafe fn count<V, C, E>(haystack: &[u8], confirm: C, eq: E) -> usize
+where
+    V: Vector,
+    C: Fn(u8) -> bool,
+    E: Fn(V) -> V,
+{
+    // Each iteration of the unrolled loop adds at most LOOP_VECTORS to a byte
+    // counter, so this is the most iterations we can run before one could
+    // overflow.
+    const MAX_ITERS: usize = 255 / LOOP_VECTORS;
+
+    let vector_size = size_of::<V>();
+    let len = haystack.len();
+    let loop_size = cmp::min(LOOP_VECTORS * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+    let mut count = 0;
+
+    if haystack.len() < vector_size {
+        while ptr < end_ptr {
+            if confirm(*ptr) {
+                count += 1;
+            }
+            ptr = ptr.offset(1);
+        }
+        return count;
+    }
+
+    while loop_size == LOOP_VECTORS * vector_size
+        && ptr <= end_ptr.sub(loop_size)
+    {
+        let mut counts = V::splat(0);
+        let mut iters = 0;
+        while iters < MAX_ITERS && ptr <= end_ptr.sub(loop_size) {
+            let eqa = eq(V::load_unaligned(ptr));
+            let eqb = eq(V::load_unaligned(ptr.add(vector_size)));
+            let eqc = eq(V::load_unaligned(ptr.add(2 * vector_size)));
+            let eqd = eq(V::load_unaligned(ptr.add(3 * vector_size)));
+            counts = counts.sub(eqa).sub(eqb).sub(eqc).sub(eqd);
+            ptr = ptr.add(loop_size);
+            iters += 1;
+        }
+        count += counts.sum_bytes();
+    }
+    // There are at most 3 vectors left here, so no counter can overflow.
+    let mut counts = V::splat(0);
+    while ptr <= end_ptr.sub(vector_size) {
+        counts = counts.sub(eq(V::load_unaligned(ptr)));
+        ptr = ptr.add(vector_size);
+    }
+    count += counts.sum_bytes();
+    if ptr < end_ptr {
+        // Search the final vector, which overlaps with bytes we've already
+        // counted. So we shift the bits for those bytes out of the mask.
+        let overlap = vector_size - sub(end_ptr, ptr);
+        ptr = ptr.sub(overlap);
+        debug_assert_eq!(sub(end_ptr, ptr), vector_size);
+
+        let mask = eq(V::load_unaligned(ptr)).movemask();
+        count += (mask >> overlap).count_ones() as usize;
+    }
+    count
+}
+
+/// The shared implementation of all nth-occurrence routines. `confirm` and
+/// `eq` are as for `count`.
+///
+/// Entire chunks are skipped by counting the bits set in their match masks.
+/// Only once we find the chunk containing the match do we need to look for
+/// the position of the match within it. The unrolled loop examines as many
+/// vectors at once as there are bits in a `u64` for their masks.
+#[inline(always)]
+unsafe fn nth<V, C, E>(
+    mut n: usize,
+    haystack: &[u8],
+    confirm: C,
+    eq: E,
+) -> Option<usize>
+where
+    V: Vector,
+    C: Fn(u8) -> bool,
+    E: Fn(V) -> V,
+{
+    let vector_size = size_of::<V>();
+    let loop_vectors = 64 / vector_size;
+    let len = haystack.len();
+    let loop_size = cmp::min(loop_vectors * vector_size, len);
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = start_ptr;
+
+    if haystack.len() < vector_size {
+        while ptr < end_ptr {
+            if confirm(*ptr) {
+                if n == 0 {
+                    return Some(sub(ptr, start_ptr));
+                }
+                n -= 1;
+            }
+            ptr = ptr.offset(1);
+        }
+        return None;
+    }
+
+    let mask = |ptr: *const u8| eq(V::load_unaligned(ptr)).movemask() as u64;
+    while loop_size == loop_vectors * vector_size
+        && ptr <= end_ptr.sub(loop_size)
+    {
+        let mut masks = 0;
+        for i in 0..loop_vectors {
+            masks |= mask(ptr.add(i * vector_size)) << (i * vector_size);
+        }
+        let found = masks.count_ones() as usize;
+        if n < found {
+            return Some(sub(ptr, start_ptr) + nth_pos(masks, n));
+        }
+        n -= found;
+        ptr = ptr.add(loop_size);
+    }
+    while ptr <= end_ptr.sub(vector_size) {
+        let masks = mask(ptr);
+        let found = masks.count_ones() as usize;
+        if n < found {
+            return Some(sub(ptr, start_ptr) + nth_pos(masks, n));
+        }
+        n -= found;
+        ptr = ptr.add(vector_size);
+    }
+    if ptr < end_ptr {
+        // As with counting, shift out the bits for bytes we've already seen.
+        let overlap = vector_size - sub(end_ptr, ptr);
+        ptr = ptr.sub(overlap);
+        debug_assert_eq!(sub(end_ptr, ptr), vector_size);
+
+        let masks = mask(ptr) >> overlap;
+        if n < masks.count_ones() as usize {
+            return Some(sub(ptr, start_ptr) + overlap + nth_pos(masks, n));
+        }
+    }
+    None
+}
+
+#[inline(always)]
+unsafe fn forward_search1<V: Vector>(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vn1: V,
+    fold: bool,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= size_of::<V>());
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(size_of::<V>()));
+
+    let chunk = fold_vector(V::load_unaligned(ptr), fold);
+    let mask = chunk.cmpeq(vn1).movemask();
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + forward_pos(mask))
+    } else {
+        None
+    }
+}
+
+#[inline(always)]
+unsafe fn forward_search2<V: Vector>(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vn1: V,
+    vn2: V,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= size_of::<V>());
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(size_of::<V>()));
+
+    let chunk = V::load_unaligned(ptr);
+    let eq1 = chunk.cmpeq(vn1);
+    let eq2 = chunk.cmpeq(vn2);
+    let mask = eq1.or(eq2).movemask();
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + forward_pos(mask))
+    } else {
+        None
+    }
+}
+
+#[inline(always)]
+unsafe fn forward_search3<V: Vector>(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vn1: V,
+    vn2: V,
+    vn3: V,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= size_of::<V>());
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(size_of::<V>()));
+
+    let chunk = V::load_unaligned(ptr);
+    let eq1 = chunk.cmpeq(vn1);
+    let eq2 = chunk.cmpeq(vn2);
+    let eq3 = chunk.cmpeq(vn3);
+    let mask = eq1.or(eq2).or(eq3).movemask();
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + forward_pos(mask))
+    } else {
+        None
+    }
+}
+
+#[inline(always)]
+unsafe fn reverse_search1<V: Vector>(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vn1: V,
+    fold: bool,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= size_of::<V>());
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(size_of::<V>()));
+
+    let chunk = fold_vector(V::load_unaligned(ptr), fold);
+    let mask = vn1.cmpeq(chunk).movemask();
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + reverse_pos(mask))
+    } else {
+        None
+    }
+}
+
+#[inline(always)]
+unsafe fn reverse_search2<V: Vector>(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vn1: V,
+    vn2: V,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= size_of::<V>());
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(size_of::<V>()));
+
+    let chunk = V::load_unaligned(ptr);
+    let eq1 = chunk.cmpeq(vn1);
+    let eq2 = chunk.cmpeq(vn2);
+    let mask = eq1.or(eq2).movemask();
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + reverse_pos(mask))
+    } else {
+        None
+    }
+}
+
+#[inline(always)]
+unsafe fn reverse_search3<V: Vector>(
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    vn1: V,
+    vn2: V,
+    vn3: V,
+) -> Option<usize> {
+    debug_assert!(sub(end_ptr, start_ptr) >= size_of::<V>());
+    debug_assert!(start_ptr <= ptr);
+    debug_assert!(ptr <= end_ptr.sub(size_of::<V>()));
+
+    let chunk = V::load_unaligned(ptr);
+    let eq1 = chunk.cmpeq(vn1);
+    let eq2 = chunk.cmpeq(vn2);
+    let eq3 = chunk.cmpeq(vn3);
+    let mask = eq1.or(eq2).or(eq3).movemask();
+    if mask != 0 {
+        Some(sub(ptr, start_ptr) + reverse_pos(mask))
+    } else {
+        None
+    }
+}
+
+/// Set the ASCII case bit of every byte in `chunk` if `fold` is true.
+#[inline(always)]
+unsafe fn fold_vector<V: Vector>(chunk: V, fold: bool) -> V {
+    if fold {
+        chunk.or(V::splat(0x20))
+    } else {
+        chunk
+    }
+}
+
+/// Set the ASCII case bit of `b` if `fold` is true.
+#[inline(always)]
+fn fold_byte(b: u8, fold: bool) -> u8 {
+    if fold {
+        b | 0x20
+    } else {
+        b
+    }
+}
+
+/// Compute the position of the first matching byte from the given mask. The
+/// position returned is always less than the size of the vector the mask was
+/// computed from.
+///
+/// The mask given is expected to be the result of `Vector::movemask`.
+#[inline(always)]
+fn forward_pos(mask: u32) -> usize {
+    // We are dealing with little endian here, where the most significant byte
+    // is at a higher address. That means the least significant bit that is set
+    // corresponds to the position of our first matching byte. That position
+    // corresponds to the number of zeros after the least significant bit.
+    mask.trailing_zeros() as usize
+}
+
+/// Compute the position of the last matching byte from the given mask. The
+/// position returned is always less than the size of the vector the mask was
+/// computed from.
+///
+/// The mask given is expected to be the result of `Vector::movemask`.
+#[inline(always)]
+fn reverse_pos(mask: u32) -> usize {
+    // We are dealing with little endian here, where the most significant byte
+    // is at a higher address. That means the most significant bit that is set
+    // corresponds to the position of our last matching byte. Since bits that
+    // don't correspond to a byte in the vector are always zero, the position
+    // from the start of the mask is 32 - (leading zeros) - 1.
+    32 - mask.leading_zeros() as usize - 1
+}
+
+/// Return whichever of `n1` or `n2` is at position `i` in the given chunk,
+/// where `vn1` is `n1` repeated. The byte at position `i` must be one of the
+/// needles.
+#[inline(always)]
+unsafe fn which2<V: Vector>(chunk: V, i: usize, vn1: V, n1: u8, n2: u8) -> u8 {
+    if chunk.cmpeq(vn1).movemask() & (1 << i) != 0 {
+        n1
+    } else {
+        n2
+    }
+}
+
+/// Return whichever of `n1`, `n2` or `n3` is at position `i` in the given
+/// chunk, where `vn1` and `vn2` are `n1` and `n2` repeated, respectively. The
+/// byte at position `i` must be one of the needles.
+#[inline(always)]
+unsafe fn which3<V: Vector>(
+    chunk: V,
+    i: usize,
+    vn1: V,
+    vn2: V,
+    n1: u8,
+    n2: u8,
+    n3: u8,
+) -> u8 {
+    if chunk.cmpeq(vn1).movemask() & (1 << i) != 0 {
+        n1
+    } else {
+        which2(chunk, i, vn2, n2, n3)
+    }
+}
+
+/// Compute a mask from the given vector of equality comparisons, where a bit
+/// is set if and only if the corresponding lane is *not* set. That is, the
+/// mask returned indicates the bytes that did not match any needle.
+///
+/// As with `Vector::movemask`, bits that don't correspond to a lane in the
+/// vector are always zero.
+#[inline(always)]
+unsafe fn not_mask<V: Vector>(eq: V) -> u32 {
+    let lanes = (!0u64 >> (64 - size_of::<V>())) as u32;
+    !eq.movemask() & lanes
+}
+
+/// Compute the position of the `n`th (starting at zero) bit set in the given
+/// mask. The mask must have more than `n` bits set.
+#[inline(always)]
+fn nth_pos(mut mask: u64, n: usize) -> usize {
+    debug_assert!(n < mask.count_ones() as usize);
+    for _ in 0..n {
+        mask &= mask - 1;
+    }
+    mask.trailing_zeros() as usize
+}
+
+/// Subtract `b` from `a` and return the difference. `a` should be greater than
+/// or equal to `b`.
+#[inline(always)]
+fn sub(a: *const u8, b: *const u8) -> usize {
+    debug_assert!(a >= b);
+    (a as usize) - (b as usize)
+}
@@ -1,4 +1,13 @@
-use crate::{memchr, memchr2, memchr3, memrchr, memrchr2, memrchr3};
+use crate::{
+    memchr,
+    memchr::{
+        memchr2_not, memchr2_which, memchr3_not, memchr3_which,
+        memchr_ascii_nocase, memchr_not, memrchr2_not, memrchr2_which,
+        memrchr3_not, memrchr3_which, memrchr_ascii_nocase, memrchr_not,
+        ByteSet,
+    },
+    memchr2, memchr3, memrchr, memrchr2, memrchr3,
+};
 
 macro_rules! iter_next {
     // Common code for the memchr iterators:
@@ -30,6 +39,30 @@ macro_rules! iter_next_back {
     };
 }
 
+macro_rules! iter_next_which {
+    // Like iter_next, but for search results that also include the needle
+    // that matched.
+    ($self_:expr, $search_result:expr) => {
+        match $search_result {
+            None => None,
+            Some((index, needle)) => {
+                iter_next!($self_, Some(index)).map(|pos| (pos, needle))
+            }
+        }
+    };
+}
+
+macro_rules! iter_next_back_which {
+    ($self_:expr, $search_result:expr) => {
+        match $search_result {
+            None => None,
+            Some((index, needle)) => {
+                iter_next_back!($self_, Some(index)).map(|pos| (pos, needle))
+            }
+        }
+    };
+}
+
 /// An iterator for `memchr`.
 pub struct Memchr<'a> {
     needle: u8,
@@ -171,3 +204,564 @@ impl<'a> DoubleEndedIterator for Memchr3<'a> {
         )
     }
 }
+
+/// An iterator for `ByteSet::find_iter`.
+pub struct ByteSetIter<'a> {
+    set: ByteSet,
+    // The haystack to iterate over
+    haystack: &'a [u8],
+    // The index
+    position: usize,
+}
+
+impl<'a> ByteSetIter<'a> {
+    /// Creates a new iterator that yields all positions of any byte in the
+    /// given set in haystack.
+    #[inline]
+    pub fn new(set: ByteSet, haystack: &[u8]) -> ByteSetIter<'_> {
+        ByteSetIter { set: set, haystack: haystack, position: 0 }
+    }
+}
+
+impl<'a> Iterator for ByteSetIter<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        iter_next!(self, self.set.find(self.haystack))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.haystack.len()))
+    }
+}
+
+impl<'a> DoubleEndedIterator for ByteSetIter<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        iter_next_back!(self, self.set.rfind(self.haystack))
+    }
+}
+
+/// An iterator for `memchr_ascii_nocase`.
+pub struct MemchrAsciiNocase<'a> {
+    needle: u8,
+    // The haystack to iterate over
+    haystack: &'a [u8],
+    // The index
+    position: usize,
+}
+
+impl<'a> MemchrAsciiNocase<'a> {
+    /// Creates a new iterator that yields all positions of needle in
+    /// haystack, ignoring ASCII case.
+    #[inline]
+    pub fn new(needle: u8, haystack: &[u8]) -> MemchrAsciiNocase<'_> {
+        MemchrAsciiNocase { needle: needle, haystack: haystack, position: 0 }
+    }
+}
+
+impl<'a> Iterator for MemchrAsciiNocase<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        iter_next!(self, memchr_ascii_nocase(self.needle, self.haystack))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.haystack.len()))
+    }
+}
+
+impl<'a> DoubleEndedIterator for MemchrAsciiNocase<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        iter_next_back!(self, memrchr_ascii_nocase(self.needle, self.haystack))
+    }
+}
+
+/// An iterator for `memchr_not`.
+pub struct MemchrNot<'a> {
+    needle: u8,
+    // The haystack to iterate over
+    haystack: &'a [u8],
+    // The index
+    position: usize,
+}
+
+impl<'a> MemchrNot<'a> {
+    /// Creates a new iterator that yields all positions in haystack whose
+    /// byte is not equal to needle.
+    #[inline]
+    pub fn new(needle: u8, haystack: &[u8]) -> MemchrNot<'_> {
+        MemchrNot { needle: needle, haystack: haystack, position: 0 }
+    }
+}
+
+impl<'a> Iterator for MemchrNot<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        iter_next!(self, memchr_not(self.needle, self.haystack))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.haystack.len()))
+    }
+}
+
+impl<'a> DoubleEndedIterator for MemchrNot<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        iter_next_back!(self, memrchr_not(self.needle, self.haystack))
+    }
+}
+
+/// An iterator for `memchr2_not`.
+pub struct Memchr2Not<'a> {
+    needle1: u8,
+    needle2: u8,
+    // The haystack to iterate over
+    haystack: &'a [u8],
+    // The index
+    position: usize,
+}
+
+impl<'a> Memchr2Not<'a> {
+    /// Creates a new iterator that yields all positions in haystack whose
+    /// byte is not equal to either needle.
+    #[inline]
+    pub fn new(needle1: u8, needle2: u8, haystack: &[u8]) -> Memchr2Not<'_> {
+        Memchr2Not {
+            needle1: needle1,
+            needle2: needle2,
+            haystack: haystack,
+            position: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Memchr2Not<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        iter_next!(
+            self,
+            memchr2_not(self.needle1, self.needle2, self.haystack)
+        )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.haystack.len()))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Memchr2Not<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        iter_next_back!(
+            self,
+            memrchr2_not(self.needle1, self.needle2, self.haystack)
+        )
+    }
+}
+
+/// An iterator for `memchr3_not`.
+pub struct Memchr3Not<'a> {
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    // The haystack to iterate over
+    haystack: &'a [u8],
+    // The index
+    position: usize,
+}
+
+impl<'a> Memchr3Not<'a> {
+    /// Creates a new iterator that yields all positions in haystack whose
+    /// byte is not equal to any of the needles.
+    #[inline]
+    pub fn new(
+        needle1: u8,
+        needle2: u8,
+        needle3: u8,
+        haystack: &[u8],
+    ) -> Memchr3Not<'_> {
+        Memchr3Not {
+            needle1: needle1,
+            needle2: needle2,
+            needle3: needle3,
+            haystack: haystack,
+            position: 0,
+        }
+    }
+}
+
+impl<'a> Iterator for Memchr3Not<'a> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        iter_next!(
+            self,
+            memchr3_not(
+                self.needle1,
+                self.needle2,
+                self.needle3,
+                self.haystack
+            )
+        )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.haystack.len()))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Memchr3Not<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        iter_next_back!(
+            self,
+            memrchr3_not(
+                self.needle1,
+                self.needle2,
+                self.needle3,
+                self.haystack
+            )
+        )
+    }
+}
+
+/// An iterator for `memchr2_which`.
+pub struct Memchr2Which<'a> {
+    needle1: u8,
+    needle2: u8,
+    // The haystack to iterate over
+    haystack: &'a [u8],
+    // The index
+    position: usize,
+}
+
+impl<'a> Memchr2Which<'a> {
+    /// Creates a new iterator that yields all positions of either needle in
+    /// haystack, along with the needle found at each position.
+    #[inline]
+    pub fn new(needle1: u8, needle2: u8, haystack: &[u8]) -> Memchr2Which<'_> {
+        Memchr2Which { needle1, needle2, haystack, position: 0 }
+    }
+}
+
+impl<'a> Iterator for Memchr2Which<'a> {
+    type Item = (usize, u8);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, u8)> {
+        iter_next_which!(
+            self,
+            memchr2_which(self.needle1, self.needle2, self.haystack)
+        )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.haystack.len()))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Memchr2Which<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        iter_next_back_which!(
+            self,
+            memrchr2_which(self.needle1, self.needle2, self.haystack)
+        )
+    }
+}
+
+/// An iterator for `memchr3_which`.
+pub struct Memchr3Which<'a> {
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    // The haystack to iterate over
+    haystack: &'a [u8],
+    // The index
+    position: usize,
+}
+
+impl<'a> Memchr3Which<'a> {
+    /// Creates a new iterator that yields all positions of any of the
+    /// needles in haystack, along with the needle found at each position.
+    #[inline]
+    pub fn new(
+        needle1: u8,
+        needle2: u8,
+        needle3: u8,
+        haystack: &[u8],
+    ) -> Memchr3Which<'_> {
+        Memchr3Which { needle1, needle2, needle3, haystack, position: 0 }
+    }
+}
+
+impl<'a> Iterator for Memchr3Which<'a> {
+    type Item = (usize, u8);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, u8)> {
+        iter_next_which!(
+            self,
+            memchr3_which(
+                self.needle1,
+                self.needle2,
+                self.needle3,
+                self.haystack
+            )
+        )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.haystack.len()))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Memchr3Which<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        iter_next_back_which!(
+            self,
+            memrchr3_which(
+                self.needle1,
+                self.needle2,
+                self.needle3,
+                self.haystack
+            )
+        )
+    }
+}
+
+/// An iterator over subslices of a haystack separated by delimiters, where
+/// the delimiters are found by the iterator `I` (one of [`Memchr`],
+/// [`Memchr2`] or [`Memchr3`]). The delimiters are not included in the
+/// subslices.
+///
+/// This is created by `memchr_split` and friends.
+#[derive(Clone, Debug)]
+pub struct Split<'a, I> {
+    // The entire haystack. The delimiter iterator reports positions relative
+    // to it.
+    haystack: &'a [u8],
+    it: I,
+    // The bounds of the part of the haystack that hasn't been yielded yet.
+    start: usize,
+    end: usize,
+    finished: bool,
+}
+
+impl<'a, I> Split<'a, I> {
+    /// Creates a new iterator over the subslices of haystack separated by
+    /// the delimiters that `it` finds in it.
+    #[inline]
+    pub(crate) fn new(haystack: &'a [u8], it: I) -> Split<'a, I> {
+        Split { haystack, it, start: 0, end: haystack.len(), finished: false }
+    }
+
+    /// Return the part of the haystack that hasn't been yielded yet and stop
+    /// iteration.
+    #[inline]
+    fn finish(&mut self) -> Option<&'a [u8]> {
+        if self.finished {
+            return None;
+        }
+        self.finished = true;
+        Some(&self.haystack[self.start..self.end])
+    }
+}
+
+impl<'a, I: Iterator<Item = usize>> Iterator for Split<'a, I> {
+    type Item = &'a [u8];
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.finished {
+            return None;
+        }
+        match self.it.next() {
+            None => self.finish(),
+            Some(i) => {
+                let piece = &self.haystack[self.start..i];
+                self.start = i + 1;
+                Some(piece)
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished {
+            (0, Some(0))
+        } else {
+            (1, Some(self.end - self.start + 1))
+        }
+    }
+}
+
+impl<'a, I: DoubleEndedIterator<Item = usize>> DoubleEndedIterator
+    for Split<'a, I>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a [u8]> {
+        if self.finished {
+            return None;
+        }
+        match self.it.next_back() {
+            None => self.finish(),
+            Some(i) => {
+                let piece = &self.haystack[i + 1..self.end];
+                self.end = i;
+                Some(piece)
+            }
+        }
+    }
+}
+
+/// An iterator over at most `n` subslices of a haystack separated by
+/// delimiters, where the delimiters are found by the iterator `I`. The last
+/// subslice yielded contains the remainder of the haystack, including any
+/// delimiters in it.
+///
+/// This is created by `memchr_splitn` and friends.
+#[derive(Clone, Debug)]
+pub struct SplitN<'a, I> {
+    split: Split<'a, I>,
+    // The number of subslices that may still be yielded.
+    count: usize,
+}
+
+impl<'a, I> SplitN<'a, I> {
+    /// Creates a new iterator over at most `count` subslices of haystack
+    /// separated by the delimiters that `it` finds in it.
+    #[inline]
+    pub(crate) fn new(
+        count: usize,
+        haystack: &'a [u8],
+        it: I,
+    ) -> SplitN<'a, I> {
+        SplitN { split: Split::new(haystack, it), count }
+    }
+}
+
+impl<'a, I: Iterator<Item = usize>> Iterator for SplitN<'a, I> {
+    type Item = &'a [u8];
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a [u8]> {
+        match self.count {
+            0 => None,
+            1 => {
+                self.count = 0;
+                self.split.finish()
+            }
+            _ => {
+                self.count -= 1;
+                self.split.next()
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.split.size_hint();
+        let upper =
+            upper.map_or(self.count, |n| core::cmp::min(n, self.count));
+        (core::cmp::min(lower, self.count), Some(upper))
+    }
+}
+
+/// An iterator over subslices of a haystack, where each subslice is
+/// terminated by a delimiter found by the iterator `I`. Unlike [`Split`],
+/// each subslice includes its delimiter, and if the haystack ends with a
+/// delimiter, then no empty subslice is yielded after it.
+///
+/// This is created by `memchr_split_inclusive` and friends.
+#[derive(Clone, Debug)]
+pub struct SplitInclusive<'a, I> {
+    split: Split<'a, I>,
+}
+
+impl<'a, I> SplitInclusive<'a, I> {
+    /// Creates a new iterator over the subslices of haystack terminated by
+    /// the delimiters that `it` finds in it.
+    #[inline]
+    pub(crate) fn new(haystack: &'a [u8], it: I) -> SplitInclusive<'a, I> {
+        SplitInclusive { split: Split::new(haystack, it) }
+    }
+
+    /// Like `Split::finish`, but never yields an empty subslice.
+    #[inline]
+    fn finish(&mut self) -> Option<&'a [u8]> {
+        self.split.finish().filter(|piece| !piece.is_empty())
+    }
+}
+
+impl<'a, I: Iterator<Item = usize>> Iterator for SplitInclusive<'a, I> {
+    type Item = &'a [u8];
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.split.finished {
+            return None;
+        }
+        match self.split.it.next() {
+            None => self.finish(),
+            Some(i) => {
+                let piece = &self.split.haystack[self.split.start..i + 1];
+                self.split.start = i + 1;
+                Some(piece)
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (_, upper) = self.split.size_hint();
+        (0, upper)
+    }
+}
+
+impl<'a, I: DoubleEndedIterator<Item = usize>> DoubleEndedIterator
+    for SplitInclusive<'a, I>
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a [u8]> {
+        if self.split.finished {
+            return None;
+        }
+        let mut found = self.split.it.next_back();
+        // A delimiter at the very end of what remains terminates the last
+        // subslice instead of starting a new (empty) one, so skip it. This
+        // can only happen before anything has been yielded from the back,
+        // since otherwise the last subslice's delimiter was already
+        // consumed.
+        if found.map(|i| i + 1) == Some(self.split.end) {
+            found = self.split.it.next_back();
+        }
+        match found {
+            None => self.finish(),
+            Some(i) => {
+                let piece = &self.split.haystack[i + 1..self.split.end];
+                self.split.end = i + 1;
+                Some(piece)
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use crate::memchr::{memchr, memrchr};
+
+/// Controls how line terminators are handled by the [`Lines`] iterator.
+///
+/// In all cases, lines are split on `\n`, and the last line in a haystack
+/// is yielded even if it has no terminator. A haystack that ends with a
+/// terminator does not have an empty line after it, and an empty haystack
+/// has no lines.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum LineTerminator {
+    /// Strip the trailing `\n` from each line. A `\r` preceding the `\n` is
+    /// left in place.
+    Lf,
+    /// Strip the trailing `\n` from each line, along with a `\r` immediately
+    /// preceding it, if one exists. A `\r` that isn't followed by a `\n` is
+    /// left in place. This is the same behavior as `str::lines`.
+    Crlf,
+    /// Keep the terminator, if one exists, at the end of each line. This
+    /// makes it possible to reconstruct the haystack by concatenating every
+    /// line.
+    Keep,
+}
+
+impl Default for LineTerminator {
+    fn default() -> LineTerminator {
+        LineTerminator::Crlf
+    }
+}
+
+impl LineTerminator {
+    /// Strip the terminator from the given line according to this
+    /// configuration. The line given must end at a `\n` or at the end of
+    /// the haystack.
+    #[inline(always)]
+    fn strip<'a>(&self, line: &'a [u8]) -> &'a [u8] {
+        if *self == LineTerminator::Keep {
+            return line;
+        }
+        let line = match line.split_last() {
+            Some((&b'\n', rest)) => rest,
+            _ => return line,
+        };
+        match line.split_last() {
+            Some((&b'\r', rest)) if *self == LineTerminator::Crlf => rest,
+            _ => line,
+        }
+    }
+}
+
+/// An iterator over the lines in a haystack.
+///
+/// This is created by the [`lines`](crate::lines) and
+/// [`lines_with`](crate::lines_with) functions. It can be consumed from both
+/// ends.
+#[derive(Clone, Debug)]
+pub struct Lines<'a> {
+    // The part of the haystack that hasn't been yielded yet.
+    haystack: &'a [u8],
+    terminator: LineTerminator,
+}
+
+impl<'a> Lines<'a> {
+    /// Creates a new iterator over the lines in the given haystack, with
+    /// terminators handled according to the given configuration.
+    #[inline]
+    pub fn new(haystack: &'a [u8], terminator: LineTerminator) -> Lines<'a> {
+        Lines { haystack, terminator }
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a [u8];
+
+    #[inline]
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.haystack.is_empty() {
+            return None;
+        }
+        let end = memchr(b'\n', self.haystack)
+            .map_or(self.haystack.len(), |i| i + 1);
+        let (line, rest) = self.haystack.split_at(end);
+        self.haystack = rest;
+        Some(self.terminator.strip(line))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.haystack.is_empty() {
+            (0, Some(0))
+        } else {
+            (1, Some(self.haystack.len()))
+        }
+    }
+}
+
+impl<'a> DoubleEndedIterator for Lines<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<&'a [u8]> {
+        let last = match self.haystack.split_last() {
+            None => return None,
+            // The terminator of the last line isn't the start of a new line,
+            // so we skip it when looking for the start of the last line.
+            Some((&b'\n', rest)) => rest,
+            Some(_) => self.haystack,
+        };
+        let start = memrchr(b'\n', last).map_or(0, |i| i + 1);
+        let (rest, line) = self.haystack.split_at(start);
+        self.haystack = rest;
+        Some(self.terminator.strip(line))
+    }
+}
@@ -1,16 +1,35 @@
 use core::iter::Rev;
 
-pub use self::iter::{Memchr, Memchr2, Memchr3};
+pub use self::byteset::ByteSet;
+pub use self::iter::{
+    ByteSetIter, Memchr, Memchr2, Memchr2Not, Memchr2Which, Memchr3,
+    Memchr3Not, Memchr3Which, MemchrAsciiNocase, MemchrNot, Split,
+    SplitInclusive, SplitN,
+};
+pub use self::lines::{LineTerminator, Lines};
 
+pub mod byteset;
 // N.B. If you're looking for the cfg knobs for libc, see build.rs.
 #[cfg(memchr_libc)]
-mod c;
+pub(crate) mod c;
 #[allow(dead_code)]
 pub mod fallback;
+#[cfg(all(
+    not(miri),
+    any(
+        all(target_arch = "x86_64", memchr_runtime_simd),
+        memchr_portable_simd
+    )
+))]
+mod genericsimd;
 mod iter;
+mod lines;
 pub mod naive;
+#[cfg(all(memchr_portable_simd, not(miri)))]
+#[allow(dead_code)]
+pub mod portable;
 #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
-mod x86;
+pub(crate) mod x86;
 
 /// An iterator over all occurrences of the needle in a haystack.
 #[inline]
@@ -62,6 +81,382 @@ pub fn memrchr3_iter(
     Memchr3::new(needle1, needle2, needle3, haystack).rev()
 }
 
+/// An iterator over all occurrences of the needle in a haystack, ignoring
+/// ASCII case.
+#[inline]
+pub fn memchr_ascii_nocase_iter(
+    needle: u8,
+    haystack: &[u8],
+) -> MemchrAsciiNocase<'_> {
+    MemchrAsciiNocase::new(needle, haystack)
+}
+
+/// An iterator over all occurrences of the needle in a haystack, ignoring
+/// ASCII case, in reverse.
+#[inline]
+pub fn memrchr_ascii_nocase_iter(
+    needle: u8,
+    haystack: &[u8],
+) -> Rev<MemchrAsciiNocase<'_>> {
+    MemchrAsciiNocase::new(needle, haystack).rev()
+}
+
+/// An iterator over all occurrences of the needles in a haystack, along
+/// with the needle found at each occurrence.
+#[inline]
+pub fn memchr2_which_iter(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Memchr2Which<'_> {
+    Memchr2Which::new(needle1, needle2, haystack)
+}
+
+/// An iterator over all occurrences of the needles in a haystack, along
+/// with the needle found at each occurrence.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr3_which_iter;
+///
+/// let haystack = b"a,b;c\td";
+/// let got: Vec<(usize, u8)> =
+///     memchr3_which_iter(b',', b';', b'\t', haystack).collect();
+/// assert_eq!(got, vec![(1, b','), (3, b';'), (5, b'\t')]);
+/// ```
+#[inline]
+pub fn memchr3_which_iter(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Memchr3Which<'_> {
+    Memchr3Which::new(needle1, needle2, needle3, haystack)
+}
+
+/// An iterator over all occurrences of the needles in a haystack, along
+/// with the needle found at each occurrence, in reverse.
+#[inline]
+pub fn memrchr2_which_iter(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Rev<Memchr2Which<'_>> {
+    Memchr2Which::new(needle1, needle2, haystack).rev()
+}
+
+/// An iterator over all occurrences of the needles in a haystack, along
+/// with the needle found at each occurrence, in reverse.
+#[inline]
+pub fn memrchr3_which_iter(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Rev<Memchr3Which<'_>> {
+    Memchr3Which::new(needle1, needle2, needle3, haystack).rev()
+}
+
+/// An iterator over all positions in a haystack whose byte is not equal to
+/// the given needle.
+#[inline]
+pub fn memchr_not_iter(needle: u8, haystack: &[u8]) -> MemchrNot<'_> {
+    MemchrNot::new(needle, haystack)
+}
+
+/// An iterator over all positions in a haystack whose byte is not equal to
+/// either of the given needles.
+#[inline]
+pub fn memchr2_not_iter(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Memchr2Not<'_> {
+    Memchr2Not::new(needle1, needle2, haystack)
+}
+
+/// An iterator over all positions in a haystack whose byte is not equal to
+/// any of the given needles.
+#[inline]
+pub fn memchr3_not_iter(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Memchr3Not<'_> {
+    Memchr3Not::new(needle1, needle2, needle3, haystack)
+}
+
+/// An iterator over all positions in a haystack whose byte is not equal to
+/// the given needle, in reverse.
+#[inline]
+pub fn memrchr_not_iter(needle: u8, haystack: &[u8]) -> Rev<MemchrNot<'_>> {
+    MemchrNot::new(needle, haystack).rev()
+}
+
+/// An iterator over all positions in a haystack whose byte is not equal to
+/// either of the given needles, in reverse.
+#[inline]
+pub fn memrchr2_not_iter(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Rev<Memchr2Not<'_>> {
+    Memchr2Not::new(needle1, needle2, haystack).rev()
+}
+
+/// An iterator over all positions in a haystack whose byte is not equal to
+/// any of the given needles, in reverse.
+#[inline]
+pub fn memrchr3_not_iter(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Rev<Memchr3Not<'_>> {
+    Memchr3Not::new(needle1, needle2, needle3, haystack).rev()
+}
+
+/// An iterator over subslices of a haystack separated by the needle.
+///
+/// This is like `<[u8]>::split`, except it only splits on a single byte and
+/// finds delimiters with `memchr`. The delimiters are not included in the
+/// subslices yielded. Like `<[u8]>::split`, an empty haystack yields a
+/// single empty subslice, and consecutive delimiters yield empty subslices
+/// between them.
+///
+/// The iterator returned implements `DoubleEndedIterator`, so it can be
+/// consumed from both ends.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_split;
+///
+/// let fields: Vec<&[u8]> = memchr_split(b'\t', b"foo\tbar\t\tbaz").collect();
+/// assert_eq!(fields, vec![&b"foo"[..], b"bar", b"", b"baz"]);
+/// ```
+#[inline]
+pub fn memchr_split(needle: u8, haystack: &[u8]) -> Split<'_, Memchr<'_>> {
+    Split::new(haystack, Memchr::new(needle, haystack))
+}
+
+/// An iterator over subslices of a haystack separated by either of the
+/// needles.
+#[inline]
+pub fn memchr2_split(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Split<'_, Memchr2<'_>> {
+    Split::new(haystack, Memchr2::new(needle1, needle2, haystack))
+}
+
+/// An iterator over subslices of a haystack separated by any of the
+/// needles.
+#[inline]
+pub fn memchr3_split(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Split<'_, Memchr3<'_>> {
+    Split::new(haystack, Memchr3::new(needle1, needle2, needle3, haystack))
+}
+
+/// An iterator over subslices of a haystack separated by the needle, in
+/// reverse.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_rsplit;
+///
+/// let parts: Vec<&[u8]> = memchr_rsplit(b'/', b"usr/local/bin").collect();
+/// assert_eq!(parts, vec![&b"bin"[..], b"local", b"usr"]);
+/// ```
+#[inline]
+pub fn memchr_rsplit(
+    needle: u8,
+    haystack: &[u8],
+) -> Rev<Split<'_, Memchr<'_>>> {
+    memchr_split(needle, haystack).rev()
+}
+
+/// An iterator over subslices of a haystack separated by either of the
+/// needles, in reverse.
+#[inline]
+pub fn memchr2_rsplit(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Rev<Split<'_, Memchr2<'_>>> {
+    memchr2_split(needle1, needle2, haystack).rev()
+}
+
+/// An iterator over subslices of a haystack separated by any of the needles,
+/// in reverse.
+#[inline]
+pub fn memchr3_rsplit(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Rev<Split<'_, Memchr3<'_>>> {
+    memchr3_split(needle1, needle2, needle3, haystack).rev()
+}
+
+/// An iterator over at most `n` subslices of a haystack separated by the
+/// needle.
+///
+/// The last subslice yielded contains the remainder of the haystack,
+/// including any occurrences of the needle in it. If `n` is zero, then
+/// nothing is yielded.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_splitn;
+///
+/// let parts: Vec<&[u8]> =
+///     memchr_splitn(2, b'=', b"key=value=more").collect();
+/// assert_eq!(parts, vec![&b"key"[..], b"value=more"]);
+/// ```
+#[inline]
+pub fn memchr_splitn(
+    n: usize,
+    needle: u8,
+    haystack: &[u8],
+) -> SplitN<'_, Memchr<'_>> {
+    SplitN::new(n, haystack, Memchr::new(needle, haystack))
+}
+
+/// An iterator over at most `n` subslices of a haystack separated by either
+/// of the needles.
+#[inline]
+pub fn memchr2_splitn(
+    n: usize,
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> SplitN<'_, Memchr2<'_>> {
+    SplitN::new(n, haystack, Memchr2::new(needle1, needle2, haystack))
+}
+
+/// An iterator over at most `n` subslices of a haystack separated by any of
+/// the needles.
+#[inline]
+pub fn memchr3_splitn(
+    n: usize,
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> SplitN<'_, Memchr3<'_>> {
+    let it = Memchr3::new(needle1, needle2, needle3, haystack);
+    SplitN::new(n, haystack, it)
+}
+
+/// An iterator over subslices of a haystack that are each terminated by the
+/// needle, where the terminator is included in each subslice.
+///
+/// This is like `<[u8]>::split_inclusive`. If the haystack doesn't end with
+/// the needle, then the last subslice has no terminator. An empty haystack
+/// yields no subslices.
+///
+/// The iterator returned implements `DoubleEndedIterator`, so it can be
+/// consumed from both ends.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr_split_inclusive;
+///
+/// let haystack = b"foo\nbar\nbaz";
+/// let lines: Vec<&[u8]> = memchr_split_inclusive(b'\n', haystack).collect();
+/// assert_eq!(lines, vec![&b"foo\n"[..], b"bar\n", b"baz"]);
+/// ```
+#[inline]
+pub fn memchr_split_inclusive(
+    needle: u8,
+    haystack: &[u8],
+) -> SplitInclusive<'_, Memchr<'_>> {
+    SplitInclusive::new(haystack, Memchr::new(needle, haystack))
+}
+
+/// An iterator over subslices of a haystack that are each terminated by
+/// either of the needles, where the terminator is included in each subslice.
+#[inline]
+pub fn memchr2_split_inclusive(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> SplitInclusive<'_, Memchr2<'_>> {
+    SplitInclusive::new(haystack, Memchr2::new(needle1, needle2, haystack))
+}
+
+/// An iterator over subslices of a haystack that are each terminated by any
+/// of the needles, where the terminator is included in each subslice.
+#[inline]
+pub fn memchr3_split_inclusive(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> SplitInclusive<'_, Memchr3<'_>> {
+    let it = Memchr3::new(needle1, needle2, needle3, haystack);
+    SplitInclusive::new(haystack, it)
+}
+
+/// An iterator over the lines in a haystack.
+///
+/// Lines are split on `\n`, and the terminator of each line is stripped along
+/// with a `\r` immediately preceding it, if one exists. The last line is
+/// yielded even if it has no terminator. This is the same behavior as
+/// `str::lines`, but for arbitrary bytes. Use [`lines_with`] to configure how
+/// terminators are handled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::lines;
+///
+/// let haystack = b"foo\r\nbar\n\nbaz";
+/// let got: Vec<&[u8]> = lines(haystack).collect();
+/// assert_eq!(got, vec![&b"foo"[..], b"bar", b"", b"baz"]);
+///
+/// let got: Vec<&[u8]> = lines(haystack).rev().collect();
+/// assert_eq!(got, vec![&b"baz"[..], b"", b"bar", b"foo"]);
+/// ```
+#[inline]
+pub fn lines(haystack: &[u8]) -> Lines<'_> {
+    Lines::new(haystack, LineTerminator::Crlf)
+}
+
+/// An iterator over the lines in a haystack, with line terminators handled
+/// according to the given configuration.
+///
+/// # Example
+///
+/// This shows how to keep line terminators, which makes it possible to
+/// reconstruct the original haystack.
+///
+/// ```
+/// use memchr::{lines_with, LineTerminator};
+///
+/// let haystack = b"foo\r\nbar\nbaz";
+/// let got: Vec<&[u8]> = lines_with(haystack, LineTerminator::Keep).collect();
+/// assert_eq!(got, vec![&b"foo\r\n"[..], b"bar\n", b"baz"]);
+///
+/// let got: Vec<&[u8]> = lines_with(haystack, LineTerminator::Lf).collect();
+/// assert_eq!(got, vec![&b"foo\r"[..], b"bar", b"baz"]);
+/// ```
+#[inline]
+pub fn lines_with(haystack: &[u8], terminator: LineTerminator) -> Lines<'_> {
+    Lines::new(haystack, terminator)
+}
+
 /// Search for the first occurrence of a byte in a slice.
 ///
 /// This returns the index corresponding to the first occurrence of `needle` in
@@ -98,18 +493,30 @@ pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
     }
 
     #[cfg(all(
-        memchr_libc,
+        memchr_portable_simd,
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
         not(miri),
     ))]
     #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr(n1, haystack)
+    }
+
+    #[cfg(all(
+        memchr_libc_memchr,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
     fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
         c::memchr(n1, haystack)
     }
 
     #[cfg(all(
-        not(memchr_libc),
+        not(memchr_libc_memchr),
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -161,8 +568,19 @@ pub fn memchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
         x86::memchr2(n1, n2, haystack)
     }
 
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr2(n1, n2, haystack)
+    }
+
     #[cfg(all(
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -220,10 +638,21 @@ pub fn memchr3(
     }
 
     #[cfg(all(
+        memchr_portable_simd,
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
         not(miri),
     ))]
     #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr3(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
     fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
         fallback::memchr3(n1, n2, n3, haystack)
     }
@@ -270,10 +699,21 @@ pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
         x86::memrchr(n1, haystack)
     }
 
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr(n1, haystack)
+    }
+
     #[cfg(all(
         memchr_libc,
         target_os = "linux",
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
         not(miri)
     ))]
     #[inline(always)]
@@ -284,6 +724,7 @@ pub fn memrchr(needle: u8, haystack: &[u8]) -> Option<usize> {
     #[cfg(all(
         not(all(memchr_libc, target_os = "linux")),
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -336,10 +777,21 @@ pub fn memrchr2(needle1: u8, needle2: u8, haystack: &[u8]) -> Option<usize> {
     }
 
     #[cfg(all(
+        memchr_portable_simd,
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
         not(miri),
     ))]
     #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr2(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
     fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
         fallback::memrchr2(n1, n2, haystack)
     }
@@ -393,8 +845,19 @@ pub fn memrchr3(
         x86::memrchr3(n1, n2, n3, haystack)
     }
 
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr3(n1, n2, n3, haystack)
+    }
+
     #[cfg(all(
         not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
         not(miri),
     ))]
     #[inline(always)]
@@ -408,3 +871,1209 @@ pub fn memrchr3(
         imp(needle1, needle2, needle3, haystack)
     }
 }
+
+/// Search for the first occurrence of a byte in a slice, ignoring ASCII
+/// case.
+///
+/// If `needle` is an ASCII letter, then this returns the index of the first
+/// byte in `haystack` that is equal to either its lowercase or uppercase
+/// variant. Otherwise, this is equivalent to `memchr`. `None` is returned if
+/// no such byte is found. If an index is returned, it is guaranteed to be
+/// less than `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().position(|b| b.eq_ignore_ascii_case(&needle))`,
+/// `memchr_ascii_nocase` will use a highly optimized routine. Unlike
+/// `memchr2(b'x', b'X', haystack)`, it folds the case of the haystack while
+/// searching it, so that it only needs to do a single comparison per vector.
+///
+/// # Example
+///
+/// This shows how to find the start of a header name without caring about
+/// its case.
+///
+/// ```
+/// use memchr::memchr_ascii_nocase;
+///
+/// let haystack = b"Host: x\r\nCONTENT-LENGTH: 42\r\n";
+/// assert_eq!(memchr_ascii_nocase(b'c', haystack), Some(9));
+/// assert_eq!(memchr_ascii_nocase(b'C', haystack), Some(9));
+/// ```
+#[inline]
+pub fn memchr_ascii_nocase(needle: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memchr_ascii_nocase(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memchr_ascii_nocase(n1, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr_ascii_nocase(n1, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memchr_ascii_nocase(n1, haystack)
+    }
+
+    if !needle.is_ascii_alphabetic() {
+        memchr(needle, haystack)
+    } else if haystack.is_empty() {
+        None
+    } else {
+        imp(needle.to_ascii_lowercase(), haystack)
+    }
+}
+
+/// Search for the last occurrence of a byte in a slice, ignoring ASCII case.
+///
+/// If `needle` is an ASCII letter, then this returns the index of the last
+/// byte in `haystack` that is equal to either its lowercase or uppercase
+/// variant. Otherwise, this is equivalent to `memrchr`. `None` is returned if
+/// no such byte is found. If an index is returned, it is guaranteed to be
+/// less than `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().rposition(|b| b.eq_ignore_ascii_case(&needle))`,
+/// `memrchr_ascii_nocase` will use a highly optimized routine.
+///
+/// # Example
+///
+/// This shows how to find the last position of a byte in a byte string
+/// without caring about its case.
+///
+/// ```
+/// use memchr::memrchr_ascii_nocase;
+///
+/// let haystack = b"the quick brown FOX";
+/// assert_eq!(memrchr_ascii_nocase(b'o', haystack), Some(17));
+/// ```
+#[inline]
+pub fn memrchr_ascii_nocase(needle: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memrchr_ascii_nocase(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memrchr_ascii_nocase(n1, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr_ascii_nocase(n1, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memrchr_ascii_nocase(n1, haystack)
+    }
+
+    if !needle.is_ascii_alphabetic() {
+        memrchr(needle, haystack)
+    } else if haystack.is_empty() {
+        None
+    } else {
+        imp(needle.to_ascii_lowercase(), haystack)
+    }
+}
+
+/// Like `memchr2`, but also reports which of the two needles matched.
+///
+/// This returns the index corresponding to the first occurrence of `needle1`
+/// or `needle2` in `haystack`, along with the needle found at that index, or
+/// `None` if neither one is found. If an index is returned, it is guaranteed
+/// to be less than `usize::MAX`.
+///
+/// This is operationally the same as
+/// `memchr2(needle1, needle2, haystack).map(|i| (i, haystack[i]))`, but
+/// where possible, the needle is determined from the comparisons already
+/// performed by the search instead of by reading the haystack again.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr2_which;
+///
+/// let haystack = b"key=\"value\"";
+/// assert_eq!(memchr2_which(b'"', b'=', haystack), Some((3, b'=')));
+/// ```
+#[inline]
+pub fn memchr2_which(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        naive::memchr2_which(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        x86::memchr2_which(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        portable::memchr2_which(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        fallback::memchr2_which(n1, n2, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, haystack)
+    }
+}
+
+/// Like `memchr3`, but also reports which of the three needles matched.
+///
+/// This returns the index corresponding to the first occurrence of any of
+/// the needles in `haystack`, along with the needle found at that index, or
+/// `None` if none of them are found. If an index is returned, it is
+/// guaranteed to be less than `usize::MAX`.
+///
+/// # Example
+///
+/// This shows how a lexer might branch on the kind of byte found.
+///
+/// ```
+/// use memchr::memchr3_which;
+///
+/// let haystack = b"abc\\\"def\"";
+/// match memchr3_which(b'"', b'\\', b'\n', haystack) {
+///     Some((i, b'\\')) => assert_eq!(i, 3),
+///     _ => unreachable!(),
+/// }
+/// ```
+#[inline]
+pub fn memchr3_which(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        naive::memchr3_which(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        x86::memchr3_which(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        portable::memchr3_which(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        fallback::memchr3_which(n1, n2, n3, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, needle3, haystack)
+    }
+}
+
+/// Like `memrchr2`, but also reports which of the two needles matched.
+///
+/// This returns the index corresponding to the last occurrence of `needle1`
+/// or `needle2` in `haystack`, along with the needle found at that index, or
+/// `None` if neither one is found. If an index is returned, it is guaranteed
+/// to be less than `usize::MAX`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memrchr2_which;
+///
+/// let haystack = b"key=\"value\"";
+/// assert_eq!(memrchr2_which(b'"', b'=', haystack), Some((10, b'"')));
+/// ```
+#[inline]
+pub fn memrchr2_which(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        naive::memrchr2_which(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        x86::memrchr2_which(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        portable::memrchr2_which(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        fallback::memrchr2_which(n1, n2, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, haystack)
+    }
+}
+
+/// Like `memrchr3`, but also reports which of the three needles matched.
+///
+/// This returns the index corresponding to the last occurrence of any of the
+/// needles in `haystack`, along with the needle found at that index, or
+/// `None` if none of them are found. If an index is returned, it is
+/// guaranteed to be less than `usize::MAX`.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memrchr3_which;
+///
+/// let haystack = b"a,b;c\td";
+/// assert_eq!(memrchr3_which(b',', b';', b'\t', haystack), Some((5, b'\t')));
+/// ```
+#[inline]
+pub fn memrchr3_which(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        naive::memrchr3_which(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        x86::memrchr3_which(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        portable::memrchr3_which(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+        fallback::memrchr3_which(n1, n2, n3, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, needle3, haystack)
+    }
+}
+
+/// Search for the first byte in a slice that is not equal to the given byte.
+///
+/// This returns the index corresponding to the first byte in `haystack` that
+/// is not equal to `needle`, or `None` if every byte is equal to `needle`
+/// (which includes the case where `haystack` is empty). If an index is
+/// returned, it is guaranteed to be less than `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().position(|&b| b != needle)`, `memchr_not` will use a
+/// highly optimized routine that can be up to an order of magnitude faster in
+/// some cases. This makes it useful for skipping over long runs of padding.
+///
+/// # Example
+///
+/// This shows how to skip leading spaces in a byte string.
+///
+/// ```
+/// use memchr::memchr_not;
+///
+/// let haystack = b"     the quick brown fox";
+/// assert_eq!(memchr_not(b' ', haystack), Some(5));
+/// assert_eq!(memchr_not(b' ', b"     "), None);
+/// ```
+#[inline]
+pub fn memchr_not(needle: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memchr_not(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memchr_not(n1, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr_not(n1, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memchr_not(n1, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle, haystack)
+    }
+}
+
+/// Like `memchr_not`, but searches for the first byte that is equal to neither
+/// of two bytes.
+///
+/// This returns the index corresponding to the first byte in `haystack` that
+/// is not equal to either `needle1` or `needle2`, or `None` if no such byte
+/// exists. If an index is returned, it is guaranteed to be less than
+/// `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().position(|&b| b != needle1 && b != needle2)`,
+/// `memchr2_not` will use a highly optimized routine that can be up to an
+/// order of magnitude faster in some cases.
+///
+/// # Example
+///
+/// This shows how to skip leading whitespace in a byte string.
+///
+/// ```
+/// use memchr::memchr2_not;
+///
+/// let haystack = b" \t \tthe quick brown fox";
+/// assert_eq!(memchr2_not(b' ', b'\t', haystack), Some(4));
+/// ```
+#[inline]
+pub fn memchr2_not(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memchr2_not(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memchr2_not(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr2_not(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memchr2_not(n1, n2, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, haystack)
+    }
+}
+
+/// Like `memchr_not`, but searches for the first byte that is equal to none of
+/// three bytes.
+///
+/// This returns the index corresponding to the first byte in `haystack` that
+/// is not equal to any of `needle1`, `needle2` or `needle3`, or `None` if no
+/// such byte exists. If an index is returned, it is guaranteed to be less than
+/// `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().position(|&b| b != needle1 && b != needle2 &&
+/// b != needle3)`, `memchr3_not` will use a highly optimized routine that can
+/// be up to an order of magnitude faster in some cases.
+///
+/// # Example
+///
+/// This shows how to skip leading whitespace in a byte string.
+///
+/// ```
+/// use memchr::memchr3_not;
+///
+/// let haystack = b" \t\n the quick brown fox";
+/// assert_eq!(memchr3_not(b' ', b'\t', b'\n', haystack), Some(4));
+/// ```
+#[inline]
+pub fn memchr3_not(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memchr3_not(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memchr3_not(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr3_not(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memchr3_not(n1, n2, n3, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, needle3, haystack)
+    }
+}
+
+/// Search for the last byte in a slice that is not equal to the given byte.
+///
+/// This returns the index corresponding to the last byte in `haystack` that
+/// is not equal to `needle`, or `None` if every byte is equal to `needle`
+/// (which includes the case where `haystack` is empty). If an index is
+/// returned, it is guaranteed to be less than `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().rposition(|&b| b != needle)`, `memrchr_not` will use a
+/// highly optimized routine that can be up to an order of magnitude faster in
+/// some cases.
+///
+/// # Example
+///
+/// This shows how to trim trailing NUL bytes from a byte string.
+///
+/// ```
+/// use memchr::memrchr_not;
+///
+/// let haystack = b"the quick brown fox\x00\x00\x00";
+/// let end = memrchr_not(b'\x00', haystack).map_or(0, |i| i + 1);
+/// assert_eq!(&haystack[..end], b"the quick brown fox");
+/// ```
+#[inline]
+pub fn memrchr_not(needle: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memrchr_not(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memrchr_not(n1, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr_not(n1, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memrchr_not(n1, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle, haystack)
+    }
+}
+
+/// Like `memrchr_not`, but searches for the last byte that is equal to neither
+/// of two bytes.
+///
+/// This returns the index corresponding to the last byte in `haystack` that
+/// is not equal to either `needle1` or `needle2`, or `None` if no such byte
+/// exists. If an index is returned, it is guaranteed to be less than
+/// `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().rposition(|&b| b != needle1 && b != needle2)`,
+/// `memrchr2_not` will use a highly optimized routine that can be up to an
+/// order of magnitude faster in some cases.
+///
+/// # Example
+///
+/// This shows how to find the end of a line, ignoring trailing whitespace.
+///
+/// ```
+/// use memchr::memrchr2_not;
+///
+/// let haystack = b"the quick brown fox \t \t";
+/// assert_eq!(memrchr2_not(b' ', b'\t', haystack), Some(18));
+/// ```
+#[inline]
+pub fn memrchr2_not(
+    needle1: u8,
+    needle2: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memrchr2_not(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memrchr2_not(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr2_not(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memrchr2_not(n1, n2, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, haystack)
+    }
+}
+
+/// Like `memrchr_not`, but searches for the last byte that is equal to none of
+/// three bytes.
+///
+/// This returns the index corresponding to the last byte in `haystack` that
+/// is not equal to any of `needle1`, `needle2` or `needle3`, or `None` if no
+/// such byte exists. If an index is returned, it is guaranteed to be less than
+/// `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().rposition(|&b| b != needle1 && b != needle2 &&
+/// b != needle3)`, `memrchr3_not` will use a highly optimized routine that can
+/// be up to an order of magnitude faster in some cases.
+///
+/// # Example
+///
+/// This shows how to find the end of a line, ignoring trailing whitespace.
+///
+/// ```
+/// use memchr::memrchr3_not;
+///
+/// let haystack = b"the quick brown fox \t\n";
+/// assert_eq!(memrchr3_not(b' ', b'\t', b'\n', haystack), Some(18));
+/// ```
+#[inline]
+pub fn memrchr3_not(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memrchr3_not(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memrchr3_not(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memrchr3_not(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memrchr3_not(n1, n2, n3, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(needle1, needle2, needle3, haystack)
+    }
+}
+
+/// Count the number of occurrences of a byte in a slice.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().filter(|&&b| b == needle).count()` or
+/// `memchr_iter(needle, haystack).count()`, `memchr_count` will use a highly
+/// optimized routine that counts every match in a vector at once, instead of
+/// restarting a search after each match. This makes it much faster when
+/// matches are frequent, e.g., when counting lines.
+///
+/// # Example
+///
+/// This shows how to count the number of lines in a byte string.
+///
+/// ```
+/// use memchr::memchr_count;
+///
+/// let haystack = b"foo\nbar\nbaz\n";
+/// assert_eq!(memchr_count(b'\n', haystack), 3);
+/// ```
+#[inline]
+pub fn memchr_count(needle: u8, haystack: &[u8]) -> usize {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> usize {
+        naive::memchr_count(n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> usize {
+        x86::memchr_count(n1, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> usize {
+        portable::memchr_count(n1, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, haystack: &[u8]) -> usize {
+        fallback::memchr_count(n1, haystack)
+    }
+
+    if haystack.is_empty() {
+        0
+    } else {
+        imp(needle, haystack)
+    }
+}
+
+/// Like `memchr_count`, but counts the occurrences of either of two bytes.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().filter(|&&b| b == needle1 || b == needle2).count()`,
+/// `memchr2_count` will use a highly optimized routine.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr2_count;
+///
+/// let haystack = b"foo\r\nbar\r\n";
+/// assert_eq!(memchr2_count(b'\r', b'\n', haystack), 4);
+/// ```
+#[inline]
+pub fn memchr2_count(needle1: u8, needle2: u8, haystack: &[u8]) -> usize {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+        naive::memchr2_count(n1, n2, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+        x86::memchr2_count(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+        portable::memchr2_count(n1, n2, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+        fallback::memchr2_count(n1, n2, haystack)
+    }
+
+    if haystack.is_empty() {
+        0
+    } else {
+        imp(needle1, needle2, haystack)
+    }
+}
+
+/// Like `memchr_count`, but counts the occurrences of any of three bytes.
+///
+/// While this is operationally the same as something like
+/// `haystack.iter().filter(|&&b| b == needle1 || b == needle2 ||
+/// b == needle3).count()`, `memchr3_count` will use a highly optimized
+/// routine.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memchr3_count;
+///
+/// let haystack = b"a,b;c\td";
+/// assert_eq!(memchr3_count(b',', b';', b'\t', haystack), 3);
+/// ```
+#[inline]
+pub fn memchr3_count(
+    needle1: u8,
+    needle2: u8,
+    needle3: u8,
+    haystack: &[u8],
+) -> usize {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+        naive::memchr3_count(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+        x86::memchr3_count(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+        portable::memchr3_count(n1, n2, n3, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+        fallback::memchr3_count(n1, n2, n3, haystack)
+    }
+
+    if haystack.is_empty() {
+        0
+    } else {
+        imp(needle1, needle2, needle3, haystack)
+    }
+}
+
+/// Search for the `n`th occurrence of a byte in a slice, where `n` starts at
+/// zero.
+///
+/// This returns the index corresponding to the `n`th occurrence of `needle`
+/// in `haystack`, or `None` if there are `n` or fewer occurrences. In
+/// particular, `memchr_nth(0, needle, haystack)` is equivalent to
+/// `memchr(needle, haystack)`. If an index is returned, it is guaranteed to
+/// be less than `usize::MAX`.
+///
+/// While this is operationally the same as something like
+/// `memchr_iter(needle, haystack).nth(n)`, `memchr_nth` will use a highly
+/// optimized routine that skips over entire chunks of the haystack by
+/// counting the matches in them, and only looks for the position of a match
+/// in the chunk that contains it.
+///
+/// # Example
+///
+/// This shows how to find the start of the third line in a byte string.
+///
+/// ```
+/// use memchr::memchr_nth;
+///
+/// let haystack = b"foo\nbar\nbaz\nquux";
+/// let start = memchr_nth(1, b'\n', haystack).map_or(0, |i| i + 1);
+/// assert_eq!(&haystack[start..start + 3], b"baz");
+/// assert_eq!(memchr_nth(3, b'\n', haystack), None);
+/// ```
+#[inline]
+pub fn memchr_nth(n: usize, needle: u8, haystack: &[u8]) -> Option<usize> {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n: usize, n1: u8, haystack: &[u8]) -> Option<usize> {
+        naive::memchr_nth(n, n1, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n: usize, n1: u8, haystack: &[u8]) -> Option<usize> {
+        x86::memchr_nth(n, n1, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n: usize, n1: u8, haystack: &[u8]) -> Option<usize> {
+        portable::memchr_nth(n, n1, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n: usize, n1: u8, haystack: &[u8]) -> Option<usize> {
+        fallback::memchr_nth(n, n1, haystack)
+    }
+
+    if haystack.is_empty() {
+        None
+    } else {
+        imp(n, needle, haystack)
+    }
+}
+
+/// Replace every occurrence of a byte in a slice with another byte, and
+/// return the number of bytes replaced.
+///
+/// While this is operationally the same as something like
+/// `for b in haystack.iter_mut() { if *b == from { *b = to; } }`, `replace`
+/// will use a highly optimized routine that rewrites every match in a
+/// vector at once.
+///
+/// # Example
+///
+/// This shows how to turn NUL separated records into lines.
+///
+/// ```
+/// use memchr::replace;
+///
+/// let mut haystack = b"foo\x00bar\x00baz".to_vec();
+/// assert_eq!(replace(&mut haystack, b'\x00', b'\n'), 2);
+/// assert_eq!(haystack, b"foo\nbar\nbaz");
+/// ```
+#[inline]
+pub fn replace(haystack: &mut [u8], from: u8, to: u8) -> usize {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, to: u8, haystack: &mut [u8]) -> usize {
+        naive::replace(n1, to, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, to: u8, haystack: &mut [u8]) -> usize {
+        x86::replace(n1, to, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, to: u8, haystack: &mut [u8]) -> usize {
+        portable::replace(n1, to, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, to: u8, haystack: &mut [u8]) -> usize {
+        fallback::replace(n1, to, haystack)
+    }
+
+    if haystack.is_empty() {
+        0
+    } else {
+        imp(from, to, haystack)
+    }
+}
+
+/// Like `replace`, but replaces every occurrence of either of two bytes.
+///
+/// # Example
+///
+/// This shows how to normalize tabs and vertical tabs to spaces.
+///
+/// ```
+/// use memchr::replace2;
+///
+/// let mut haystack = b"foo\tbar\x0Bbaz".to_vec();
+/// assert_eq!(replace2(&mut haystack, b'\t', b'\x0B', b' '), 2);
+/// assert_eq!(haystack, b"foo bar baz");
+/// ```
+#[inline]
+pub fn replace2(haystack: &mut [u8], from1: u8, from2: u8, to: u8) -> usize {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, to: u8, haystack: &mut [u8]) -> usize {
+        naive::replace2(n1, n2, to, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, to: u8, haystack: &mut [u8]) -> usize {
+        x86::replace2(n1, n2, to, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, to: u8, haystack: &mut [u8]) -> usize {
+        portable::replace2(n1, n2, to, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, to: u8, haystack: &mut [u8]) -> usize {
+        fallback::replace2(n1, n2, to, haystack)
+    }
+
+    if haystack.is_empty() {
+        0
+    } else {
+        imp(from1, from2, to, haystack)
+    }
+}
+
+/// Like `replace`, but replaces every occurrence of any of three bytes.
+///
+/// # Example
+///
+/// ```
+/// use memchr::replace3;
+///
+/// let mut haystack = b"a,b;c\td".to_vec();
+/// assert_eq!(replace3(&mut haystack, b',', b';', b'\t', b'|'), 3);
+/// assert_eq!(haystack, b"a|b|c|d");
+/// ```
+#[inline]
+pub fn replace3(
+    haystack: &mut [u8],
+    from1: u8,
+    from2: u8,
+    from3: u8,
+    to: u8,
+) -> usize {
+    #[cfg(miri)]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, to: u8, haystack: &mut [u8]) -> usize {
+        naive::replace3(n1, n2, n3, to, haystack)
+    }
+
+    #[cfg(all(target_arch = "x86_64", memchr_runtime_simd, not(miri)))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, to: u8, haystack: &mut [u8]) -> usize {
+        x86::replace3(n1, n2, n3, to, haystack)
+    }
+
+    #[cfg(all(
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, to: u8, haystack: &mut [u8]) -> usize {
+        portable::replace3(n1, n2, n3, to, haystack)
+    }
+
+    #[cfg(all(
+        not(all(target_arch = "x86_64", memchr_runtime_simd)),
+        not(memchr_portable_simd),
+        not(miri),
+    ))]
+    #[inline(always)]
+    fn imp(n1: u8, n2: u8, n3: u8, to: u8, haystack: &mut [u8]) -> usize {
+        fallback::replace3(n1, n2, n3, to, haystack)
+    }
+
+    if haystack.is_empty() {
+        0
+    } else {
+        imp(from1, from2, from3, to, haystack)
+    }
+}
@@ -23,3 +23,102 @@ pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
 pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
     haystack.iter().rposition(|&b| b == n1 || b == n2 || b == n3)
 }
+
+pub fn memchr_ascii_nocase(n1: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b.eq_ignore_ascii_case(&n1))
+}
+
+pub fn memrchr_ascii_nocase(n1: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|&b| b.eq_ignore_ascii_case(&n1))
+}
+
+pub fn memchr_not(n1: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b != n1)
+}
+
+pub fn memchr2_not(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b != n1 && b != n2)
+}
+
+pub fn memchr3_not(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().position(|&b| b != n1 && b != n2 && b != n3)
+}
+
+pub fn memrchr_not(n1: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|&b| b != n1)
+}
+
+pub fn memrchr2_not(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|&b| b != n1 && b != n2)
+}
+
+pub fn memrchr3_not(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().rposition(|&b| b != n1 && b != n2 && b != n3)
+}
+
+pub fn memchr_count(n1: u8, haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&b| b == n1).count()
+}
+
+pub fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&b| b == n1 || b == n2).count()
+}
+
+pub fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    haystack.iter().filter(|&&b| b == n1 || b == n2 || b == n3).count()
+}
+
+pub fn memchr_nth(n: usize, n1: u8, haystack: &[u8]) -> Option<usize> {
+    haystack.iter().enumerate().filter(|&(_, &b)| b == n1).nth(n).map(|p| p.0)
+}
+
+pub fn memchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    memchr2(n1, n2, haystack).map(|i| (i, haystack[i]))
+}
+
+pub fn memchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    memchr3(n1, n2, n3, haystack).map(|i| (i, haystack[i]))
+}
+
+pub fn memrchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    memrchr2(n1, n2, haystack).map(|i| (i, haystack[i]))
+}
+
+pub fn memrchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    memrchr3(n1, n2, n3, haystack).map(|i| (i, haystack[i]))
+}
+
+pub fn replace(n1: u8, to: u8, haystack: &mut [u8]) -> usize {
+    replace_matches(haystack, to, |b| b == n1)
+}
+
+pub fn replace2(n1: u8, n2: u8, to: u8, haystack: &mut [u8]) -> usize {
+    replace_matches(haystack, to, |b| b == n1 || b == n2)
+}
+
+pub fn replace3(n1: u8, n2: u8, n3: u8, to: u8, haystack: &mut [u8]) -> usize {
+    replace_matches(haystack, to, |b| b == n1 || b == n2 || b == n3)
+}
+
+fn replace_matches<F: Fn(u8) -> bool>(
+    haystack: &mut [u8],
+    to: u8,
+    matches: F,
+) -> usize {
+    let mut count = 0;
+    for b in haystack.iter_mut().filter(|b| matches(**b)) {
+        *b = to;
+        count += 1;
+    }
+    count
+}
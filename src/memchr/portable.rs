@@ -0,0 +1,179 @@
+// This module provides vectorized implementations of memchr and friends using
+// the portable SIMD API found in core::simd. It is only available with the
+// 'nightly' feature enabled, since core::simd is not yet stable.
+//
+// The routines themselves are implemented generically over the Vector trait,
+// which u8x16 implements. See src/memchr/genericsimd.rs.
+//
+// This is used on targets that don't have a hand written SIMD implementation
+// (which is currently everything except x86_64). core::simd lowers to
+// whatever vector instructions the target has, e.g., NEON on aarch64 or
+// simd128 on wasm32. On targets without any vector instructions at all, it
+// gets lowered to scalar code, which may be slower than the fallback
+// implementation.
+
+use core::simd::u8x16;
+
+use crate::memchr::genericsimd;
+
+/// Return the first index matching the byte `n1` in `haystack`.
+pub fn memchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr::<u8x16>(n1, haystack) }
+}
+
+/// Like `memchr`, but searches for two bytes instead of one.
+pub fn memchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr2::<u8x16>(n1, n2, haystack) }
+}
+
+/// Like `memchr`, but searches for three bytes instead of one.
+pub fn memchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr3::<u8x16>(n1, n2, n3, haystack) }
+}
+
+/// Return the last index matching the byte `n1` in `haystack`.
+pub fn memrchr(n1: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr::<u8x16>(n1, haystack) }
+}
+
+/// Like `memrchr`, but searches for two bytes instead of one.
+pub fn memrchr2(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr2::<u8x16>(n1, n2, haystack) }
+}
+
+/// Like `memrchr`, but searches for three bytes instead of one.
+pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr3::<u8x16>(n1, n2, n3, haystack) }
+}
+
+/// Like `memchr`, but ignores ASCII case. `n1` must be a lowercase ASCII
+/// letter.
+pub fn memchr_ascii_nocase(n1: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr_ascii_nocase::<u8x16>(n1, haystack) }
+}
+
+/// Like `memrchr`, but ignores ASCII case. `n1` must be a lowercase ASCII
+/// letter.
+pub fn memrchr_ascii_nocase(n1: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr_ascii_nocase::<u8x16>(n1, haystack) }
+}
+
+/// Return the first index of a byte in `haystack` that isn't `n1`.
+pub fn memchr_not(n1: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr_not::<u8x16>(n1, haystack) }
+}
+
+/// Like `memchr_not`, but skips over two bytes instead of one.
+pub fn memchr2_not(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr2_not::<u8x16>(n1, n2, haystack) }
+}
+
+/// Like `memchr_not`, but skips over three bytes instead of one.
+pub fn memchr3_not(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr3_not::<u8x16>(n1, n2, n3, haystack) }
+}
+
+/// Return the last index of a byte in `haystack` that isn't `n1`.
+pub fn memrchr_not(n1: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr_not::<u8x16>(n1, haystack) }
+}
+
+/// Like `memrchr_not`, but skips over two bytes instead of one.
+pub fn memrchr2_not(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr2_not::<u8x16>(n1, n2, haystack) }
+}
+
+/// Like `memrchr_not`, but skips over three bytes instead of one.
+pub fn memrchr3_not(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr3_not::<u8x16>(n1, n2, n3, haystack) }
+}
+
+/// Like `memchr2`, but also returns the needle that matched.
+pub fn memchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr2_which::<u8x16>(n1, n2, haystack) }
+}
+
+/// Like `memchr3`, but also returns the needle that matched.
+pub fn memchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr3_which::<u8x16>(n1, n2, n3, haystack) }
+}
+
+/// Like `memrchr2`, but also returns the needle that matched.
+pub fn memrchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr2_which::<u8x16>(n1, n2, haystack) }
+}
+
+/// Like `memrchr3`, but also returns the needle that matched.
+pub fn memrchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memrchr3_which::<u8x16>(n1, n2, n3, haystack) }
+}
+
+/// Return the number of bytes in `haystack` equal to `n1`.
+pub fn memchr_count(n1: u8, haystack: &[u8]) -> usize {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr_count::<u8x16>(n1, haystack) }
+}
+
+/// Like `memchr_count`, but counts two bytes instead of one.
+pub fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr2_count::<u8x16>(n1, n2, haystack) }
+}
+
+/// Like `memchr_count`, but counts three bytes instead of one.
+pub fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr3_count::<u8x16>(n1, n2, n3, haystack) }
+}
+
+/// Return the index of the `n`th occurrence of `n1` in `haystack`.
+pub fn memchr_nth(n: usize, n1: u8, haystack: &[u8]) -> Option<usize> {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::memchr_nth::<u8x16>(n, n1, haystack) }
+}
+
+/// Replace every occurrence of `n1` in `haystack` with `to`.
+pub fn replace(n1: u8, to: u8, haystack: &mut [u8]) -> usize {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::replace::<u8x16>(n1, to, haystack) }
+}
+
+/// Like `replace`, but replaces two bytes instead of one.
+pub fn replace2(n1: u8, n2: u8, to: u8, haystack: &mut [u8]) -> usize {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::replace2::<u8x16>(n1, n2, to, haystack) }
+}
+
+/// Like `replace`, but replaces three bytes instead of one.
+pub fn replace3(n1: u8, n2: u8, n3: u8, to: u8, haystack: &mut [u8]) -> usize {
+    // SAFETY: core::simd doesn't require any target features to be enabled.
+    unsafe { genericsimd::replace3::<u8x16>(n1, n2, n3, to, haystack) }
+}
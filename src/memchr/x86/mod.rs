@@ -1,10 +1,198 @@
-use super::fallback;
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+use core::{
+    arch::x86_64::{__cpuid, __cpuid_count, _xgetbv},
+    ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicU8, Ordering},
+};
 
-// We only use AVX when we can detect at runtime whether it's available, which
-// requires std.
-#[cfg(feature = "std")]
-mod avx;
-mod sse2;
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+use super::{fallback, naive};
+
+pub(crate) mod avx;
+// When AVX2 is enabled at compile time, only the SSE2 routines used by the AVX
+// routines for short haystacks are used.
+#[cfg_attr(all(memchr_runtime_avx, target_feature = "avx2"), allow(dead_code))]
+pub(crate) mod sse2;
+
+/// Returns true if and only if the AVX2 routines in this module can be called
+/// in the current environment.
+#[inline(always)]
+pub(crate) fn is_avx2_available() -> bool {
+    #[cfg(all(memchr_runtime_avx, target_feature = "avx2"))]
+    {
+        true
+    }
+    #[cfg(all(
+        feature = "std",
+        not(all(memchr_runtime_avx, target_feature = "avx2"))
+    ))]
+    {
+        cfg!(memchr_runtime_avx) && is_x86_feature_detected!("avx2")
+    }
+    #[cfg(all(
+        not(feature = "std"),
+        not(all(memchr_runtime_avx, target_feature = "avx2"))
+    ))]
+    {
+        cfg!(memchr_runtime_avx) && is_avx2_detected_by_cpuid()
+    }
+}
+
+/// Returns true if and only if the current CPU supports AVX2 and the OS has
+/// enabled it, as reported by the cpuid instruction. The answer is computed
+/// on the first call and cached for subsequent calls.
+///
+/// This is what we use to detect AVX2 when std isn't available, since
+/// is_x86_feature_detected! requires std.
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+#[cfg_attr(feature = "std", allow(dead_code))]
+fn is_avx2_detected_by_cpuid() -> bool {
+    // 0 means we haven't checked yet, 1 means no AVX2 and 2 means AVX2.
+    static AVX2: AtomicU8 = AtomicU8::new(0);
+
+    match AVX2.load(Ordering::Relaxed) {
+        1 => false,
+        2 => true,
+        _ => {
+            // SAFETY: The cpuid instruction is available on all x86_64 CPUs.
+            let yes = unsafe { cpuid_has_avx2() };
+            AVX2.store(if yes { 2 } else { 1 }, Ordering::Relaxed);
+            yes
+        }
+    }
+}
+
+/// Uses cpuid (and xgetbv) to determine whether AVX2 can be used. This
+/// roughly follows what std does for is_x86_feature_detected!("avx2").
+///
+/// # Safety
+///
+/// Callers must ensure that the cpuid instruction is available, which is
+/// always the case on x86_64.
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+#[cfg_attr(feature = "std", allow(dead_code))]
+unsafe fn cpuid_has_avx2() -> bool {
+    // AVX2 is reported in leaf 7, so make sure that it exists.
+    if __cpuid(0).eax < 7 {
+        return false;
+    }
+    // The CPU has to support AVX, and the OS has to have enabled xgetbv
+    // (OSXSAVE) so that we can ask it whether it saves the AVX registers.
+    let ecx = __cpuid(1).ecx;
+    let (osxsave, avx) = (1 << 27, 1 << 28);
+    if ecx & osxsave == 0 || ecx & avx == 0 {
+        return false;
+    }
+    // The OS has to save and restore both the XMM and YMM registers on
+    // context switches. Otherwise, using AVX2 could corrupt them.
+    let (xmm, ymm) = (1 << 1, 1 << 2);
+    if xcr0() & (xmm | ymm) != xmm | ymm {
+        return false;
+    }
+    let avx2 = 1 << 5;
+    __cpuid_count(7, 0).ebx & avx2 != 0
+}
+
+/// Returns the value of the XCR0 register.
+///
+/// # Safety
+///
+/// Callers must ensure that the CPU supports the xsave feature and that the
+/// OS has enabled it, which is indicated by the OSXSAVE cpuid bit.
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+#[cfg_attr(feature = "std", allow(dead_code))]
+#[target_feature(enable = "xsave")]
+unsafe fn xcr0() -> u64 {
+    _xgetbv(0)
+}
+
+/// Returns true if and only if the ifunc dispatch below selects a routine at
+/// runtime, and can therefore be made to select a different one when a
+/// backend is forced via `crate::arch::force`.
+pub(crate) fn is_runtime_dispatch() -> bool {
+    cfg!(not(all(memchr_runtime_avx, target_feature = "avx2")))
+}
+
+/// A registered ifunc function pointer.
+///
+/// Each use of the ifunc macro below registers its function pointer here the
+/// first time it picks a routine. This permits `reset_ifuncs` to put every
+/// function pointer back to its detection routine when a backend is forced,
+/// so that the forced backend takes effect on the next call. Registration
+/// happens outside of the hot path, so this doesn't cost anything once a
+/// routine has been picked.
+///
+/// The ifuncs used by `ByteSet` are registered here too.
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+pub(crate) struct Ifunc {
+    /// The function pointer that is called by the ifunc.
+    fun: &'static AtomicPtr<()>,
+    /// The routine that picks which function to use.
+    detect: AtomicPtr<()>,
+    /// The next registered ifunc, or null if this is the last one.
+    next: AtomicPtr<Ifunc>,
+    /// Whether this ifunc has been added to the list yet.
+    registered: AtomicBool,
+}
+
+/// The head of the list of registered ifuncs.
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+static IFUNCS: AtomicPtr<Ifunc> = AtomicPtr::new(ptr::null_mut());
+
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+impl Ifunc {
+    pub(crate) const fn new(
+        fun: &'static AtomicPtr<()>,
+        detect: *mut (),
+    ) -> Ifunc {
+        Ifunc {
+            fun,
+            detect: AtomicPtr::new(detect),
+            next: AtomicPtr::new(ptr::null_mut()),
+            registered: AtomicBool::new(false),
+        }
+    }
+
+    /// Add this ifunc to the list of registered ifuncs, if it hasn't been
+    /// added already.
+    pub(crate) fn register(&'static self) {
+        if self.registered.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let this = self as *const Ifunc as *mut Ifunc;
+        let mut head = IFUNCS.load(Ordering::SeqCst);
+        loop {
+            self.next.store(head, Ordering::SeqCst);
+            match IFUNCS.compare_exchange_weak(
+                head,
+                this,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return,
+                Err(actual) => head = actual,
+            }
+        }
+    }
+}
+
+/// Reset every registered ifunc such that the next call to it will pick a
+/// routine again.
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
+pub(crate) fn reset_ifuncs() {
+    let mut cur = IFUNCS.load(Ordering::SeqCst);
+    // SAFETY: Every pointer in the list is derived from a reference to an
+    // Ifunc that lives in a static, and is therefore always valid.
+    while let Some(ifunc) = unsafe { cur.as_ref() } {
+        ifunc.fun.store(ifunc.detect.load(Ordering::SeqCst), Ordering::SeqCst);
+        cur = ifunc.next.load(Ordering::SeqCst);
+    }
+}
+
+/// When the ifunc dispatch isn't used, there are no function pointers to
+/// reset.
+#[cfg(all(memchr_runtime_avx, target_feature = "avx2"))]
+pub(crate) fn reset_ifuncs() {}
 
 /// This macro employs a gcc-like "ifunc" trick where by upon first calling
 /// `memchr` (for example), CPU feature detection will be performed at runtime
@@ -27,33 +215,68 @@ mod sse2;
 /// which probably can't be inlined anyway---unless you've compiled your
 /// entire program with AVX2 enabled. However, even then, the various memchr
 /// implementations aren't exactly small, so inlining might not help anyway!
+/// (When AVX2 is enabled at compile time, a different definition of this
+/// macro is used that calls the AVX2 routines directly. See below.)
 ///
 /// # Safety
 ///
 /// Callers must ensure that fnty is function pointer type.
-#[cfg(feature = "std")]
+///
+/// By default, the haystack is assumed to be a `&[u8]`, every argument
+/// before it is assumed to be a needle byte and the routine is assumed to
+/// return an `Option<usize>`. Routines with other signatures can spell out
+/// the types of their arguments and the return type, e.g.,
+/// `haystack: &[u8], n: usize, n1: u8 => usize`.
+#[cfg(not(all(memchr_runtime_avx, target_feature = "avx2")))]
 macro_rules! unsafe_ifunc {
     ($fnty:ty, $name:ident, $haystack:ident, $($needle:ident),+) => {{
-        use std::{mem, sync::atomic::{AtomicPtr, Ordering}};
+        unsafe_ifunc!(
+            $fnty,
+            $name,
+            $haystack: &[u8],
+            $($needle: u8),+ => Option<usize>
+        )
+    }};
+    (
+        $fnty:ty, $name:ident, $haystack:ident: $hty:ty,
+        $($needle:ident: $nty:ty),+ => $ret:ty
+    ) => {{
+        use core::mem;
 
         type FnRaw = *mut ();
 
         static FN: AtomicPtr<()> = AtomicPtr::new(detect as FnRaw);
 
-        fn detect($($needle: u8),+, haystack: &[u8]) -> Option<usize> {
-            let fun =
-                if cfg!(memchr_runtime_avx) && is_x86_feature_detected!("avx2") {
-                    avx::$name as FnRaw
-                } else if cfg!(memchr_runtime_sse2) {
-                    sse2::$name as FnRaw
-                } else {
-                    fallback::$name as FnRaw
-                };
-            FN.store(fun as FnRaw, Ordering::Relaxed);
+        static IFUNC: Ifunc = Ifunc::new(&FN, detect as FnRaw);
+
+        fn detect($($needle: $nty),+, haystack: $hty) -> $ret {
+            use crate::arch::{self, Backend};
+
+            // A forced backend is only ever one that is available in the
+            // current environment. See crate::arch::force.
+            let forced = arch::forced();
+            let fun = match forced {
+                Some(Backend::Avx2) => avx::$name as FnRaw,
+                Some(Backend::Sse2) => sse2::$name as FnRaw,
+                Some(Backend::Fallback) => fallback::$name as FnRaw,
+                Some(Backend::Naive) => naive::$name as FnRaw,
+                _ if is_avx2_available() => avx::$name as FnRaw,
+                _ if cfg!(memchr_runtime_sse2) => sse2::$name as FnRaw,
+                _ => fallback::$name as FnRaw,
+            };
+            FN.store(fun as FnRaw, Ordering::SeqCst);
+            IFUNC.register();
+            // If a different backend was forced while we were busy picking
+            // one, then we might have missed the reset. So make sure the next
+            // call detects again.
+            if arch::forced() != forced {
+                FN.store(detect as FnRaw, Ordering::SeqCst);
+            }
             // SAFETY: By virtue of the caller contract, $fnty is a function
             // pointer, which is always safe to transmute with a *mut ().
             // Also, if 'fun is the AVX routine, then it is guaranteed to be
-            // supported since we checked the avx2 feature.
+            // supported since we checked the avx2 feature. (Either above, or
+            // when the AVX2 backend was forced.)
             unsafe {
                 mem::transmute::<FnRaw, $fnty>(fun)($($needle),+, haystack)
             }
@@ -70,24 +293,24 @@ macro_rules! unsafe_ifunc {
     }}
 }
 
-/// When std isn't available to provide runtime CPU feature detection, or if
-/// runtime CPU feature detection has been explicitly disabled, then just
-/// call our optimized SSE2 routine directly. SSE2 is avalbale on all x86_64
-/// targets, so no CPU feature detection is necessary.
+/// When AVX2 is enabled at compile time (e.g., with `-C target-cpu=native` on
+/// a CPU that supports it), then every CPU that this binary runs on must
+/// support AVX2. So we skip the function pointer entirely and call our AVX2
+/// routines directly, which also permits them to be inlined into the caller.
+/// This works with or without std.
 ///
 /// # Safety
 ///
 /// There are no safety requirements for this definition of the macro. It is
-/// safe for all inputs since it is restricted to either the fallback routine
-/// or the SSE routine, which is always safe to call on x86_64.
-#[cfg(not(feature = "std"))]
+/// safe for all inputs since the AVX2 routines only require the avx2 target
+/// feature, which is enabled at compile time.
+#[cfg(all(memchr_runtime_avx, target_feature = "avx2"))]
 macro_rules! unsafe_ifunc {
-    ($fnty:ty, $name:ident, $haystack:ident, $($needle:ident),+) => {{
-        if cfg!(memchr_runtime_sse2) {
-            unsafe { sse2::$name($($needle),+, $haystack) }
-        } else {
-            fallback::$name($($needle),+, $haystack)
-        }
+    (
+        $fnty:ty, $name:ident, $haystack:ident $(: $hty:ty)?,
+        $($needle:ident $(: $nty:ty)?),+ $(=> $ret:ty)?
+    ) => {{
+        unsafe { avx::$name($($needle),+, $haystack) }
     }}
 }
 
@@ -146,3 +369,237 @@ pub fn memrchr3(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
         n3
     )
 }
+
+#[inline(always)]
+pub fn memchr_ascii_nocase(n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(
+        fn(u8, &[u8]) -> Option<usize>,
+        memchr_ascii_nocase,
+        haystack,
+        n1
+    )
+}
+
+#[inline(always)]
+pub fn memrchr_ascii_nocase(n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(
+        fn(u8, &[u8]) -> Option<usize>,
+        memrchr_ascii_nocase,
+        haystack,
+        n1
+    )
+}
+
+#[inline(always)]
+pub fn memchr_not(n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(fn(u8, &[u8]) -> Option<usize>, memchr_not, haystack, n1)
+}
+
+#[inline(always)]
+pub fn memchr2_not(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(
+        fn(u8, u8, &[u8]) -> Option<usize>,
+        memchr2_not,
+        haystack,
+        n1,
+        n2
+    )
+}
+
+#[inline(always)]
+pub fn memchr3_not(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(
+        fn(u8, u8, u8, &[u8]) -> Option<usize>,
+        memchr3_not,
+        haystack,
+        n1,
+        n2,
+        n3
+    )
+}
+
+#[inline(always)]
+pub fn memrchr_not(n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(fn(u8, &[u8]) -> Option<usize>, memrchr_not, haystack, n1)
+}
+
+#[inline(always)]
+pub fn memrchr2_not(n1: u8, n2: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(
+        fn(u8, u8, &[u8]) -> Option<usize>,
+        memrchr2_not,
+        haystack,
+        n1,
+        n2
+    )
+}
+
+#[inline(always)]
+pub fn memrchr3_not(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(
+        fn(u8, u8, u8, &[u8]) -> Option<usize>,
+        memrchr3_not,
+        haystack,
+        n1,
+        n2,
+        n3
+    )
+}
+
+#[inline(always)]
+pub fn memchr_count(n1: u8, haystack: &[u8]) -> usize {
+    unsafe_ifunc!(
+        fn(u8, &[u8]) -> usize,
+        memchr_count,
+        haystack: &[u8],
+        n1: u8 => usize
+    )
+}
+
+#[inline(always)]
+pub fn memchr2_count(n1: u8, n2: u8, haystack: &[u8]) -> usize {
+    unsafe_ifunc!(
+        fn(u8, u8, &[u8]) -> usize,
+        memchr2_count,
+        haystack: &[u8],
+        n1: u8,
+        n2: u8 => usize
+    )
+}
+
+#[inline(always)]
+pub fn memchr3_count(n1: u8, n2: u8, n3: u8, haystack: &[u8]) -> usize {
+    unsafe_ifunc!(
+        fn(u8, u8, u8, &[u8]) -> usize,
+        memchr3_count,
+        haystack: &[u8],
+        n1: u8,
+        n2: u8,
+        n3: u8 => usize
+    )
+}
+
+#[inline(always)]
+pub fn memchr_nth(n: usize, n1: u8, haystack: &[u8]) -> Option<usize> {
+    unsafe_ifunc!(
+        fn(usize, u8, &[u8]) -> Option<usize>,
+        memchr_nth,
+        haystack: &[u8],
+        n: usize,
+        n1: u8 => Option<usize>
+    )
+}
+
+#[inline(always)]
+pub fn memchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    unsafe_ifunc!(
+        fn(u8, u8, &[u8]) -> Option<(usize, u8)>,
+        memchr2_which,
+        haystack: &[u8],
+        n1: u8,
+        n2: u8 => Option<(usize, u8)>
+    )
+}
+
+#[inline(always)]
+pub fn memchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    unsafe_ifunc!(
+        fn(u8, u8, u8, &[u8]) -> Option<(usize, u8)>,
+        memchr3_which,
+        haystack: &[u8],
+        n1: u8,
+        n2: u8,
+        n3: u8 => Option<(usize, u8)>
+    )
+}
+
+#[inline(always)]
+pub fn memrchr2_which(n1: u8, n2: u8, haystack: &[u8]) -> Option<(usize, u8)> {
+    unsafe_ifunc!(
+        fn(u8, u8, &[u8]) -> Option<(usize, u8)>,
+        memrchr2_which,
+        haystack: &[u8],
+        n1: u8,
+        n2: u8 => Option<(usize, u8)>
+    )
+}
+
+#[inline(always)]
+pub fn memrchr3_which(
+    n1: u8,
+    n2: u8,
+    n3: u8,
+    haystack: &[u8],
+) -> Option<(usize, u8)> {
+    unsafe_ifunc!(
+        fn(u8, u8, u8, &[u8]) -> Option<(usize, u8)>,
+        memrchr3_which,
+        haystack: &[u8],
+        n1: u8,
+        n2: u8,
+        n3: u8 => Option<(usize, u8)>
+    )
+}
+
+#[inline(always)]
+pub fn replace(n1: u8, to: u8, haystack: &mut [u8]) -> usize {
+    unsafe_ifunc!(
+        fn(u8, u8, &mut [u8]) -> usize,
+        replace,
+        haystack: &mut [u8],
+        n1: u8,
+        to: u8 => usize
+    )
+}
+
+#[inline(always)]
+pub fn replace2(n1: u8, n2: u8, to: u8, haystack: &mut [u8]) -> usize {
+    unsafe_ifunc!(
+        fn(u8, u8, u8, &mut [u8]) -> usize,
+        replace2,
+        haystack: &mut [u8],
+        n1: u8,
+        n2: u8,
+        to: u8 => usize
+    )
+}
+
+#[inline(always)]
+pub fn replace3(n1: u8, n2: u8, n3: u8, to: u8, haystack: &mut [u8]) -> usize {
+    unsafe_ifunc!(
+        fn(u8, u8, u8, u8, &mut [u8]) -> usize,
+        replace3,
+        haystack: &mut [u8],
+        n1: u8,
+        n2: u8,
+        n3: u8,
+        to: u8 => usize
+    )
+}
+
+#[cfg(all(
+    test,
+    feature = "std",
+    not(all(memchr_runtime_avx, target_feature = "avx2"))
+))]
+mod tests {
+    // Our own AVX2 detection is only used without std, but we can still check
+    // it against std's detection whenever std is available.
+    #[test]
+    fn cpuid_avx2_matches_std() {
+        assert_eq!(
+            is_x86_feature_detected!("avx2"),
+            super::is_avx2_detected_by_cpuid(),
+        );
+        // And once more to exercise the cached answer.
+        assert_eq!(
+            is_x86_feature_detected!("avx2"),
+            super::is_avx2_detected_by_cpuid(),
+        );
+    }
+}
@@ -0,0 +1,637 @@
+/*!
+This module implements substring search for UTF-8 needles under Unicode
+simple case folding.
+
+Under simple case folding, every codepoint belongs to an equivalence class of
+codepoints that fold to the same codepoint. For example, `k`, `K` and `K`
+(KELVIN SIGN) form one class, as do `Ǆ`, `ǅ` and `ǆ`. A needle matches at a
+position in the haystack if the haystack, decoded as UTF-8 from that
+position, contains a sequence of codepoints each of which is in the same
+class as the corresponding codepoint in the needle.
+
+Since members of the same class can have UTF-8 encodings of different
+lengths, the length of a match may differ from the length of the needle.
+
+The search works by picking one codepoint in the needle as an anchor and
+looking for candidate positions of the anchor in the haystack. At each
+candidate, the parts of the needle before and after the anchor are then
+matched in reverse and forward, respectively. The anchor is either
+
+* a run of caseless codepoints (which only ever match themselves), in which
+  case candidates are found with a `Finder` for the run, or
+* the codepoint in the needle whose case variants have the rarest leading
+  bytes in their UTF-8 encodings, in which case candidates are found with
+  `memchr`, `memchr2`, `memchr3` or `memchr_ascii_nocase` on those leading
+  bytes.
+
+Because a leading byte of a UTF-8 encoding never appears in the middle of
+another encoding, every candidate found this way is at a codepoint boundary
+in a valid UTF-8 haystack, and candidates are visited in the same order as
+the starting positions of the matches they correspond to. Thus, the first
+match found is always the leftmost one.
+*/
+
+use core::{cmp, str};
+
+use crate::{
+    cow::CowBytes,
+    memmem::{
+        byte_frequencies::BYTE_FREQUENCIES,
+        case_folding_simple::CASE_FOLDING_SIMPLE, Finder, FinderBuilder,
+    },
+};
+
+/// The minimum length, in bytes, of a run of caseless codepoints in the
+/// needle for it to be used as the anchor instead of a single codepoint.
+///
+/// Shorter runs are typically spaces or punctuation, for which the leading
+/// bytes of a rare cased codepoint usually make a better prefilter.
+const MIN_LITERAL_LEN: usize = 4;
+
+/// A single substring searcher fixed to a particular needle, which matches
+/// under Unicode simple case folding.
+///
+/// This is like [`Finder`], except that each codepoint in the needle matches
+/// any codepoint in the haystack that is equivalent to it under Unicode simple
+/// case folding. For example, `"ǅ"` matches `"Ǆ"`, `"ǅ"` and `"ǆ"`, and
+/// `"kelvin"` matches `"KELVIN"` and `"\u{212A}elvin"` (where `U+212A` is
+/// KELVIN SIGN).
+///
+/// The needle must be valid UTF-8, but the haystack may be arbitrary bytes.
+/// Invalid UTF-8 in the haystack never matches any part of the needle.
+///
+/// Simple case folding only ever maps a single codepoint to a single
+/// codepoint. In particular, this means that `"STRASSE"` does not match
+/// `"straße"`, since that requires full case folding, which maps `ß` to
+/// `ss`. (`ß` does match `ẞ` though.)
+///
+/// Since case variants of a codepoint may have UTF-8 encodings of different
+/// lengths, a match may have a different length than the needle. So unlike
+/// `Finder`, searches report both the start and end offsets of a match.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::CaseFoldFinder;
+///
+/// let finder = CaseFoldFinder::new("ǆemal");
+/// assert_eq!(Some((4, 10)), finder.find("the ǅemal".as_bytes()));
+///
+/// // U+212A KELVIN SIGN is encoded with 3 bytes.
+/// let finder = CaseFoldFinder::new("kelvin");
+/// assert_eq!(Some((2, 10)), finder.find("0 \u{212A}ELVIN".as_bytes()));
+/// ```
+#[derive(Clone, Debug)]
+pub struct CaseFoldFinder<'n> {
+    needle: CowBytes<'n>,
+    /// The offset in the needle at which the anchor starts. Candidates
+    /// reported by the anchor's searcher are aligned with this offset.
+    anchor: usize,
+    searcher: Anchor<'n>,
+}
+
+/// The searcher used to find candidate positions of the anchor.
+#[derive(Clone, Debug)]
+enum Anchor<'n> {
+    /// The needle is empty, and so it matches at every position.
+    Empty,
+    /// The anchor is a run of caseless codepoints, which is searched for
+    /// with a substring searcher.
+    Literal(Finder<'n>),
+    /// The anchor is a single codepoint, and candidates are found by
+    /// searching for the leading bytes of each of its case variants.
+    Lead(LeadBytes),
+}
+
+impl<'n> CaseFoldFinder<'n> {
+    /// Create a new finder for the given needle.
+    #[inline]
+    pub fn new<B: ?Sized + AsRef<str>>(needle: &'n B) -> CaseFoldFinder<'n> {
+        FinderBuilder::new().build_case_fold(needle)
+    }
+
+    /// Create a new finder for the given needle, using the configuration in
+    /// the given builder to construct any substring searchers.
+    pub(crate) fn with_builder(
+        builder: &FinderBuilder,
+        needle: &'n str,
+    ) -> CaseFoldFinder<'n> {
+        // The literal anchor is caseless, so it never needs to ignore case.
+        let mut builder = builder.clone();
+        builder.ascii_case_insensitive(false);
+
+        let bytes = needle.as_bytes();
+        let (anchor, searcher) = if needle.is_empty() {
+            (0, Anchor::Empty)
+        } else {
+            let (start, end) = longest_caseless_run(needle);
+            if end - start == needle.len() || end - start >= MIN_LITERAL_LEN {
+                let finder = builder.build_forward(&bytes[start..end]);
+                (start, Anchor::Literal(finder))
+            } else {
+                let (at, lead) = needle
+                    .char_indices()
+                    .map(|(at, c)| (at, LeadBytes::new(c)))
+                    .min_by_key(|&(_, lead)| lead.rank())
+                    .unwrap();
+                (at, Anchor::Lead(lead))
+            }
+        };
+        CaseFoldFinder { needle: CowBytes::new(bytes), anchor, searcher }
+    }
+
+    /// Returns the start and end offsets of the first occurrence of this
+    /// finder's needle in the given haystack, or `None` if no such
+    /// occurrence exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::CaseFoldFinder;
+    ///
+    /// let finder = CaseFoldFinder::new("σίσυφος");
+    /// let haystack = "ο ΣΊΣΥΦΟΣ".as_bytes();
+    /// assert_eq!(Some((3, haystack.len())), finder.find(haystack));
+    /// ```
+    pub fn find(&self, haystack: &[u8]) -> Option<(usize, usize)> {
+        let needle = self.needle.as_slice();
+        let (before, after) = needle.split_at(self.anchor);
+        let mut at = 0;
+        loop {
+            let pos = at + self.find_candidate(&haystack[at..])?;
+            if let Some(start) = match_reverse(before, &haystack[..pos]) {
+                if let Some(len) = match_forward(after, &haystack[pos..]) {
+                    return Some((start, pos + len));
+                }
+            }
+            at = pos + 1;
+        }
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// finder's needle in the given haystack. Each occurrence is reported
+    /// as its start and end offsets.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::CaseFoldFinder;
+    ///
+    /// let finder = CaseFoldFinder::new("ǆ");
+    /// let haystack = "Ǆ ǅ ǆ".as_bytes();
+    /// let got: Vec<(usize, usize)> = finder.find_iter(haystack).collect();
+    /// assert_eq!(vec![(0, 2), (3, 5), (6, 8)], got);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> CaseFoldFindIter<'h, 'a> {
+        CaseFoldFindIter { haystack, finder: self.as_ref(), pos: 0 }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle.
+    ///
+    /// If this is already an owned finder, then this is a no-op. Otherwise,
+    /// this copies the needle.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn into_owned(self) -> CaseFoldFinder<'static> {
+        let searcher = match self.searcher {
+            Anchor::Empty => Anchor::Empty,
+            Anchor::Literal(finder) => Anchor::Literal(finder.into_owned()),
+            Anchor::Lead(lead) => Anchor::Lead(lead),
+        };
+        CaseFoldFinder {
+            needle: self.needle.into_owned(),
+            anchor: self.anchor,
+            searcher,
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    ///
+    /// This is primarily useful if your finder is owned and you'd like to
+    /// store its borrowed variant in some intermediate data structure.
+    #[inline]
+    pub fn as_ref(&self) -> CaseFoldFinder<'_> {
+        let searcher = match self.searcher {
+            Anchor::Empty => Anchor::Empty,
+            Anchor::Literal(ref finder) => Anchor::Literal(finder.as_ref()),
+            Anchor::Lead(lead) => Anchor::Lead(lead),
+        };
+        CaseFoldFinder {
+            needle: CowBytes::new(self.needle.as_slice()),
+            anchor: self.anchor,
+            searcher,
+        }
+    }
+
+    /// Returns the needle that this finder searches for.
+    ///
+    /// Note that the lifetime of the needle returned is tied to the lifetime
+    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
+    /// finder's needle can be either borrowed or owned, so the lifetime of the
+    /// needle returned must necessarily be the shorter of the two.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+
+    /// Returns the position of the next candidate for the anchor in the
+    /// given haystack, aligned with the start of the anchor.
+    #[inline(always)]
+    fn find_candidate(&self, haystack: &[u8]) -> Option<usize> {
+        match self.searcher {
+            Anchor::Empty => Some(0),
+            Anchor::Literal(ref finder) => finder.find(haystack),
+            Anchor::Lead(ref lead) => lead.find(haystack),
+        }
+    }
+}
+
+/// An iterator over non-overlapping matches of a [`CaseFoldFinder`].
+///
+/// Matches are reported as their start and end offsets.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct CaseFoldFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    finder: CaseFoldFinder<'n>,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for CaseFoldFindIter<'h, 'n> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+        let (start, end) = self.finder.find(&self.haystack[self.pos..])?;
+        let (start, end) = (self.pos + start, self.pos + end);
+        self.pos = if start == end { end + 1 } else { end };
+        Some((start, end))
+    }
+}
+
+/// The distinct leading bytes of the UTF-8 encodings of each case variant of
+/// a codepoint. Under simple case folding, these are never more than three.
+#[derive(Clone, Copy, Debug)]
+struct LeadBytes {
+    bytes: [u8; 3],
+    len: usize,
+}
+
+impl LeadBytes {
+    /// Collect the leading bytes of every case variant of `c`.
+    fn new(c: char) -> LeadBytes {
+        let mut lead = LeadBytes { bytes: [0; 3], len: 0 };
+        lead.add(c);
+        for &v in variants(c) {
+            lead.add(v);
+        }
+        lead
+    }
+
+    /// Add the leading byte of the UTF-8 encoding of `c`, if it isn't
+    /// already present.
+    fn add(&mut self, c: char) {
+        let b = c.encode_utf8(&mut [0; 4]).as_bytes()[0];
+        if !self.bytes[..self.len].contains(&b) {
+            self.bytes[self.len] = b;
+            self.len += 1;
+        }
+    }
+
+    /// Returns a heuristic rank of how often these leading bytes are
+    /// expected to appear in a haystack. Lower is rarer.
+    ///
+    /// This is the rank of the most common byte, plus a penalty for each
+    /// additional byte, since searching for more bytes is a bit slower.
+    fn rank(&self) -> usize {
+        let max = self.bytes[..self.len]
+            .iter()
+            .map(|&b| BYTE_FREQUENCIES[b as usize] as usize)
+            .max()
+            .unwrap_or(0);
+        max + self.len
+    }
+
+    /// Returns the position of the first of these leading bytes in the
+    /// given haystack.
+    #[inline(always)]
+    fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let b = self.bytes;
+        match self.len {
+            1 => crate::memchr(b[0], haystack),
+            // The two cases of an ASCII letter can be found with a single
+            // comparison per vector.
+            2 if b[0].eq_ignore_ascii_case(&b[1]) => {
+                crate::memchr_ascii_nocase(b[0], haystack)
+            }
+            2 => crate::memchr2(b[0], b[1], haystack),
+            _ => crate::memchr3(b[0], b[1], b[2], haystack),
+        }
+    }
+}
+
+/// Match `needle` against the beginning of `haystack` under simple case
+/// folding, and return the length of the matching prefix of `haystack`.
+///
+/// `needle` must be valid UTF-8.
+fn match_forward(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+    let (mut n, mut h) = (0, 0);
+    while n < needle.len() {
+        let (nc, nlen) = decode(&needle[n..])?;
+        let (hc, hlen) = decode(&haystack[h..])?;
+        if !is_equivalent(nc, hc) {
+            return None;
+        }
+        n += nlen;
+        h += hlen;
+    }
+    Some(h)
+}
+
+/// Match `needle` against the end of `haystack` under simple case folding,
+/// and return the starting position of the matching suffix of `haystack`.
+///
+/// `needle` must be valid UTF-8.
+fn match_reverse(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+    let (mut n, mut h) = (needle.len(), haystack.len());
+    while n > 0 {
+        let (nc, nlen) = decode_last(&needle[..n])?;
+        let (hc, hlen) = decode_last(&haystack[..h])?;
+        if !is_equivalent(nc, hc) {
+            return None;
+        }
+        n -= nlen;
+        h -= hlen;
+    }
+    Some(h)
+}
+
+/// Returns true if and only if `c1` and `c2` are equivalent under simple
+/// case folding.
+#[inline(always)]
+fn is_equivalent(c1: char, c2: char) -> bool {
+    c1 == c2 || canonical(c1) == canonical(c2)
+}
+
+/// Returns the smallest codepoint that is equivalent to `c` under simple
+/// case folding.
+///
+/// This isn't necessarily the codepoint that `c` folds to (which is usually
+/// the lowercase variant), but since every codepoint in an equivalence class
+/// has the same smallest member, it serves just as well for comparing
+/// codepoints.
+#[inline(always)]
+fn canonical(c: char) -> char {
+    // For ASCII letters, the uppercase variant is always the smallest member
+    // of the class. Every other ASCII codepoint is caseless.
+    if c.is_ascii() {
+        return c.to_ascii_uppercase();
+    }
+    variants(c).iter().fold(c, |min, &v| cmp::min(min, v))
+}
+
+/// Returns every other codepoint that is equivalent to `c` under simple case
+/// folding. This is empty if `c` is caseless.
+fn variants(c: char) -> &'static [char] {
+    match CASE_FOLDING_SIMPLE.binary_search_by_key(&c, |&(c1, _)| c1) {
+        Ok(i) => CASE_FOLDING_SIMPLE[i].1,
+        Err(_) => &[],
+    }
+}
+
+/// Returns the start and end of the longest run of caseless codepoints in
+/// the given needle. If there are several, the first one is returned.
+fn longest_caseless_run(needle: &str) -> (usize, usize) {
+    let (mut best, mut start) = ((0, 0), 0);
+    for (at, c) in needle.char_indices() {
+        let end = at + c.len_utf8();
+        if !variants(c).is_empty() {
+            start = end;
+        } else if end - start > best.1 - best.0 {
+            best = (start, end);
+        }
+    }
+    best
+}
+
+/// Decode the codepoint at the beginning of `bytes`, along with the length
+/// of its encoding. If `bytes` doesn't begin with a valid UTF-8 encoding of a
+/// codepoint, then this returns `None`.
+#[inline(always)]
+fn decode(bytes: &[u8]) -> Option<(char, usize)> {
+    let b = *bytes.first()?;
+    if b < 0x80 {
+        return Some((b as char, 1));
+    }
+    let len = match b {
+        0xC2..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF4 => 4,
+        _ => return None,
+    };
+    let c = str::from_utf8(bytes.get(..len)?).ok()?.chars().next()?;
+    Some((c, len))
+}
+
+/// Decode the codepoint at the end of `bytes`, along with the length of its
+/// encoding. If `bytes` doesn't end with a valid UTF-8 encoding of a
+/// codepoint, then this returns `None`.
+#[inline(always)]
+fn decode_last(bytes: &[u8]) -> Option<(char, usize)> {
+    let b = *bytes.last()?;
+    if b < 0x80 {
+        return Some((b as char, 1));
+    }
+    // Look for the leading byte, skipping over at most three continuation
+    // bytes.
+    let start = bytes.len().saturating_sub(4);
+    let at = bytes[start..].iter().rposition(|&b| b & 0xC0 != 0x80)?;
+    let (c, len) = decode(&bytes[start + at..])?;
+    if start + at + len == bytes.len() {
+        Some((c, len))
+    } else {
+        None
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    /// The naive reference implementation: try to match the needle at every
+    /// position in the haystack.
+    fn naive_find(needle: &str, haystack: &[u8]) -> Option<(usize, usize)> {
+        (0..=haystack.len()).find_map(|start| {
+            let len = match_forward(needle.as_bytes(), &haystack[start..])?;
+            Some((start, start + len))
+        })
+    }
+
+    /// Map each of the given bytes to a codepoint with interesting case
+    /// folding behavior, so that matches are common.
+    fn interesting(bytes: &[u8]) -> String {
+        const ALPHABET: &[char] = &[
+            'k', 'K', '\u{212A}', 's', 'S', '\u{17F}', '\u{1C4}', '\u{1C5}',
+            '\u{1C6}', '\u{3C3}', '\u{3C2}', '\u{3A3}', '\u{DF}', '\u{1E9E}',
+            '@', '`', 'x', ' ',
+        ];
+        bytes.iter().map(|&b| ALPHABET[b as usize % ALPHABET.len()]).collect()
+    }
+
+    #[test]
+    fn simple_folds() {
+        let find =
+            |n: &str, h: &str| CaseFoldFinder::new(n).find(h.as_bytes());
+
+        assert_eq!(Some((0, 2)), find("Ǆ", "ǆ"));
+        assert_eq!(Some((1, 3)), find("ǆ", "xǅ"));
+        assert_eq!(Some((0, 3)), find("k", "\u{212A}"));
+        assert_eq!(Some((0, 1)), find("\u{212A}", "K"));
+        assert_eq!(Some((0, 6)), find("ΣΑΣ", "σας"));
+        assert_eq!(Some((0, 3)), find("ß", "ẞ"));
+        assert_eq!(Some((0, 7)), find("strasse", "STRASSE"));
+        assert_eq!(Some((0, 1)), find("ſ", "s\u{17F}"));
+        // Full case folding is not supported.
+        assert_eq!(None, find("strasse", "straße"));
+        // ASCII codepoints that differ only in the ASCII case bit, but which
+        // aren't letters, don't match.
+        assert_eq!(None, find("@", "`"));
+        // Caseless needles match exactly.
+        assert_eq!(Some((3, 9)), find("日本", "xx 日本"));
+        assert_eq!(None, find("日本", "日 本"));
+        assert_eq!(Some((0, 0)), find("", "abc"));
+        assert_eq!(Some((0, 0)), find("", ""));
+        assert_eq!(None, find("a", ""));
+    }
+
+    #[test]
+    fn mixed_anchors() {
+        let find =
+            |n: &str, h: &str| CaseFoldFinder::new(n).find(h.as_bytes());
+
+        // A long caseless run in the middle of the needle is used as the
+        // anchor, with cased codepoints matched on either side of it.
+        assert_eq!(Some((2, 14)), find("k 日本 s", "- \u{212A} 日本 S -"));
+        assert_eq!(None, find("k 日本 s", "- \u{212A} 日本 x -"));
+        assert_eq!(None, find("k 日本 s", "- x 日本 s -"));
+        // Codepoints before the anchor may have encodings of a different
+        // length than in the needle.
+        assert_eq!(Some((0, 4)), find("kq", "\u{212A}q"));
+        assert_eq!(Some((0, 5)), find("kkq", "\u{212A}kq"));
+        assert_eq!(Some((3, 5)), find("kq", "\u{212A}kqq"));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let finder = CaseFoldFinder::new("ǆx");
+        assert_eq!(Some((3, 6)), finder.find(b"\xC7\xFF\xC7\xC7\x84X"));
+        assert_eq!(None, finder.find(b"\xC7x\x84x\xC7"));
+        assert_eq!(None, finder.find(b"\x84x"));
+
+        let finder = CaseFoldFinder::new("kǆ");
+        assert_eq!(Some((1, 4)), finder.find(b"\x84k\xC7\x86"));
+        assert_eq!(None, finder.find(b"\xE2\x84\xC7\x86"));
+    }
+
+    #[test]
+    fn find_iter() {
+        let finder = CaseFoldFinder::new("ss");
+        let haystack = "SSſs ss".as_bytes();
+        let got: Vec<(usize, usize)> = finder.find_iter(haystack).collect();
+        assert_eq!(vec![(0, 2), (2, 5), (6, 8)], got);
+
+        let finder = CaseFoldFinder::new("");
+        let got: Vec<(usize, usize)> = finder.find_iter(b"ab").collect();
+        assert_eq!(vec![(0, 0), (1, 1), (2, 2)], got);
+    }
+
+    #[test]
+    fn canonical_matches_table() {
+        // The smallest member of each class, found by brute force.
+        let brute = |c: char| {
+            let mut min = c;
+            for &(c1, vs) in CASE_FOLDING_SIMPLE {
+                if c1 == c || vs.contains(&c) {
+                    min = cmp::min(min, c1);
+                }
+            }
+            min
+        };
+        for &(c, vs) in CASE_FOLDING_SIMPLE {
+            assert_eq!(brute(c), canonical(c), "{:?}", c);
+            for &v in vs {
+                assert_eq!(canonical(c), canonical(v), "{:?} {:?}", c, v);
+            }
+        }
+        for b in 0..0x80u8 {
+            assert_eq!(brute(b as char), canonical(b as char), "{:?}", b);
+        }
+    }
+
+    #[test]
+    fn decode_last_rejects_partial_encodings() {
+        assert_eq!(Some(('ǆ', 2)), decode_last("xǆ".as_bytes()));
+        assert_eq!(None, decode_last(b"\xC7"));
+        assert_eq!(None, decode_last(b"\x86"));
+        assert_eq!(None, decode_last(b"\xE2\x84\xAA\x86"));
+        assert_eq!(None, decode_last(b"\x80\x80\x80\x80"));
+    }
+
+    quickcheck::quickcheck! {
+        fn qc_matches_naive(needle: Vec<u8>, haystack: Vec<u8>) -> bool {
+            let mut needle = interesting(&needle);
+            while needle.chars().count() > 4 {
+                needle.pop();
+            }
+            let haystack = interesting(&haystack);
+            let finder = CaseFoldFinder::new(&needle);
+            let expected = naive_find(&needle, haystack.as_bytes());
+            expected == finder.find(haystack.as_bytes())
+        }
+
+        fn qc_matches_naive_arbitrary_bytes(
+            needle: String,
+            haystack: Vec<u8>
+        ) -> bool {
+            let finder = CaseFoldFinder::new(&needle);
+            naive_find(&needle, &haystack) == finder.find(&haystack)
+        }
+
+        fn qc_finds_planted_variants(
+            prefix: Vec<u8>,
+            needle: Vec<u8>,
+            picks: Vec<usize>
+        ) -> bool {
+            let needle = interesting(&needle);
+            // Replace each codepoint in the needle with one of its variants.
+            let planted: String = needle
+                .chars()
+                .zip(picks.iter().cloned().chain(core::iter::repeat(0)))
+                .map(|(c, pick)| {
+                    let vs = variants(c);
+                    if pick % (vs.len() + 1) == 0 {
+                        c
+                    } else {
+                        vs[pick % (vs.len() + 1) - 1]
+                    }
+                })
+                .collect();
+            let haystack = format!("{}{}", interesting(&prefix), planted);
+            let finder = CaseFoldFinder::new(&needle);
+            let got = finder.find(haystack.as_bytes());
+            assert_eq!(naive_find(&needle, haystack.as_bytes()), got);
+            got.is_some()
+        }
+    }
+}
@@ -1,6 +1,12 @@
 use core::mem::size_of;
 
-use crate::memmem::{util::memcmp, vector::Vector, NeedleInfo};
+use crate::{
+    memmem::{
+        util::{memcmp, memcmp_ascii_nocase},
+        NeedleInfo,
+    },
+    vector::Vector,
+};
 
 /// The minimum length of a needle required for this algorithm. The minimum
 /// is 2 since a length of 1 should just use memchr and a length of 0 isn't
@@ -49,13 +55,20 @@ pub(crate) const MAX_NEEDLE_LEN: usize = 32;
 /// want to guarantee worst case additive time. Some of the benchmarks try to
 /// justify this (see the pathological ones).
 ///
-/// The prefilter variant of this has more comments. Also note that we only
-/// implement this for forward searches for now. If you have a compelling use
-/// case for accelerated reverse search, please file an issue.
+/// The prefilter variant of this has more comments. The reverse variant of
+/// this searcher is `Reverse`.
+///
+/// When the rare bytes were detected for an ASCII case insensitive search,
+/// both the rare bytes and the haystack are folded by setting the 0x20 bit
+/// before they're compared. For ASCII letters, this maps both cases to the
+/// lowercase letter. For other bytes, it may conflate two distinct bytes
+/// (e.g., `@` and `` ` ``), but that only results in a false positive
+/// candidate, which is rejected when the candidate is confirmed.
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct Forward {
     rare1i: u8,
     rare2i: u8,
+    ascii_nocase: bool,
 }
 
 impl Forward {
@@ -73,7 +86,8 @@ impl Forward {
         {
             return None;
         }
-        Some(Forward { rare1i, rare2i })
+        let ascii_nocase = ninfo.rarebytes.is_ascii_nocase();
+        Some(Forward { rare1i, rare2i, ascii_nocase })
     }
 
     /// Returns the minimum length of haystack that is needed for this searcher
@@ -85,6 +99,43 @@ impl Forward {
     }
 }
 
+/// The implementation of the reverse vector accelerated substring search.
+///
+/// This is the same as `Forward`, except it scans the haystack from back to
+/// front and reports the last match. The rare byte offsets it uses should be
+/// the ones detected for reverse searching.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Reverse {
+    rare1i: u8,
+    rare2i: u8,
+    ascii_nocase: bool,
+}
+
+impl Reverse {
+    /// Create a new "generic simd" reverse searcher. If one could not be
+    /// created from the given inputs, then None is returned.
+    pub(crate) fn new(ninfo: &NeedleInfo, needle: &[u8]) -> Option<Reverse> {
+        let (rare1i, rare2i) = ninfo.rarebytes.as_rare_ordered_u8();
+        // See Forward::new for why we give up in these cases.
+        if needle.len() < MIN_NEEDLE_LEN
+            || needle.len() > MAX_NEEDLE_LEN
+            || rare1i == rare2i
+        {
+            return None;
+        }
+        let ascii_nocase = ninfo.rarebytes.is_ascii_nocase();
+        Some(Reverse { rare1i, rare2i, ascii_nocase })
+    }
+
+    /// Returns the minimum length of haystack that is needed for this searcher
+    /// to work for a particular vector. Passing a haystack with a length
+    /// smaller than this will cause `rev_find` to panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len<V: Vector>(&self) -> usize {
+        self.rare2i as usize + size_of::<V>()
+    }
+}
+
 /// Searches the given haystack for the given needle. The needle given should
 /// be the same as the needle that this searcher was initialized with.
 ///
@@ -104,6 +155,22 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
     fwd: &Forward,
     haystack: &[u8],
     needle: &[u8],
+) -> Option<usize> {
+    // We pass a constant for ascii_nocase so that each copy of the search
+    // loop is specialized, and case sensitive searches don't pay for folding.
+    if fwd.ascii_nocase {
+        fwd_find_imp::<V>(fwd, haystack, needle, true)
+    } else {
+        fwd_find_imp::<V>(fwd, haystack, needle, false)
+    }
+}
+
+#[inline(always)]
+unsafe fn fwd_find_imp<V: Vector>(
+    fwd: &Forward,
+    haystack: &[u8],
+    needle: &[u8],
+    ascii_nocase: bool,
 ) -> Option<usize> {
     // It would be nice if we didn't have this check here, since the meta
     // searcher should handle it for us. But without this, I don't think we
@@ -127,9 +194,8 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
         MAX_NEEDLE_LEN,
     );
 
-    let (rare1i, rare2i) = (fwd.rare1i as usize, fwd.rare2i as usize);
-    let rare1chunk = V::splat(needle[rare1i]);
-    let rare2chunk = V::splat(needle[rare2i]);
+    let cn =
+        ChunkNeedle::<V>::new(fwd.rare1i, fwd.rare2i, needle, ascii_nocase);
 
     let start_ptr = haystack.as_ptr();
     let end_ptr = start_ptr.add(haystack.len());
@@ -142,9 +208,7 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
     // In the end, I decided the complexity from unrolling wasn't worth it. I
     // used the memmem/krate/prebuilt/huge-en/ benchmarks to compare.
     while ptr <= max_ptr {
-        let m = fwd_find_in_chunk(
-            fwd, needle, ptr, end_ptr, rare1chunk, rare2chunk, !0,
-        );
+        let m = fwd_find_in_chunk(&cn, ptr, end_ptr, !0);
         if let Some(chunki) = m {
             return Some(matched(start_ptr, ptr, chunki));
         }
@@ -184,9 +248,7 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
         // ignored.
         let mask = !((1 << overlap) - 1);
         ptr = max_ptr;
-        let m = fwd_find_in_chunk(
-            fwd, needle, ptr, end_ptr, rare1chunk, rare2chunk, mask,
-        );
+        let m = fwd_find_in_chunk(&cn, ptr, end_ptr, mask);
         if let Some(chunki) = m {
             return Some(matched(start_ptr, ptr, chunki));
         }
@@ -194,14 +256,46 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
     None
 }
 
+/// The needle being searched for, along with everything derived from it that
+/// stays the same for every chunk of the haystack searched.
+struct ChunkNeedle<'n, V> {
+    needle: &'n [u8],
+    /// The offsets of the rare bytes in the needle.
+    rare1i: usize,
+    rare2i: usize,
+    /// Vectors with the rare1 and rare2 bytes repeated in each 8-bit lane,
+    /// respectively. When ascii_nocase is true, these are folded with
+    /// `splat_folded`.
+    rare1chunk: V,
+    rare2chunk: V,
+    ascii_nocase: bool,
+}
+
+impl<'n, V: Vector> ChunkNeedle<'n, V> {
+    #[inline(always)]
+    unsafe fn new(
+        rare1i: u8,
+        rare2i: u8,
+        needle: &'n [u8],
+        ascii_nocase: bool,
+    ) -> ChunkNeedle<'n, V> {
+        let (rare1i, rare2i) = (rare1i as usize, rare2i as usize);
+        ChunkNeedle {
+            needle,
+            rare1i,
+            rare2i,
+            rare1chunk: splat_folded::<V>(needle[rare1i], ascii_nocase),
+            rare2chunk: splat_folded::<V>(needle[rare2i], ascii_nocase),
+            ascii_nocase,
+        }
+    }
+}
+
 /// Search for an occurrence of two rare bytes from the needle in the chunk
 /// pointed to by ptr, with the end of the haystack pointed to by end_ptr. When
 /// an occurrence is found, memcmp is run to check if a match occurs at the
 /// corresponding position.
 ///
-/// rare1chunk and rare2chunk correspond to vectors with the rare1 and rare2
-/// bytes repeated in each 8-bit lane, respectively.
-///
 /// mask should have bits set corresponding the positions in the chunk in which
 /// matches are considered. This is only used for the last vector load where
 /// the beginning of the vector might have overlapped with the last load in
@@ -215,19 +309,17 @@ pub(crate) unsafe fn fwd_find<V: Vector>(
 /// loads on ptr up to (end_ptr - needle.len()).
 #[inline(always)]
 unsafe fn fwd_find_in_chunk<V: Vector>(
-    fwd: &Forward,
-    needle: &[u8],
+    cn: &ChunkNeedle<'_, V>,
     ptr: *const u8,
     end_ptr: *const u8,
-    rare1chunk: V,
-    rare2chunk: V,
     mask: u32,
 ) -> Option<usize> {
-    let chunk0 = V::load_unaligned(ptr.add(fwd.rare1i as usize));
-    let chunk1 = V::load_unaligned(ptr.add(fwd.rare2i as usize));
+    let (needle, ascii_nocase) = (cn.needle, cn.ascii_nocase);
+    let chunk0 = load_folded::<V>(ptr.add(cn.rare1i), ascii_nocase);
+    let chunk1 = load_folded::<V>(ptr.add(cn.rare2i), ascii_nocase);
 
-    let eq0 = chunk0.cmpeq(rare1chunk);
-    let eq1 = chunk1.cmpeq(rare2chunk);
+    let eq0 = chunk0.cmpeq(cn.rare1chunk);
+    let eq1 = chunk1.cmpeq(cn.rare2chunk);
 
     let mut match_offsets = eq0.and(eq1).movemask() & mask;
     while match_offsets != 0 {
@@ -237,7 +329,7 @@ unsafe fn fwd_find_in_chunk<V: Vector>(
             return None;
         }
         let chunk = core::slice::from_raw_parts(ptr, needle.len());
-        if memcmp(needle, chunk) {
+        if confirm(needle, chunk, ascii_nocase) {
             return Some(offset);
         }
         match_offsets &= match_offsets - 1;
@@ -245,6 +337,177 @@ unsafe fn fwd_find_in_chunk<V: Vector>(
     None
 }
 
+/// Searches the given haystack for the last occurrence of the given needle.
+/// The needle given should be the same as the needle that this searcher was
+/// initialized with.
+///
+/// # Panics
+///
+/// When the given haystack has a length smaller than `min_haystack_len`.
+///
+/// # Safety
+///
+/// Since this is meant to be used with vector functions, callers need to
+/// specialize this inside of a function with a `target_feature` attribute.
+/// Therefore, callers must ensure that whatever target feature is being used
+/// supports the vector functions that this function is specialized for. (For
+/// the specific vector functions used, see the Vector trait implementations.)
+#[inline(always)]
+pub(crate) unsafe fn rev_find<V: Vector>(
+    rev: &Reverse,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    // See fwd_find for why we pass a constant for ascii_nocase.
+    if rev.ascii_nocase {
+        rev_find_imp::<V>(rev, haystack, needle, true)
+    } else {
+        rev_find_imp::<V>(rev, haystack, needle, false)
+    }
+}
+
+#[inline(always)]
+unsafe fn rev_find_imp<V: Vector>(
+    rev: &Reverse,
+    haystack: &[u8],
+    needle: &[u8],
+    ascii_nocase: bool,
+) -> Option<usize> {
+    // See fwd_find for why we have this check.
+    if haystack.len() < needle.len() {
+        return None;
+    }
+    let min_haystack_len = rev.min_haystack_len::<V>();
+    assert!(haystack.len() >= min_haystack_len, "haystack too small");
+    debug_assert!(needle.len() <= haystack.len());
+    debug_assert!(
+        needle.len() >= MIN_NEEDLE_LEN,
+        "needle must be at least {} bytes",
+        MIN_NEEDLE_LEN,
+    );
+    debug_assert!(
+        needle.len() <= MAX_NEEDLE_LEN,
+        "needle must be at most {} bytes",
+        MAX_NEEDLE_LEN,
+    );
+
+    let cn =
+        ChunkNeedle::<V>::new(rev.rare1i, rev.rare2i, needle, ascii_nocase);
+
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let max_ptr = end_ptr.sub(min_haystack_len);
+    let mut ptr = max_ptr;
+
+    loop {
+        let m = rev_find_in_chunk(&cn, ptr, end_ptr, !0);
+        if let Some(chunki) = m {
+            return Some(matched(start_ptr, ptr, chunki));
+        }
+        if diff(ptr, start_ptr) < size_of::<V>() {
+            break;
+        }
+        ptr = ptr.sub(size_of::<V>());
+    }
+    if ptr > start_ptr {
+        let overlap = diff(ptr, start_ptr);
+        debug_assert!(
+            overlap < size_of::<V>(),
+            "overlap ({}) cannot possibly be >= than a vector ({})",
+            overlap,
+            size_of::<V>(),
+        );
+        // The mask only has its first N least significant bits set, where
+        // N=overlap. This way, any matches that occur in rev_find_in_chunk
+        // at positions we've already searched are automatically ignored.
+        let mask = (1 << overlap) - 1;
+        ptr = start_ptr;
+        let m = rev_find_in_chunk(&cn, ptr, end_ptr, mask);
+        if let Some(chunki) = m {
+            return Some(matched(start_ptr, ptr, chunki));
+        }
+    }
+    None
+}
+
+/// Like `fwd_find_in_chunk`, but visits candidates from the end of the chunk
+/// to its beginning and returns the last match found.
+///
+/// Since the last chunk searched may extend past the last position at which
+/// the needle can begin, candidates at such positions are skipped.
+///
+/// # Safety
+///
+/// It must be safe to do an unaligned read of size(V) bytes starting at both
+/// (ptr + rare1i) and (ptr + rare2i). It must also be safe to do unaligned
+/// loads on ptr up to (end_ptr - needle.len()).
+#[inline(always)]
+unsafe fn rev_find_in_chunk<V: Vector>(
+    cn: &ChunkNeedle<'_, V>,
+    ptr: *const u8,
+    end_ptr: *const u8,
+    mask: u32,
+) -> Option<usize> {
+    let (needle, ascii_nocase) = (cn.needle, cn.ascii_nocase);
+    let chunk0 = load_folded::<V>(ptr.add(cn.rare1i), ascii_nocase);
+    let chunk1 = load_folded::<V>(ptr.add(cn.rare2i), ascii_nocase);
+
+    let eq0 = chunk0.cmpeq(cn.rare1chunk);
+    let eq1 = chunk1.cmpeq(cn.rare2chunk);
+
+    let mut match_offsets = eq0.and(eq1).movemask() & mask;
+    while match_offsets != 0 {
+        let offset = 31 - match_offsets.leading_zeros() as usize;
+        match_offsets &= !(1 << offset);
+        let ptr = ptr.add(offset);
+        if end_ptr.sub(needle.len()) < ptr {
+            continue;
+        }
+        let chunk = core::slice::from_raw_parts(ptr, needle.len());
+        if confirm(needle, chunk, ascii_nocase) {
+            return Some(offset);
+        }
+    }
+    None
+}
+
+/// Returns a vector with the given byte in every lane. If ascii_nocase is
+/// true, then the byte is folded by setting its 0x20 bit, which maps ASCII
+/// uppercase letters to lowercase.
+#[inline(always)]
+pub(crate) unsafe fn splat_folded<V: Vector>(b: u8, ascii_nocase: bool) -> V {
+    V::splat(if ascii_nocase { b | 0x20 } else { b })
+}
+
+/// Does an unaligned load of a vector from ptr. If ascii_nocase is true, then
+/// each byte is folded in the same way as `splat_folded`.
+///
+/// # Safety
+///
+/// It must be safe to do an unaligned read of size(V) bytes starting at ptr.
+#[inline(always)]
+pub(crate) unsafe fn load_folded<V: Vector>(
+    ptr: *const u8,
+    ascii_nocase: bool,
+) -> V {
+    let chunk = V::load_unaligned(ptr);
+    if ascii_nocase {
+        chunk.or(V::splat(0x20))
+    } else {
+        chunk
+    }
+}
+
+/// Confirm a candidate match, ignoring ASCII case if requested.
+#[inline(always)]
+fn confirm(needle: &[u8], chunk: &[u8], ascii_nocase: bool) -> bool {
+    if ascii_nocase {
+        memcmp_ascii_nocase(needle, chunk)
+    } else {
+        memcmp(needle, chunk)
+    }
+}
+
 /// Accepts a chunk-relative offset and returns a haystack relative offset
 /// after updating the prefilter state.
 ///
@@ -0,0 +1,477 @@
+/*!
+This module implements approximate substring search under Hamming distance.
+That is, it finds every position in a haystack at which a needle matches with
+at most `k` of its bytes substituted.
+
+Candidates are found with the pigeonhole principle: if the needle is split
+into `k + 1` pieces, then any window with at most `k` mismatches must contain
+at least one of the pieces exactly, at the same offset as in the needle. So
+each piece is searched for with its own [`Finder`], and only the windows that
+line up with an occurrence of some piece are compared with the needle.
+
+Searching is thus fast when `k` is small relative to the length of the needle,
+since the pieces are long and occur rarely. As `k` approaches the length of
+the needle, the pieces get shorter and occur more often, and the search
+degrades to comparing the needle with nearly every window in the haystack.
+*/
+
+use crate::{cow::CowBytes, memmem::Finder};
+
+/// Returns the index of the first position in `haystack` at which `needle`
+/// matches with at most `k` mismatched bytes.
+///
+/// A mismatch is a substitution of one byte for another. Insertions and
+/// deletions are not permitted, so a match always has the same length as the
+/// needle. If `k` is at least the length of the needle, then every position
+/// at which the needle fits matches, and an empty needle always matches at
+/// `0`.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Complexity
+///
+/// This routine uses a [`Finder`] for each of `k + 1` pieces of the needle to
+/// find candidates, and compares the needle with the haystack at each
+/// candidate. In the worst case, this runs in `O(haystack.len() *
+/// needle.len())` time.
+///
+/// If you need to search for the same needle many times, then
+/// [`HammingFinder`] avoids building the finders for its pieces each time.
+///
+/// # Examples
+///
+/// ```
+/// use memchr::memmem;
+///
+/// let haystack = b"GATTACAGATCACA";
+/// assert_eq!(None, memmem::find_hamming(haystack, b"GATGACA", 0));
+/// assert_eq!(Some(0), memmem::find_hamming(haystack, b"GATGACA", 1));
+/// assert_eq!(None, memmem::find_hamming(haystack, b"TTTTTTT", 2));
+/// ```
+#[inline]
+pub fn find_hamming(
+    haystack: &[u8],
+    needle: &[u8],
+    k: usize,
+) -> Option<usize> {
+    HammingFinder::new(needle, k).find(haystack)
+}
+
+/// Returns an iterator over every position in `haystack` at which `needle`
+/// matches with at most `k` mismatched bytes. The iterator yields the byte
+/// offset at which each match begins, in ascending order.
+///
+/// Unlike [`find_iter`](crate::memmem::find_iter), matches may overlap,
+/// since approximate matches commonly occur at adjacent positions.
+///
+/// See [`find_hamming`] for more details on what constitutes a match.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use memchr::memmem;
+///
+/// let haystack = b"recieve, receive, recleve, retrieve";
+/// let it = memmem::find_hamming_iter(haystack, "receive", 2);
+/// assert_eq!(vec![0, 9, 18], it.collect::<Vec<usize>>());
+/// ```
+#[inline]
+pub fn find_hamming_iter<'h, 'n, N: 'n + ?Sized + AsRef<[u8]>>(
+    haystack: &'h [u8],
+    needle: &'n N,
+    k: usize,
+) -> HammingFindIter<'h, 'n> {
+    HammingFindIter::new(haystack, HammingFinder::new(needle, k))
+}
+
+/// An approximate substring searcher fixed to a particular needle and a
+/// maximum number of mismatches.
+///
+/// A needle matches at a position in the haystack if at most `k` of its bytes
+/// differ from the haystack there. See [`find_hamming`] for more details.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::HammingFinder;
+///
+/// let finder = HammingFinder::new("invoice", 1);
+/// assert_eq!(Some(4), finder.find(b"the lnvoice total"));
+/// assert_eq!(None, finder.find(b"the lnvolce total"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct HammingFinder<'n> {
+    needle: CowBytes<'n>,
+    k: usize,
+    /// The `k + 1` pieces of the needle along with the offset at which each
+    /// starts. This is empty when `k` is at least the length of the needle,
+    /// since every window matches then.
+    pieces: Vec<(usize, Finder<'n>)>,
+}
+
+impl<'n> HammingFinder<'n> {
+    /// Create a new finder for the given needle that permits at most `k`
+    /// mismatched bytes.
+    pub fn new<B: ?Sized + AsRef<[u8]>>(
+        needle: &'n B,
+        k: usize,
+    ) -> HammingFinder<'n> {
+        let needle = needle.as_ref();
+        let mut pieces = vec![];
+        if k < needle.len() {
+            // Split the needle into k + 1 pieces whose lengths differ by at
+            // most one. Every piece is non-empty since k + 1 <= needle.len().
+            let count = k + 1;
+            let (len, extra) = (needle.len() / count, needle.len() % count);
+            let mut start = 0;
+            for i in 0..count {
+                let end = start + len + if i < extra { 1 } else { 0 };
+                pieces.push((start, Finder::new(&needle[start..end])));
+                start = end;
+            }
+            debug_assert_eq!(start, needle.len());
+        }
+        HammingFinder { needle: CowBytes::new(needle), k, pieces }
+    }
+
+    /// Returns the index of the first position in the given haystack at
+    /// which this finder's needle matches with at most `k` mismatched bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::HammingFinder;
+    ///
+    /// let finder = HammingFinder::new("ACGT", 1);
+    /// assert_eq!(Some(2), finder.find(b"TTAGGTAC"));
+    /// ```
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let nlen = self.needle.len();
+        let mut last = haystack.len().checked_sub(nlen)?;
+        if self.pieces.is_empty() {
+            // Every window matches when no piece has to match exactly.
+            return Some(0);
+        }
+        // Find the first match lined up with each piece in turn. Once a
+        // match is found, later pieces only need to look for an earlier one,
+        // so the windows searched shrink as we go.
+        let mut found = None;
+        for piece in 0..self.pieces.len() {
+            let mut at = 0;
+            while let Some(candidate) =
+                self.next_candidate(haystack, piece, at, last)
+            {
+                if self.is_match(&haystack[candidate..candidate + nlen]) {
+                    if candidate == 0 {
+                        return Some(0);
+                    }
+                    found = Some(candidate);
+                    last = candidate - 1;
+                    break;
+                }
+                at = candidate + 1;
+            }
+        }
+        found
+    }
+
+    /// Returns an iterator over every position in the given haystack at
+    /// which this finder's needle matches with at most `k` mismatched bytes.
+    /// The iterator yields the byte offset at which each match begins, in
+    /// ascending order. Matches may overlap.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::HammingFinder;
+    ///
+    /// let finder = HammingFinder::new("aaa", 1);
+    /// let got: Vec<usize> = finder.find_iter(b"aabaa").collect();
+    /// assert_eq!(vec![0, 1, 2], got);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> HammingFindIter<'h, 'a> {
+        HammingFindIter::new(haystack, self.as_ref())
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle.
+    ///
+    /// If this is already an owned finder, then this is a no-op. Otherwise,
+    /// this copies the needle.
+    #[inline]
+    pub fn into_owned(self) -> HammingFinder<'static> {
+        HammingFinder {
+            needle: self.needle.into_owned(),
+            k: self.k,
+            pieces: self
+                .pieces
+                .into_iter()
+                .map(|(start, finder)| (start, finder.into_owned()))
+                .collect(),
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    ///
+    /// This is primarily useful if your finder is owned and you'd like to
+    /// store its borrowed variant in some intermediate data structure.
+    #[inline]
+    pub fn as_ref(&self) -> HammingFinder<'_> {
+        HammingFinder {
+            needle: CowBytes::new(self.needle.as_slice()),
+            k: self.k,
+            pieces: self
+                .pieces
+                .iter()
+                .map(|&(start, ref finder)| (start, finder.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// Returns the needle that this finder searches for.
+    ///
+    /// Note that the lifetime of the needle returned is tied to the lifetime
+    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
+    /// finder's needle can be either borrowed or owned, so the lifetime of the
+    /// needle returned must necessarily be the shorter of the two.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+
+    /// Returns the maximum number of mismatched bytes permitted in a match.
+    #[inline]
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the start of the first candidate window in `at..=last` that
+    /// contains the piece at the given index exactly, at the piece's offset
+    /// in the needle.
+    ///
+    /// `last` must leave room for the needle, i.e., `last + needle.len()`
+    /// must not exceed the length of the haystack.
+    fn next_candidate(
+        &self,
+        haystack: &[u8],
+        piece: usize,
+        at: usize,
+        last: usize,
+    ) -> Option<usize> {
+        let (start, ref finder) = self.pieces[piece];
+        if at > last {
+            return None;
+        }
+        // The piece must occur at a window start in at..=last, so bound the
+        // search such that no occurrence past the last window is found.
+        let end = last + start + finder.needle().len();
+        let i = finder.find(&haystack[at + start..end])?;
+        Some(at + i)
+    }
+
+    /// Returns true if and only if the needle differs from the given window
+    /// in at most `k` bytes.
+    fn is_match(&self, window: &[u8]) -> bool {
+        let mut mismatches = 0;
+        for (&b1, &b2) in window.iter().zip(self.needle.as_slice()) {
+            if b1 != b2 {
+                mismatches += 1;
+                if mismatches > self.k {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+}
+
+/// An iterator over every approximate match of a needle in a haystack.
+///
+/// Matches are reported by the byte offset at which they begin, in ascending
+/// order. Matches may overlap.
+///
+/// This iterator can be created with the [`find_hamming_iter`] function or
+/// with the [`HammingFinder::find_iter`] method.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+///
+/// This is only available when the `std` feature is enabled.
+#[derive(Debug)]
+pub struct HammingFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    finder: HammingFinder<'n>,
+    /// The start of the next window to consider.
+    pos: usize,
+    /// The next candidate window found for each piece of the needle, or
+    /// `None` if that piece has no more occurrences.
+    candidates: Vec<Option<usize>>,
+}
+
+impl<'h, 'n> HammingFindIter<'h, 'n> {
+    fn new(
+        haystack: &'h [u8],
+        finder: HammingFinder<'n>,
+    ) -> HammingFindIter<'h, 'n> {
+        let last = haystack.len().checked_sub(finder.needle.len());
+        let candidates = (0..finder.pieces.len())
+            .map(|piece| finder.next_candidate(haystack, piece, 0, last?))
+            .collect();
+        HammingFindIter { haystack, finder, pos: 0, candidates }
+    }
+}
+
+impl<'h, 'n> Iterator for HammingFindIter<'h, 'n> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let nlen = self.finder.needle.len();
+        if self.finder.pieces.is_empty() {
+            // Every window matches when no piece has to match exactly.
+            if self.pos + nlen > self.haystack.len() {
+                return None;
+            }
+            self.pos += 1;
+            return Some(self.pos - 1);
+        }
+        loop {
+            let candidate = self.candidates.iter().filter_map(|&c| c).min()?;
+            // Every candidate leaves room for the needle, so this can't
+            // underflow.
+            let last = self.haystack.len() - nlen;
+            for piece in 0..self.candidates.len() {
+                if self.candidates[piece] == Some(candidate) {
+                    self.candidates[piece] = self.finder.next_candidate(
+                        self.haystack,
+                        piece,
+                        candidate + 1,
+                        last,
+                    );
+                }
+            }
+            self.pos = candidate + 1;
+            let window = &self.haystack[candidate..candidate + nlen];
+            if self.finder.is_match(window) {
+                return Some(candidate);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The naive reference implementation: compare the needle with every
+    /// window in the haystack.
+    fn naive(haystack: &[u8], needle: &[u8], k: usize) -> Vec<usize> {
+        if haystack.len() < needle.len() {
+            return vec![];
+        }
+        (0..=haystack.len() - needle.len())
+            .filter(|&i| {
+                let window = &haystack[i..i + needle.len()];
+                window.iter().zip(needle).filter(|(a, b)| a != b).count() <= k
+            })
+            .collect()
+    }
+
+    /// Each test is a haystack, a needle, `k` and the expected matches.
+    type HammingTest = (&'static str, &'static str, usize, &'static [usize]);
+
+    const HAMMING_TESTS: &[HammingTest] = &[
+        ("", "", 0, &[0]),
+        ("ab", "", 3, &[0, 1, 2]),
+        ("", "a", 0, &[]),
+        ("", "a", 1, &[]),
+        ("ab", "xyz", 5, &[]),
+        ("abc", "xyz", 3, &[0]),
+        ("abc", "xy", 2, &[0, 1]),
+        ("abc", "b", 0, &[1]),
+        ("abc", "z", 1, &[0, 1, 2]),
+        ("abcabc", "abc", 0, &[0, 3]),
+        ("abcabc", "abd", 1, &[0, 3]),
+        ("abcabc", "xbc", 1, &[0, 3]),
+        ("abcabc", "axc", 1, &[0, 3]),
+        ("abcabc", "bxa", 1, &[1]),
+        ("abcabc", "bxx", 1, &[]),
+        ("abcabc", "bxx", 2, &[1]),
+        ("abcabcx", "bxx", 2, &[1, 4]),
+        ("aaaaa", "aba", 1, &[0, 1, 2]),
+        ("GATTACAGATCACA", "GATGACA", 0, &[]),
+        ("GATTACAGATCACA", "GATGACA", 1, &[0, 7]),
+    ];
+
+    #[test]
+    fn simple() {
+        for &(haystack, needle, k, expected) in HAMMING_TESTS {
+            let (haystack, needle) = (haystack.as_bytes(), needle.as_bytes());
+            let got: Vec<usize> =
+                find_hamming_iter(haystack, needle, k).collect();
+            assert_eq!(
+                expected, &*got,
+                "haystack: {:?}, needle: {:?}, k: {}",
+                haystack, needle, k,
+            );
+            let first = expected.first().copied();
+            assert_eq!(first, find_hamming(haystack, needle, k));
+            assert_eq!(expected, &*naive(haystack, needle, k));
+        }
+    }
+
+    #[test]
+    fn pieces() {
+        let lens = |finder: &HammingFinder| -> Vec<(usize, usize)> {
+            finder
+                .pieces
+                .iter()
+                .map(|(start, f)| (*start, f.needle().len()))
+                .collect()
+        };
+        let finder = HammingFinder::new("abcdefg", 2);
+        assert_eq!(vec![(0, 3), (3, 2), (5, 2)], lens(&finder));
+        let finder = HammingFinder::new("abc", 2);
+        assert_eq!(vec![(0, 1), (1, 1), (2, 1)], lens(&finder));
+        let finder = HammingFinder::new("abc", 3);
+        assert!(lens(&finder).is_empty());
+    }
+
+    #[test]
+    fn into_owned() {
+        let finder = {
+            let needle = String::from("needle");
+            HammingFinder::new(&needle, 1).into_owned()
+        };
+        assert_eq!(b"needle", finder.needle());
+        assert_eq!(1, finder.k());
+        assert_eq!(Some(4), finder.find(b"the noedle"));
+    }
+
+    #[cfg(not(miri))]
+    quickcheck::quickcheck! {
+        fn qc_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            k: u8
+        ) -> bool {
+            use crate::memmem::proptests::to_alphabet;
+
+            let haystack = to_alphabet(b"ab", &haystack);
+            let needle = to_alphabet(b"ab", &needle[..needle.len().min(12)]);
+            let k = k as usize % 5;
+            let expected = naive(&haystack, &needle, k);
+            let got: Vec<usize> =
+                find_hamming_iter(&haystack, &needle, k).collect();
+            let first = find_hamming(&haystack, &needle, k);
+            expected == got && expected.first().copied() == first
+        }
+    }
+}
@@ -0,0 +1,558 @@
+/*!
+This module implements substring search for needles with a mask, where only
+some bits (or none) of each needle byte have to match.
+
+This is commonly used for binary signatures, where some bytes vary between
+occurrences (such as the operand of a call instruction) and are thus written
+as wildcards, e.g., `E8 ?? ?? ?? ?? 48 8B`.
+
+Searching uses the same prefilters as `Finder`, but the rare bytes given to
+them are only picked from positions that are fully fixed by the mask. Every
+candidate reported by a prefilter is then verified by comparing the needle
+with the haystack under the mask.
+*/
+
+use crate::{
+    cow::CowBytes,
+    memmem::{
+        prefilter::{self, Pre, PrefilterFn, PrefilterState},
+        rabinkarp::NeedleHash,
+        rarebytes::RareNeedleBytes,
+        util::memcmp_masked,
+        FinderBuilder, NeedleInfo,
+    },
+};
+
+/// A single substring searcher fixed to a particular masked needle.
+///
+/// A masked needle is a needle along with a mask of the same length. A
+/// needle matches at a position in the haystack if, for every byte in the
+/// needle, the bits that are set in the corresponding byte of the mask are
+/// equal to the same bits in the haystack. Thus, a mask byte of `0xFF`
+/// requires an exact match, a mask byte of `0x00` matches any byte and, for
+/// example, a mask byte of `0xF0` only requires the high nibble to match.
+///
+/// If the builder this was built with ignores ASCII case, then both the
+/// needle and the haystack are folded to ASCII lowercase before being
+/// masked.
+///
+/// Candidates are found with the same prefilters used by [`Finder`], but
+/// only bytes whose mask is `0xFF` are considered by them. So a needle
+/// should have at least one fully fixed byte (preferably a rare one) in its
+/// first 256 bytes for searching to be fast. Unlike `Finder`, this makes no
+/// guarantee about worst case time complexity.
+///
+/// With the `std` feature enabled, a needle and its mask can be parsed from
+/// a signature written in hex with [`MaskedNeedle`].
+///
+/// # Example
+///
+/// This finds a relative call (`E8`) followed by `mov` (`48 8B`), without
+/// caring about the call's 4 byte offset.
+///
+/// ```
+/// use memchr::memmem::MaskedFinder;
+///
+/// let needle = b"\xE8\x00\x00\x00\x00\x48\x8B";
+/// let mask = b"\xFF\x00\x00\x00\x00\xFF\xFF";
+/// let finder = MaskedFinder::new(needle, mask);
+/// let haystack = b"\x90\x90\xE8\x12\x34\x56\x78\x48\x8B\xC3";
+/// assert_eq!(Some(2), finder.find(haystack));
+/// ```
+///
+/// [`Finder`]: crate::memmem::Finder
+#[derive(Clone, Debug)]
+pub struct MaskedFinder<'n> {
+    needle: CowBytes<'n>,
+    mask: CowBytes<'n>,
+    /// The rare bytes picked from the fixed positions of the needle, for
+    /// use by the prefilter. The Rabin-Karp hash is unused.
+    ninfo: NeedleInfo,
+    /// A prefilter function, if one was deemed appropriate. There is never
+    /// one when the needle has no fixed positions.
+    prefn: Option<PrefilterFn>,
+    /// Whether bytes are folded to ASCII lowercase before being masked.
+    ascii_nocase: bool,
+}
+
+impl<'n> MaskedFinder<'n> {
+    /// Create a new finder for the given needle and mask.
+    ///
+    /// # Panics
+    ///
+    /// When the needle and mask have different lengths.
+    #[inline]
+    pub fn new<N, M>(needle: &'n N, mask: &'n M) -> MaskedFinder<'n>
+    where
+        N: ?Sized + AsRef<[u8]>,
+        M: ?Sized + AsRef<[u8]>,
+    {
+        FinderBuilder::new().build_masked(needle, mask)
+    }
+
+    /// Create a new finder for the given needle and mask, using the
+    /// configuration in the given builder.
+    pub(crate) fn with_builder(
+        builder: &FinderBuilder,
+        needle: &'n [u8],
+        mask: &'n [u8],
+    ) -> MaskedFinder<'n> {
+        assert_eq!(
+            needle.len(),
+            mask.len(),
+            "needle and mask must have the same length",
+        );
+        let ascii_nocase = builder.config.ascii_case_insensitive;
+        let rarebytes =
+            RareNeedleBytes::forward_masked(needle, mask, ascii_nocase);
+        let ninfo = NeedleInfo {
+            rarebytes: rarebytes.unwrap_or_default(),
+            nhash: NeedleHash::default(),
+        };
+        let prefn = match rarebytes {
+            None => None,
+            Some(ref rare) => {
+                prefilter::forward(&builder.config.prefilter, rare, needle)
+            }
+        };
+        MaskedFinder {
+            needle: CowBytes::new(needle),
+            mask: CowBytes::new(mask),
+            ninfo,
+            prefn,
+            ascii_nocase,
+        }
+    }
+
+    /// Returns the index of the first occurrence of this finder's masked
+    /// needle in the given haystack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::MaskedFinder;
+    ///
+    /// // Only the high nibble of the second byte has to match.
+    /// let finder = MaskedFinder::new(b"A\x30", b"\xFF\xF0");
+    /// assert_eq!(Some(2), finder.find(b"ABA7"));
+    /// assert_eq!(None, finder.find(b"ABAB"));
+    /// ```
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        let mut prestate = self.prefilter_state();
+        self.find_with(&mut prestate, haystack)
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of this
+    /// finder's masked needle in the given haystack. The iterator returned
+    /// yields byte offsets at which each match begins.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::MaskedFinder;
+    ///
+    /// let finder = MaskedFinder::new(b"a.c", b"\xFF\x00\xFF");
+    /// let got: Vec<usize> = finder.find_iter(b"abc a-c aac").collect();
+    /// assert_eq!(vec![0, 4, 8], got);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> MaskedFindIter<'h, 'a> {
+        MaskedFindIter {
+            haystack,
+            prestate: self.prefilter_state(),
+            finder: self.as_ref(),
+            pos: 0,
+        }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needle or the mask.
+    ///
+    /// If this is already an owned finder, then this is a no-op. Otherwise,
+    /// this copies the needle and the mask.
+    ///
+    /// This is only available when the `std` feature is enabled.
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn into_owned(self) -> MaskedFinder<'static> {
+        MaskedFinder {
+            needle: self.needle.into_owned(),
+            mask: self.mask.into_owned(),
+            ninfo: self.ninfo,
+            prefn: self.prefn,
+            ascii_nocase: self.ascii_nocase,
+        }
+    }
+
+    /// Convert this finder into its borrowed variant.
+    ///
+    /// This is primarily useful if your finder is owned and you'd like to
+    /// store its borrowed variant in some intermediate data structure.
+    #[inline]
+    pub fn as_ref(&self) -> MaskedFinder<'_> {
+        MaskedFinder {
+            needle: CowBytes::new(self.needle.as_slice()),
+            mask: CowBytes::new(self.mask.as_slice()),
+            ninfo: self.ninfo,
+            prefn: self.prefn,
+            ascii_nocase: self.ascii_nocase,
+        }
+    }
+
+    /// Returns the needle that this finder searches for.
+    ///
+    /// Note that the lifetime of the needle returned is tied to the lifetime
+    /// of the finder, and may be shorter than the `'n` lifetime. Namely, a
+    /// finder's needle can be either borrowed or owned, so the lifetime of the
+    /// needle returned must necessarily be the shorter of the two.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        self.needle.as_slice()
+    }
+
+    /// Returns the mask applied to the needle that this finder searches
+    /// for.
+    ///
+    /// The same caveat about lifetimes as for `needle` applies.
+    #[inline]
+    pub fn mask(&self) -> &[u8] {
+        self.mask.as_slice()
+    }
+
+    fn prefilter_state(&self) -> PrefilterState {
+        if self.prefn.is_none() {
+            PrefilterState::inert()
+        } else {
+            PrefilterState::new()
+        }
+    }
+
+    fn find_with(
+        &self,
+        prestate: &mut PrefilterState,
+        haystack: &[u8],
+    ) -> Option<usize> {
+        let (needle, mask) = (self.needle.as_slice(), self.mask.as_slice());
+        if haystack.len() < needle.len() {
+            return None;
+        }
+        let last = haystack.len() - needle.len();
+        let mut pre = self.prefn.map(|prefn| Pre {
+            state: prestate,
+            prefn,
+            ninfo: &self.ninfo,
+        });
+        let mut pos = 0;
+        while pos <= last {
+            if let Some(pre) = pre.as_mut() {
+                if pre.should_call() {
+                    pos += pre.call(&haystack[pos..], needle)?;
+                    if pos > last {
+                        return None;
+                    }
+                }
+            }
+            let window = &haystack[pos..pos + needle.len()];
+            if memcmp_masked(window, needle, mask, self.ascii_nocase) {
+                return Some(pos);
+            }
+            pos += 1;
+        }
+        None
+    }
+}
+
+/// An iterator over non-overlapping matches of a [`MaskedFinder`].
+///
+/// Matches are reported by the byte offset at which they begin.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needle.
+#[derive(Debug)]
+pub struct MaskedFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    prestate: PrefilterState,
+    finder: MaskedFinder<'n>,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for MaskedFindIter<'h, 'n> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+        let result = self
+            .finder
+            .find_with(&mut self.prestate, &self.haystack[self.pos..]);
+        match result {
+            None => None,
+            Some(i) => {
+                let pos = self.pos + i;
+                self.pos = pos + core::cmp::max(1, self.finder.needle.len());
+                Some(pos)
+            }
+        }
+    }
+}
+
+/// A needle and mask parsed from a signature written in hex.
+///
+/// A signature is a sequence of tokens separated by whitespace. Each token
+/// is either `?`, which matches any byte, or an even number of hexadecimal
+/// digits, where each pair of digits is a byte that has to match exactly.
+/// Either digit in a pair may be replaced with `?` to only require the other
+/// nibble to match. For example, `E8 ?? ?? ?? ?? 48 8B`, `E8 ? ? ? ? 488B`
+/// and `4? 8B` are all valid signatures.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::{MaskedFinder, MaskedNeedle};
+///
+/// let sig = MaskedNeedle::parse("E8 ?? ?? ?? ?? 4? 8B").unwrap();
+/// assert_eq!(b"\xE8\x00\x00\x00\x00\x40\x8B", sig.needle());
+/// assert_eq!(b"\xFF\x00\x00\x00\x00\xF0\xFF", sig.mask());
+///
+/// let finder = MaskedFinder::new(sig.needle(), sig.mask());
+/// let haystack = b"\xC3\xE8\xAA\xBB\xCC\xDD\x4C\x8B";
+/// assert_eq!(Some(1), finder.find(haystack));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MaskedNeedle {
+    needle: Vec<u8>,
+    mask: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl MaskedNeedle {
+    /// Parse a needle and mask from the given signature.
+    ///
+    /// If the signature is invalid, then an error is returned that reports
+    /// the position of the first invalid token.
+    pub fn parse(signature: &str) -> Result<MaskedNeedle, MaskedNeedleError> {
+        let mut parsed = MaskedNeedle { needle: vec![], mask: vec![] };
+        for token in signature.split_ascii_whitespace() {
+            let offset = token.as_ptr() as usize - signature.as_ptr() as usize;
+            let token = token.as_bytes();
+            if token == b"?" {
+                parsed.needle.push(0);
+                parsed.mask.push(0);
+                continue;
+            }
+            if token.len() % 2 != 0 {
+                return Err(MaskedNeedleError { offset });
+            }
+            for pair in token.chunks(2) {
+                let (hi, himask) =
+                    nibble(pair[0]).ok_or(MaskedNeedleError { offset })?;
+                let (lo, lomask) =
+                    nibble(pair[1]).ok_or(MaskedNeedleError { offset })?;
+                parsed.needle.push((hi << 4) | lo);
+                parsed.mask.push((himask << 4) | lomask);
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Returns the parsed needle. Bits that are not set in the mask are
+    /// always zero.
+    #[inline]
+    pub fn needle(&self) -> &[u8] {
+        &self.needle
+    }
+
+    /// Returns the parsed mask.
+    #[inline]
+    pub fn mask(&self) -> &[u8] {
+        &self.mask
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::str::FromStr for MaskedNeedle {
+    type Err = MaskedNeedleError;
+
+    fn from_str(signature: &str) -> Result<MaskedNeedle, MaskedNeedleError> {
+        MaskedNeedle::parse(signature)
+    }
+}
+
+/// An error that occurs when parsing an invalid signature with
+/// [`MaskedNeedle::parse`].
+///
+/// This is only available when the `std` feature is enabled.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MaskedNeedleError {
+    offset: usize,
+}
+
+#[cfg(feature = "std")]
+impl MaskedNeedleError {
+    /// Returns the byte offset in the signature at which the invalid token
+    /// begins.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for MaskedNeedleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "invalid token in signature at offset {}", self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MaskedNeedleError {}
+
+/// Parse a single hexadecimal digit or `?` into a nibble and its mask.
+#[cfg(feature = "std")]
+fn nibble(digit: u8) -> Option<(u8, u8)> {
+    match digit {
+        b'?' => Some((0, 0)),
+        b'0'..=b'9' => Some((digit - b'0', 0xF)),
+        b'a'..=b'f' => Some((digit - b'a' + 10, 0xF)),
+        b'A'..=b'F' => Some((digit - b'A' + 10, 0xF)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The naive reference implementation: try the needle at every
+    /// position.
+    fn naive(haystack: &[u8], needle: &[u8], mask: &[u8]) -> Option<usize> {
+        if haystack.len() < needle.len() {
+            return None;
+        }
+        (0..=haystack.len() - needle.len()).find(|&i| {
+            haystack[i..i + needle.len()]
+                .iter()
+                .zip(needle)
+                .zip(mask)
+                .all(|((h, n), m)| h & m == n & m)
+        })
+    }
+
+    /// Each test is a haystack, a needle, a mask and the expected result.
+    type MaskedTest =
+        (&'static [u8], &'static [u8], &'static [u8], Option<usize>);
+
+    #[test]
+    fn simple() {
+        let tests: &[MaskedTest] = &[
+            (b"", b"", b"", Some(0)),
+            (b"abc", b"", b"", Some(0)),
+            (b"", b"a", b"\xFF", None),
+            (b"abc", b"z", b"\x00", Some(0)),
+            (b"abc", b"zzzz", b"\x00\x00\x00\x00", None),
+            (b"abc", b"b", b"\xFF", Some(1)),
+            (b"xxabcxx", b"a\x00c", b"\xFF\x00\xFF", Some(2)),
+            (b"xxaBcxx", b"a\x00c", b"\xFF\x00\xFF", Some(2)),
+            (b"xxabdxx", b"a\x00c", b"\xFF\x00\xFF", None),
+            (b"xxxxxq", b"\x00\x00q", b"\x00\x00\xFF", Some(3)),
+            (b"qxxxxx", b"q\x00\x00", b"\xFF\x00\x00", Some(0)),
+            (b"qxxxxx", b"\x00q\x00", b"\x00\xFF\x00", None),
+            (b"\x12\x34\x56", b"\x30\x06", b"\xF0\x0F", Some(1)),
+            (b"\x12\x34\x56", b"\x30\x07", b"\xF0\x0F", None),
+        ];
+        for &(haystack, needle, mask, expected) in tests {
+            let finder = MaskedFinder::new(needle, mask);
+            assert_eq!(
+                expected,
+                finder.find(haystack),
+                "haystack: {:?}, needle: {:?}, mask: {:?}",
+                haystack,
+                needle,
+                mask,
+            );
+            assert_eq!(expected, naive(haystack, needle, mask));
+        }
+    }
+
+    #[test]
+    fn ascii_case_insensitive() {
+        let finder = FinderBuilder::new()
+            .ascii_case_insensitive(true)
+            .build_masked("ab?d", b"\xFF\xFF\x00\xFF");
+        assert_eq!(Some(2), finder.find(b"xxABcDxx"));
+        assert_eq!(None, finder.find(b"xxABcExx"));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn find_iter() {
+        let finder = MaskedFinder::new("", "");
+        assert_eq!(vec![0, 1, 2], finder.find_iter(b"ab").collect::<Vec<_>>());
+
+        let finder = MaskedFinder::new("a?", b"\xFF\x00");
+        let got: Vec<usize> = finder.find_iter(b"aaaaa").collect();
+        assert_eq!(vec![0, 2], got);
+    }
+
+    #[test]
+    fn fixed_positions_only_in_prefilter() {
+        // The wildcard bytes here are 'z', which is much rarer than 'e', so
+        // a prefilter that considered them would never find a match.
+        let needle = b"zzzezzzezzz";
+        let mask = b"\x00\x00\x00\xFF\x00\x00\x00\xFF\x00\x00\x00";
+        let haystack = "the needle here we expect".repeat(20);
+        let finder = MaskedFinder::new(needle, mask);
+        let expected = naive(haystack.as_bytes(), needle, mask);
+        assert!(expected.is_some());
+        assert_eq!(expected, finder.find(haystack.as_bytes()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn parse() {
+        let sig = MaskedNeedle::parse(" E8 ? ?? 488b  c? ?3 ").unwrap();
+        assert_eq!(b"\xE8\x00\x00\x48\x8B\xC0\x03", sig.needle());
+        assert_eq!(b"\xFF\x00\x00\xFF\xFF\xF0\x0F", sig.mask());
+        assert_eq!(Ok(sig), " E8 ? ?? 488b  c? ?3 ".parse());
+
+        let sig = MaskedNeedle::parse("").unwrap();
+        assert!(sig.needle().is_empty() && sig.mask().is_empty());
+
+        assert_eq!(3, MaskedNeedle::parse("E8 ??? 48").unwrap_err().offset());
+        assert_eq!(3, MaskedNeedle::parse("E8 G8").unwrap_err().offset());
+        assert_eq!(0, MaskedNeedle::parse("E").unwrap_err().offset());
+        assert_eq!(3, MaskedNeedle::parse("00 é0").unwrap_err().offset());
+    }
+
+    #[cfg(all(feature = "std", not(miri)))]
+    quickcheck::quickcheck! {
+        fn qc_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<(u8, u8)>
+        ) -> bool {
+            use crate::memmem::proptests::to_alphabet;
+
+            let haystack = to_alphabet(b"abc", &haystack);
+            // Make most mask bytes either fully fixed or wildcards.
+            let mask: Vec<u8> = needle
+                .iter()
+                .map(|&(_, m)| match m % 4 {
+                    0 => 0x00,
+                    1 => 0xFE,
+                    _ => 0xFF,
+                })
+                .collect();
+            let needle: Vec<u8> = needle.iter().map(|&(b, _)| b).collect();
+            let needle = to_alphabet(b"abc", &needle);
+            let finder = MaskedFinder::new(&needle, &mask);
+            naive(&haystack, &needle, &mask) == finder.find(&haystack)
+        }
+    }
+}
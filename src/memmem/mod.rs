@@ -33,9 +33,6 @@ assert_eq!(None, it.next());
 This example shows how to use [`rfind_iter`] to find occurrences of a substring
 in a haystack starting from the end of the haystack.
 
-**NOTE:** This module does not implement double ended iterators, so reverse
-searches aren't done by calling `rev` on a forward iterator.
-
 ```
 use memchr::memmem;
 
@@ -48,6 +45,99 @@ assert_eq!(Some(0), it.next());
 assert_eq!(None, it.next());
 ```
 
+# Example: iterating over substring matches from both ends
+
+The iterator returned by [`find_iter`] is double ended, so it can also be
+consumed from the end of the haystack (e.g., by calling `rev` on it). Both
+ends can be used at once, for example, to get the first and last matches in
+a single pass. The two ends never report overlapping matches.
+
+```
+use memchr::memmem;
+
+let haystack = b"foo bar foo baz foo";
+
+let mut it = memmem::find_iter(haystack, "foo");
+assert_eq!(Some(0), it.next());
+assert_eq!(Some(16), it.next_back());
+assert_eq!(Some(8), it.next());
+assert_eq!(None, it.next_back());
+```
+
+# Example: iterating over overlapping substring matches
+
+The iterators above resume searching after the end of each match, so they
+never report matches that overlap. [`find_overlapping_iter`] and
+[`rfind_overlapping_iter`] instead report every offset at which the needle
+occurs.
+
+```
+use memchr::memmem;
+
+let haystack = b"abababa";
+
+let it = memmem::find_overlapping_iter(haystack, "aba");
+assert_eq!(vec![0, 2, 4], it.collect::<Vec<usize>>());
+```
+
+# Example: searching under Unicode case folding
+
+[`CaseFoldFinder`] matches UTF-8 needles under Unicode simple case folding,
+without needing to fold the case of the haystack first. Since case variants
+can have encodings of different lengths, it reports both the start and end of
+each match.
+
+```
+use memchr::memmem::CaseFoldFinder;
+
+let finder = CaseFoldFinder::new("ǆungl");
+let haystack = "U ǄUNGLI, u ǅungli".as_bytes();
+assert_eq!(Some((2, 8)), finder.find(haystack));
+```
+
+# Example: searching for several needles at once
+
+[`MultiFinder`] looks for any of a set of needles in a single pass over the
+haystack, and reports where the leftmost match starts along with the index of
+the needle that matched. This requires the `std` feature.
+
+```
+use memchr::memmem::MultiFinder;
+
+let finder = MultiFinder::new(&["ERROR", "WARN", "PANIC"]);
+let haystack = b"INFO start\nWARN disk\nERROR io\n";
+assert_eq!(Some((11, 1)), finder.find(haystack));
+```
+
+# Example: searching with wildcards
+
+[`MaskedFinder`] searches for a needle in which some bytes (or some bits of
+bytes) may be anything, which is common for binary signatures. With the `std`
+feature, [`MaskedNeedle`] parses such a signature written in hex.
+
+```
+use memchr::memmem::{MaskedFinder, MaskedNeedle};
+
+let sig = MaskedNeedle::parse("E8 ?? ?? ?? ?? 48 8B").unwrap();
+let finder = MaskedFinder::new(sig.needle(), sig.mask());
+let haystack = b"\x55\xE8\x10\x20\x30\x40\x48\x8B\x05";
+assert_eq!(Some(1), finder.find(haystack));
+```
+
+# Example: approximate search with mismatches
+
+[`find_hamming_iter`] reports every position at which a needle matches with
+at most `k` bytes substituted, which is useful for noisy input such as OCR
+output or sequencing reads. This requires the `std` feature.
+
+```
+use memchr::memmem;
+
+let haystack = b"ACGTTGCA ACCTTGCA AGGTAGCA";
+let it = memmem::find_hamming_iter(haystack, "ACGTTGCA", 1);
+assert_eq!(vec![0, 9], it.collect::<Vec<usize>>());
+```
+
 # Example: repeating a search for the same needle
 
 It may be possible for the overhead of constructing a substring searcher to be
@@ -66,9 +156,24 @@ assert_eq!(None, finder.find(b"quux baz bar"));
 ```
 */
 
-pub use self::prefilter::Prefilter;
+pub use self::{
+    casefold::{CaseFoldFindIter, CaseFoldFinder},
+    masked::{MaskedFindIter, MaskedFinder},
+    prefilter::Prefilter,
+};
+
+#[cfg(feature = "std")]
+pub use self::{
+    hamming::{
+        find_hamming, find_hamming_iter, HammingFindIter, HammingFinder,
+    },
+    masked::{MaskedNeedle, MaskedNeedleError},
+    multi::{MultiFindIter, MultiFinder},
+    rabinkarp::{RabinKarpFindIter, RabinKarpSet},
+};
 
 use crate::{
+    arch::{self, Backend},
     cow::CowBytes,
     memmem::{
         prefilter::{Pre, PrefilterFn, PrefilterState},
@@ -146,16 +251,41 @@ macro_rules! define_memmem_simple_tests {
 }
 
 mod byte_frequencies;
-#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+mod case_folding_simple;
+mod casefold;
+#[cfg(not(feature = "safe-only"))]
 mod genericsimd;
+#[cfg(feature = "std")]
+mod hamming;
+mod masked;
+#[cfg(feature = "std")]
+mod multi;
+#[cfg(all(not(miri), memchr_portable_simd))]
+#[allow(dead_code)]
+mod portable;
 mod prefilter;
 mod rabinkarp;
 mod rarebytes;
+#[cfg(not(feature = "safe-only"))]
+#[cfg_attr(
+    any(
+        all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+        all(not(miri), memchr_portable_simd),
+    ),
+    allow(dead_code)
+)]
+mod swar;
+#[cfg(all(
+    feature = "std",
+    not(miri),
+    any(
+        all(target_arch = "x86_64", memchr_runtime_simd),
+        memchr_portable_simd
+    )
+))]
+mod teddy;
 mod twoway;
 mod util;
-// SIMD is only supported on x86_64 currently.
-#[cfg(target_arch = "x86_64")]
-mod vector;
 #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
 mod x86;
 
@@ -226,6 +356,82 @@ pub fn rfind_iter<'h, 'n, N: 'n + ?Sized + AsRef<[u8]>>(
     FindRevIter::new(haystack, FinderRev::new(needle))
 }
 
+/// Returns an iterator over all occurrences of a substring in a haystack,
+/// including occurrences that overlap with one another.
+///
+/// Unlike [`find_iter`], which resumes searching after the end of each
+/// match, this resumes searching one byte after the start of each match.
+/// So every offset at which the needle occurs is reported.
+///
+/// # Complexity
+///
+/// Since every occurrence is reported, this routine may take
+/// `O(needle.len() * haystack.len())` time in the worst case. For example,
+/// when both the needle and the haystack consist of a single repeated byte.
+///
+/// This routine is guaranteed to have worst case constant space
+/// complexity.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use memchr::memmem;
+///
+/// let haystack = b"abababa";
+/// let mut it = memmem::find_overlapping_iter(haystack, b"aba");
+/// assert_eq!(Some(0), it.next());
+/// assert_eq!(Some(2), it.next());
+/// assert_eq!(Some(4), it.next());
+/// assert_eq!(None, it.next());
+/// ```
+#[inline]
+pub fn find_overlapping_iter<'h, 'n, N: 'n + ?Sized + AsRef<[u8]>>(
+    haystack: &'h [u8],
+    needle: &'n N,
+) -> FindIter<'h, 'n> {
+    FindIter::overlapping(haystack, Finder::new(needle))
+}
+
+/// Returns a reverse iterator over all occurrences of a substring in a
+/// haystack, including occurrences that overlap with one another.
+///
+/// Unlike [`rfind_iter`], which resumes searching before the start of each
+/// match, this resumes searching one byte before the end of each match. So
+/// every offset at which the needle occurs is reported.
+///
+/// # Complexity
+///
+/// Since every occurrence is reported, this routine may take
+/// `O(needle.len() * haystack.len())` time in the worst case. For example,
+/// when both the needle and the haystack consist of a single repeated byte.
+///
+/// This routine is guaranteed to have worst case constant space
+/// complexity.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use memchr::memmem;
+///
+/// let haystack = b"abababa";
+/// let mut it = memmem::rfind_overlapping_iter(haystack, b"aba");
+/// assert_eq!(Some(4), it.next());
+/// assert_eq!(Some(2), it.next());
+/// assert_eq!(Some(0), it.next());
+/// assert_eq!(None, it.next());
+/// ```
+#[inline]
+pub fn rfind_overlapping_iter<'h, 'n, N: 'n + ?Sized + AsRef<[u8]>>(
+    haystack: &'h [u8],
+    needle: &'n N,
+) -> FindRevIter<'h, 'n> {
+    FindRevIter::overlapping(haystack, FinderRev::new(needle))
+}
+
 /// Returns the index of the first occurrence of the given needle.
 ///
 /// Note that if you're are searching for the same needle in many different
@@ -299,9 +505,29 @@ pub fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     }
 }
 
-/// An iterator over non-overlapping substring matches.
+/// An iterator over substring matches.
+///
+/// Matches are reported by the byte offset at which they begin. Iterators
+/// created by [`find_iter`] report non-overlapping matches, while iterators
+/// created by [`find_overlapping_iter`] report every match.
+///
+/// This iterator is double ended. Calling only `next` reports the same
+/// matches as [`find_iter`], while calling only `next_back` reports the same
+/// matches as [`rfind_iter`]. When both are used, each end only searches the
+/// part of the haystack that hasn't been consumed by the other end, so the
+/// matches reported never overlap.
+///
+/// Note that when a needle can overlap with itself, searching from the back
+/// may report different matches than searching from the front. For example,
+/// `aa` is found at offset `0` in `aaa` from the front, but at offset `1`
+/// from the back.
 ///
-/// Matches are reported by the byte offset at which they begin.
+/// In overlapping mode, both ends report from the same set of matches (every
+/// offset at which the needle occurs), so each match is reported exactly
+/// once regardless of how calls to `next` and `next_back` are interleaved.
+///
+/// The reverse searcher used by `next_back` is only built when `next_back` is
+/// first called, so forward iteration doesn't pay for it.
 ///
 /// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
 /// needle.
@@ -311,6 +537,16 @@ pub struct FindIter<'h, 'n> {
     prestate: PrefilterState,
     finder: Finder<'n>,
     pos: usize,
+    /// The reverse searcher and its prefilter state, built lazily on the
+    /// first call to `next_back`.
+    rsearcher: Option<SearcherRev<'n>>,
+    rprestate: PrefilterState,
+    /// The end of the part of the haystack that hasn't been consumed by
+    /// `next_back`.
+    end: usize,
+    /// When true, searching resumes one byte past the start of the previous
+    /// match instead of at its end.
+    overlapping: bool,
 }
 
 impl<'h, 'n> FindIter<'h, 'n> {
@@ -320,7 +556,24 @@ impl<'h, 'n> FindIter<'h, 'n> {
         finder: Finder<'n>,
     ) -> FindIter<'h, 'n> {
         let prestate = finder.searcher.prefilter_state();
-        FindIter { haystack, prestate, finder, pos: 0 }
+        FindIter {
+            haystack,
+            prestate,
+            finder,
+            pos: 0,
+            rsearcher: None,
+            rprestate: PrefilterState::inert(),
+            end: haystack.len(),
+            overlapping: false,
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn overlapping(
+        haystack: &'h [u8],
+        finder: Finder<'n>,
+    ) -> FindIter<'h, 'n> {
+        FindIter { overlapping: true, ..FindIter::new(haystack, finder) }
     }
 }
 
@@ -328,37 +581,86 @@ impl<'h, 'n> Iterator for FindIter<'h, 'n> {
     type Item = usize;
 
     fn next(&mut self) -> Option<usize> {
-        if self.pos > self.haystack.len() {
+        if self.pos > self.end {
             return None;
         }
         let result = self
             .finder
             .searcher
-            .find(&mut self.prestate, &self.haystack[self.pos..]);
+            .find(&mut self.prestate, &self.haystack[self.pos..self.end]);
+        match result {
+            None => None,
+            Some(i) => {
+                let pos = self.pos + i;
+                self.pos = if self.overlapping {
+                    pos + 1
+                } else {
+                    pos + core::cmp::max(1, self.finder.needle().len())
+                };
+                Some(pos)
+            }
+        }
+    }
+}
+
+impl<'h, 'n> DoubleEndedIterator for FindIter<'h, 'n> {
+    fn next_back(&mut self) -> Option<usize> {
+        if self.pos > self.end {
+            return None;
+        }
+        if self.rsearcher.is_none() {
+            let rsearcher = self.finder.searcher.reverse();
+            self.rprestate = rsearcher.prefilter_state();
+            self.rsearcher = Some(rsearcher);
+        }
+        let rsearcher = self.rsearcher.as_ref().unwrap();
+        let result = rsearcher
+            .rfind(&mut self.rprestate, &self.haystack[self.pos..self.end]);
         match result {
             None => None,
             Some(i) => {
                 let pos = self.pos + i;
-                self.pos = pos + core::cmp::max(1, self.finder.needle().len());
+                let nlen = self.finder.needle().len();
+                if nlen > 0 && self.overlapping {
+                    // Keep all but the last byte of the match, so that
+                    // matches starting before it can still be found.
+                    self.end = pos + nlen - 1;
+                } else if nlen > 0 {
+                    self.end = pos;
+                } else if pos > self.pos {
+                    // An empty needle matches at every position, so we need
+                    // to step past the one we just reported.
+                    self.end = pos - 1;
+                } else {
+                    // This was the last position left, so make sure neither
+                    // end reports anything else.
+                    self.pos = pos + 1;
+                }
                 Some(pos)
             }
         }
     }
 }
 
-/// An iterator over non-overlapping substring matches in reverse.
+/// An iterator over substring matches in reverse.
 ///
-/// Matches are reported by the byte offset at which they begin.
+/// Matches are reported by the byte offset at which they begin. Iterators
+/// created by [`rfind_iter`] report non-overlapping matches, while iterators
+/// created by [`rfind_overlapping_iter`] report every match.
 ///
 /// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
 /// needle.
 #[derive(Debug)]
 pub struct FindRevIter<'h, 'n> {
     haystack: &'h [u8],
+    prestate: PrefilterState,
     finder: FinderRev<'n>,
     /// When searching with an empty needle, this gets set to `None` after
     /// we've yielded the last element at `0`.
     pos: Option<usize>,
+    /// When true, searching resumes one byte before the end of the previous
+    /// match instead of at its start.
+    overlapping: bool,
 }
 
 impl<'h, 'n> FindRevIter<'h, 'n> {
@@ -367,8 +669,17 @@ impl<'h, 'n> FindRevIter<'h, 'n> {
         haystack: &'h [u8],
         finder: FinderRev<'n>,
     ) -> FindRevIter<'h, 'n> {
+        let prestate = finder.searcher.prefilter_state();
         let pos = Some(haystack.len());
-        FindRevIter { haystack, finder, pos }
+        FindRevIter { haystack, prestate, finder, pos, overlapping: false }
+    }
+
+    #[inline(always)]
+    pub(crate) fn overlapping(
+        haystack: &'h [u8],
+        finder: FinderRev<'n>,
+    ) -> FindRevIter<'h, 'n> {
+        FindRevIter { overlapping: true, ..FindRevIter::new(haystack, finder) }
     }
 }
 
@@ -380,12 +691,18 @@ impl<'h, 'n> Iterator for FindRevIter<'h, 'n> {
             None => return None,
             Some(pos) => pos,
         };
-        let result = self.finder.rfind(&self.haystack[..pos]);
+        let result = self
+            .finder
+            .searcher
+            .rfind(&mut self.prestate, &self.haystack[..pos]);
         match result {
             None => None,
             Some(i) => {
+                let nlen = self.finder.needle().len();
                 if pos == i {
                     self.pos = pos.checked_sub(1);
+                } else if self.overlapping {
+                    self.pos = Some(i + nlen - 1);
                 } else {
                     self.pos = Some(i);
                 }
@@ -482,6 +799,46 @@ impl<'n> Finder<'n> {
         FindIter::new(haystack, self.as_ref())
     }
 
+    /// Returns an iterator over all occurrences of a substring in a haystack,
+    /// including occurrences that overlap with one another.
+    ///
+    /// The prefilter state is kept for the life of the iterator, so unlike
+    /// calling `find` repeatedly on `&haystack[i + 1..]`, the prefilter's
+    /// heuristics still apply across matches.
+    ///
+    /// # Complexity
+    ///
+    /// Since every occurrence is reported, this routine may take
+    /// `O(needle.len() * haystack.len())` time in the worst case. For
+    /// example, when both the needle and the haystack consist of a single
+    /// repeated byte.
+    ///
+    /// This routine is guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::Finder;
+    ///
+    /// let haystack = b"aaaa";
+    /// let finder = Finder::new(b"aa");
+    /// let mut it = finder.find_overlapping_iter(haystack);
+    /// assert_eq!(Some(0), it.next());
+    /// assert_eq!(Some(1), it.next());
+    /// assert_eq!(Some(2), it.next());
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[inline]
+    pub fn find_overlapping_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindIter<'h, 'a> {
+        FindIter::overlapping(haystack, self.as_ref())
+    }
+
     /// Convert this finder into its owned variant, such that it no longer
     /// borrows the needle.
     ///
@@ -520,6 +877,30 @@ impl<'n> Finder<'n> {
     pub fn needle(&self) -> &[u8] {
         self.searcher.needle()
     }
+
+    /// Returns the backend that this finder picked when it was built.
+    ///
+    /// For needles consisting of a single byte, this returns the backend
+    /// used by [`memchr`](crate::memchr). When none of the vectorized
+    /// searchers can handle the needle (e.g., because it's too long), then
+    /// this returns [`Backend::Fallback`], even if a vectorized prefilter is
+    /// used to find candidates.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::{arch::Backend, memmem::Finder};
+    ///
+    /// let finder = Finder::new("foo");
+    /// if finder.backend() != Backend::Fallback {
+    ///     println!("searching with vector instructions!");
+    /// }
+    /// assert_eq!(Some(4), finder.find(b"bar foo"));
+    /// ```
+    #[inline]
+    pub fn backend(&self) -> Backend {
+        self.searcher.backend()
+    }
 }
 
 /// A single substring reverse searcher fixed to a particular needle.
@@ -575,7 +956,8 @@ impl<'n> FinderRev<'n> {
     /// assert_eq!(None, FinderRev::new("quux").rfind(haystack));
     /// ```
     pub fn rfind<B: AsRef<[u8]>>(&self, haystack: B) -> Option<usize> {
-        self.searcher.rfind(haystack.as_ref())
+        self.searcher
+            .rfind(&mut self.searcher.prefilter_state(), haystack.as_ref())
     }
 
     /// Returns a reverse iterator over all occurrences of a substring in a
@@ -613,6 +995,46 @@ impl<'n> FinderRev<'n> {
         FindRevIter::new(haystack, self.as_ref())
     }
 
+    /// Returns a reverse iterator over all occurrences of a substring in a
+    /// haystack, including occurrences that overlap with one another.
+    ///
+    /// The prefilter state is kept for the life of the iterator, so unlike
+    /// calling `rfind` repeatedly on shrinking prefixes of the haystack, the
+    /// prefilter's heuristics still apply across matches.
+    ///
+    /// # Complexity
+    ///
+    /// Since every occurrence is reported, this routine may take
+    /// `O(needle.len() * haystack.len())` time in the worst case. For
+    /// example, when both the needle and the haystack consist of a single
+    /// repeated byte.
+    ///
+    /// This routine is guaranteed to have worst case constant space
+    /// complexity.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use memchr::memmem::FinderRev;
+    ///
+    /// let haystack = b"aaaa";
+    /// let finder = FinderRev::new(b"aa");
+    /// let mut it = finder.rfind_overlapping_iter(haystack);
+    /// assert_eq!(Some(2), it.next());
+    /// assert_eq!(Some(1), it.next());
+    /// assert_eq!(Some(0), it.next());
+    /// assert_eq!(None, it.next());
+    /// ```
+    #[inline]
+    pub fn rfind_overlapping_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> FindRevIter<'h, 'a> {
+        FindRevIter::overlapping(haystack, self.as_ref())
+    }
+
     /// Convert this finder into its owned variant, such that it no longer
     /// borrows the needle.
     ///
@@ -656,8 +1078,9 @@ impl<'n> FinderRev<'n> {
 /// A builder for constructing non-default forward or reverse memmem finders.
 ///
 /// A builder is primarily useful for configuring a substring searcher.
-/// Currently, the only configuration exposed is the ability to disable
-/// heuristic prefilters used to speed up certain searches.
+/// Currently, the configuration exposed is the ability to disable heuristic
+/// prefilters used to speed up certain searches, and the ability to search
+/// while ignoring ASCII case.
 #[derive(Clone, Debug, Default)]
 pub struct FinderBuilder {
     config: SearcherConfig,
@@ -684,7 +1107,8 @@ impl FinderBuilder {
         &self,
         needle: &'n B,
     ) -> FinderRev<'n> {
-        FinderRev { searcher: SearcherRev::new(needle.as_ref()) }
+        let needle = CowBytes::new(needle.as_ref());
+        FinderRev { searcher: SearcherRev::new(self.config, needle) }
     }
 
     /// Configure the prefilter setting for the finder.
@@ -695,6 +1119,70 @@ impl FinderBuilder {
         self.config.prefilter = prefilter;
         self
     }
+
+    /// Configure whether the finder ignores ASCII case.
+    ///
+    /// When enabled, an ASCII letter in the needle matches both its
+    /// lowercase and uppercase variants in the haystack. All other bytes,
+    /// including non-ASCII bytes, must match exactly. This is disabled by
+    /// default.
+    ///
+    /// This avoids the need to fold the case of the haystack into a
+    /// temporary buffer before searching it. The search still uses the same
+    /// algorithms (including vectorized candidate search), but it may be
+    /// somewhat slower than a case sensitive search.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::FinderBuilder;
+    ///
+    /// let finder = FinderBuilder::new()
+    ///     .ascii_case_insensitive(true)
+    ///     .build_forward("content-length");
+    /// let haystack = b"Host: example.com\r\nContent-Length: 42\r\n";
+    /// assert_eq!(Some(19), finder.find(haystack));
+    /// ```
+    pub fn ascii_case_insensitive(&mut self, yes: bool) -> &mut FinderBuilder {
+        self.config.ascii_case_insensitive = yes;
+        self
+    }
+
+    /// Build a finder using the given needle that matches under Unicode
+    /// simple case folding.
+    ///
+    /// The needle must be valid UTF-8. See [`CaseFoldFinder`] for details.
+    /// The prefilter configuration of this builder is respected, but since
+    /// a `CaseFoldFinder` always ignores case, the ASCII case
+    /// insensitivity setting has no effect.
+    pub fn build_case_fold<'n, B: ?Sized + AsRef<str>>(
+        &self,
+        needle: &'n B,
+    ) -> CaseFoldFinder<'n> {
+        CaseFoldFinder::with_builder(self, needle.as_ref())
+    }
+
+    /// Build a finder using the given needle and mask from the current
+    /// settings.
+    ///
+    /// Only the bits that are set in each byte of the mask have to match.
+    /// When this builder ignores ASCII case, bytes are folded to lowercase
+    /// before being masked. See [`MaskedFinder`] for details.
+    ///
+    /// # Panics
+    ///
+    /// When the needle and mask have different lengths.
+    pub fn build_masked<'n, N, M>(
+        &self,
+        needle: &'n N,
+        mask: &'n M,
+    ) -> MaskedFinder<'n>
+    where
+        N: ?Sized + AsRef<[u8]>,
+        M: ?Sized + AsRef<[u8]>,
+    {
+        MaskedFinder::with_builder(self, needle.as_ref(), mask.as_ref())
+    }
 }
 
 /// The internal implementation of a forward substring searcher.
@@ -718,6 +1206,9 @@ struct Searcher<'n> {
     /// Some substring search implementations (like Two-Way) benefit greatly
     /// if we can quickly find candidate starting positions for a match.
     prefn: Option<PrefilterFn>,
+    /// The configuration this searcher was built with. This is kept around
+    /// so that an equivalent reverse searcher can be built on demand.
+    config: SearcherConfig,
     /// The actual substring implementation in use.
     kind: SearcherKind,
 }
@@ -757,6 +1248,46 @@ struct SearcherConfig {
     /// This permits changing the behavior of the prefilter, since it can have
     /// a variable impact on performance.
     prefilter: Prefilter,
+    /// Whether ASCII letters in the needle match both of their cases.
+    ascii_case_insensitive: bool,
+}
+
+impl SearcherConfig {
+    /// Compute the needle facts for a forward searcher with this config.
+    fn needle_info(&self, needle: &[u8]) -> NeedleInfo {
+        if self.ascii_case_insensitive {
+            NeedleInfo::new_ascii_nocase(needle)
+        } else {
+            NeedleInfo::new(needle)
+        }
+    }
+
+    /// Compute the needle facts for a reverse searcher with this config.
+    fn needle_info_rev(&self, needle: &[u8]) -> NeedleInfo {
+        if self.ascii_case_insensitive {
+            NeedleInfo::reverse_ascii_nocase(needle)
+        } else {
+            NeedleInfo::reverse(needle)
+        }
+    }
+
+    /// Build a forward Two-Way searcher with this config.
+    fn twoway(&self, needle: &[u8]) -> twoway::Forward {
+        if self.ascii_case_insensitive {
+            twoway::Forward::new_ascii_nocase(needle)
+        } else {
+            twoway::Forward::new(needle)
+        }
+    }
+
+    /// Build a reverse Two-Way searcher with this config.
+    fn twoway_rev(&self, needle: &[u8]) -> twoway::Reverse {
+        if self.ascii_case_insensitive {
+            twoway::Reverse::new_ascii_nocase(needle)
+        } else {
+            twoway::Reverse::new(needle)
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -767,6 +1298,9 @@ enum SearcherKind {
     /// This is used whenever the needle is a single byte. In this case, we
     /// always use memchr.
     OneByte(u8),
+    /// This is used whenever the needle is a single byte and ASCII case is
+    /// ignored. In this case, we always use memchr_ascii_nocase.
+    OneByteAsciiNocase(u8),
     /// Two-Way is the generic work horse and is what provides our additive
     /// linear time guarantee. In general, it's used when the needle is bigger
     /// than 8 bytes or so.
@@ -775,6 +1309,18 @@ enum SearcherKind {
     GenericSIMD128(x86::sse::Forward),
     #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
     GenericSIMD256(x86::avx::Forward),
+    #[cfg(all(
+        not(miri),
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd))
+    ))]
+    GenericSIMDPortable(portable::Forward),
+    #[cfg(not(any(
+        all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+        all(not(miri), memchr_portable_simd),
+        feature = "safe-only",
+    )))]
+    GenericSIMDSwar(swar::Forward),
 }
 
 impl<'n> Searcher<'n> {
@@ -782,11 +1328,13 @@ impl<'n> Searcher<'n> {
     fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
         use self::SearcherKind::*;
 
-        let ninfo = NeedleInfo::new(needle);
+        let ninfo = config.needle_info(needle);
         let prefn =
             prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
         let kind = if needle.len() == 0 {
             Empty
+        } else if needle.len() == 1 && config.ascii_case_insensitive {
+            OneByteAsciiNocase(needle[0])
         } else if needle.len() == 1 {
             OneByte(needle[0])
         } else if let Some(fwd) = x86::avx::Forward::new(&ninfo, needle) {
@@ -794,55 +1342,149 @@ impl<'n> Searcher<'n> {
         } else if let Some(fwd) = x86::sse::Forward::new(&ninfo, needle) {
             GenericSIMD128(fwd)
         } else {
-            TwoWay(twoway::Forward::new(needle))
+            TwoWay(config.twoway(needle))
         };
-        Searcher { needle: CowBytes::new(needle), ninfo, prefn, kind }
+        Searcher { needle: CowBytes::new(needle), ninfo, prefn, config, kind }
     }
 
-    #[cfg(not(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)))]
+    #[cfg(all(
+        not(miri),
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd))
+    ))]
     fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
         use self::SearcherKind::*;
 
-        let ninfo = NeedleInfo::new(needle);
+        let ninfo = config.needle_info(needle);
         let prefn =
             prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
         let kind = if needle.len() == 0 {
             Empty
+        } else if needle.len() == 1 && config.ascii_case_insensitive {
+            OneByteAsciiNocase(needle[0])
         } else if needle.len() == 1 {
             OneByte(needle[0])
+        } else if let Some(fwd) = portable::Forward::new(&ninfo, needle) {
+            GenericSIMDPortable(fwd)
         } else {
-            TwoWay(twoway::Forward::new(needle))
+            TwoWay(config.twoway(needle))
         };
-        Searcher { needle: CowBytes::new(needle), ninfo, prefn, kind }
+        Searcher { needle: CowBytes::new(needle), ninfo, prefn, config, kind }
     }
 
-    /// Return a fresh prefilter state that can be used with this searcher.
-    /// A prefilter state is used to track the effectiveness of a searcher's
-    /// prefilter for speeding up searches. Therefore, the prefilter state
-    /// should generally be reused on subsequent searches (such as in an
-    /// iterator). For searches on a different haystack, then a new prefilter
-    /// state should be used.
-    ///
-    /// This always initializes a valid (but possibly inert) prefilter state
-    /// even if this searcher does not have a prefilter enabled.
-    fn prefilter_state(&self) -> PrefilterState {
-        if self.prefn.is_none() {
-            PrefilterState::inert()
+    #[cfg(not(any(
+        all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+        all(not(miri), memchr_portable_simd),
+        feature = "safe-only",
+    )))]
+    fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
+        use self::SearcherKind::*;
+
+        let ninfo = config.needle_info(needle);
+        let prefn =
+            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
+        let kind = if needle.len() == 0 {
+            Empty
+        } else if needle.len() == 1 && config.ascii_case_insensitive {
+            OneByteAsciiNocase(needle[0])
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else if let Some(fwd) = swar::Forward::new(&ninfo, needle) {
+            GenericSIMDSwar(fwd)
         } else {
-            PrefilterState::new()
-        }
+            TwoWay(config.twoway(needle))
+        };
+        Searcher { needle: CowBytes::new(needle), ninfo, prefn, config, kind }
     }
 
-    fn needle(&self) -> &[u8] {
+    /// With the 'safe-only' feature, none of the vectorized searchers are
+    /// available, since they all require unsafe code.
+    #[cfg(feature = "safe-only")]
+    fn new(config: SearcherConfig, needle: &'n [u8]) -> Searcher<'n> {
+        use self::SearcherKind::*;
+
+        let ninfo = config.needle_info(needle);
+        let prefn =
+            prefilter::forward(&config.prefilter, &ninfo.rarebytes, needle);
+        let kind = if needle.len() == 0 {
+            Empty
+        } else if needle.len() == 1 && config.ascii_case_insensitive {
+            OneByteAsciiNocase(needle[0])
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else {
+            TwoWay(config.twoway(needle))
+        };
+        Searcher { needle: CowBytes::new(needle), ninfo, prefn, config, kind }
+    }
+
+    /// Return a fresh prefilter state that can be used with this searcher.
+    /// A prefilter state is used to track the effectiveness of a searcher's
+    /// prefilter for speeding up searches. Therefore, the prefilter state
+    /// should generally be reused on subsequent searches (such as in an
+    /// iterator). For searches on a different haystack, then a new prefilter
+    /// state should be used.
+    ///
+    /// This always initializes a valid (but possibly inert) prefilter state
+    /// even if this searcher does not have a prefilter enabled.
+    fn prefilter_state(&self) -> PrefilterState {
+        if self.prefn.is_none() {
+            PrefilterState::inert()
+        } else {
+            PrefilterState::new()
+        }
+    }
+
+    fn needle(&self) -> &[u8] {
         self.needle.as_slice()
     }
 
+    /// Build a reverse searcher for the same needle and configuration as this
+    /// searcher.
+    fn reverse(&self) -> SearcherRev<'n> {
+        SearcherRev::new(self.config, self.needle.clone())
+    }
+
+    fn backend(&self) -> Backend {
+        use self::SearcherKind::*;
+
+        match self.kind {
+            Empty | TwoWay(_) => Backend::Fallback,
+            OneByte(_) | OneByteAsciiNocase(_) => arch::selected(),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD128(_) => Backend::Sse2,
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD256(_) => Backend::Avx2,
+            #[cfg(all(
+                not(miri),
+                memchr_portable_simd,
+                not(all(target_arch = "x86_64", memchr_runtime_simd))
+            ))]
+            GenericSIMDPortable(_) => Backend::Portable,
+            #[cfg(not(any(
+                all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+                all(not(miri), memchr_portable_simd),
+                feature = "safe-only",
+            )))]
+            GenericSIMDSwar(_) => Backend::Swar,
+        }
+    }
+
     fn as_ref(&self) -> Searcher<'_> {
         use self::SearcherKind::*;
 
         let kind = match self.kind {
             Empty => Empty,
             OneByte(b) => OneByte(b),
+            OneByteAsciiNocase(b) => OneByteAsciiNocase(b),
             TwoWay(tw) => TwoWay(tw),
             #[cfg(all(
                 not(miri),
@@ -856,11 +1498,24 @@ impl<'n> Searcher<'n> {
                 memchr_runtime_simd
             ))]
             GenericSIMD256(gs) => GenericSIMD256(gs),
+            #[cfg(all(
+                not(miri),
+                memchr_portable_simd,
+                not(all(target_arch = "x86_64", memchr_runtime_simd))
+            ))]
+            GenericSIMDPortable(gs) => GenericSIMDPortable(gs),
+            #[cfg(not(any(
+                all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+                all(not(miri), memchr_portable_simd),
+                feature = "safe-only",
+            )))]
+            GenericSIMDSwar(gs) => GenericSIMDSwar(gs),
         };
         Searcher {
             needle: CowBytes::new(self.needle()),
             ninfo: self.ninfo,
             prefn: self.prefn,
+            config: self.config,
             kind,
         }
     }
@@ -872,6 +1527,7 @@ impl<'n> Searcher<'n> {
         let kind = match self.kind {
             Empty => Empty,
             OneByte(b) => OneByte(b),
+            OneByteAsciiNocase(b) => OneByteAsciiNocase(b),
             TwoWay(tw) => TwoWay(tw),
             #[cfg(all(
                 not(miri),
@@ -885,11 +1541,24 @@ impl<'n> Searcher<'n> {
                 memchr_runtime_simd
             ))]
             GenericSIMD256(gs) => GenericSIMD256(gs),
+            #[cfg(all(
+                not(miri),
+                memchr_portable_simd,
+                not(all(target_arch = "x86_64", memchr_runtime_simd))
+            ))]
+            GenericSIMDPortable(gs) => GenericSIMDPortable(gs),
+            #[cfg(not(any(
+                all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+                all(not(miri), memchr_portable_simd),
+                feature = "safe-only",
+            )))]
+            GenericSIMDSwar(gs) => GenericSIMDSwar(gs),
         };
         Searcher {
             needle: self.needle.into_owned(),
             ninfo: self.ninfo,
             prefn: self.prefn,
+            config: self.config,
             kind,
         }
     }
@@ -912,6 +1581,7 @@ impl<'n> Searcher<'n> {
         match self.kind {
             Empty => Some(0),
             OneByte(b) => crate::memchr(b, haystack),
+            OneByteAsciiNocase(b) => crate::memchr_ascii_nocase(b, haystack),
             TwoWay(ref tw) => {
                 // For very short haystacks (e.g., where the prefilter probably
                 // can't run), it's faster to just run RK.
@@ -949,6 +1619,34 @@ impl<'n> Searcher<'n> {
                     gs.find(haystack, needle)
                 }
             }
+            #[cfg(all(
+                not(miri),
+                memchr_portable_simd,
+                not(all(target_arch = "x86_64", memchr_runtime_simd))
+            ))]
+            GenericSIMDPortable(ref gs) => {
+                // The SIMD matcher can't handle particularly short haystacks,
+                // so we fall back to RK in these cases.
+                if haystack.len() < gs.min_haystack_len() {
+                    rabinkarp::find_with(&self.ninfo.nhash, haystack, needle)
+                } else {
+                    gs.find(haystack, needle)
+                }
+            }
+            #[cfg(not(any(
+                all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+                all(not(miri), memchr_portable_simd),
+                feature = "safe-only",
+            )))]
+            GenericSIMDSwar(ref gs) => {
+                // The SWAR matcher can't handle particularly short haystacks,
+                // so we fall back to RK in these cases.
+                if haystack.len() < gs.min_haystack_len() {
+                    rabinkarp::find_with(&self.ninfo.nhash, haystack, needle)
+                } else {
+                    gs.find(haystack, needle)
+                }
+            }
         }
     }
 
@@ -991,21 +1689,50 @@ impl NeedleInfo {
             nhash: NeedleHash::forward(needle),
         }
     }
+
+    /// Like `new`, but computes facts for searching in reverse.
+    pub(crate) fn reverse(needle: &[u8]) -> NeedleInfo {
+        NeedleInfo {
+            rarebytes: RareNeedleBytes::reverse(needle),
+            nhash: NeedleHash::reverse(needle),
+        }
+    }
+
+    /// Like `new`, but computes facts for searching while ignoring ASCII
+    /// case.
+    pub(crate) fn new_ascii_nocase(needle: &[u8]) -> NeedleInfo {
+        NeedleInfo {
+            rarebytes: RareNeedleBytes::forward_ascii_nocase(needle),
+            nhash: NeedleHash::forward_ascii_nocase(needle),
+        }
+    }
+
+    /// Like `reverse`, but computes facts for searching while ignoring ASCII
+    /// case.
+    pub(crate) fn reverse_ascii_nocase(needle: &[u8]) -> NeedleInfo {
+        NeedleInfo {
+            rarebytes: RareNeedleBytes::reverse_ascii_nocase(needle),
+            nhash: NeedleHash::reverse_ascii_nocase(needle),
+        }
+    }
 }
 
 /// The internal implementation of a reverse substring searcher.
 ///
-/// See the forward searcher docs for more details. Currently, the reverse
-/// searcher is considerably simpler since it lacks prefilter support. This
-/// was done because it adds a lot of code, and more surface area to test. And
-/// in particular, it's not clear whether a prefilter on reverse searching is
-/// worth it. (If you have a compelling use case, please file an issue!)
+/// See the forward searcher docs for more details. The reverse searcher
+/// mirrors the forward searcher: it uses a vectorized searcher for small
+/// needles and prefilter-accelerated Two-Way otherwise. Its rare byte offsets
+/// are detected for reverse searching, and its prefilter reports the end of
+/// each candidate match instead of its start.
 #[derive(Clone, Debug)]
 struct SearcherRev<'n> {
     /// The actual needle we're searching for.
     needle: CowBytes<'n>,
-    /// A Rabin-Karp hash of the needle.
-    nhash: NeedleHash,
+    /// A collection of facts computed on the needle that are useful for more
+    /// than one substring search algorithm.
+    ninfo: NeedleInfo,
+    /// A reverse prefilter function, if it was deemed appropriate.
+    prefn: Option<PrefilterFn>,
     /// The actual substring implementation in use.
     kind: SearcherRevKind,
 }
@@ -1018,27 +1745,134 @@ enum SearcherRevKind {
     /// This is used whenever the needle is a single byte. In this case, we
     /// always use memchr.
     OneByte(u8),
+    /// This is used whenever the needle is a single byte and ASCII case is
+    /// ignored. In this case, we always use memchr_ascii_nocase.
+    OneByteAsciiNocase(u8),
     /// Two-Way is the generic work horse and is what provides our additive
     /// linear time guarantee. In general, it's used when the needle is bigger
     /// than 8 bytes or so.
     TwoWay(twoway::Reverse),
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    GenericSIMD128(x86::sse::Reverse),
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    GenericSIMD256(x86::avx::Reverse),
+    #[cfg(all(
+        not(miri),
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd))
+    ))]
+    GenericSIMDPortable(portable::Reverse),
+    #[cfg(not(any(
+        all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+        all(not(miri), memchr_portable_simd),
+        feature = "safe-only",
+    )))]
+    GenericSIMDSwar(swar::Reverse),
 }
 
 impl<'n> SearcherRev<'n> {
-    fn new(needle: &'n [u8]) -> SearcherRev<'n> {
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    fn new(config: SearcherConfig, needle: CowBytes<'n>) -> SearcherRev<'n> {
         use self::SearcherRevKind::*;
 
+        let ninfo = config.needle_info_rev(&needle);
+        let prefn =
+            prefilter::reverse(&config.prefilter, &ninfo.rarebytes, &needle);
         let kind = if needle.len() == 0 {
             Empty
+        } else if needle.len() == 1 && config.ascii_case_insensitive {
+            OneByteAsciiNocase(needle[0])
         } else if needle.len() == 1 {
             OneByte(needle[0])
+        } else if let Some(rev) = x86::avx::Reverse::new(&ninfo, &needle) {
+            GenericSIMD256(rev)
+        } else if let Some(rev) = x86::sse::Reverse::new(&ninfo, &needle) {
+            GenericSIMD128(rev)
         } else {
-            TwoWay(twoway::Reverse::new(needle))
+            TwoWay(config.twoway_rev(&needle))
         };
-        SearcherRev {
-            needle: CowBytes::new(needle),
-            nhash: NeedleHash::reverse(needle),
-            kind,
+        SearcherRev { needle, ninfo, prefn, kind }
+    }
+
+    #[cfg(all(
+        not(miri),
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd))
+    ))]
+    fn new(config: SearcherConfig, needle: CowBytes<'n>) -> SearcherRev<'n> {
+        use self::SearcherRevKind::*;
+
+        let ninfo = config.needle_info_rev(&needle);
+        let prefn =
+            prefilter::reverse(&config.prefilter, &ninfo.rarebytes, &needle);
+        let kind = if needle.len() == 0 {
+            Empty
+        } else if needle.len() == 1 && config.ascii_case_insensitive {
+            OneByteAsciiNocase(needle[0])
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else if let Some(rev) = portable::Reverse::new(&ninfo, &needle) {
+            GenericSIMDPortable(rev)
+        } else {
+            TwoWay(config.twoway_rev(&needle))
+        };
+        SearcherRev { needle, ninfo, prefn, kind }
+    }
+
+    #[cfg(not(any(
+        all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+        all(not(miri), memchr_portable_simd),
+        feature = "safe-only",
+    )))]
+    fn new(config: SearcherConfig, needle: CowBytes<'n>) -> SearcherRev<'n> {
+        use self::SearcherRevKind::*;
+
+        let ninfo = config.needle_info_rev(&needle);
+        let prefn =
+            prefilter::reverse(&config.prefilter, &ninfo.rarebytes, &needle);
+        let kind = if needle.len() == 0 {
+            Empty
+        } else if needle.len() == 1 && config.ascii_case_insensitive {
+            OneByteAsciiNocase(needle[0])
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else if let Some(rev) = swar::Reverse::new(&ninfo, &needle) {
+            GenericSIMDSwar(rev)
+        } else {
+            TwoWay(config.twoway_rev(&needle))
+        };
+        SearcherRev { needle, ninfo, prefn, kind }
+    }
+
+    /// With the 'safe-only' feature, none of the vectorized searchers are
+    /// available, since they all require unsafe code.
+    #[cfg(feature = "safe-only")]
+    fn new(config: SearcherConfig, needle: CowBytes<'n>) -> SearcherRev<'n> {
+        use self::SearcherRevKind::*;
+
+        let ninfo = config.needle_info_rev(&needle);
+        let prefn =
+            prefilter::reverse(&config.prefilter, &ninfo.rarebytes, &needle);
+        let kind = if needle.len() == 0 {
+            Empty
+        } else if needle.len() == 1 && config.ascii_case_insensitive {
+            OneByteAsciiNocase(needle[0])
+        } else if needle.len() == 1 {
+            OneByte(needle[0])
+        } else {
+            TwoWay(config.twoway_rev(&needle))
+        };
+        SearcherRev { needle, ninfo, prefn, kind }
+    }
+
+    /// Return a fresh prefilter state that can be used with this searcher.
+    ///
+    /// See the forward searcher's method of the same name for more details.
+    fn prefilter_state(&self) -> PrefilterState {
+        if self.prefn.is_none() {
+            PrefilterState::inert()
+        } else {
+            PrefilterState::new()
         }
     }
 
@@ -1052,11 +1886,37 @@ impl<'n> SearcherRev<'n> {
         let kind = match self.kind {
             Empty => Empty,
             OneByte(b) => OneByte(b),
+            OneByteAsciiNocase(b) => OneByteAsciiNocase(b),
             TwoWay(tw) => TwoWay(tw),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD128(gs) => GenericSIMD128(gs),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD256(gs) => GenericSIMD256(gs),
+            #[cfg(all(
+                not(miri),
+                memchr_portable_simd,
+                not(all(target_arch = "x86_64", memchr_runtime_simd))
+            ))]
+            GenericSIMDPortable(gs) => GenericSIMDPortable(gs),
+            #[cfg(not(any(
+                all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+                all(not(miri), memchr_portable_simd),
+                feature = "safe-only",
+            )))]
+            GenericSIMDSwar(gs) => GenericSIMDSwar(gs),
         };
         SearcherRev {
             needle: CowBytes::new(self.needle()),
-            nhash: self.nhash,
+            ninfo: self.ninfo,
+            prefn: self.prefn,
             kind,
         }
     }
@@ -1068,11 +1928,37 @@ impl<'n> SearcherRev<'n> {
         let kind = match self.kind {
             Empty => Empty,
             OneByte(b) => OneByte(b),
+            OneByteAsciiNocase(b) => OneByteAsciiNocase(b),
             TwoWay(tw) => TwoWay(tw),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD128(gs) => GenericSIMD128(gs),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD256(gs) => GenericSIMD256(gs),
+            #[cfg(all(
+                not(miri),
+                memchr_portable_simd,
+                not(all(target_arch = "x86_64", memchr_runtime_simd))
+            ))]
+            GenericSIMDPortable(gs) => GenericSIMDPortable(gs),
+            #[cfg(not(any(
+                all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+                all(not(miri), memchr_portable_simd),
+                feature = "safe-only",
+            )))]
+            GenericSIMDSwar(gs) => GenericSIMDSwar(gs),
         };
         SearcherRev {
             needle: self.needle.into_owned(),
-            nhash: self.nhash,
+            ninfo: self.ninfo,
+            prefn: self.prefn,
             kind,
         }
     }
@@ -1081,7 +1967,11 @@ impl<'n> SearcherRev<'n> {
     /// chosen at construction and executing it on the given haystack with the
     /// prefilter's current state of effectiveness.
     #[inline(always)]
-    fn rfind(&self, haystack: &[u8]) -> Option<usize> {
+    fn rfind(
+        &self,
+        state: &mut PrefilterState,
+        haystack: &[u8],
+    ) -> Option<usize> {
         use self::SearcherRevKind::*;
 
         let needle = self.needle();
@@ -1091,17 +1981,95 @@ impl<'n> SearcherRev<'n> {
         match self.kind {
             Empty => Some(haystack.len()),
             OneByte(b) => crate::memrchr(b, haystack),
+            OneByteAsciiNocase(b) => crate::memrchr_ascii_nocase(b, haystack),
             TwoWay(ref tw) => {
                 // For very short haystacks (e.g., where the prefilter probably
                 // can't run), it's faster to just run RK.
                 if rabinkarp::is_fast(haystack, needle) {
-                    rabinkarp::rfind_with(&self.nhash, haystack, needle)
+                    rabinkarp::rfind_with(&self.ninfo.nhash, haystack, needle)
+                } else {
+                    self.rfind_tw(tw, state, haystack, needle)
+                }
+            }
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD128(ref gs) => {
+                // The SIMD matcher can't handle particularly short haystacks,
+                // so we fall back to RK in these cases.
+                if haystack.len() < gs.min_haystack_len() {
+                    rabinkarp::rfind_with(&self.ninfo.nhash, haystack, needle)
+                } else {
+                    gs.rfind(haystack, needle)
+                }
+            }
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            GenericSIMD256(ref gs) => {
+                // The SIMD matcher can't handle particularly short haystacks,
+                // so we fall back to RK in these cases.
+                if haystack.len() < gs.min_haystack_len() {
+                    rabinkarp::rfind_with(&self.ninfo.nhash, haystack, needle)
+                } else {
+                    gs.rfind(haystack, needle)
+                }
+            }
+            #[cfg(all(
+                not(miri),
+                memchr_portable_simd,
+                not(all(target_arch = "x86_64", memchr_runtime_simd))
+            ))]
+            GenericSIMDPortable(ref gs) => {
+                // The SIMD matcher can't handle particularly short haystacks,
+                // so we fall back to RK in these cases.
+                if haystack.len() < gs.min_haystack_len() {
+                    rabinkarp::rfind_with(&self.ninfo.nhash, haystack, needle)
                 } else {
-                    tw.rfind(haystack, needle)
+                    gs.rfind(haystack, needle)
+                }
+            }
+            #[cfg(not(any(
+                all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+                all(not(miri), memchr_portable_simd),
+                feature = "safe-only",
+            )))]
+            GenericSIMDSwar(ref gs) => {
+                // The SWAR matcher can't handle particularly short haystacks,
+                // so we fall back to RK in these cases.
+                if haystack.len() < gs.min_haystack_len() {
+                    rabinkarp::rfind_with(&self.ninfo.nhash, haystack, needle)
+                } else {
+                    gs.rfind(haystack, needle)
                 }
             }
         }
     }
+
+    /// Calls Two-Way on the given haystack/needle in reverse.
+    ///
+    /// This is marked as unlineable for the same reasons as the forward
+    /// searcher's `find_tw`.
+    #[inline(never)]
+    fn rfind_tw(
+        &self,
+        tw: &twoway::Reverse,
+        state: &mut PrefilterState,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        if let Some(prefn) = self.prefn {
+            if state.is_effective() {
+                let mut pre = Pre { state, prefn, ninfo: &self.ninfo };
+                return tw.rfind(Some(&mut pre), haystack, needle);
+            }
+        }
+        tw.rfind(None, haystack, needle)
+    }
 }
 
 /// This module defines some generic quickcheck properties useful for testing
@@ -1116,6 +2084,216 @@ mod proptests {
     // defined at the top of this file.
     define_memmem_quickcheck_tests!(super::find, super::rfind);
 
+    quickcheck::quickcheck! {
+        fn qc_iter_rev_matches_rfind_iter(
+            haystack: Vec<u8>,
+            needle: Vec<u8>
+        ) -> bool {
+            let (haystack, needle) = small_alphabet(&haystack, &needle);
+            let got: Vec<usize> =
+                super::find_iter(&haystack, &needle).rev().collect();
+            let expected: Vec<usize> =
+                super::rfind_iter(&haystack, &needle).collect();
+            got == expected
+        }
+
+        fn qc_iter_double_ended_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            dirs: Vec<bool>
+        ) -> bool {
+            let (haystack, needle) = small_alphabet(&haystack, &needle);
+            if needle.is_empty() {
+                return true;
+            }
+            double_ended_matches_naive(&haystack, &needle, &dirs)
+        }
+
+        fn qc_overlapping_iter_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<u8>
+        ) -> bool {
+            let (haystack, needle) = small_alphabet(&haystack, &needle);
+            let expected = naive_find_all(&haystack, &needle);
+            let got: Vec<usize> =
+                super::find_overlapping_iter(&haystack, &needle).collect();
+            assert_eq!(expected, got);
+            let got: Vec<usize> =
+                super::rfind_overlapping_iter(&haystack, &needle).collect();
+            let expected: Vec<usize> = expected.into_iter().rev().collect();
+            got == expected
+        }
+
+        fn qc_overlapping_iter_double_ended_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<u8>,
+            dirs: Vec<bool>
+        ) -> bool {
+            let (haystack, needle) = small_alphabet(&haystack, &needle);
+            let mut it = super::find_overlapping_iter(&haystack, &needle);
+            let (mut front, mut back) = (vec![], vec![]);
+            for k in 0.. {
+                let forward = dirs.get(k).cloned().unwrap_or(k % 2 == 0);
+                let m = if forward { it.next() } else { it.next_back() };
+                match m {
+                    None => break,
+                    Some(i) if forward => front.push(i),
+                    Some(i) => back.push(i),
+                }
+            }
+            assert_eq!(None, it.next());
+            assert_eq!(None, it.next_back());
+            front.extend(back.into_iter().rev());
+            front == naive_find_all(&haystack, &needle)
+        }
+
+        fn qc_ascii_nocase_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<u8>
+        ) -> bool {
+            let (haystack, mut needle) = (
+                nocase_alphabet(&haystack),
+                nocase_alphabet(&needle),
+            );
+            needle.truncate(4);
+            matches_naive_ascii_nocase(false, &haystack, &needle, find_nocase)
+                && matches_naive_ascii_nocase(
+                    true,
+                    &haystack,
+                    &needle,
+                    rfind_nocase,
+                )
+        }
+
+        fn qc_ascii_nocase_finds_planted_needle(
+            haystack: Vec<u8>,
+            start: usize,
+            len: usize,
+            flips: Vec<bool>
+        ) -> bool {
+            if haystack.is_empty() {
+                return true;
+            }
+            let start = start % haystack.len();
+            let end = start + len % (haystack.len() - start + 1);
+            let needle: Vec<u8> = haystack[start..end]
+                .iter()
+                .zip(flips.iter().cloned().chain(core::iter::repeat(true)))
+                .map(|(&b, flip)| if flip { flip_ascii_case(b) } else { b })
+                .collect();
+            matches_naive_ascii_nocase(false, &haystack, &needle, find_nocase)
+                && matches_naive_ascii_nocase(
+                    true,
+                    &haystack,
+                    &needle,
+                    rfind_nocase,
+                )
+        }
+    }
+
+    fn find_nocase(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        super::FinderBuilder::new()
+            .ascii_case_insensitive(true)
+            .build_forward(needle)
+            .find(haystack)
+    }
+
+    fn rfind_nocase(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        super::FinderBuilder::new()
+            .ascii_case_insensitive(true)
+            .build_reverse(needle)
+            .rfind(haystack)
+    }
+
+    /// Map each of the given bytes to a byte in `alphabet`. Quickcheck rarely
+    /// generates haystacks and needles that match, so tests use this to make
+    /// matches common.
+    pub(crate) fn to_alphabet(alphabet: &[u8], bs: &[u8]) -> Vec<u8> {
+        bs.iter().map(|&b| alphabet[b as usize % alphabet.len()]).collect()
+    }
+
+    /// Map the given bytes to a small alphabet of letters in both cases,
+    /// along with '@' and '`', which differ only in the ASCII case bit but
+    /// are not letters.
+    pub(crate) fn nocase_alphabet(bs: &[u8]) -> Vec<u8> {
+        to_alphabet(b"aAbB@`", bs)
+    }
+
+    /// Swap the case of the given byte if it's an ASCII letter.
+    fn flip_ascii_case(b: u8) -> u8 {
+        if b.is_ascii_lowercase() {
+            b.to_ascii_uppercase()
+        } else {
+            b.to_ascii_lowercase()
+        }
+    }
+
+    /// Return the starting offset of every match of `needle` in `haystack`,
+    /// including overlapping matches.
+    fn naive_find_all(haystack: &[u8], needle: &[u8]) -> Vec<usize> {
+        if needle.len() > haystack.len() {
+            return vec![];
+        }
+        (0..=haystack.len() - needle.len())
+            .filter(|&i| &haystack[i..i + needle.len()] == needle)
+            .collect()
+    }
+
+    /// Map the given haystack and needle to a small alphabet and cap the
+    /// length of the needle, so that matches (including overlapping ones)
+    /// are common.
+    fn small_alphabet(haystack: &[u8], needle: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut needle = to_alphabet(b"abc", needle);
+        needle.truncate(4);
+        (to_alphabet(b"abc", haystack), needle)
+    }
+
+    /// Consume a double ended substring iterator from both ends, choosing
+    /// the end to advance from `dirs` (and alternating once it runs out), and
+    /// check the matches against a naive search.
+    fn double_ended_matches_naive(
+        haystack: &[u8],
+        needle: &[u8],
+        dirs: &[bool],
+    ) -> bool {
+        let mut it = super::find_iter(haystack, needle);
+        let (mut front, mut back) = (vec![], vec![]);
+        for k in 0.. {
+            let forward = dirs.get(k).cloned().unwrap_or(k % 2 == 0);
+            let m = if forward { it.next() } else { it.next_back() };
+            match m {
+                None => break,
+                Some(i) if forward => front.push(i),
+                Some(i) => back.push(i),
+            }
+        }
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next_back());
+
+        // Each end reports a prefix of what it would report on its own.
+        let (mut all_front, mut all_back) = (vec![], vec![]);
+        let mut pos = 0;
+        while let Some(i) = naive_find(&haystack[pos..], needle) {
+            all_front.push(pos + i);
+            pos += i + needle.len();
+        }
+        let mut end = haystack.len();
+        while let Some(i) = naive_rfind(&haystack[..end], needle) {
+            all_back.push(i);
+            end = i;
+        }
+        assert_eq!(&all_front[..front.len()], &front[..]);
+        assert_eq!(&all_back[..back.len()], &back[..]);
+
+        // The ends never overlap, and once both are exhausted, no match is
+        // left between them.
+        let start = front.last().map_or(0, |&i| i + needle.len());
+        let end = back.last().cloned().unwrap_or(haystack.len());
+        assert!(start <= end, "front: {:?}, back: {:?}", front, back);
+        assert_eq!(None, naive_find(&haystack[start..end], needle));
+        true
+    }
+
     /// Check that every prefix of the given byte string is a substring.
     pub(crate) fn prefix_is_substring(
         reverse: bool,
@@ -1171,6 +2349,28 @@ mod proptests {
         }
     }
 
+    /// Like `matches_naive`, but for searches that ignore ASCII case.
+    pub(crate) fn matches_naive_ascii_nocase(
+        reverse: bool,
+        haystack: &[u8],
+        needle: &[u8],
+        mut search: impl FnMut(&[u8], &[u8]) -> Option<usize>,
+    ) -> bool {
+        let h = haystack.to_ascii_lowercase();
+        let n = needle.to_ascii_lowercase();
+        let expected =
+            if reverse { naive_rfind(&h, &n) } else { naive_find(&h, &n) };
+        assert_eq!(
+            expected,
+            search(haystack, needle),
+            "reverse: {:?}, haystack: {:?}, needle: {:?}",
+            reverse,
+            haystack,
+            needle,
+        );
+        true
+    }
+
     /// Naively search forwards for the given needle in the given haystack.
     fn naive_find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
         if needle.is_empty() {
@@ -1209,6 +2409,154 @@ mod proptests {
 mod testsimples {
     define_memmem_simple_tests!(super::find, super::rfind);
 
+    #[test]
+    fn find_iter_double_ended() {
+        let mut it = super::find_iter(b"foo bar foo", "foo");
+        assert_eq!(Some(8), it.next_back());
+        assert_eq!(Some(0), it.next());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next_back());
+
+        // The ends stop when they meet, even if a match straddles the point
+        // at which they meet.
+        let mut it = super::find_iter(b"aaaaa", "aa");
+        assert_eq!(Some(0), it.next());
+        assert_eq!(Some(3), it.next_back());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next_back());
+
+        // A self-overlapping needle can be found at different offsets from
+        // each end.
+        let mut it = super::find_iter(b"aaa", "aa");
+        assert_eq!(Some(1), it.next_back());
+        assert_eq!(None, it.next());
+
+        let mut it = super::find_iter(b"abc", "");
+        assert_eq!(Some(0), it.next());
+        assert_eq!(Some(3), it.next_back());
+        assert_eq!(Some(1), it.next());
+        assert_eq!(Some(2), it.next_back());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next_back());
+
+        let mut it = super::find_iter(b"", "");
+        assert_eq!(Some(0), it.next_back());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next_back());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn ascii_case_insensitive() {
+        use super::FinderBuilder;
+
+        let find = |haystack: &[u8], needle: &str| {
+            FinderBuilder::new()
+                .ascii_case_insensitive(true)
+                .build_forward(needle)
+                .find(haystack)
+        };
+        let rfind = |haystack: &[u8], needle: &str| {
+            FinderBuilder::new()
+                .ascii_case_insensitive(true)
+                .build_reverse(needle)
+                .rfind(haystack)
+        };
+
+        assert_eq!(Some(4), find(b"foo BAR bar", "bar"));
+        assert_eq!(Some(8), rfind(b"foo BAR bar", "BAR"));
+        assert_eq!(Some(1), find(b"xYz", "y"));
+        assert_eq!(Some(1), rfind(b"xYzz", "y"));
+        assert_eq!(Some(0), find(b"abc", ""));
+        assert_eq!(Some(3), rfind(b"abc", ""));
+        // Only letters ignore case, even though '@' and '`' differ in the
+        // same bit as the two cases of a letter.
+        assert_eq!(None, find(b"`", "@"));
+        assert_eq!(None, find(b"a`b", "A@B"));
+        assert_eq!(Some(3), find(b"a`bA@B", "a@b"));
+        assert_eq!(Some(0), rfind(b"A@Ba`b", "a@b"));
+        assert_eq!(None, find(b"[\\]", "{|}"));
+        // Non-ASCII bytes must match exactly, even though the UTF-8
+        // encodings of 'À' and 'à' also differ only in the ASCII case bit.
+        assert_eq!(None, find("À".as_bytes(), "à"));
+        assert_eq!(Some(1), find("xÀ".as_bytes(), "À"));
+
+        // Needles of various lengths planted in long haystacks, so that the
+        // vector searchers, the prefilters and Two-Way all get exercised.
+        let needles = [
+            "zq",
+            "Sherlock",
+            "sherlock holmes",
+            "The Quick Brown Fox Jumps Over The Lazy Dog",
+            "a needle that is long enough to exceed the length of any vector",
+        ];
+        for needle in needles.iter() {
+            for &pos in [0, 1, 15, 16, 31, 32, 33, 500, 1000].iter() {
+                let mut haystack = vec![b'.'; 1000 + needle.len()];
+                haystack[pos..pos + needle.len()]
+                    .copy_from_slice(needle.to_ascii_uppercase().as_bytes());
+                let lower = needle.to_ascii_lowercase();
+                assert_eq!(Some(pos), find(&haystack, &lower));
+                assert_eq!(Some(pos), rfind(&haystack, &lower));
+                assert_eq!(Some(pos), find(&haystack, needle));
+                assert_eq!(Some(pos), rfind(&haystack, needle));
+
+                let haystack = haystack.to_ascii_lowercase();
+                let upper = needle.to_ascii_uppercase();
+                assert_eq!(Some(pos), find(&haystack, &upper));
+                assert_eq!(Some(pos), rfind(&haystack, &upper));
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn ascii_case_insensitive_iter() {
+        let finder = super::FinderBuilder::new()
+            .ascii_case_insensitive(true)
+            .build_forward("ab");
+        let got: Vec<usize> = finder.find_iter(b"ab AB aB Ab a b").collect();
+        assert_eq!(vec![0, 3, 6, 9], got);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn find_overlapping_iter() {
+        let got: Vec<usize> =
+            super::find_overlapping_iter(b"aaaaa", "aa").collect();
+        assert_eq!(vec![0, 1, 2, 3], got);
+        let got: Vec<usize> =
+            super::rfind_overlapping_iter(b"aaaaa", "aa").collect();
+        assert_eq!(vec![3, 2, 1, 0], got);
+
+        let got: Vec<usize> =
+            super::find_overlapping_iter(b"abc", "").collect();
+        assert_eq!(vec![0, 1, 2, 3], got);
+        let got: Vec<usize> =
+            super::rfind_overlapping_iter(b"abc", "").collect();
+        assert_eq!(vec![3, 2, 1, 0], got);
+
+        // Both ends report from the same set of matches.
+        let mut it = super::find_overlapping_iter(b"aaaaa", "aa");
+        assert_eq!(Some(0), it.next());
+        assert_eq!(Some(3), it.next_back());
+        assert_eq!(Some(1), it.next());
+        assert_eq!(Some(2), it.next_back());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next_back());
+
+        // A haystack long enough for the vectorized searchers and the
+        // prefilter to be used.
+        let mut haystack = vec![b'z'; 1000];
+        haystack[500..506].copy_from_slice(b"xyxyxy");
+        let got: Vec<usize> =
+            super::find_overlapping_iter(&haystack, "xyxy").collect();
+        assert_eq!(vec![500, 502], got);
+        let got: Vec<usize> =
+            super::rfind_overlapping_iter(&haystack, "xyxy").collect();
+        assert_eq!(vec![502, 500], got);
+    }
+
     /// Each test is a (needle, haystack, expected_fwd, expected_rev) tuple.
     type SearchTest =
         (&'static str, &'static str, Option<usize>, Option<usize>);
@@ -0,0 +1,597 @@
+/*!
+This module implements searching for any of several needles in a single pass
+over the haystack.
+
+For small sets of needles, the Teddy algorithm (see src/memmem/teddy.rs) is
+used when the target supports the vector shuffles it needs. Otherwise, and
+for haystacks too short to fill a vector, candidates are found by searching
+for the rarest byte of every needle at once with a [`ByteSet`], and then
+verified by comparing each needle whose rarest byte was found.
+*/
+
+use core::cmp;
+
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+use core::arch::x86_64::__m128i;
+
+use crate::{
+    cow::CowBytes,
+    memchr::ByteSet,
+    memmem::{rarebytes::RareNeedleBytes, util::is_prefix},
+};
+
+#[cfg(any(
+    all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+    all(not(miri), memchr_portable_simd),
+))]
+use crate::memmem::teddy::{self, Teddy};
+
+/// A substring searcher for a set of needles, which reports the leftmost
+/// occurrence of any of them in a single pass over the haystack.
+///
+/// This is like running a [`Finder`](crate::memmem::Finder) for each needle
+/// and keeping the leftmost match, except that the haystack is only scanned
+/// once. If more than one needle matches at the leftmost position, then the
+/// needle that was given first is reported. Matches are reported as the
+/// starting offset of the match along with the index of the needle that
+/// matched.
+///
+/// Searching is fastest for small sets of needles (up to 64), since those
+/// can be searched for with a vectorized algorithm on `x86_64` (with SSSE3
+/// or AVX2) and when the `nightly` feature is enabled. Its false positive
+/// rate depends mostly on how distinct the first three bytes of the needles
+/// are. Larger sets are searched for using the rarest byte in each needle.
+///
+/// Unlike `Finder`, this makes no guarantee about worst case time
+/// complexity, since every candidate is verified by comparing the needles
+/// directly.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::MultiFinder;
+///
+/// let finder = MultiFinder::new(&["apple", "banana", "cherry"]);
+/// let haystack = b"cherry pie and banana bread";
+/// assert_eq!(Some((0, 2)), finder.find(haystack));
+/// assert_eq!(Some((16, 1)), finder.find(b"apricot pie and banana bread"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultiFinder<'n> {
+    needles: Vec<CowBytes<'n>>,
+    searcher: MultiSearcher,
+}
+
+/// The searcher used by a `MultiFinder`, which doesn't depend on the
+/// lifetime of its needles.
+#[derive(Clone, Debug)]
+struct MultiSearcher {
+    kind: MultiSearcherKind,
+    /// The rare byte searcher is always available, since it works on every
+    /// target and for every haystack.
+    rarebytes: RareBytes,
+}
+
+#[derive(Clone, Debug)]
+enum MultiSearcherKind {
+    /// There are no needles, so nothing ever matches.
+    Empty,
+    /// The needle at the given index is empty, and so every position in the
+    /// haystack matches (although possibly with a smaller needle index).
+    EmptyNeedle(usize),
+    /// Candidates are found by looking for the rarest byte of every needle.
+    RareBytes,
+    /// Teddy with SSSE3 vectors. This is also used with AVX2 for haystacks
+    /// that are too short for 32 byte vectors.
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    Teddy128(Teddy),
+    /// Teddy with AVX2 vectors.
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    Teddy256(Teddy),
+    /// Teddy with the vectors from `core::simd`.
+    #[cfg(all(
+        not(miri),
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd))
+    ))]
+    TeddyPortable(Teddy),
+}
+
+impl<'n> MultiFinder<'n> {
+    /// Create a new finder for the given needles.
+    ///
+    /// The index reported for a match is the position of the needle that
+    /// matched in the order given here.
+    pub fn new<I, B>(needles: I) -> MultiFinder<'n>
+    where
+        I: IntoIterator<Item = &'n B>,
+        B: 'n + ?Sized + AsRef<[u8]>,
+    {
+        let needles: Vec<CowBytes<'n>> =
+            needles.into_iter().map(|n| CowBytes::new(n)).collect();
+        let searcher = MultiSearcher::new(&needles);
+        MultiFinder { needles, searcher }
+    }
+
+    /// Returns the leftmost occurrence of any of this finder's needles in
+    /// the given haystack, as its starting offset and the index of the
+    /// needle that matched.
+    ///
+    /// If more than one needle matches at the leftmost position, then the
+    /// smallest index is reported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::MultiFinder;
+    ///
+    /// let finder = MultiFinder::new(&["Sam", "Samwise"]);
+    /// assert_eq!(Some((4, 0)), finder.find(b"Mr. Samwise Gamgee"));
+    /// assert_eq!(None, finder.find(b"Frodo"));
+    /// ```
+    pub fn find(&self, haystack: &[u8]) -> Option<(usize, usize)> {
+        self.searcher.find(&self.needles, haystack)
+    }
+
+    /// Returns an iterator over all non-overlapping occurrences of any of
+    /// this finder's needles in the given haystack. Each occurrence is
+    /// reported as its starting offset and the index of the needle that
+    /// matched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::MultiFinder;
+    ///
+    /// let finder = MultiFinder::new(&["foo", "bar", "oba"]);
+    /// let got: Vec<(usize, usize)> =
+    ///     finder.find_iter(b"foobar barfoo").collect();
+    /// assert_eq!(vec![(0, 0), (3, 1), (7, 1), (10, 0)], got);
+    /// ```
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> MultiFindIter<'h, 'a> {
+        MultiFindIter { haystack, finder: self, pos: 0 }
+    }
+
+    /// Convert this finder into its owned variant, such that it no longer
+    /// borrows the needles.
+    ///
+    /// This copies any needles that are borrowed.
+    #[inline]
+    pub fn into_owned(self) -> MultiFinder<'static> {
+        MultiFinder {
+            needles: self
+                .needles
+                .into_iter()
+                .map(|n| n.into_owned())
+                .collect(),
+            searcher: self.searcher,
+        }
+    }
+
+    /// Returns the number of needles in this finder.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.needles.len()
+    }
+
+    /// Returns true if and only if this finder has no needles.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.needles.is_empty()
+    }
+
+    /// Returns the needle at the given index.
+    ///
+    /// # Panics
+    ///
+    /// When `index` is not less than `self.len()`.
+    #[inline]
+    pub fn needle(&self, index: usize) -> &[u8] {
+        self.needles[index].as_slice()
+    }
+}
+
+/// An iterator over non-overlapping matches of a [`MultiFinder`].
+///
+/// Matches are reported as their starting offset and the index of the
+/// needle that matched.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needles.
+#[derive(Debug)]
+pub struct MultiFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    finder: &'n MultiFinder<'n>,
+    pos: usize,
+}
+
+impl<'h, 'n> Iterator for MultiFindIter<'h, 'n> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        if self.pos > self.haystack.len() {
+            return None;
+        }
+        let (start, id) = self.finder.find(&self.haystack[self.pos..])?;
+        let start = self.pos + start;
+        let len = self.finder.needle(id).len();
+        self.pos = start + cmp::max(1, len);
+        Some((start, id))
+    }
+}
+
+impl MultiSearcher {
+    fn new(needles: &[CowBytes<'_>]) -> MultiSearcher {
+        use self::MultiSearcherKind::*;
+
+        let rarebytes = self::RareBytes::new(needles);
+        let kind = if needles.is_empty() {
+            Empty
+        } else if let Some(i) = needles.iter().position(|n| n.is_empty()) {
+            EmptyNeedle(i)
+        } else {
+            MultiSearcher::teddy(needles).unwrap_or(RareBytes)
+        };
+        MultiSearcher { kind, rarebytes }
+    }
+
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    fn teddy(needles: &[CowBytes<'_>]) -> Option<MultiSearcherKind> {
+        use crate::{
+            arch::{self, Backend},
+            memchr::x86,
+        };
+
+        let teddy = Teddy::new(needles)?;
+        if arch::allows(Backend::Avx2) && x86::is_avx2_available() {
+            Some(MultiSearcherKind::Teddy256(teddy))
+        } else if arch::allows(Backend::Sse2)
+            && is_x86_feature_detected!("ssse3")
+        {
+            Some(MultiSearcherKind::Teddy128(teddy))
+        } else {
+            None
+        }
+    }
+
+    #[cfg(all(
+        not(miri),
+        memchr_portable_simd,
+        not(all(target_arch = "x86_64", memchr_runtime_simd))
+    ))]
+    fn teddy(needles: &[CowBytes<'_>]) -> Option<MultiSearcherKind> {
+        Teddy::new(needles).map(MultiSearcherKind::TeddyPortable)
+    }
+
+    #[cfg(not(any(
+        all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+        all(not(miri), memchr_portable_simd),
+    )))]
+    fn teddy(_needles: &[CowBytes<'_>]) -> Option<MultiSearcherKind> {
+        None
+    }
+
+    #[inline(always)]
+    fn find(
+        &self,
+        needles: &[CowBytes<'_>],
+        haystack: &[u8],
+    ) -> Option<(usize, usize)> {
+        use self::MultiSearcherKind::*;
+
+        match self.kind {
+            Empty => None,
+            EmptyNeedle(i) => needles[..i]
+                .iter()
+                .position(|n| is_prefix(haystack, n.as_slice()))
+                .or(Some(i))
+                .map(|id| (0, id)),
+            RareBytes => self.rarebytes.find(needles, haystack),
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            Teddy128(ref teddy) => {
+                if haystack.len() < teddy.min_haystack_len::<__m128i>() {
+                    return self.rarebytes.find(needles, haystack);
+                }
+                // SAFETY: Teddy128 is only constructed when SSSE3 is
+                // available.
+                unsafe { teddy::find_ssse3(teddy, needles, haystack) }
+            }
+            #[cfg(all(
+                not(miri),
+                target_arch = "x86_64",
+                memchr_runtime_simd
+            ))]
+            Teddy256(ref teddy) => {
+                if haystack.len() < teddy.min_haystack_len::<__m128i>() {
+                    return self.rarebytes.find(needles, haystack);
+                }
+                // SAFETY: Teddy256 is only constructed when AVX2 is
+                // available.
+                unsafe { teddy::find_avx2(teddy, needles, haystack) }
+            }
+            #[cfg(all(
+                not(miri),
+                memchr_portable_simd,
+                not(all(target_arch = "x86_64", memchr_runtime_simd))
+            ))]
+            TeddyPortable(ref teddy) => {
+                use core::simd::u8x16;
+
+                if haystack.len() < teddy.min_haystack_len::<u8x16>() {
+                    return self.rarebytes.find(needles, haystack);
+                }
+                // SAFETY: The portable vectors are always available.
+                unsafe { teddy.find::<u8x16>(needles, haystack) }
+            }
+        }
+    }
+}
+
+/// A searcher that finds candidates by looking for the rarest byte (as
+/// predicted by its frequency rank) of every needle at once.
+#[derive(Clone, Debug)]
+struct RareBytes {
+    /// The rarest byte of every needle.
+    set: ByteSet,
+    /// For each byte value, the index of every needle whose rarest byte it
+    /// is, along with the offset of that byte in the needle.
+    needles: Vec<Vec<(usize, usize)>>,
+    /// The largest offset in `needles`.
+    max_offset: usize,
+}
+
+impl RareBytes {
+    fn new(needles: &[CowBytes<'_>]) -> RareBytes {
+        let mut rare = RareBytes {
+            set: ByteSet::empty(),
+            needles: vec![vec![]; 256],
+            max_offset: 0,
+        };
+        for (i, needle) in needles.iter().enumerate() {
+            let needle = needle.as_slice();
+            if needle.is_empty() {
+                continue;
+            }
+            let (offset, _) = RareNeedleBytes::forward(needle).as_rare_usize();
+            rare.set.insert(needle[offset]);
+            rare.needles[needle[offset] as usize].push((i, offset));
+            rare.max_offset = cmp::max(rare.max_offset, offset);
+        }
+        rare
+    }
+
+    /// Returns the leftmost match of any non-empty needle, as its starting
+    /// offset and the index of the needle. Ties are broken in favor of the
+    /// smallest index.
+    fn find(
+        &self,
+        needles: &[CowBytes<'_>],
+        haystack: &[u8],
+    ) -> Option<(usize, usize)> {
+        let mut found: Option<(usize, usize)> = None;
+        let mut at = 0;
+        while let Some(i) = self.set.find(&haystack[at..]) {
+            let pos = at + i;
+            // Candidates are visited in order of the position of the rare
+            // byte, which isn't the same as the order of the positions at
+            // which their needles start. But no needle with a rare byte
+            // beyond this point can start at or before a match found
+            // already.
+            if let Some((start, _)) = found {
+                if pos > start + self.max_offset {
+                    break;
+                }
+            }
+            for &(id, offset) in &self.needles[haystack[pos] as usize] {
+                if pos < offset {
+                    continue;
+                }
+                let candidate = (pos - offset, id);
+                if let Some(found) = found {
+                    if found <= candidate {
+                        continue;
+                    }
+                }
+                if is_prefix(&haystack[pos - offset..], needles[id].as_slice())
+                {
+                    found = Some(candidate);
+                }
+            }
+            at = pos + 1;
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The naive reference implementation: try every needle at every
+    /// position.
+    fn naive(needles: &[&[u8]], haystack: &[u8]) -> Option<(usize, usize)> {
+        (0..=haystack.len()).find_map(|start| {
+            needles
+                .iter()
+                .position(|n| haystack[start..].starts_with(n))
+                .map(|id| (start, id))
+        })
+    }
+
+    fn cows<'a>(needles: &[&'a [u8]]) -> Vec<CowBytes<'a>> {
+        needles.iter().map(|n| CowBytes::new(*n)).collect()
+    }
+
+    /// Runs the given search routine on every suffix of a handful of
+    /// haystacks, and checks it against `naive`.
+    fn run_search_tests(
+        needles: &[&[u8]],
+        mut search: impl FnMut(&[u8]) -> Option<(usize, usize)>,
+    ) {
+        let haystacks: &[&[u8]] = &[
+            b"",
+            b"a",
+            b"the quick brown fox jumps over the lazy dog",
+            b"zzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzzz\
+              zzzzzzzzzzzzzzzzzzzzzzzza",
+            b"abcabcabcabcabcabcabcabcabcabcabcabcabc\
+              abcabcabcabcabcabcabcabcabc",
+            b"xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxquick the lazy dog",
+        ];
+        for &haystack in haystacks {
+            for start in 0..=haystack.len() {
+                let haystack = &haystack[start..];
+                assert_eq!(
+                    naive(needles, haystack),
+                    search(haystack),
+                    "needles: {:?}, haystack: {:?}",
+                    needles
+                        .iter()
+                        .map(|n| String::from_utf8_lossy(n))
+                        .collect::<Vec<_>>(),
+                    String::from_utf8_lossy(haystack),
+                );
+            }
+        }
+    }
+
+    const NEEDLE_SETS: &[&[&[u8]]] = &[
+        &[],
+        &[b""],
+        &[b"z", b""],
+        &[b"a"],
+        &[b"dog", b"the", b"fox"],
+        &[b"lazy", b"la", b"l"],
+        &[b"the", b"the quick"],
+        &[b"the quick", b"the"],
+        &[b"zza", b"zzzz", b"bca"],
+        &[b"cab", b"abc", b"bca", b"xq", b"xxxxq"],
+        &[
+            b"a", b"b", b"c", b"d", b"e", b"f", b"g", b"h", b"i", b"j", b"qu",
+            b"ju",
+        ],
+    ];
+
+    #[test]
+    fn finder() {
+        for &needles in NEEDLE_SETS {
+            let finder = MultiFinder::new(needles);
+            run_search_tests(needles, |h| finder.find(h));
+        }
+    }
+
+    #[test]
+    fn rarebytes() {
+        for &needles in NEEDLE_SETS {
+            if needles.iter().any(|n| n.is_empty()) {
+                continue;
+            }
+            let cows = cows(needles);
+            let rare = RareBytes::new(&cows);
+            run_search_tests(needles, |h| rare.find(&cows, h));
+        }
+    }
+
+    #[test]
+    fn many_needles() {
+        let owned: Vec<Vec<u8>> = (0..200u32)
+            .map(|i| format!("{}-{}", i % 7, i).into_bytes())
+            .collect();
+        let needles: Vec<&[u8]> = owned.iter().map(|n| n.as_slice()).collect();
+        let finder = MultiFinder::new(&needles);
+        let haystack = b"prefix 3-199 and 1-22, then 5-103 and 3-10";
+        let got: Vec<(usize, usize)> = finder.find_iter(haystack).collect();
+        assert_eq!(vec![(7, 199), (17, 22), (28, 103), (38, 10)], got);
+        run_search_tests(&needles, |h| finder.find(h));
+    }
+
+    #[test]
+    fn find_iter() {
+        let finder = MultiFinder::new(&["", "a"]);
+        let got: Vec<(usize, usize)> = finder.find_iter(b"aa").collect();
+        assert_eq!(vec![(0, 0), (1, 0), (2, 0)], got);
+
+        let finder = MultiFinder::new(&["a", ""]);
+        let got: Vec<(usize, usize)> = finder.find_iter(b"ab").collect();
+        assert_eq!(vec![(0, 0), (1, 1), (2, 1)], got);
+    }
+
+    #[test]
+    fn into_owned() {
+        let finder = {
+            let needles = vec![b"foo".to_vec(), b"bar".to_vec()];
+            MultiFinder::new(&needles).into_owned()
+        };
+        assert_eq!(2, finder.len());
+        assert_eq!(b"bar", finder.needle(1));
+        assert_eq!(Some((4, 1)), finder.find(b"baz bar"));
+    }
+
+    #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+    #[test]
+    fn teddy_ssse3() {
+        if !is_x86_feature_detected!("ssse3") {
+            return;
+        }
+        for &needles in NEEDLE_SETS {
+            let cows = cows(needles);
+            let teddy = match Teddy::new(&cows) {
+                None => continue,
+                Some(teddy) => teddy,
+            };
+            run_search_tests(needles, |h| {
+                if h.len() < teddy.min_haystack_len::<__m128i>() {
+                    return naive(needles, h);
+                }
+                unsafe { teddy::find_ssse3(&teddy, &cows, h) }
+            });
+        }
+    }
+
+    #[cfg(all(not(miri), memchr_portable_simd))]
+    #[test]
+    fn teddy_portable() {
+        use core::simd::u8x16;
+
+        for &needles in NEEDLE_SETS {
+            let cows = cows(needles);
+            let teddy = match Teddy::new(&cows) {
+                None => continue,
+                Some(teddy) => teddy,
+            };
+            run_search_tests(needles, |h| {
+                if h.len() < teddy.min_haystack_len::<u8x16>() {
+                    return naive(needles, h);
+                }
+                unsafe { teddy.find::<u8x16>(&cows, h) }
+            });
+        }
+    }
+
+    #[cfg(not(miri))]
+    quickcheck::quickcheck! {
+        fn qc_matches_naive(needles: Vec<Vec<u8>>, haystack: Vec<u8>) -> bool {
+            use crate::memmem::proptests::to_alphabet;
+
+            let needles: Vec<Vec<u8>> = needles
+                .iter()
+                .map(|n| to_alphabet(b"abc", &n[..n.len().min(4)]))
+                .collect();
+            let haystack = to_alphabet(b"abc", &haystack);
+            let needles: Vec<&[u8]> =
+                needles.iter().map(|n| n.as_slice()).collect();
+            let finder = MultiFinder::new(&needles);
+            naive(&needles, &haystack) == finder.find(&haystack)
+        }
+    }
+}
@@ -28,13 +28,17 @@ Another version of this also appeared in bstr:
 https://github.com/BurntSushi/bstr/blob/a444256ca7407fe180ee32534688549655b7a38e/src/search/prefilter.rs#L83-L340
 */
 
+use core::cmp;
+
 use crate::memmem::{
     prefilter::{PrefilterFnTy, PrefilterState},
+    util::fold,
     NeedleInfo,
 };
 
 // Check that the functions below satisfy the Prefilter function type.
 const _: PrefilterFnTy = find;
+const _: PrefilterFnTy = rfind;
 
 /// Look for a possible occurrence of needle. The position returned
 /// corresponds to the beginning of the occurrence, if one exists.
@@ -46,20 +50,43 @@ const _: PrefilterFnTy = find;
 ///
 /// This should only be used when Freqy is constructed for forward
 /// searching.
+///
+/// If the rare bytes were detected while ignoring ASCII case, then both
+/// cases of each rare byte are searched for.
 pub(crate) fn find(
     prestate: &mut PrefilterState,
     ninfo: &NeedleInfo,
     haystack: &[u8],
     needle: &[u8],
+) -> Option<usize> {
+    if ninfo.rarebytes.is_ascii_nocase() {
+        find_imp(prestate, ninfo, haystack, needle, true)
+    } else {
+        find_imp(prestate, ninfo, haystack, needle, false)
+    }
+}
+
+#[inline(always)]
+fn find_imp(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+    ascii_nocase: bool,
 ) -> Option<usize> {
     let mut i = 0;
     let (rare1i, rare2i) = ninfo.rarebytes.as_rare_usize();
     let (rare1, rare2) = ninfo.rarebytes.as_rare_bytes(needle);
+    let rare2 = fold(rare2, ascii_nocase);
     while prestate.is_effective() {
         // Use a fast vectorized implementation to skip to the next
         // occurrence of the rarest byte (heuristically chosen) in the
         // needle.
-        let found = crate::memchr(rare1, &haystack[i..])?;
+        let found = if ascii_nocase {
+            crate::memchr_ascii_nocase(rare1, &haystack[i..])?
+        } else {
+            crate::memchr(rare1, &haystack[i..])?
+        };
         prestate.update(found);
         i += found;
 
@@ -73,7 +100,9 @@ pub(crate) fn find(
         // Align our rare2 byte with the haystack. A mismatch means that
         // a match is impossible.
         let aligned_rare2i = i - rare1i + rare2i;
-        if haystack.get(aligned_rare2i) != Some(&rare2) {
+        let folded =
+            haystack.get(aligned_rare2i).map(|&b| fold(b, ascii_nocase));
+        if folded != Some(rare2) {
             i += 1;
             continue;
         }
@@ -88,6 +117,76 @@ pub(crate) fn find(
     Some(i.saturating_sub(rare1i))
 }
 
+/// Look for a possible occurrence of needle, searching backwards from the end
+/// of the haystack. The position returned corresponds to the end of the
+/// occurrence, if one exists.
+///
+/// Like `find`, this never returns false negatives but may return false
+/// positives.
+///
+/// This should only be used with rare bytes detected for reverse searching.
+pub(crate) fn rfind(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    if ninfo.rarebytes.is_ascii_nocase() {
+        rfind_imp(prestate, ninfo, haystack, needle, true)
+    } else {
+        rfind_imp(prestate, ninfo, haystack, needle, false)
+    }
+}
+
+#[inline(always)]
+fn rfind_imp(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+    ascii_nocase: bool,
+) -> Option<usize> {
+    let mut end = haystack.len();
+    let (rare1i, rare2i) = ninfo.rarebytes.as_rare_usize();
+    let (rare1, rare2) = ninfo.rarebytes.as_rare_bytes(needle);
+    let rare2 = fold(rare2, ascii_nocase);
+    while prestate.is_effective() {
+        // Use a fast vectorized implementation to skip to the previous
+        // occurrence of the rarest byte (heuristically chosen) in the
+        // needle.
+        let found = if ascii_nocase {
+            crate::memrchr_ascii_nocase(rare1, &haystack[..end])?
+        } else {
+            crate::memrchr(rare1, &haystack[..end])?
+        };
+        prestate.update(end - found);
+        end = found;
+        // If we can't align our rare1 byte with the start of the haystack,
+        // then a match is impossible here or anywhere before it.
+        if found < rare1i {
+            return None;
+        }
+        // Likewise, if the needle would run past the end of the haystack,
+        // then a match is impossible at this position.
+        let start = found - rare1i;
+        if start + needle.len() > haystack.len() {
+            continue;
+        }
+        // Align our rare2 byte with the haystack. A mismatch means that
+        // a match is impossible.
+        if fold(haystack[start + rare2i], ascii_nocase) != rare2 {
+            continue;
+        }
+        // We've done what we can. There might be a match here.
+        return Some(start + needle.len());
+    }
+    // The only way we get here is if we believe our skipping heuristic
+    // has become ineffective. We're allowed to return false positives,
+    // so return the end of the last position we retreated to, aligned to
+    // the haystack.
+    Some(cmp::min(haystack.len(), end + needle.len() - rare1i))
+}
+
 #[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
@@ -110,6 +209,23 @@ mod tests {
         assert_eq!(None, freqy_find(b"yayb", b"yzyz"));
     }
 
+    fn freqy_rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        let ninfo = NeedleInfo::reverse(needle);
+        let mut prestate = PrefilterState::new();
+        rfind(&mut prestate, &ninfo, haystack, needle)
+    }
+
+    #[test]
+    fn freqy_reverse() {
+        assert_eq!(Some(3), freqy_rfind(b"BARFOO", b"BAR"));
+        assert_eq!(Some(6), freqy_rfind(b"FOOBAR", b"BAR"));
+        assert_eq!(Some(4), freqy_rfind(b"yyzy", b"zyzy"));
+        assert_eq!(Some(3), freqy_rfind(b"zyzz", b"yz"));
+        assert_eq!(None, freqy_rfind(b"zyzz", b"zyzy"));
+        assert_eq!(None, freqy_rfind(b"zazb", b"zyzy"));
+        assert_eq!(None, freqy_rfind(b"yzya", b"zyzy"));
+    }
+
     #[test]
     #[cfg(not(miri))]
     fn prefilter_permutations() {
@@ -119,4 +235,13 @@ mod tests {
         // platforms.
         unsafe { PrefilterTest::run_all_tests(super::find) };
     }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn prefilter_permutations_rev() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        // SAFETY: super::rfind is safe to call for all inputs and on all
+        // platforms.
+        unsafe { PrefilterTest::run_all_tests_rev(super::rfind) };
+    }
 }
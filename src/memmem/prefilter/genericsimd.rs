@@ -1,9 +1,12 @@
 use core::mem::size_of;
 
-use crate::memmem::{
-    prefilter::{PrefilterFnTy, PrefilterState},
+use crate::{
+    memmem::{
+        genericsimd::{load_folded, splat_folded},
+        prefilter::{PrefilterFnTy, PrefilterState},
+        NeedleInfo,
+    },
     vector::Vector,
-    NeedleInfo,
 };
 
 /// The implementation of the forward vector accelerated candidate finder.
@@ -25,6 +28,11 @@ use crate::memmem::{
 /// function parameters. `fallback` is a prefilter that is used if the haystack
 /// is too small to be handled with the given vector size.
 ///
+/// If the rare bytes in `ninfo` were detected while ignoring ASCII case, then
+/// the rare bytes and the haystack are both folded by setting their 0x20 bit
+/// before being compared. This may produce more false positives for bytes
+/// that aren't ASCII letters, but never any false negatives.
+///
 /// This routine is not safe because it is intended for callers to specialize
 /// this with a particular vector (e.g., __m256i) and then call it with the
 /// relevant target feature (e.g., avx2) enabled.
@@ -47,6 +55,24 @@ pub(crate) unsafe fn find<V: Vector>(
     haystack: &[u8],
     needle: &[u8],
     fallback: PrefilterFnTy,
+) -> Option<usize> {
+    // Specialize the search loop on whether ASCII case is ignored, so that
+    // case sensitive searches don't pay for folding.
+    if ninfo.rarebytes.is_ascii_nocase() {
+        find_imp::<V>(prestate, ninfo, haystack, needle, fallback, true)
+    } else {
+        find_imp::<V>(prestate, ninfo, haystack, needle, fallback, false)
+    }
+}
+
+#[inline(always)]
+unsafe fn find_imp<V: Vector>(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+    fallback: PrefilterFnTy,
+    ascii_nocase: bool,
 ) -> Option<usize> {
     assert!(needle.len() >= 2, "needle must be at least 2 bytes");
     let (rare1i, rare2i) = ninfo.rarebytes.as_rare_ordered_usize();
@@ -60,8 +86,8 @@ pub(crate) unsafe fn find<V: Vector>(
     let max_ptr = end_ptr.sub(min_haystack_len);
     let mut ptr = start_ptr;
 
-    let rare1chunk = V::splat(needle[rare1i]);
-    let rare2chunk = V::splat(needle[rare2i]);
+    let rare1chunk = splat_folded::<V>(needle[rare1i], ascii_nocase);
+    let rare2chunk = splat_folded::<V>(needle[rare2i], ascii_nocase);
 
     // N.B. I did experiment with unrolling the loop to deal with size(V)
     // bytes at a time and 2*size(V) bytes at a time. The double unroll
@@ -70,7 +96,14 @@ pub(crate) unsafe fn find<V: Vector>(
     // worth it. I used the memmem/krate/prebuilt/huge-en/ benchmarks to
     // compare.
     while ptr <= max_ptr {
-        let m = find_in_chunk2(ptr, rare1i, rare2i, rare1chunk, rare2chunk);
+        let m = find_in_chunk2(
+            ptr,
+            rare1i,
+            rare2i,
+            rare1chunk,
+            rare2chunk,
+            ascii_nocase,
+        );
         if let Some(chunki) = m {
             return Some(matched(prestate, start_ptr, ptr, chunki));
         }
@@ -83,7 +116,14 @@ pub(crate) unsafe fn find<V: Vector>(
         // out even though we might technically search part of the haystack
         // that we've already searched (because we know it can't match).
         ptr = max_ptr;
-        let m = find_in_chunk2(ptr, rare1i, rare2i, rare1chunk, rare2chunk);
+        let m = find_in_chunk2(
+            ptr,
+            rare1i,
+            rare2i,
+            rare1chunk,
+            rare2chunk,
+            ascii_nocase,
+        );
         if let Some(chunki) = m {
             return Some(matched(prestate, start_ptr, ptr, chunki));
         }
@@ -92,6 +132,95 @@ pub(crate) unsafe fn find<V: Vector>(
     None
 }
 
+/// The implementation of the reverse vector accelerated candidate finder.
+///
+/// This is the same algorithm as `find`, except it scans the haystack from
+/// back to front and reports the position immediately following the last
+/// candidate. The rare byte offsets in `ninfo` should be the ones detected
+/// for reverse searching.
+///
+/// `prestate`, `ninfo`, `haystack` and `needle` are the four prefilter
+/// function parameters. `fallback` is a reverse prefilter that is used if the
+/// haystack is too small to be handled with the given vector size.
+///
+/// # Panics
+///
+/// If `needle.len() <= 1`, then this panics.
+///
+/// # Safety
+///
+/// Since this is meant to be used with vector functions, callers need to
+/// specialize this inside of a function with a `target_feature` attribute.
+/// Therefore, callers must ensure that whatever target feature is being used
+/// supports the vector functions that this function is specialized for. (For
+/// the specific vector functions used, see the Vector trait implementations.)
+#[inline(always)]
+pub(crate) unsafe fn rfind<V: Vector>(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+    fallback: PrefilterFnTy,
+) -> Option<usize> {
+    // See find for why this is specialized on ascii_nocase.
+    if ninfo.rarebytes.is_ascii_nocase() {
+        rfind_imp::<V>(prestate, ninfo, haystack, needle, fallback, true)
+    } else {
+        rfind_imp::<V>(prestate, ninfo, haystack, needle, fallback, false)
+    }
+}
+
+#[inline(always)]
+unsafe fn rfind_imp<V: Vector>(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+    fallback: PrefilterFnTy,
+    ascii_nocase: bool,
+) -> Option<usize> {
+    assert!(needle.len() >= 2, "needle must be at least 2 bytes");
+    let (rare1i, rare2i) = ninfo.rarebytes.as_rare_ordered_usize();
+    // Every candidate reported must fit entirely within the haystack, so the
+    // last chunk we look at starts at the last position at which the needle
+    // can begin, minus the size of a vector.
+    let min_haystack_len = needle.len() + size_of::<V>() - 1;
+    if haystack.len() < min_haystack_len {
+        return fallback(prestate, ninfo, haystack, needle);
+    }
+
+    let start_ptr = haystack.as_ptr();
+    let end_ptr = start_ptr.add(haystack.len());
+    let mut ptr = end_ptr.sub(min_haystack_len);
+    let rare1chunk = splat_folded::<V>(needle[rare1i], ascii_nocase);
+    let rare2chunk = splat_folded::<V>(needle[rare2i], ascii_nocase);
+
+    loop {
+        let m = rfind_in_chunk2(
+            ptr,
+            rare1i,
+            rare2i,
+            rare1chunk,
+            rare2chunk,
+            ascii_nocase,
+        );
+        if let Some(chunki) = m {
+            return Some(matched_rev(
+                prestate, start_ptr, end_ptr, ptr, chunki, needle,
+            ));
+        }
+        if ptr == start_ptr {
+            break;
+        }
+        // As with the forward case, we don't need to mask anything out when
+        // the last chunk overlaps with the previous one, since we know there
+        // are no candidates in the previous one.
+        ptr = ptr.sub(core::cmp::min(size_of::<V>(), diff(ptr, start_ptr)));
+    }
+    prestate.update(haystack.len());
+    None
+}
+
 // Below are two different techniques for checking whether a candidate
 // match exists in a given chunk or not. find_in_chunk2 checks two bytes
 // where as find_in_chunk3 checks three bytes. The idea behind checking
@@ -110,7 +239,8 @@ pub(crate) unsafe fn find<V: Vector>(
 /// chunk pointed to by ptr.
 ///
 /// rare1chunk and rare2chunk correspond to vectors with the rare1 and rare2
-/// bytes repeated in each 8-bit lane, respectively.
+/// bytes repeated in each 8-bit lane, respectively. When ascii_nocase is
+/// true, they must have been folded with `splat_folded`.
 ///
 /// # Safety
 ///
@@ -123,9 +253,10 @@ unsafe fn find_in_chunk2<V: Vector>(
     rare2i: usize,
     rare1chunk: V,
     rare2chunk: V,
+    ascii_nocase: bool,
 ) -> Option<usize> {
-    let chunk0 = V::load_unaligned(ptr.add(rare1i));
-    let chunk1 = V::load_unaligned(ptr.add(rare2i));
+    let chunk0 = load_folded::<V>(ptr.add(rare1i), ascii_nocase);
+    let chunk1 = load_folded::<V>(ptr.add(rare2i), ascii_nocase);
 
     let eq0 = chunk0.cmpeq(rare1chunk);
     let eq1 = chunk1.cmpeq(rare2chunk);
@@ -137,6 +268,35 @@ unsafe fn find_in_chunk2<V: Vector>(
     Some(match_offsets.trailing_zeros() as usize)
 }
 
+/// Like `find_in_chunk2`, but returns the last candidate in the chunk
+/// instead of the first.
+///
+/// # Safety
+///
+/// It must be safe to do an unaligned read of size(V) bytes starting at both
+/// (ptr + rare1i) and (ptr + rare2i).
+#[inline(always)]
+unsafe fn rfind_in_chunk2<V: Vector>(
+    ptr: *const u8,
+    rare1i: usize,
+    rare2i: usize,
+    rare1chunk: V,
+    rare2chunk: V,
+    ascii_nocase: bool,
+) -> Option<usize> {
+    let chunk0 = load_folded::<V>(ptr.add(rare1i), ascii_nocase);
+    let chunk1 = load_folded::<V>(ptr.add(rare2i), ascii_nocase);
+
+    let eq0 = chunk0.cmpeq(rare1chunk);
+    let eq1 = chunk1.cmpeq(rare2chunk);
+
+    let match_offsets = eq0.and(eq1).movemask();
+    if match_offsets == 0 {
+        return None;
+    }
+    Some(31 - match_offsets.leading_zeros() as usize)
+}
+
 /// Search for an occurrence of two rare bytes and the first byte (even if one
 /// of the rare bytes is equivalent to the first byte) from the needle in the
 /// current chunk pointed to by ptr.
@@ -199,6 +359,25 @@ fn matched(
     found
 }
 
+/// Like `matched`, but for reverse searches. This returns the haystack
+/// relative offset immediately following the candidate.
+///
+/// This is unlineable for the same reason as `matched`.
+#[cold]
+#[inline(never)]
+fn matched_rev(
+    prestate: &mut PrefilterState,
+    start_ptr: *const u8,
+    end_ptr: *const u8,
+    ptr: *const u8,
+    chunki: usize,
+    needle: &[u8],
+) -> usize {
+    let found = diff(ptr, start_ptr) + chunki + needle.len();
+    prestate.update(diff(end_ptr, start_ptr) - found);
+    found
+}
+
 /// Subtract `b` from `a` and return the difference. `a` must be greater than
 /// or equal to `b`.
 fn diff(a: *const u8, b: *const u8) -> usize {
@@ -1,14 +1,27 @@
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+use crate::arch::{self, Backend};
 use crate::memmem::{rarebytes::RareNeedleBytes, NeedleInfo};
 
+#[cfg_attr(
+    not(any(
+        all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+        feature = "safe-only",
+    )),
+    allow(dead_code)
+)]
 mod fallback;
-#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+#[cfg(not(feature = "safe-only"))]
 mod genericsimd;
+#[cfg(not(feature = "safe-only"))]
+mod swar;
 #[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
 mod x86;
 
 /// The maximum frequency rank permitted for the fallback prefilter. If the
 /// rarest byte in the needle has a frequency rank above this value, then no
 /// prefilter is used if the fallback prefilter would otherwise be selected.
+/// The SWAR prefilter is held to the same limit, since it also degrades when
+/// given common bytes.
 const MAX_FALLBACK_RANK: usize = 250;
 
 /// A combination of prefilter effectiveness state, the prefilter function and
@@ -46,12 +59,11 @@ impl<'a> Pre<'a> {
 
 /// A prefilter function.
 ///
-/// A prefilter function describes both forward and reverse searches.
-/// (Although, we don't currently implement prefilters for reverse searching.)
-/// In the case of a forward search, the position returned corresponds to
-/// the starting offset of a match (confirmed or possible). Its minimum
-/// value is `0`, and its maximum value is `haystack.len() - 1`. In the case
-/// of a reverse search, the position returned corresponds to the position
+/// A prefilter function describes both forward and reverse searches. In the
+/// case of a forward search, the position returned corresponds to the
+/// starting offset of a match (confirmed or possible). Its minimum value is
+/// `0`, and its maximum value is `haystack.len() - 1`. In the case of a
+/// reverse search, the position returned corresponds to the position
 /// immediately after a match (confirmed or possible). Its minimum value is `1`
 /// and its maximum value is `haystack.len()`.
 ///
@@ -83,6 +95,7 @@ pub(crate) struct PrefilterFn(PrefilterFnTy);
 /// which generally won't be inlineable into the surrounding code anyway.
 /// (Unless AVX2 is enabled at compile time, but this is typically rare, since
 /// it produces a non-portable binary.)
+#[cfg(not(feature = "safe-only"))]
 pub(crate) type PrefilterFnTy = unsafe fn(
     prestate: &mut PrefilterState,
     ninfo: &NeedleInfo,
@@ -90,6 +103,17 @@ pub(crate) type PrefilterFnTy = unsafe fn(
     needle: &[u8],
 ) -> Option<usize>;
 
+/// The type of a prefilter function when the 'safe-only' feature is enabled.
+/// In that case, the only prefilter is the fallback prefilter, which is safe
+/// to call in all environments.
+#[cfg(feature = "safe-only")]
+pub(crate) type PrefilterFnTy = fn(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize>;
+
 impl PrefilterFn {
     /// Create a new prefilter function from the function pointer given.
     ///
@@ -99,11 +123,22 @@ impl PrefilterFn {
     /// for all inputs in the current environment. For example, if the given
     /// prefilter function uses AVX instructions, then the caller must ensure
     /// that the appropriate AVX CPU features are enabled.
+    #[cfg(not(feature = "safe-only"))]
     pub(crate) unsafe fn new(prefn: PrefilterFnTy) -> PrefilterFn {
         PrefilterFn(prefn)
     }
 
+    /// Create a new prefilter function from the function pointer given.
+    ///
+    /// With the 'safe-only' feature, every prefilter function is safe to
+    /// call, so this is safe too.
+    #[cfg(feature = "safe-only")]
+    pub(crate) fn new(prefn: PrefilterFnTy) -> PrefilterFn {
+        PrefilterFn(prefn)
+    }
+
     /// Call the underlying prefilter function with the given arguments.
+    #[cfg(not(feature = "safe-only"))]
     pub fn call(
         self,
         prestate: &mut PrefilterState,
@@ -115,6 +150,18 @@ impl PrefilterFn {
         // function is safe to call for all inputs in the current environment.
         unsafe { (self.0)(prestate, ninfo, haystack, needle) }
     }
+
+    /// Call the underlying prefilter function with the given arguments.
+    #[cfg(feature = "safe-only")]
+    pub fn call(
+        self,
+        prestate: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        (self.0)(prestate, ninfo, haystack, needle)
+    }
 }
 
 impl core::fmt::Debug for PrefilterFn {
@@ -280,21 +327,21 @@ pub(crate) fn forward(
         return None;
     }
 
-    #[cfg(feature = "std")]
-    {
-        if cfg!(memchr_runtime_avx) {
-            if is_x86_feature_detected!("avx2") {
-                // SAFETY: x86::avx::find only requires the avx2 feature,
-                // which we've just checked above.
-                return unsafe { Some(PrefilterFn::new(x86::avx::find)) };
-            }
-        }
+    if crate::memchr::x86::is_avx2_available() && arch::allows(Backend::Avx2) {
+        // SAFETY: x86::avx::find only requires the avx2 feature, which we've
+        // just checked above.
+        return unsafe { Some(PrefilterFn::new(x86::avx::find)) };
     }
-    if cfg!(memchr_runtime_sse2) {
+    if cfg!(memchr_runtime_sse2) && arch::allows(Backend::Sse2) {
         // SAFETY: x86::sse::find only requires the sse2 feature, which is
         // guaranteed to be available on x86_64.
         return unsafe { Some(PrefilterFn::new(x86::sse::find)) };
     }
+    // If a backend has been forced by this point, then it's either the
+    // fallback or the naive backend. The latter doesn't get a prefilter.
+    if !arch::allows(Backend::Fallback) {
+        return None;
+    }
     // Check that our rarest byte has a reasonably low rank. The main issue
     // here is that the fallback prefilter can perform pretty poorly if it's
     // given common bytes. So we try to avoid the worst cases here.
@@ -308,9 +355,15 @@ pub(crate) fn forward(
 
 /// Determine which prefilter function, if any, to use.
 ///
-/// Since SIMD is currently only supported on x86_64, this will just select
-/// the fallback prefilter if the rare bytes provided have a low enough rank.
-#[cfg(not(all(not(miri), target_arch = "x86_64", memchr_runtime_simd)))]
+/// Off of x86_64 (or under miri), this selects the SWAR prefilter, which
+/// treats a u64 as a vector of 8 bytes and works on every target, if the rare
+/// bytes provided have a low enough rank. This is true even when the
+/// `memchr_portable_simd` cfg is set: in that case, the searcher itself uses
+/// `core::simd` vectors, but its prefilter is still the SWAR one.
+#[cfg(not(any(
+    all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+    feature = "safe-only",
+)))]
 #[inline(always)]
 pub(crate) fn forward(
     config: &Prefilter,
@@ -320,10 +373,116 @@ pub(crate) fn forward(
     if config.is_none() || needle.len() <= 1 {
         return None;
     }
+    // Like the fallback prefilter on x86_64, the SWAR prefilter performs
+    // poorly when its rarest byte is actually common, so avoid it then.
     let (rare1_rank, _) = rare.as_ranks(needle);
     if rare1_rank <= MAX_FALLBACK_RANK {
-        // SAFETY: fallback::find is safe to call in all environments.
-        return unsafe { Some(PrefilterFn::new(fallback::find)) };
+        // SAFETY: swar::find is safe to call in all environments.
+        return unsafe { Some(PrefilterFn::new(swar::find)) };
+    }
+    None
+}
+
+/// Determine which prefilter function, if any, to use.
+///
+/// With the 'safe-only' feature, this selects the fallback prefilter (which
+/// is built on memchr) under the same conditions as on x86_64 when no vector
+/// prefilter can be used.
+#[cfg(feature = "safe-only")]
+#[inline(always)]
+pub(crate) fn forward(
+    config: &Prefilter,
+    rare: &RareNeedleBytes,
+    needle: &[u8],
+) -> Option<PrefilterFn> {
+    if config.is_none() || needle.len() <= 1 {
+        return None;
+    }
+    let (rare1_rank, _) = rare.as_ranks(needle);
+    if rare1_rank <= MAX_FALLBACK_RANK {
+        return Some(PrefilterFn::new(fallback::find));
+    }
+    None
+}
+
+/// Determine which reverse prefilter function, if any, to use.
+///
+/// This mirrors `forward`, and the rare bytes given should be the ones
+/// detected for reverse searching.
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+#[inline(always)]
+pub(crate) fn reverse(
+    config: &Prefilter,
+    rare: &RareNeedleBytes,
+    needle: &[u8],
+) -> Option<PrefilterFn> {
+    if config.is_none() || needle.len() <= 1 {
+        return None;
+    }
+
+    if crate::memchr::x86::is_avx2_available() && arch::allows(Backend::Avx2) {
+        // SAFETY: x86::avx::rfind only requires the avx2 feature, which we've
+        // just checked above.
+        return unsafe { Some(PrefilterFn::new(x86::avx::rfind)) };
+    }
+    if cfg!(memchr_runtime_sse2) && arch::allows(Backend::Sse2) {
+        // SAFETY: x86::sse::rfind only requires the sse2 feature, which is
+        // guaranteed to be available on x86_64.
+        return unsafe { Some(PrefilterFn::new(x86::sse::rfind)) };
+    }
+    if !arch::allows(Backend::Fallback) {
+        return None;
+    }
+    let (rare1_rank, _) = rare.as_ranks(needle);
+    if rare1_rank <= MAX_FALLBACK_RANK {
+        // SAFETY: fallback::rfind is safe to call in all environments.
+        return unsafe { Some(PrefilterFn::new(fallback::rfind)) };
+    }
+    None
+}
+
+/// Determine which reverse prefilter function, if any, to use.
+///
+/// This mirrors `forward` by selecting the SWAR prefilter when the rarest
+/// byte is rare enough.
+#[cfg(not(any(
+    all(not(miri), target_arch = "x86_64", memchr_runtime_simd),
+    feature = "safe-only",
+)))]
+#[inline(always)]
+pub(crate) fn reverse(
+    config: &Prefilter,
+    rare: &RareNeedleBytes,
+    needle: &[u8],
+) -> Option<PrefilterFn> {
+    if config.is_none() || needle.len() <= 1 {
+        return None;
+    }
+    let (rare1_rank, _) = rare.as_ranks(needle);
+    if rare1_rank <= MAX_FALLBACK_RANK {
+        // SAFETY: swar::rfind is safe to call in all environments.
+        return unsafe { Some(PrefilterFn::new(swar::rfind)) };
+    }
+    None
+}
+
+/// Determine which reverse prefilter function, if any, to use.
+///
+/// This mirrors `forward` by selecting the fallback prefilter when the
+/// rarest byte is rare enough.
+#[cfg(feature = "safe-only")]
+#[inline(always)]
+pub(crate) fn reverse(
+    config: &Prefilter,
+    rare: &RareNeedleBytes,
+    needle: &[u8],
+) -> Option<PrefilterFn> {
+    if config.is_none() || needle.len() <= 1 {
+        return None;
+    }
+    let (rare1_rank, _) = rare.as_ranks(needle);
+    if rare1_rank <= MAX_FALLBACK_RANK {
+        return Some(PrefilterFn::new(fallback::rfind));
     }
     None
 }
@@ -379,7 +538,8 @@ pub(crate) mod tests {
     /// and get the expected output.
     pub(crate) struct PrefilterTest {
         // These fields represent the inputs and expected output of a forwards
-        // prefilter function.
+        // prefilter function. (Or a reverse prefilter function, after the test
+        // has been converted with `reverse`.)
         pub(crate) ninfo: NeedleInfo,
         pub(crate) haystack: Vec<u8>,
         pub(crate) needle: Vec<u8>,
@@ -417,6 +577,37 @@ pub(crate) mod tests {
             }
         }
 
+        /// Run all generated reverse prefilter tests on the given prefn.
+        ///
+        /// # Safety
+        ///
+        /// Callers must ensure that the given prefilter function pointer is
+        /// safe to call for all inputs in the current environment.
+        pub(crate) unsafe fn run_all_tests_rev(prefn: PrefilterFnTy) {
+            PrefilterTest::run_all_tests_rev_filter(prefn, |_| true)
+        }
+
+        /// Run all generated reverse prefilter tests that pass the given
+        /// predicate on the given prefn.
+        ///
+        /// # Safety
+        ///
+        /// Callers must ensure that the given prefilter function pointer is
+        /// safe to call for all inputs in the current environment.
+        pub(crate) unsafe fn run_all_tests_rev_filter(
+            prefn: PrefilterFnTy,
+            mut predicate: impl FnMut(&PrefilterTest) -> bool,
+        ) {
+            for seed in PREFILTER_TEST_SEEDS {
+                for test in seed.generate() {
+                    let test = test.reverse();
+                    if predicate(&test) {
+                        test.run(prefn);
+                    }
+                }
+            }
+        }
+
         /// Create a new prefilter test from a seed and some chose offsets to
         /// rare bytes in the seed's needle.
         ///
@@ -462,6 +653,28 @@ pub(crate) mod tests {
             Some(PrefilterTest { ninfo, haystack, needle, output })
         }
 
+        /// Convert this forward prefilter test into a reverse prefilter test.
+        ///
+        /// This moves the rare offsets to the last occurrence of each rare
+        /// byte (as the reverse rare byte detector would) and makes the
+        /// expected output point to the end of the match instead of its
+        /// start.
+        fn reverse(self) -> PrefilterTest {
+            let PrefilterTest { ninfo, haystack, needle, output } = self;
+            let (rare1, rare2) = ninfo.rarebytes.as_rare_bytes(&needle);
+            let rare1i = crate::memrchr(rare1, &needle).unwrap();
+            let rare2i = crate::memrchr(rare2, &needle).unwrap();
+            let ninfo = NeedleInfo {
+                rarebytes: RareNeedleBytes::new(
+                    u8::try_from(rare1i).unwrap(),
+                    u8::try_from(rare2i).unwrap(),
+                ),
+                nhash: rabinkarp::NeedleHash::reverse(&needle),
+            };
+            let output = output.map(|i| i + needle.len());
+            PrefilterTest { ninfo, haystack, needle, output }
+        }
+
         /// Run this specific test on the given prefilter function. If the
         /// outputs do no match, then this routine panics with a failure
         /// message.
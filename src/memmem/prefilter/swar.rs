@@ -0,0 +1,103 @@
+use crate::memmem::{
+    prefilter::{PrefilterFnTy, PrefilterState},
+    NeedleInfo,
+};
+
+// Check that the functions below satisfy the Prefilter function type.
+const _: PrefilterFnTy = find;
+const _: PrefilterFnTy = rfind;
+
+/// A SWAR accelerated candidate finder for single-substring search. This
+/// treats a u64 as a vector of 8 bytes, and so works on every target.
+///
+/// # Safety
+///
+/// This is always safe to call. It is only marked as unsafe so that it
+/// satisfies the Prefilter function type.
+pub(crate) unsafe fn find(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    // If the haystack is too small for SWAR, then just run memchr on the
+    // rarest byte and be done with it.
+    fn simple_memchr_fallback(
+        _prestate: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        let (rare, _) = ninfo.rarebytes.as_rare_ordered_usize();
+        let found = if ninfo.rarebytes.is_ascii_nocase() {
+            crate::memchr_ascii_nocase(needle[rare], haystack)
+        } else {
+            crate::memchr(needle[rare], haystack)
+        };
+        found.map(|i| i.saturating_sub(rare))
+    }
+    super::genericsimd::find::<u64>(
+        prestate,
+        ninfo,
+        haystack,
+        needle,
+        simple_memchr_fallback,
+    )
+}
+
+/// A SWAR accelerated reverse candidate finder for single-substring search.
+///
+/// # Safety
+///
+/// This is always safe to call. It is only marked as unsafe so that it
+/// satisfies the Prefilter function type.
+pub(crate) unsafe fn rfind(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    // If the haystack is too small for SWAR, then just run memrchr on the
+    // rarest byte and be done with it.
+    fn simple_memrchr_fallback(
+        _prestate: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        let (rare, _) = ninfo.rarebytes.as_rare_ordered_usize();
+        let found = if ninfo.rarebytes.is_ascii_nocase() {
+            crate::memrchr_ascii_nocase(needle[rare], haystack)?
+        } else {
+            crate::memrchr(needle[rare], haystack)?
+        };
+        let start = found.checked_sub(rare)?;
+        Some(core::cmp::min(haystack.len(), start + needle.len()))
+    }
+    super::genericsimd::rfind::<u64>(
+        prestate,
+        ninfo,
+        haystack,
+        needle,
+        simple_memrchr_fallback,
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    #[test]
+    #[cfg(not(miri))]
+    fn prefilter_permutations() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        // SAFETY: super::find is safe to call for all inputs.
+        unsafe { PrefilterTest::run_all_tests(super::find) };
+    }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn prefilter_permutations_rev() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        // SAFETY: super::rfind is safe to call for all inputs.
+        unsafe { PrefilterTest::run_all_tests_rev(super::rfind) };
+    }
+}
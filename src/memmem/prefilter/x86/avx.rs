@@ -7,6 +7,7 @@ use crate::memmem::{
 
 // Check that the functions below satisfy the Prefilter function type.
 const _: PrefilterFnTy = find;
+const _: PrefilterFnTy = rfind;
 
 /// An AVX2 accelerated candidate finder for single-substring search.
 ///
@@ -30,7 +31,29 @@ pub(crate) unsafe fn find(
     )
 }
 
-#[cfg(test)]
+/// An AVX2 accelerated reverse candidate finder for single-substring search.
+///
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn rfind(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    super::super::genericsimd::rfind::<__m256i>(
+        prestate,
+        ninfo,
+        haystack,
+        needle,
+        super::sse::rfind,
+    )
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     #[test]
     #[cfg(not(miri))]
@@ -43,4 +66,16 @@ mod tests {
         // CPU support AVX2, which we checked above.
         unsafe { PrefilterTest::run_all_tests(super::find) };
     }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn prefilter_permutations_rev() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        if !is_x86_feature_detected!("avx2") {
+            return;
+        }
+        // SAFETY: The safety of super::rfind only requires that the current
+        // CPU support AVX2, which we checked above.
+        unsafe { PrefilterTest::run_all_tests_rev(super::rfind) };
+    }
 }
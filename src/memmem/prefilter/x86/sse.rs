@@ -7,6 +7,7 @@ use crate::memmem::{
 
 // Check that the functions below satisfy the Prefilter function type.
 const _: PrefilterFnTy = find;
+const _: PrefilterFnTy = rfind;
 
 /// An SSE2 accelerated candidate finder for single-substring search.
 ///
@@ -32,7 +33,12 @@ pub(crate) unsafe fn find(
         needle: &[u8],
     ) -> Option<usize> {
         let (rare, _) = ninfo.rarebytes.as_rare_ordered_usize();
-        crate::memchr(needle[rare], haystack).map(|i| i.saturating_sub(rare))
+        let found = if ninfo.rarebytes.is_ascii_nocase() {
+            crate::memchr_ascii_nocase(needle[rare], haystack)
+        } else {
+            crate::memchr(needle[rare], haystack)
+        };
+        found.map(|i| i.saturating_sub(rare))
     }
     super::super::genericsimd::find::<__m128i>(
         prestate,
@@ -43,6 +49,45 @@ pub(crate) unsafe fn find(
     )
 }
 
+/// An SSE2 accelerated reverse candidate finder for single-substring search.
+///
+/// # Safety
+///
+/// Callers must ensure that the sse2 CPU feature is enabled in the current
+/// environment. This feature should be enabled in all x86_64 targets.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn rfind(
+    prestate: &mut PrefilterState,
+    ninfo: &NeedleInfo,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    // If the haystack is too small for SSE2, then just run memrchr on the
+    // rarest byte and be done with it.
+    fn simple_memrchr_fallback(
+        _prestate: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        let (rare, _) = ninfo.rarebytes.as_rare_ordered_usize();
+        let found = if ninfo.rarebytes.is_ascii_nocase() {
+            crate::memrchr_ascii_nocase(needle[rare], haystack)?
+        } else {
+            crate::memrchr(needle[rare], haystack)?
+        };
+        let start = found.checked_sub(rare)?;
+        Some(core::cmp::min(haystack.len(), start + needle.len()))
+    }
+    super::super::genericsimd::rfind::<__m128i>(
+        prestate,
+        ninfo,
+        haystack,
+        needle,
+        simple_memrchr_fallback,
+    )
+}
+
 #[cfg(all(test, feature = "std"))]
 mod tests {
     #[test]
@@ -52,4 +97,12 @@ mod tests {
         // SAFETY: super::find is safe to call for all inputs on x86.
         unsafe { PrefilterTest::run_all_tests(super::find) };
     }
+
+    #[test]
+    #[cfg(not(miri))]
+    fn prefilter_permutations_rev() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+        // SAFETY: super::rfind is safe to call for all inputs on x86.
+        unsafe { PrefilterTest::run_all_tests_rev(super::rfind) };
+    }
 }
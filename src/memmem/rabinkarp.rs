@@ -27,10 +27,22 @@ https://en.wikipedia.org/wiki/Rabin%E2%80%93Karp_algorithm
 But ESMAJ provides something a bit more concrete:
 http://www-igm.univ-mlv.fr/~lecroq/string/node5.html
 
+Besides searching for a single needle, this module also provides
+`RabinKarpSet`, which searches for many needles of the same length at once.
+Every needle's hash goes into a hash table, and each window of the haystack
+is looked up in that table as its hash rolls through the haystack.
+
 Finally, aho-corasick uses Rabin-Karp for multiple pattern match in some cases:
 https://github.com/BurntSushi/aho-corasick/blob/3852632f10587db0ff72ef29e88d58bf305a0946/src/packed/rabinkarp.rs
 */
 
+#[cfg(feature = "std")]
+use core::cmp;
+
+#[cfg(feature = "std")]
+use crate::cow::CowBytes;
+use crate::memmem::util::{self, fold};
+
 /// Whether RK is believed to be very fast for the given needle/haystack.
 pub(crate) fn is_fast(haystack: &[u8], _needle: &[u8]) -> bool {
     haystack.len() < 16
@@ -43,26 +55,50 @@ pub(crate) fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 
 /// Search for the first occurrence of needle in haystack using Rabin-Karp with
 /// a pre-computed needle hash.
+///
+/// If the needle hash was computed while ignoring ASCII case, then so is
+/// this search.
 pub(crate) fn find_with(
+    nhash: &NeedleHash,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    if nhash.ascii_nocase {
+        find_with_imp(nhash, haystack, needle, true)
+    } else {
+        find_with_imp(nhash, haystack, needle, false)
+    }
+}
+
+#[inline(always)]
+fn find_with_imp(
     nhash: &NeedleHash,
     mut haystack: &[u8],
     needle: &[u8],
+    ascii_nocase: bool,
 ) -> Option<usize> {
     if haystack.len() < needle.len() {
         return None;
     }
     let start = haystack.as_ptr() as usize;
-    let mut hash = Hash::from_bytes_fwd(&haystack[..needle.len()]);
+    let mut hash = Hash::new();
+    for &b in &haystack[..needle.len()] {
+        hash.add(fold(b, ascii_nocase));
+    }
     // N.B. I've experimented with unrolling this loop, but couldn't realize
     // any obvious gains.
     loop {
-        if nhash.eq(hash) && is_prefix(haystack, needle) {
+        if nhash.eq(hash) && is_prefix(haystack, needle, ascii_nocase) {
             return Some(haystack.as_ptr() as usize - start);
         }
         if needle.len() >= haystack.len() {
             return None;
         }
-        hash.roll(&nhash, haystack[0], haystack[needle.len()]);
+        hash.roll(
+            nhash,
+            fold(haystack[0], ascii_nocase),
+            fold(haystack[needle.len()], ascii_nocase),
+        );
         haystack = &haystack[1..];
     }
 }
@@ -74,18 +110,37 @@ pub(crate) fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
 
 /// Search for the last occurrence of needle in haystack using Rabin-Karp with
 /// a pre-computed needle hash.
+///
+/// If the needle hash was computed while ignoring ASCII case, then so is
+/// this search.
 pub(crate) fn rfind_with(
+    nhash: &NeedleHash,
+    haystack: &[u8],
+    needle: &[u8],
+) -> Option<usize> {
+    if nhash.ascii_nocase {
+        rfind_with_imp(nhash, haystack, needle, true)
+    } else {
+        rfind_with_imp(nhash, haystack, needle, false)
+    }
+}
+
+#[inline(always)]
+fn rfind_with_imp(
     nhash: &NeedleHash,
     mut haystack: &[u8],
     needle: &[u8],
+    ascii_nocase: bool,
 ) -> Option<usize> {
     if haystack.len() < needle.len() {
         return None;
     }
-    let mut hash =
-        Hash::from_bytes_rev(&haystack[haystack.len() - needle.len()..]);
+    let mut hash = Hash::new();
+    for &b in haystack[haystack.len() - needle.len()..].iter().rev() {
+        hash.add(fold(b, ascii_nocase));
+    }
     loop {
-        if nhash.eq(hash) && is_suffix(haystack, needle) {
+        if nhash.eq(hash) && is_suffix(haystack, needle, ascii_nocase) {
             return Some(haystack.len() - needle.len());
         }
         if needle.len() >= haystack.len() {
@@ -93,13 +148,260 @@ pub(crate) fn rfind_with(
         }
         hash.roll(
             &nhash,
-            haystack[haystack.len() - 1],
-            haystack[haystack.len() - needle.len() - 1],
+            fold(haystack[haystack.len() - 1], ascii_nocase),
+            fold(haystack[haystack.len() - needle.len() - 1], ascii_nocase),
         );
         haystack = &haystack[..haystack.len() - 1];
     }
 }
 
+/// A set of needles, all of the same length, that are searched for at once
+/// using Rabin-Karp.
+///
+/// The hash of every needle is stored in a hash table. Searching then rolls
+/// a hash over every window in the haystack with the same length as the
+/// needles, and looks up each window's hash in the table. A needle only
+/// matches if its hash is equal to the window's hash and the needle is
+/// equal to the window. Thus, a search visits every byte in the haystack
+/// once, regardless of the number of needles, plus the cost of comparing
+/// the needles whose hash collides with a window.
+///
+/// Unlike [`MultiFinder`](crate::memmem::MultiFinder), which reports the
+/// leftmost match, this reports every position at which any needle matches,
+/// along with the index of every needle that matches there. This makes it a
+/// good fit for checking a haystack against a large set of fixed length
+/// tokens, such as hashes or identifiers.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use memchr::memmem::RabinKarpSet;
+///
+/// let set = RabinKarpSet::new(&["abc", "bcd", "xyz", "abc"]);
+/// let hits: Vec<(usize, usize)> = set.find_iter(b"xxabcd").collect();
+/// assert_eq!(vec![(2, 0), (2, 3), (3, 1)], hits);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct RabinKarpSet<'n> {
+    needles: Vec<CowBytes<'n>>,
+    /// The length of every needle.
+    needle_len: usize,
+    /// The hash of the first needle, which is only used for rolling the
+    /// hash of a window in the haystack. Since all needles have the same
+    /// length, they all have the same `hash_2pow`.
+    nhash: NeedleHash,
+    /// The hash table, which maps the hash of a needle to its hash and its
+    /// index. Entries in each bucket are ordered by needle index.
+    buckets: Vec<Vec<(Hash, usize)>>,
+    /// The number of bits in a bucket index, i.e., `buckets.len()` is equal
+    /// to `1 << bucket_bits`.
+    bucket_bits: u32,
+}
+
+#[cfg(feature = "std")]
+impl<'n> RabinKarpSet<'n> {
+    /// Create a new set for the given needles.
+    ///
+    /// The index reported for a match is the position of the needle that
+    /// matched in the order given here. Duplicate needles are permitted, and
+    /// every copy is reported.
+    ///
+    /// # Panics
+    ///
+    /// When the needles given don't all have the same length.
+    pub fn new<I, B>(needles: I) -> RabinKarpSet<'n>
+    where
+        I: IntoIterator<Item = &'n B>,
+        B: 'n + ?Sized + AsRef<[u8]>,
+    {
+        let needles: Vec<CowBytes<'n>> =
+            needles.into_iter().map(|n| CowBytes::new(n)).collect();
+        let needle_len = needles.first().map_or(0, |n| n.len());
+        let nhash = needles.first().map_or(NeedleHash::default(), |n| {
+            NeedleHash::forward(n.as_slice())
+        });
+        // Use twice as many buckets as there are needles, and at least two
+        // buckets, so that a bucket index has at least one bit.
+        let bucket_bits = cmp::max(2, needles.len() * 2)
+            .next_power_of_two()
+            .trailing_zeros();
+        let mut set = RabinKarpSet {
+            needles: vec![],
+            needle_len,
+            nhash,
+            buckets: vec![vec![]; 1 << bucket_bits],
+            bucket_bits,
+        };
+        for (i, needle) in needles.iter().enumerate() {
+            assert_eq!(
+                needle_len,
+                needle.len(),
+                "all needles in a Rabin-Karp set must have the same length",
+            );
+            let hash = NeedleHash::forward(needle.as_slice()).hash;
+            let bucket = set.bucket(hash);
+            set.buckets[bucket].push((hash, i));
+        }
+        set.needles = needles;
+        set
+    }
+
+    /// Returns the first position at which any needle in this set matches,
+    /// along with the index of the needle. If several needles match there,
+    /// then the smallest index is reported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use memchr::memmem::RabinKarpSet;
+    ///
+    /// let set = RabinKarpSet::new(&["1f3a", "9c0d"]);
+    /// assert_eq!(Some((4, 1)), set.find(b"id: 9c0d, 1f3a"));
+    /// assert_eq!(None, set.find(b"id: 0000"));
+    /// ```
+    pub fn find(&self, haystack: &[u8]) -> Option<(usize, usize)> {
+        self.find_iter(haystack).next()
+    }
+
+    /// Returns an iterator over every position at which any needle in this
+    /// set matches in the given haystack. For each position, the index of
+    /// every needle that matches there is reported in ascending order.
+    ///
+    /// Since all needles have the same length, matches may overlap.
+    ///
+    /// When searching a stream in chunks, keep the last
+    /// `needle_len() - 1` bytes of each chunk and prepend them to the next
+    /// one to find matches that span chunks.
+    #[inline]
+    pub fn find_iter<'a, 'h>(
+        &'a self,
+        haystack: &'h [u8],
+    ) -> RabinKarpFindIter<'h, 'a> {
+        let mut hash = Hash::new();
+        if let Some(window) = haystack.get(..self.needle_len) {
+            for &b in window {
+                hash.add(b);
+            }
+        }
+        RabinKarpFindIter { haystack, set: self, pos: 0, hash, entry: 0 }
+    }
+
+    /// Convert this set into its owned variant, such that it no longer
+    /// borrows the needles.
+    ///
+    /// This copies any needles that are borrowed.
+    #[inline]
+    pub fn into_owned(self) -> RabinKarpSet<'static> {
+        RabinKarpSet {
+            needles: self
+                .needles
+                .into_iter()
+                .map(|n| n.into_owned())
+                .collect(),
+            needle_len: self.needle_len,
+            nhash: self.nhash,
+            buckets: self.buckets,
+            bucket_bits: self.bucket_bits,
+        }
+    }
+
+    /// Returns the number of needles in this set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.needles.len()
+    }
+
+    /// Returns true if and only if this set has no needles.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.needles.is_empty()
+    }
+
+    /// Returns the length shared by every needle in this set. This is `0`
+    /// if the set is empty.
+    #[inline]
+    pub fn needle_len(&self) -> usize {
+        self.needle_len
+    }
+
+    /// Returns the needle at the given index.
+    ///
+    /// # Panics
+    ///
+    /// When `index` is not less than `self.len()`.
+    #[inline]
+    pub fn needle(&self, index: usize) -> &[u8] {
+        self.needles[index].as_slice()
+    }
+
+    /// Returns the index of the bucket for the given hash.
+    ///
+    /// The hash itself is a poor bucket index, since its low bits only
+    /// depend on the last few bytes of a window. So it is mixed with a
+    /// multiplication first, and the high bits of the product are used.
+    fn bucket(&self, hash: Hash) -> usize {
+        (hash.0.wrapping_mul(0x9E3779B9) >> (32 - self.bucket_bits)) as usize
+    }
+}
+
+/// An iterator over every match of a [`RabinKarpSet`].
+///
+/// Matches are reported as their starting offset and the index of the
+/// needle that matched.
+///
+/// `'h` is the lifetime of the haystack while `'n` is the lifetime of the
+/// needles.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct RabinKarpFindIter<'h, 'n> {
+    haystack: &'h [u8],
+    set: &'n RabinKarpSet<'n>,
+    /// The start of the current window in the haystack.
+    pos: usize,
+    /// The hash of the current window.
+    hash: Hash,
+    /// The next entry to check in the bucket of the current window.
+    entry: usize,
+}
+
+#[cfg(feature = "std")]
+impl<'h, 'n> Iterator for RabinKarpFindIter<'h, 'n> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let set = self.set;
+        if set.is_empty() {
+            return None;
+        }
+        let len = set.needle_len;
+        while self.pos + len <= self.haystack.len() {
+            let window = &self.haystack[self.pos..self.pos + len];
+            let bucket = &set.buckets[set.bucket(self.hash)];
+            while let Some(&(hash, id)) = bucket.get(self.entry) {
+                self.entry += 1;
+                if hash == self.hash
+                    && util::memcmp(window, set.needles[id].as_slice())
+                {
+                    return Some((self.pos, id));
+                }
+            }
+            self.entry = 0;
+            if len > 0 && self.pos + len < self.haystack.len() {
+                self.hash.roll(
+                    &set.nhash,
+                    self.haystack[self.pos],
+                    self.haystack[self.pos + len],
+                );
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
 /// A hash derived from a needle.
 #[derive(Clone, Copy, Debug, Default)]
 pub(crate) struct NeedleHash {
@@ -110,35 +412,47 @@ pub(crate) struct NeedleHash {
     /// where n is the length of the needle. This is how we "remove" a byte
     /// from the hash once the hash window rolls past it.
     hash_2pow: u32,
+    /// Whether the hash was computed from the needle's bytes folded to ASCII
+    /// lowercase, for use in ASCII case insensitive searches.
+    ascii_nocase: bool,
 }
 
 impl NeedleHash {
     /// Create a new Rabin-Karp hash for the given needle for use in forward
     /// searching.
     pub(crate) fn forward(needle: &[u8]) -> NeedleHash {
-        let mut nh = NeedleHash { hash: Hash::new(), hash_2pow: 1 };
-        if needle.is_empty() {
-            return nh;
-        }
-        nh.hash.add(needle[0]);
-        for &b in needle.iter().skip(1) {
-            nh.hash.add(b);
-            nh.hash_2pow = nh.hash_2pow.wrapping_shl(1);
-        }
-        nh
+        NeedleHash::new(needle.iter().cloned(), false)
     }
 
     /// Create a new Rabin-Karp hash for the given needle for use in reverse
     /// searching.
     pub(crate) fn reverse(needle: &[u8]) -> NeedleHash {
-        let mut nh = NeedleHash { hash: Hash::new(), hash_2pow: 1 };
-        if needle.is_empty() {
-            return nh;
-        }
-        nh.hash.add(needle[needle.len() - 1]);
-        for &b in needle.iter().rev().skip(1) {
-            nh.hash.add(b);
-            nh.hash_2pow = nh.hash_2pow.wrapping_shl(1);
+        NeedleHash::new(needle.iter().rev().cloned(), false)
+    }
+
+    /// Like `forward`, but the hash ignores ASCII case.
+    pub(crate) fn forward_ascii_nocase(needle: &[u8]) -> NeedleHash {
+        NeedleHash::new(needle.iter().cloned(), true)
+    }
+
+    /// Like `reverse`, but the hash ignores ASCII case.
+    pub(crate) fn reverse_ascii_nocase(needle: &[u8]) -> NeedleHash {
+        NeedleHash::new(needle.iter().rev().cloned(), true)
+    }
+
+    /// Create a new Rabin-Karp hash from the needle's bytes, given in the
+    /// order in which they should be hashed.
+    fn new<I: Iterator<Item = u8>>(
+        bytes: I,
+        ascii_nocase: bool,
+    ) -> NeedleHash {
+        let mut nh =
+            NeedleHash { hash: Hash::new(), hash_2pow: 1, ascii_nocase };
+        for (i, b) in bytes.enumerate() {
+            nh.hash.add(fold(b, ascii_nocase));
+            if i > 0 {
+                nh.hash_2pow = nh.hash_2pow.wrapping_shl(1);
+            }
         }
         nh
     }
@@ -160,24 +474,6 @@ impl Hash {
         Hash(0)
     }
 
-    /// Create a new hash from the bytes given for use in forward searches.
-    pub(crate) fn from_bytes_fwd(bytes: &[u8]) -> Hash {
-        let mut hash = Hash::new();
-        for &b in bytes {
-            hash.add(b);
-        }
-        hash
-    }
-
-    /// Create a new hash from the bytes given for use in reverse searches.
-    fn from_bytes_rev(bytes: &[u8]) -> Hash {
-        let mut hash = Hash::new();
-        for &b in bytes.iter().rev() {
-            hash.add(b);
-        }
-        hash
-    }
-
     /// Add 'new' and remove 'old' from this hash. The given needle hash should
     /// correspond to the hash computed for the needle being searched for.
     ///
@@ -209,8 +505,12 @@ impl Hash {
 /// memmem/krate/prebuilt/sliceslice-words/words benchmark.
 #[cold]
 #[inline(never)]
-fn is_prefix(haystack: &[u8], needle: &[u8]) -> bool {
-    crate::memmem::util::is_prefix(haystack, needle)
+fn is_prefix(haystack: &[u8], needle: &[u8], ascii_nocase: bool) -> bool {
+    if ascii_nocase {
+        util::is_prefix_ascii_nocase(haystack, needle)
+    } else {
+        util::is_prefix(haystack, needle)
+    }
 }
 
 /// Returns true if the given needle is a suffix of the given haystack.
@@ -218,8 +518,12 @@ fn is_prefix(haystack: &[u8], needle: &[u8]) -> bool {
 /// See is_prefix for why this is forcefully not inlined.
 #[cold]
 #[inline(never)]
-fn is_suffix(haystack: &[u8], needle: &[u8]) -> bool {
-    crate::memmem::util::is_suffix(haystack, needle)
+fn is_suffix(haystack: &[u8], needle: &[u8], ascii_nocase: bool) -> bool {
+    if ascii_nocase {
+        util::is_suffix_ascii_nocase(haystack, needle)
+    } else {
+        util::is_suffix(haystack, needle)
+    }
 }
 
 #[cfg(test)]
@@ -231,3 +535,103 @@ mod simpletests {
 mod proptests {
     define_memmem_quickcheck_tests!(super::find, super::rfind);
 }
+
+#[cfg(all(test, feature = "std"))]
+mod settests {
+    use super::RabinKarpSet;
+
+    /// The naive reference implementation: try every needle at every
+    /// position.
+    fn naive(needles: &[&[u8]], haystack: &[u8]) -> Vec<(usize, usize)> {
+        let mut hits = vec![];
+        for start in 0..=haystack.len() {
+            for (id, needle) in needles.iter().enumerate() {
+                if haystack[start..].starts_with(needle) {
+                    hits.push((start, id));
+                }
+            }
+        }
+        hits
+    }
+
+    fn find_all(needles: &[&[u8]], haystack: &[u8]) -> Vec<(usize, usize)> {
+        RabinKarpSet::new(needles).find_iter(haystack).collect()
+    }
+
+    #[test]
+    fn simple() {
+        let needles: &[&[u8]] = &[b"aba", b"bab", b"aba", b"xyz"];
+        let haystack = b"ababab xyz";
+        assert_eq!(naive(needles, haystack), find_all(needles, haystack));
+        assert_eq!(
+            vec![(0, 0), (0, 2), (1, 1), (2, 0), (2, 2), (3, 1), (7, 3)],
+            find_all(needles, haystack),
+        );
+        assert_eq!(Vec::<(usize, usize)>::new(), find_all(needles, b"ab"));
+        assert_eq!(Vec::<(usize, usize)>::new(), find_all(&[], b"ab"));
+    }
+
+    #[test]
+    fn empty_needles() {
+        let needles: &[&[u8]] = &[b"", b""];
+        assert_eq!(naive(needles, b"ab"), find_all(needles, b"ab"));
+        assert_eq!(vec![(0, 0), (0, 1)], find_all(needles, b""));
+    }
+
+    #[test]
+    fn long_needles() {
+        // The hash only depends on the last 32 bytes of a window, so needles
+        // that only differ before that always collide.
+        let a = [b'a'; 40];
+        let mut b = a;
+        b[0] = b'b';
+        let needles: &[&[u8]] = &[&a, &b];
+        let mut haystack = vec![b'a'; 50];
+        haystack[5] = b'b';
+        assert_eq!(naive(needles, &haystack), find_all(needles, &haystack));
+        assert_eq!(vec![(5, 1), (6, 0)], find_all(needles, &haystack[..46]));
+    }
+
+    #[test]
+    fn many_needles() {
+        let owned: Vec<Vec<u8>> = (0..5000u32)
+            .map(|i| {
+                format!("{:08x}", i.wrapping_mul(2654435761)).into_bytes()
+            })
+            .collect();
+        let needles: Vec<&[u8]> = owned.iter().map(|n| n.as_slice()).collect();
+        let set = RabinKarpSet::new(&needles);
+        let mut haystack = vec![];
+        for &i in &[4999, 17, 0, 2500] {
+            haystack.extend_from_slice(b"--");
+            haystack.extend_from_slice(&owned[i]);
+        }
+        let hits: Vec<(usize, usize)> = set.find_iter(&haystack).collect();
+        assert_eq!(naive(&needles, &haystack), hits);
+        assert_eq!(vec![(2, 4999), (12, 17), (22, 0), (32, 2500)], hits);
+    }
+
+    #[test]
+    #[should_panic(expected = "must have the same length")]
+    fn mixed_lengths() {
+        RabinKarpSet::new(&["abc", "ab"]);
+    }
+
+    #[cfg(not(miri))]
+    quickcheck::quickcheck! {
+        fn qc_matches_naive(
+            needles: Vec<(u8, u8, u8)>,
+            haystack: Vec<u8>
+        ) -> bool {
+            use crate::memmem::proptests::to_alphabet;
+
+            let needles: Vec<Vec<u8>> = needles
+                .iter()
+                .map(|&(a, b, c)| to_alphabet(b"abc", &[a, b, c]))
+                .collect();
+            let needles: Vec<&[u8]> = needles.iter().map(|n| &n[..]).collect();
+            let haystack = to_alphabet(b"abc", &haystack);
+            naive(&needles, &haystack) == find_all(&needles, &haystack)
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use core::cmp;
+
+use crate::memmem::util::fold;
+
 /// A heuristic frequency based detection of rare bytes for substring search.
 ///
 /// This detector attempts to pick out two bytes in a needle that are predicted
@@ -11,6 +15,16 @@
 /// Note that we use `u8` to represent the offsets of the rare bytes in a
 /// needle to reduce space usage. This means that rare byte occurring after the
 /// first 255 bytes in a needle will never be used.
+///
+/// The offsets documented below are for forward searching. When constructed
+/// for reverse searching, each offset is instead the rightmost offset of its
+/// byte, such that the first candidate found by scanning backwards through a
+/// haystack lines up with the end of a possible match.
+///
+/// When constructed for ASCII case insensitive searching, bytes are compared
+/// with their case folded, and the rank of an ASCII letter accounts for both
+/// of its cases. Searchers using the rare bytes must then look for both cases
+/// of each byte too. (See `is_ascii_nocase`.)
 #[derive(Clone, Copy, Debug, Default)]
 pub(crate) struct RareNeedleBytes {
     /// The leftmost offset of the rarest byte in the needle, according to
@@ -32,6 +46,8 @@ pub(crate) struct RareNeedleBytes {
     /// always should be different from rare1i since it would otherwise be
     /// ineffective as a guard.
     rare2i: u8,
+    /// Whether the rare bytes were detected while ignoring ASCII case.
+    ascii_nocase: bool,
 }
 
 impl RareNeedleBytes {
@@ -39,12 +55,83 @@ impl RareNeedleBytes {
     /// only used in tests for generating input data.
     #[cfg(all(test, feature = "std"))]
     pub(crate) fn new(rare1i: u8, rare2i: u8) -> RareNeedleBytes {
-        RareNeedleBytes { rare1i, rare2i }
+        RareNeedleBytes { rare1i, rare2i, ascii_nocase: false }
     }
 
     /// Detect the leftmost offsets of the two rarest bytes in the given
     /// needle.
     pub(crate) fn forward(needle: &[u8]) -> RareNeedleBytes {
+        RareNeedleBytes::forward_imp(needle, false)
+    }
+
+    /// Like `forward`, but treats both ASCII cases of a byte as the same
+    /// byte.
+    pub(crate) fn forward_ascii_nocase(needle: &[u8]) -> RareNeedleBytes {
+        RareNeedleBytes::forward_imp(needle, true)
+    }
+
+    /// Detect the rightmost offsets of the two rarest bytes in the given
+    /// needle.
+    ///
+    /// This picks the same bytes as `forward` (modulo ties), but scans the
+    /// needle from the end so that the rightmost occurrence of each is used.
+    pub(crate) fn reverse(needle: &[u8]) -> RareNeedleBytes {
+        RareNeedleBytes::reverse_imp(needle, false)
+    }
+
+    /// Like `reverse`, but treats both ASCII cases of a byte as the same
+    /// byte.
+    pub(crate) fn reverse_ascii_nocase(needle: &[u8]) -> RareNeedleBytes {
+        RareNeedleBytes::reverse_imp(needle, true)
+    }
+
+    /// Like `forward` (or `forward_ascii_nocase`), but only picks bytes at
+    /// positions where every bit of the corresponding byte in `mask` is set.
+    /// These are the only positions at which a masked needle requires an
+    /// exact byte, so they are the only ones a prefilter can look for.
+    ///
+    /// This returns `None` if no such position exists within the first 256
+    /// bytes of the needle. If there is only one, then both offsets refer to
+    /// it.
+    pub(crate) fn forward_masked(
+        needle: &[u8],
+        mask: &[u8],
+        ascii_nocase: bool,
+    ) -> Option<RareNeedleBytes> {
+        let rank = |b: u8| rank_with(b, ascii_nocase);
+        let fold = |b: u8| fold(b, ascii_nocase);
+        let mut fixed = needle
+            .iter()
+            .zip(mask)
+            .take(core::u8::MAX as usize + 1)
+            .enumerate()
+            .filter(|&(_, (_, &m))| m == 0xFF)
+            .map(|(i, (&b, _))| (b, i as u8));
+
+        let (mut rare1, mut rare1i) = fixed.next()?;
+        let mut rare2: Option<(u8, u8)> = None;
+        for (b, i) in fixed {
+            if rank(b) < rank(rare1) {
+                rare2 = Some((rare1, rare1i));
+                rare1 = b;
+                rare1i = i;
+            } else if fold(b) != fold(rare1) {
+                match rare2 {
+                    Some((b2, _)) if rank(b2) <= rank(b) => {}
+                    _ => rare2 = Some((b, i)),
+                }
+            }
+        }
+        let rare2i = match rare2 {
+            Some((_, rare2i)) => rare2i,
+            None => rare1i,
+        };
+        Some(RareNeedleBytes { rare1i, rare2i, ascii_nocase })
+    }
+
+    fn forward_imp(needle: &[u8], ascii_nocase: bool) -> RareNeedleBytes {
+        let rank = |b: u8| rank_with(b, ascii_nocase);
+        let fold = |b: u8| fold(b, ascii_nocase);
         if needle.len() <= 1 || needle.len() > core::u8::MAX as usize {
             // For needles bigger than u8::MAX, our offsets aren't big enough.
             // (We make our offsets small to reduce stack copying.)
@@ -56,7 +143,7 @@ impl RareNeedleBytes {
             // cased in Two-Way.
             //
             // TODO: Benchmar this.
-            return RareNeedleBytes { rare1i: 0, rare2i: 0 };
+            return RareNeedleBytes { rare1i: 0, rare2i: 0, ascii_nocase };
         }
 
         // Find the rarest two bytes. We make them distinct by construction.
@@ -72,7 +159,7 @@ impl RareNeedleBytes {
                 rare2i = rare1i;
                 rare1 = b;
                 rare1i = i as u8;
-            } else if b != rare1 && rank(b) < rank(rare2) {
+            } else if fold(b) != fold(rare1) && rank(b) < rank(rare2) {
                 rare2 = b;
                 rare2i = i as u8;
             }
@@ -82,12 +169,48 @@ impl RareNeedleBytes {
         // candidate searching using these rare bytes by increasing the rate of
         // false positives.
         assert_ne!(rare1i, rare2i);
-        RareNeedleBytes { rare1i, rare2i }
+        RareNeedleBytes { rare1i, rare2i, ascii_nocase }
     }
 
-    /// Return the rare bytes in the given needle in the forward direction.
-    /// The needle given must be the same one given to the RareNeedleBytes
-    /// constructor.
+    fn reverse_imp(needle: &[u8], ascii_nocase: bool) -> RareNeedleBytes {
+        let rank = |b: u8| rank_with(b, ascii_nocase);
+        let fold = |b: u8| fold(b, ascii_nocase);
+        if needle.len() <= 1 || needle.len() > core::u8::MAX as usize {
+            // See the comment in `forward_imp` for why we give up here.
+            return RareNeedleBytes { rare1i: 0, rare2i: 0, ascii_nocase };
+        }
+
+        let last = needle.len() - 1;
+        let (mut rare1, mut rare1i) = (needle[last], last as u8);
+        let (mut rare2, mut rare2i) = (needle[last - 1], (last - 1) as u8);
+        if rank(rare2) < rank(rare1) {
+            core::mem::swap(&mut rare1, &mut rare2);
+            core::mem::swap(&mut rare1i, &mut rare2i);
+        }
+        for (i, &b) in needle.iter().enumerate().rev().skip(2) {
+            if rank(b) < rank(rare1) {
+                rare2 = rare1;
+                rare2i = rare1i;
+                rare1 = b;
+                rare1i = i as u8;
+            } else if fold(b) != fold(rare1) && rank(b) < rank(rare2) {
+                rare2 = b;
+                rare2i = i as u8;
+            }
+        }
+        assert_ne!(rare1i, rare2i);
+        RareNeedleBytes { rare1i, rare2i, ascii_nocase }
+    }
+
+    /// Returns true if and only if these rare bytes were detected while
+    /// ignoring ASCII case. In that case, a haystack byte matches a rare byte
+    /// if the two are equal when ASCII case is ignored.
+    pub(crate) fn is_ascii_nocase(&self) -> bool {
+        self.ascii_nocase
+    }
+
+    /// Return the rare bytes in the given needle. The needle given must be
+    /// the same one given to the RareNeedleBytes constructor.
     pub(crate) fn as_rare_bytes(&self, needle: &[u8]) -> (u8, u8) {
         (needle[self.rare1i as usize], needle[self.rare2i as usize])
     }
@@ -96,7 +219,7 @@ impl RareNeedleBytes {
     /// second offset. This is useful when the caller doesn't care whether
     /// rare1 is rarer than rare2, but just wants to ensure that they are
     /// ordered with respect to one another.
-    #[cfg(memchr_runtime_simd)]
+    #[cfg(not(feature = "safe-only"))]
     pub(crate) fn as_rare_ordered_usize(&self) -> (usize, usize) {
         let (rare1i, rare2i) = self.as_rare_ordered_u8();
         (rare1i as usize, rare2i as usize)
@@ -104,7 +227,7 @@ impl RareNeedleBytes {
 
     /// Like as_rare_ordered_usize, but returns the offsets as their native
     /// u8 values.
-    #[cfg(memchr_runtime_simd)]
+    #[cfg(not(feature = "safe-only"))]
     pub(crate) fn as_rare_ordered_u8(&self) -> (u8, u8) {
         if self.rare1i <= self.rare2i {
             (self.rare1i, self.rare2i)
@@ -125,7 +248,7 @@ impl RareNeedleBytes {
     /// the same one given to the RareNeedleBytes constructor.
     pub(crate) fn as_ranks(&self, needle: &[u8]) -> (usize, usize) {
         let (b1, b2) = self.as_rare_bytes(needle);
-        (rank(b1), rank(b2))
+        (rank_with(b1, self.ascii_nocase), rank_with(b2, self.ascii_nocase))
     }
 }
 
@@ -134,3 +257,14 @@ impl RareNeedleBytes {
 fn rank(b: u8) -> usize {
     crate::memmem::byte_frequencies::BYTE_FREQUENCIES[b as usize] as usize
 }
+
+/// Like `rank`, but when `ascii_nocase` is true, returns the rank of the more
+/// frequent of the two cases of an ASCII letter, since a case insensitive
+/// search has to stop at both.
+fn rank_with(b: u8, ascii_nocase: bool) -> usize {
+    if ascii_nocase {
+        cmp::max(rank(b.to_ascii_lowercase()), rank(b.to_ascii_uppercase()))
+    } else {
+        rank(b)
+    }
+}
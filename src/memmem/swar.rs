@@ -0,0 +1,137 @@
+use crate::memmem::{genericsimd, NeedleInfo};
+
+/// A substring search routine that only works on small needles, built on top
+/// of the generic SIMD searcher by treating a u64 as a vector of 8 bytes.
+///
+/// Unlike the other generic SIMD searchers, this works on every target.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Forward(genericsimd::Forward);
+
+impl Forward {
+    /// Create a new "generic simd" forward searcher. If one could not be
+    /// created from the given inputs, then None is returned.
+    pub(crate) fn new(ninfo: &NeedleInfo, needle: &[u8]) -> Option<Forward> {
+        genericsimd::Forward::new(ninfo, needle).map(Forward)
+    }
+
+    /// Returns the minimum length of haystack that is needed for this searcher
+    /// to work. Passing a haystack with a length smaller than this will cause
+    /// `find` to panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len(&self) -> usize {
+        self.0.min_haystack_len::<u64>()
+    }
+
+    #[inline(always)]
+    pub(crate) fn find(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        // SAFETY: The u64 vector implementation uses only plain integer
+        // operations, so there are no target features that need to be
+        // enabled.
+        unsafe { genericsimd::fwd_find::<u64>(&self.0, haystack, needle) }
+    }
+}
+
+/// A reverse substring search routine that only works on small needles,
+/// built on top of the generic SIMD searcher by treating a u64 as a vector
+/// of 8 bytes.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Reverse(genericsimd::Reverse);
+
+impl Reverse {
+    /// Create a new "generic simd" reverse searcher. If one could not be
+    /// created from the given inputs, then None is returned.
+    pub(crate) fn new(ninfo: &NeedleInfo, needle: &[u8]) -> Option<Reverse> {
+        genericsimd::Reverse::new(ninfo, needle).map(Reverse)
+    }
+
+    /// Returns the minimum length of haystack that is needed for this searcher
+    /// to work. Passing a haystack with a length smaller than this will cause
+    /// `rfind` to panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len(&self) -> usize {
+        self.0.min_haystack_len::<u64>()
+    }
+
+    #[inline(always)]
+    pub(crate) fn rfind(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        // SAFETY: The u64 vector implementation uses only plain integer
+        // operations, so there are no target features that need to be
+        // enabled.
+        unsafe { genericsimd::rev_find::<u64>(&self.0, haystack, needle) }
+    }
+}
+
+#[cfg(all(test, feature = "std", not(miri)))]
+mod tests {
+    use crate::memmem::{prefilter::PrefilterState, NeedleInfo};
+
+    fn find(
+        _: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        super::Forward::new(ninfo, needle).unwrap().find(haystack, needle)
+    }
+
+    fn rfind(
+        _: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        // Reverse prefilter tests expect the end of the match.
+        let rev = super::Reverse::new(ninfo, needle).unwrap();
+        rev.rfind(haystack, needle).map(|i| i + needle.len())
+    }
+
+    #[test]
+    fn prefilter_permutations() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+
+        // SAFETY: The u64 vector implementation is available on all targets,
+        // so this is always safe to call.
+        unsafe {
+            PrefilterTest::run_all_tests_filter(find, |t| {
+                // This substring searcher only works on certain configs, so
+                // filter our tests such that Forward::new will be guaranteed
+                // to succeed. (And also remove tests with a haystack that is
+                // too small.)
+                let fwd = match super::Forward::new(&t.ninfo, &t.needle) {
+                    None => return false,
+                    Some(fwd) => fwd,
+                };
+                t.haystack.len() >= fwd.min_haystack_len()
+            })
+        }
+    }
+
+    #[test]
+    fn prefilter_permutations_rev() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+
+        // SAFETY: The u64 vector implementation is available on all targets,
+        // so this is always safe to call.
+        unsafe {
+            PrefilterTest::run_all_tests_rev_filter(rfind, |t| {
+                // This substring searcher only works on certain configs, so
+                // filter our tests such that Reverse::new will be guaranteed
+                // to succeed. (And also remove tests with a haystack that is
+                // too small.)
+                let rev = match super::Reverse::new(&t.ninfo, &t.needle) {
+                    None => return false,
+                    Some(rev) => rev,
+                };
+                t.haystack.len() >= rev.min_haystack_len()
+            })
+        }
+    }
+}
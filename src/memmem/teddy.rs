@@ -0,0 +1,328 @@
+/*!
+This module implements "Teddy," a vectorized searcher for a small set of
+needles. It was originally devised by Geoffrey Langdale for Hyperscan.
+
+The idea is to assign each needle to one of 8 buckets, and to build, for each
+of the first few bytes of the needles (their "fingerprint"), two 16 entry
+tables: one indexed by the low nibble of a byte and another indexed by its
+high nibble. Each entry is a bitmap of the buckets containing a needle whose
+byte at that position has that nibble. Looking up both nibbles of a haystack
+byte and ANDing the results thus gives the buckets with a needle that might
+have that byte at that position.
+
+Since a vector shuffle can be used as a 16 entry table lookup, this can be
+done for every byte in a vector at once. Doing it for the vectors starting at
+each of the fingerprint's positions in the haystack, and then ANDing the
+results together, gives a vector whose non-zero lanes correspond to
+candidate positions, each with the buckets that could match there. Each
+candidate is then verified by comparing every needle in those buckets.
+
+Only the first (at most) three bytes of each needle are part of its
+fingerprint, so the number of false positives mostly depends on how
+distinct those bytes are. With more needles, buckets are shared by more
+needles and their bitmaps become less selective, which is why Teddy is
+only used for small sets of needles.
+*/
+
+use core::mem::size_of;
+
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+use core::arch::x86_64::{__m128i, __m256i};
+
+use crate::{cow::CowBytes, memmem::util::is_prefix, vector::ShuffleVector};
+
+/// The maximum number of needles that Teddy accepts.
+pub(crate) const MAX_NEEDLES: usize = 64;
+
+/// The number of buckets. This is fixed by the number of bits in a lane.
+const BUCKETS: usize = 8;
+
+/// The maximum number of leading bytes of each needle used as its
+/// fingerprint. Every extra byte costs another shuffle and load for each
+/// vector in the haystack, but makes false positives less likely.
+const MAX_FINGERPRINT_LEN: usize = 3;
+
+/// A Teddy searcher for a fixed set of needles.
+///
+/// The needles themselves aren't stored here, so searching requires the
+/// same needles (in the same order) that were given to the constructor.
+#[derive(Clone, Debug)]
+pub(crate) struct Teddy {
+    /// The number of leading bytes of each needle used as its fingerprint.
+    /// This is never longer than the shortest needle.
+    fingerprint_len: usize,
+    /// The nibble tables for each position in the fingerprint. Only the
+    /// first `fingerprint_len` of them are used.
+    masks: [Mask; MAX_FINGERPRINT_LEN],
+    /// The indices of the needles assigned to each bucket, in ascending
+    /// order.
+    buckets: [Vec<usize>; BUCKETS],
+}
+
+/// The nibble tables for a single position in the fingerprint.
+///
+/// Each table is stored twice in a row, such that loading it into a 32 byte
+/// vector puts a copy of it in each 16 byte half. This is needed because a
+/// 32 byte shuffle only ever selects bytes from the same 16 byte half.
+#[derive(Clone, Copy, Debug, Default)]
+struct Mask {
+    lo: [u8; 32],
+    hi: [u8; 32],
+}
+
+impl Mask {
+    /// Record that a needle in the given bucket has the given byte at this
+    /// mask's position.
+    fn add(&mut self, bucket: usize, byte: u8) {
+        let (lo, hi) = ((byte & 0xF) as usize, (byte >> 4) as usize);
+        self.lo[lo] |= 1 << bucket;
+        self.lo[16 + lo] |= 1 << bucket;
+        self.hi[hi] |= 1 << bucket;
+        self.hi[16 + hi] |= 1 << bucket;
+    }
+
+    /// Returns the buckets that might have a needle with the given byte at
+    /// this mask's position.
+    #[inline(always)]
+    fn buckets(&self, byte: u8) -> u8 {
+        self.lo[(byte & 0xF) as usize] & self.hi[(byte >> 4) as usize]
+    }
+}
+
+impl Teddy {
+    /// Create a new Teddy searcher for the given needles.
+    ///
+    /// This returns `None` if there are no needles, more than `MAX_NEEDLES`
+    /// needles or if any needle is empty.
+    pub(crate) fn new(needles: &[CowBytes<'_>]) -> Option<Teddy> {
+        if needles.is_empty() || needles.len() > MAX_NEEDLES {
+            return None;
+        }
+        let shortest = needles.iter().map(|n| n.len()).min()?;
+        if shortest == 0 {
+            return None;
+        }
+        let fingerprint_len = core::cmp::min(MAX_FINGERPRINT_LEN, shortest);
+        let mut teddy = Teddy {
+            fingerprint_len,
+            masks: [Mask::default(); MAX_FINGERPRINT_LEN],
+            buckets: Default::default(),
+        };
+        // Needles with the same fingerprint always go in the same bucket,
+        // since they can't be told apart by the masks anyway. Otherwise, the
+        // distinct fingerprints are spread evenly over the buckets.
+        let mut fingerprints: Vec<&[u8]> = vec![];
+        for (i, needle) in needles.iter().enumerate() {
+            let fingerprint = &needle.as_slice()[..fingerprint_len];
+            let bucket =
+                match fingerprints.iter().position(|&f| f == fingerprint) {
+                    Some(distinct) => distinct % BUCKETS,
+                    None => {
+                        fingerprints.push(fingerprint);
+                        (fingerprints.len() - 1) % BUCKETS
+                    }
+                };
+            for (mask, &b) in teddy.masks.iter_mut().zip(fingerprint) {
+                mask.add(bucket, b);
+            }
+            teddy.buckets[bucket].push(i);
+        }
+        Some(teddy)
+    }
+
+    /// Returns the minimum length of haystack that is needed for searching
+    /// with vectors of type `V`. Passing a shorter haystack to `find` will
+    /// cause it to panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len<V: ShuffleVector>(&self) -> usize {
+        size_of::<V>() + self.fingerprint_len - 1
+    }
+
+    /// Search for the leftmost match of any of the given needles in the
+    /// given haystack. If more than one needle matches at the leftmost
+    /// position, then the one with the smallest index wins.
+    ///
+    /// On success, this returns the starting position of the match and the
+    /// index of the needle that matched.
+    ///
+    /// # Panics
+    ///
+    /// When `haystack.len()` is less than `min_haystack_len::<V>()`.
+    ///
+    /// # Safety
+    ///
+    /// Since this is meant to be used with vector functions, callers need to
+    /// specialize this inside of a function with a `target_feature`
+    /// attribute. Therefore, callers must ensure that whatever target feature
+    /// is being used supports the vector functions that this function is
+    /// specialized for. (For `__m128i`, this is `ssse3` and not just `sse2`.)
+    #[inline(always)]
+    pub(crate) unsafe fn find<V: ShuffleVector>(
+        &self,
+        needles: &[CowBytes<'_>],
+        haystack: &[u8],
+    ) -> Option<(usize, usize)> {
+        assert!(
+            haystack.len() >= self.min_haystack_len::<V>(),
+            "haystack too small, should be at least {} but got {}",
+            self.min_haystack_len::<V>(),
+            haystack.len(),
+        );
+
+        let masks = [
+            self.load_masks::<V>(0),
+            self.load_masks::<V>(1),
+            self.load_masks::<V>(2),
+        ];
+        // A bitmask with a bit set for every lane in a vector.
+        let all = !0u32 >> (32 - size_of::<V>());
+        let start_ptr = haystack.as_ptr();
+        let last_ptr =
+            start_ptr.add(haystack.len() - self.min_haystack_len::<V>());
+        let mut ptr = start_ptr;
+        while ptr <= last_ptr {
+            let mut mask = self.candidates(&masks, ptr) & all;
+            while mask != 0 {
+                let at = sub(ptr, start_ptr) + mask.trailing_zeros() as usize;
+                if let Some(id) = self.verify(needles, haystack, at) {
+                    return Some((at, id));
+                }
+                mask &= mask - 1;
+            }
+            ptr = ptr.add(size_of::<V>());
+        }
+        if ptr < last_ptr.add(size_of::<V>()) {
+            // Search the last vector's worth of candidates, but ignore the
+            // candidates at positions that were already searched above.
+            let skip = sub(ptr, last_ptr);
+            let mut mask = (self.candidates(&masks, last_ptr) & all) >> skip;
+            while mask != 0 {
+                let at = sub(ptr, start_ptr) + mask.trailing_zeros() as usize;
+                if let Some(id) = self.verify(needles, haystack, at) {
+                    return Some((at, id));
+                }
+                mask &= mask - 1;
+            }
+        }
+        None
+    }
+
+    /// Load the low and high nibble tables for the given fingerprint
+    /// position into vectors.
+    #[inline(always)]
+    unsafe fn load_masks<V: ShuffleVector>(&self, i: usize) -> (V, V) {
+        let mask = &self.masks[i];
+        (
+            V::load_unaligned(mask.lo.as_ptr()),
+            V::load_unaligned(mask.hi.as_ptr()),
+        )
+    }
+
+    /// Returns a bitmask of the candidate positions in the vector starting
+    /// at `ptr`, where bit `i` corresponds to the position `ptr + i`.
+    ///
+    /// `ptr` must point to at least `size_of::<V>() + fingerprint_len - 1`
+    /// readable bytes. The bits above the first `size_of::<V>()` are
+    /// unspecified.
+    #[inline(always)]
+    unsafe fn candidates<V: ShuffleVector>(
+        &self,
+        masks: &[(V, V); MAX_FINGERPRINT_LEN],
+        ptr: *const u8,
+    ) -> u32 {
+        let mut res = lookup(masks[0], V::load_unaligned(ptr));
+        for (i, &mask) in
+            masks.iter().enumerate().take(self.fingerprint_len).skip(1)
+        {
+            res = res.and(lookup(mask, V::load_unaligned(ptr.add(i))));
+        }
+        !res.cmpeq(V::splat(0)).movemask()
+    }
+
+    /// Verify the candidate at the given position, and return the smallest
+    /// index of the needles that match there, if any.
+    #[inline(always)]
+    fn verify(
+        &self,
+        needles: &[CowBytes<'_>],
+        haystack: &[u8],
+        at: usize,
+    ) -> Option<usize> {
+        let mut buckets = 0xFF;
+        let masks = &self.masks[..self.fingerprint_len];
+        for (mask, &b) in masks.iter().zip(&haystack[at..]) {
+            buckets &= mask.buckets(b);
+        }
+        let haystack = &haystack[at..];
+        let mut found = None;
+        while buckets != 0 {
+            let bucket = buckets.trailing_zeros() as usize;
+            for &id in &self.buckets[bucket] {
+                if let Some(found) = found {
+                    if found < id {
+                        break;
+                    }
+                }
+                if is_prefix(haystack, needles[id].as_slice()) {
+                    found = Some(id);
+                    break;
+                }
+            }
+            buckets &= buckets - 1;
+        }
+        found
+    }
+}
+
+/// Search with Teddy using 16 byte vectors.
+///
+/// # Safety
+///
+/// Callers must ensure that the ssse3 CPU feature is enabled in the current
+/// environment.
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+#[target_feature(enable = "ssse3")]
+pub(crate) unsafe fn find_ssse3(
+    teddy: &Teddy,
+    needles: &[CowBytes<'_>],
+    haystack: &[u8],
+) -> Option<(usize, usize)> {
+    teddy.find::<__m128i>(needles, haystack)
+}
+
+/// Search with Teddy using 32 byte vectors, or 16 byte vectors if the
+/// haystack is too short.
+///
+/// # Safety
+///
+/// Callers must ensure that the avx2 CPU feature is enabled in the current
+/// environment.
+#[cfg(all(not(miri), target_arch = "x86_64", memchr_runtime_simd))]
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn find_avx2(
+    teddy: &Teddy,
+    needles: &[CowBytes<'_>],
+    haystack: &[u8],
+) -> Option<(usize, usize)> {
+    if haystack.len() < teddy.min_haystack_len::<__m256i>() {
+        teddy.find::<__m128i>(needles, haystack)
+    } else {
+        teddy.find::<__m256i>(needles, haystack)
+    }
+}
+
+/// Returns a vector whose lanes contain the buckets that could match each of
+/// the bytes in `chunk`, according to the given low and high nibble tables.
+#[inline(always)]
+unsafe fn lookup<V: ShuffleVector>((lo, hi): (V, V), chunk: V) -> V {
+    let lonibs = chunk.and(V::splat(0xF));
+    let hinibs = chunk.high_nibbles();
+    lo.shuffle_bytes(lonibs).and(hi.shuffle_bytes(hinibs))
+}
+
+/// Subtract `b` from `a` and return the difference. `a` should be greater than
+/// or equal to `b`.
+fn sub(a: *const u8, b: *const u8) -> usize {
+    debug_assert!(a >= b);
+    (a as usize) - (b as usize)
+}
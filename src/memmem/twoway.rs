@@ -1,6 +1,9 @@
 use core::cmp;
 
-use crate::memmem::{prefilter::Pre, util};
+use crate::memmem::{
+    prefilter::Pre,
+    util::{self, fold},
+};
 
 /// Two-Way search in the forward direction.
 #[derive(Clone, Copy, Debug)]
@@ -72,27 +75,49 @@ struct TwoWay {
     /// The amount we shift by in the Two-Way search algorithm. This
     /// corresponds to the "small period" and "large period" cases.
     shift: Shift,
+    /// Whether bytes are compared while ignoring ASCII case.
+    ///
+    /// When true, everything above is computed on the needle with its bytes
+    /// folded to ASCII lowercase. That is, this is just Two-Way over an
+    /// alphabet in which both cases of an ASCII letter are the same symbol.
+    ascii_nocase: bool,
 }
 
 impl Forward {
     /// Create a searcher that uses the Two-Way algorithm by searching forwards
     /// through any haystack.
     pub(crate) fn new(needle: &[u8]) -> Forward {
+        Forward::new_imp(needle, false)
+    }
+
+    /// Like `new`, but the searcher ignores ASCII case.
+    pub(crate) fn new_ascii_nocase(needle: &[u8]) -> Forward {
+        Forward::new_imp(needle, true)
+    }
+
+    fn new_imp(needle: &[u8], ascii_nocase: bool) -> Forward {
         if needle.is_empty() {
             return Forward(TwoWay::empty());
         }
 
-        let byteset = ApproximateByteSet::new(needle);
-        let min_suffix = Suffix::forward(needle, SuffixKind::Minimal);
-        let max_suffix = Suffix::forward(needle, SuffixKind::Maximal);
+        let byteset = ApproximateByteSet::new(needle, ascii_nocase);
+        let min_suffix =
+            Suffix::forward(needle, SuffixKind::Minimal, ascii_nocase);
+        let max_suffix =
+            Suffix::forward(needle, SuffixKind::Maximal, ascii_nocase);
         let (period_lower_bound, critical_pos) =
             if min_suffix.pos > max_suffix.pos {
                 (min_suffix.period, min_suffix.pos)
             } else {
                 (max_suffix.period, max_suffix.pos)
             };
-        let shift = Shift::forward(needle, period_lower_bound, critical_pos);
-        Forward(TwoWay { byteset, critical_pos, shift })
+        let shift = Shift::forward(
+            needle,
+            period_lower_bound,
+            critical_pos,
+            ascii_nocase,
+        );
+        Forward(TwoWay { byteset, critical_pos, shift, ascii_nocase })
     }
 
     /// Find the position of the first occurrence of this searcher's needle in
@@ -113,12 +138,18 @@ impl Forward {
         debug_assert!(!needle.is_empty(), "needle should not be empty");
         debug_assert!(needle.len() <= haystack.len(), "haystack too short");
 
-        match self.0.shift {
-            Shift::Small { period } => {
-                self.find_small_imp(pre, haystack, needle, period)
+        match (self.0.shift, self.0.ascii_nocase) {
+            (Shift::Small { period }, false) => {
+                self.find_small_imp(pre, haystack, needle, period, false)
             }
-            Shift::Large { shift } => {
-                self.find_large_imp(pre, haystack, needle, shift)
+            (Shift::Small { period }, true) => {
+                self.find_small_imp(pre, haystack, needle, period, true)
+            }
+            (Shift::Large { shift }, false) => {
+                self.find_large_imp(pre, haystack, needle, shift, false)
+            }
+            (Shift::Large { shift }, true) => {
+                self.find_large_imp(pre, haystack, needle, shift, true)
             }
         }
     }
@@ -147,6 +178,9 @@ impl Forward {
     // its disabled, we explicitly inline each search implementation based on
     // whether a prefilter will be used or not. The decision on which to use
     // is made in the parent meta searcher.
+    //
+    // Similarly, `ascii_nocase` is always given as a constant by `find`, so
+    // that case sensitive searches don't pay for case folding.
 
     #[inline(always)]
     fn find_small_imp(
@@ -155,7 +189,10 @@ impl Forward {
         haystack: &[u8],
         needle: &[u8],
         period: usize,
+        ascii_nocase: bool,
     ) -> Option<usize> {
+        let eq =
+            |b1: u8, b2: u8| fold(b1, ascii_nocase) == fold(b2, ascii_nocase);
         let last_byte = needle.len() - 1;
         let mut pos = 0;
         let mut shift = 0;
@@ -176,7 +213,7 @@ impl Forward {
                 shift = 0;
                 continue;
             }
-            while i < needle.len() && needle[i] == haystack[pos + i] {
+            while i < needle.len() && eq(needle[i], haystack[pos + i]) {
                 i += 1;
             }
             if i < needle.len() {
@@ -184,10 +221,10 @@ impl Forward {
                 shift = 0;
             } else {
                 let mut j = self.0.critical_pos;
-                while j > shift && needle[j] == haystack[pos + j] {
+                while j > shift && eq(needle[j], haystack[pos + j]) {
                     j -= 1;
                 }
-                if j <= shift && needle[shift] == haystack[pos + shift] {
+                if j <= shift && eq(needle[shift], haystack[pos + shift]) {
                     return Some(pos);
                 }
                 pos += period;
@@ -204,7 +241,10 @@ impl Forward {
         haystack: &[u8],
         needle: &[u8],
         shift: usize,
+        ascii_nocase: bool,
     ) -> Option<usize> {
+        let eq =
+            |b1: u8, b2: u8| fold(b1, ascii_nocase) == fold(b2, ascii_nocase);
         let last_byte = needle.len() - 1;
         let mut pos = 0;
         'outer: while pos + needle.len() <= haystack.len() {
@@ -222,14 +262,14 @@ impl Forward {
                 continue;
             }
             let mut i = self.0.critical_pos;
-            while i < needle.len() && needle[i] == haystack[pos + i] {
+            while i < needle.len() && eq(needle[i], haystack[pos + i]) {
                 i += 1;
             }
             if i < needle.len() {
                 pos += i - self.0.critical_pos + 1;
             } else {
                 for j in (0..self.0.critical_pos).rev() {
-                    if needle[j] != haystack[pos + j] {
+                    if !eq(needle[j], haystack[pos + j]) {
                         pos += shift;
                         continue 'outer;
                     }
@@ -245,13 +285,24 @@ impl Reverse {
     /// Create a searcher that uses the Two-Way algorithm by searching in
     /// reverse through any haystack.
     pub(crate) fn new(needle: &[u8]) -> Reverse {
+        Reverse::new_imp(needle, false)
+    }
+
+    /// Like `new`, but the searcher ignores ASCII case.
+    pub(crate) fn new_ascii_nocase(needle: &[u8]) -> Reverse {
+        Reverse::new_imp(needle, true)
+    }
+
+    fn new_imp(needle: &[u8], ascii_nocase: bool) -> Reverse {
         if needle.is_empty() {
             return Reverse(TwoWay::empty());
         }
 
-        let byteset = ApproximateByteSet::new(needle);
-        let min_suffix = Suffix::reverse(needle, SuffixKind::Minimal);
-        let max_suffix = Suffix::reverse(needle, SuffixKind::Maximal);
+        let byteset = ApproximateByteSet::new(needle, ascii_nocase);
+        let min_suffix =
+            Suffix::reverse(needle, SuffixKind::Minimal, ascii_nocase);
+        let max_suffix =
+            Suffix::reverse(needle, SuffixKind::Maximal, ascii_nocase);
         let (period_lower_bound, critical_pos) =
             if min_suffix.pos < max_suffix.pos {
                 (min_suffix.period, min_suffix.pos)
@@ -259,36 +310,45 @@ impl Reverse {
                 (max_suffix.period, max_suffix.pos)
             };
         // let critical_pos = needle.len() - critical_pos;
-        let shift = Shift::reverse(needle, period_lower_bound, critical_pos);
-        Reverse(TwoWay { byteset, critical_pos, shift })
+        let shift = Shift::reverse(
+            needle,
+            period_lower_bound,
+            critical_pos,
+            ascii_nocase,
+        );
+        Reverse(TwoWay { byteset, critical_pos, shift, ascii_nocase })
     }
 
     /// Find the position of the last occurrence of this searcher's needle
     /// in the given haystack. If one does not exist, then return None.
     ///
-    /// This will automatically initialize prefilter state. This should only
-    /// be used for one-off searches.
+    /// This accepts prefilter state that is useful when using the same
+    /// searcher multiple times, such as in an iterator. The prefilter must be
+    /// a reverse prefilter.
     ///
     /// Callers must guarantee that the needle is non-empty and its length is
     /// <= the haystack's length.
     #[inline(always)]
     pub(crate) fn rfind(
         &self,
+        pre: Option<&mut Pre<'_>>,
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
         debug_assert!(!needle.is_empty(), "needle should not be empty");
         debug_assert!(needle.len() <= haystack.len(), "haystack too short");
-        // For the reverse case, we don't use a prefilter. It's plausible that
-        // perhaps we should, but it's a lot of additional code to do it, and
-        // it's not clear that it's actually worth it. If you have a really
-        // compelling use case for this, please file an issue.
-        match self.0.shift {
-            Shift::Small { period } => {
-                self.rfind_small_imp(haystack, needle, period)
+        match (self.0.shift, self.0.ascii_nocase) {
+            (Shift::Small { period }, false) => {
+                self.rfind_small_imp(pre, haystack, needle, period, false)
             }
-            Shift::Large { shift } => {
-                self.rfind_large_imp(haystack, needle, shift)
+            (Shift::Small { period }, true) => {
+                self.rfind_small_imp(pre, haystack, needle, period, true)
+            }
+            (Shift::Large { shift }, false) => {
+                self.rfind_large_imp(pre, haystack, needle, shift, false)
+            }
+            (Shift::Large { shift }, true) => {
+                self.rfind_large_imp(pre, haystack, needle, shift, true)
             }
         }
     }
@@ -297,42 +357,66 @@ impl Reverse {
     /// only useful for conveniently testing this substring implementation in
     /// isolation.
     #[cfg(test)]
-    fn rfind_general(&self, haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    fn rfind_general(
+        &self,
+        pre: Option<&mut Pre<'_>>,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
         if needle.is_empty() {
             Some(haystack.len())
         } else if haystack.len() < needle.len() {
             None
         } else {
-            self.rfind(haystack, needle)
+            self.rfind(pre, haystack, needle)
         }
     }
 
+    // As with the forward searcher, each of the two search implementations
+    // below is explicitly inlined based on whether a prefilter will be used
+    // and whether ASCII case is ignored.
+    // Since a reverse prefilter reports the end of a candidate match, it
+    // lines up with `pos` directly.
+
     #[inline(always)]
     fn rfind_small_imp(
         &self,
+        mut pre: Option<&mut Pre<'_>>,
         haystack: &[u8],
         needle: &[u8],
         period: usize,
+        ascii_nocase: bool,
     ) -> Option<usize> {
+        let eq =
+            |b1: u8, b2: u8| fold(b1, ascii_nocase) == fold(b2, ascii_nocase);
         let nlen = needle.len();
         let mut pos = haystack.len();
         let mut shift = nlen;
         while pos >= nlen {
+            if let Some(pre) = pre.as_mut() {
+                if pre.should_call() {
+                    pos = pre.call(&haystack[..pos], needle)?;
+                    shift = nlen;
+                    if pos < nlen {
+                        return None;
+                    }
+                }
+            }
             if !self.0.byteset.contains(haystack[pos - nlen]) {
                 pos -= nlen;
                 shift = nlen;
                 continue;
             }
             let mut i = cmp::min(self.0.critical_pos, shift);
-            while i > 0 && needle[i - 1] == haystack[pos - nlen + i - 1] {
+            while i > 0 && eq(needle[i - 1], haystack[pos - nlen + i - 1]) {
                 i -= 1;
             }
-            if i > 0 || needle[0] != haystack[pos - nlen] {
+            if i > 0 || !eq(needle[0], haystack[pos - nlen]) {
                 pos -= self.0.critical_pos - i + 1;
                 shift = nlen;
             } else {
                 let mut j = self.0.critical_pos;
-                while j < shift && needle[j] == haystack[pos - nlen + j] {
+                while j < shift && eq(needle[j], haystack[pos - nlen + j]) {
                     j += 1;
                 }
                 if j >= shift {
@@ -348,26 +432,38 @@ impl Reverse {
     #[inline(always)]
     fn rfind_large_imp(
         &self,
+        mut pre: Option<&mut Pre<'_>>,
         haystack: &[u8],
         needle: &[u8],
         shift: usize,
+        ascii_nocase: bool,
     ) -> Option<usize> {
+        let eq =
+            |b1: u8, b2: u8| fold(b1, ascii_nocase) == fold(b2, ascii_nocase);
         let nlen = needle.len();
         let mut pos = haystack.len();
         while pos >= nlen {
+            if let Some(pre) = pre.as_mut() {
+                if pre.should_call() {
+                    pos = pre.call(&haystack[..pos], needle)?;
+                    if pos < nlen {
+                        return None;
+                    }
+                }
+            }
             if !self.0.byteset.contains(haystack[pos - nlen]) {
                 pos -= nlen;
                 continue;
             }
             let mut i = self.0.critical_pos;
-            while i > 0 && needle[i - 1] == haystack[pos - nlen + i - 1] {
+            while i > 0 && eq(needle[i - 1], haystack[pos - nlen + i - 1]) {
                 i -= 1;
             }
-            if i > 0 || needle[0] != haystack[pos - nlen] {
+            if i > 0 || !eq(needle[0], haystack[pos - nlen]) {
                 pos -= self.0.critical_pos - i + 1;
             } else {
                 let mut j = self.0.critical_pos;
-                while j < nlen && needle[j] == haystack[pos - nlen + j] {
+                while j < nlen && eq(needle[j], haystack[pos - nlen + j]) {
                     j += 1;
                 }
                 if j == nlen {
@@ -383,9 +479,10 @@ impl Reverse {
 impl TwoWay {
     fn empty() -> TwoWay {
         TwoWay {
-            byteset: ApproximateByteSet::new(b""),
+            byteset: ApproximateByteSet::new(b"", false),
             critical_pos: 0,
             shift: Shift::Large { shift: 0 },
+            ascii_nocase: false,
         }
     }
 }
@@ -437,6 +534,7 @@ impl Shift {
         needle: &[u8],
         period_lower_bound: usize,
         critical_pos: usize,
+        ascii_nocase: bool,
     ) -> Shift {
         let large = cmp::max(critical_pos, needle.len() - critical_pos);
         if critical_pos * 2 >= needle.len() {
@@ -444,7 +542,12 @@ impl Shift {
         }
 
         let (u, v) = needle.split_at(critical_pos);
-        if !util::is_suffix(&v[..period_lower_bound], u) {
+        let is_suffix = if ascii_nocase {
+            util::is_suffix_ascii_nocase(&v[..period_lower_bound], u)
+        } else {
+            util::is_suffix(&v[..period_lower_bound], u)
+        };
+        if !is_suffix {
             return Shift::Large { shift: large };
         }
         Shift::Small { period: period_lower_bound }
@@ -460,6 +563,7 @@ impl Shift {
         needle: &[u8],
         period_lower_bound: usize,
         critical_pos: usize,
+        ascii_nocase: bool,
     ) -> Shift {
         let large = cmp::max(critical_pos, needle.len() - critical_pos);
         if (needle.len() - critical_pos) * 2 >= needle.len() {
@@ -467,7 +571,13 @@ impl Shift {
         }
 
         let (v, u) = needle.split_at(critical_pos);
-        if !util::is_prefix(&v[v.len() - period_lower_bound..], u) {
+        let v = &v[v.len() - period_lower_bound..];
+        let is_prefix = if ascii_nocase {
+            util::is_prefix_ascii_nocase(v, u)
+        } else {
+            util::is_prefix(v, u)
+        };
+        if !is_prefix {
             return Shift::Large { shift: large };
         }
         Shift::Small { period: period_lower_bound }
@@ -493,7 +603,7 @@ struct Suffix {
 }
 
 impl Suffix {
-    fn forward(needle: &[u8], kind: SuffixKind) -> Suffix {
+    fn forward(needle: &[u8], kind: SuffixKind, ascii_nocase: bool) -> Suffix {
         debug_assert!(!needle.is_empty());
 
         // suffix represents our maximal (or minimal) suffix, along with
@@ -517,8 +627,9 @@ impl Suffix {
         let mut offset = 0;
 
         while candidate_start + offset < needle.len() {
-            let current = needle[suffix.pos + offset];
-            let candidate = needle[candidate_start + offset];
+            let current = fold(needle[suffix.pos + offset], ascii_nocase);
+            let candidate =
+                fold(needle[candidate_start + offset], ascii_nocase);
             match kind.cmp(current, candidate) {
                 SuffixOrdering::Accept => {
                     suffix = Suffix { pos: candidate_start, period: 1 };
@@ -543,7 +654,7 @@ impl Suffix {
         suffix
     }
 
-    fn reverse(needle: &[u8], kind: SuffixKind) -> Suffix {
+    fn reverse(needle: &[u8], kind: SuffixKind, ascii_nocase: bool) -> Suffix {
         debug_assert!(!needle.is_empty());
 
         // See the comments in `forward` for how this works.
@@ -555,8 +666,9 @@ impl Suffix {
         let mut offset = 0;
 
         while offset < candidate_start {
-            let current = needle[suffix.pos - offset - 1];
-            let candidate = needle[candidate_start - offset - 1];
+            let current = fold(needle[suffix.pos - offset - 1], ascii_nocase);
+            let candidate =
+                fold(needle[candidate_start - offset - 1], ascii_nocase);
             match kind.cmp(current, candidate) {
                 SuffixOrdering::Accept => {
                     suffix = Suffix { pos: candidate_start, period: 1 };
@@ -648,11 +760,16 @@ impl SuffixKind {
 struct ApproximateByteSet(u64);
 
 impl ApproximateByteSet {
-    /// Create a new set from the given needle.
-    fn new(needle: &[u8]) -> ApproximateByteSet {
+    /// Create a new set from the given needle. If `ascii_nocase` is true,
+    /// then both ASCII cases of every byte in the needle are added.
+    fn new(needle: &[u8], ascii_nocase: bool) -> ApproximateByteSet {
         let mut bits = 0;
         for &b in needle {
             bits |= 1 << (b % 64);
+            if ascii_nocase {
+                bits |= 1 << (b.to_ascii_lowercase() % 64);
+                bits |= 1 << (b.to_ascii_uppercase() % 64);
+            }
         }
         ApproximateByteSet(bits)
     }
@@ -678,13 +795,13 @@ mod tests {
 
     /// Convenience wrapper for computing the suffix as a byte string.
     fn get_suffix_forward(needle: &[u8], kind: SuffixKind) -> (&[u8], usize) {
-        let s = Suffix::forward(needle, kind);
+        let s = Suffix::forward(needle, kind, false);
         (&needle[s.pos..], s.period)
     }
 
     /// Convenience wrapper for computing the reverse suffix as a byte string.
     fn get_suffix_reverse(needle: &[u8], kind: SuffixKind) -> (&[u8], usize) {
-        let s = Suffix::reverse(needle, kind);
+        let s = Suffix::reverse(needle, kind, false);
         (&needle[..s.pos], s.period)
     }
 
@@ -841,6 +958,54 @@ mod tests {
             let expected = naive_maximal_suffix_reverse(&bytes);
             expected == got
         }
+
+        fn qc_rev_prefilter_prefix_is_substring(bs: Vec<u8>) -> bool {
+            crate::memmem::proptests::prefix_is_substring(
+                true,
+                &bs,
+                super::simpletests::twoway_rfind_prefilter,
+            )
+        }
+
+        fn qc_rev_prefilter_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<u8>
+        ) -> bool {
+            crate::memmem::proptests::matches_naive(
+                true,
+                &haystack,
+                &needle,
+                super::simpletests::twoway_rfind_prefilter,
+            )
+        }
+
+        fn qc_ascii_nocase_matches_naive(
+            haystack: Vec<u8>,
+            needle: Vec<u8>
+        ) -> bool {
+            use crate::memmem::proptests::{
+                matches_naive_ascii_nocase, nocase_alphabet,
+            };
+
+            let (haystack, needle) =
+                (nocase_alphabet(&haystack), nocase_alphabet(&needle));
+            for i in 0..needle.len().min(8) {
+                let needle = &needle[..=i];
+                matches_naive_ascii_nocase(
+                    false,
+                    &haystack,
+                    needle,
+                    super::simpletests::twoway_find_ascii_nocase,
+                );
+                matches_naive_ascii_nocase(
+                    true,
+                    &haystack,
+                    needle,
+                    super::simpletests::twoway_rfind_ascii_nocase,
+                );
+            }
+            true
+        }
     }
 }
 
@@ -859,11 +1024,54 @@ mod simpletests {
         haystack: &[u8],
         needle: &[u8],
     ) -> Option<usize> {
-        Reverse::new(needle).rfind_general(haystack, needle)
+        Reverse::new(needle).rfind_general(None, haystack, needle)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn twoway_find_ascii_nocase(
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        Forward::new_ascii_nocase(needle).find_general(None, haystack, needle)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn twoway_rfind_ascii_nocase(
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        Reverse::new_ascii_nocase(needle).rfind_general(None, haystack, needle)
+    }
+
+    /// Like twoway_rfind, but with whichever reverse prefilter would be
+    /// selected for the given needle in the current environment.
+    pub(crate) fn twoway_rfind_prefilter(
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        use crate::memmem::{
+            prefilter::{self, Prefilter, PrefilterState},
+            NeedleInfo,
+        };
+
+        let ninfo = NeedleInfo::reverse(needle);
+        let prefn =
+            prefilter::reverse(&Prefilter::Auto, &ninfo.rarebytes, needle);
+        let mut state = PrefilterState::new();
+        let mut pre =
+            prefn.map(|prefn| Pre { state: &mut state, prefn, ninfo: &ninfo });
+        Reverse::new(needle).rfind_general(pre.as_mut(), haystack, needle)
     }
 
     define_memmem_simple_tests!(twoway_find, twoway_rfind);
 
+    #[test]
+    fn simple_reverse_prefilter() {
+        crate::memmem::testsimples::run_search_tests_rev(
+            twoway_rfind_prefilter,
+        );
+    }
+
     // This is a regression test caught by quickcheck that exercised a bug in
     // the reverse small period handling. The bug was that we were using 'if j
     // == shift' to determine if a match occurred, but the correct guard is 'if
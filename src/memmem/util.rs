@@ -16,6 +16,65 @@ pub(crate) fn is_suffix(haystack: &[u8], needle: &[u8]) -> bool {
         && memcmp(&haystack[haystack.len() - needle.len()..], needle)
 }
 
+/// Like `is_prefix`, but ignores ASCII case when comparing bytes.
+#[inline(always)]
+pub(crate) fn is_prefix_ascii_nocase(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.len() <= haystack.len()
+        && memcmp_ascii_nocase(&haystack[..needle.len()], needle)
+}
+
+/// Like `is_suffix`, but ignores ASCII case when comparing bytes.
+#[inline(always)]
+pub(crate) fn is_suffix_ascii_nocase(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.len() <= haystack.len()
+        && memcmp_ascii_nocase(
+            &haystack[haystack.len() - needle.len()..],
+            needle,
+        )
+}
+
+/// Return true if and only if `x` and `y` are equal when ASCII case is
+/// ignored.
+///
+/// This doesn't try to be as clever as `memcmp`, since it's only used for
+/// confirming candidates in case insensitive searches.
+#[inline(always)]
+pub(crate) fn memcmp_ascii_nocase(x: &[u8], y: &[u8]) -> bool {
+    x.eq_ignore_ascii_case(y)
+}
+
+/// Return true if and only if `x` and `y` agree on every bit that is set in
+/// the corresponding byte of `mask`. When `ascii_nocase` is true, bytes are
+/// folded to ASCII lowercase before being masked.
+///
+/// All three slices must have the same length.
+#[inline(always)]
+pub(crate) fn memcmp_masked(
+    x: &[u8],
+    y: &[u8],
+    mask: &[u8],
+    ascii_nocase: bool,
+) -> bool {
+    debug_assert!(x.len() == y.len() && y.len() == mask.len());
+    x.iter().zip(y).zip(mask).all(|((&x, &y), &m)| {
+        (fold(x, ascii_nocase) ^ fold(y, ascii_nocase)) & m == 0
+    })
+}
+
+/// Fold the given byte to ASCII lowercase when `ascii_nocase` is true, and
+/// return it unchanged otherwise.
+///
+/// Callers generally pass a constant for `ascii_nocase` into an inlined
+/// routine, such that this branch disappears.
+#[inline(always)]
+pub(crate) fn fold(b: u8, ascii_nocase: bool) -> u8 {
+    if ascii_nocase {
+        b.to_ascii_lowercase()
+    } else {
+        b
+    }
+}
+
 /// Return true if and only if x.len() == y.len() && x[i] == y[i] for all
 /// 0 <= i < x.len().
 ///
@@ -28,6 +87,7 @@ pub(crate) fn is_suffix(haystack: &[u8], needle: &[u8]) -> bool {
 /// We mark this as inline always, although, some callers may not want it
 /// inlined for better codegen (like Rabin-Karp). In that case, callers are
 /// advised to create a non-inlineable wrapper routine that calls memcmp.
+#[cfg(not(feature = "safe-only"))]
 #[inline(always)]
 pub(crate) fn memcmp(x: &[u8], y: &[u8]) -> bool {
     if x.len() != y.len() {
@@ -86,3 +146,34 @@ pub(crate) fn memcmp(x: &[u8], y: &[u8]) -> bool {
         vx == vy
     }
 }
+
+/// Like the `memcmp` above, but doesn't use any unsafe code. This is used when
+/// the 'safe-only' feature is enabled.
+///
+/// This compares 4 bytes at a time just like the unsafe version, but reads
+/// them with `chunks_exact` instead of unaligned pointer loads.
+#[cfg(feature = "safe-only")]
+#[inline(always)]
+pub(crate) fn memcmp(x: &[u8], y: &[u8]) -> bool {
+    if x.len() != y.len() {
+        return false;
+    }
+    if x.len() < 4 {
+        return x.iter().zip(y).all(|(b1, b2)| b1 == b2);
+    }
+    for (cx, cy) in x.chunks_exact(4).zip(y.chunks_exact(4)) {
+        if read_u32(cx) != read_u32(cy) {
+            return false;
+        }
+    }
+    // As above, the final comparison may overlap with the last comparison
+    // done in the loop for lengths that aren't multiples of four.
+    read_u32(&x[x.len() - 4..]) == read_u32(&y[y.len() - 4..])
+}
+
+/// Read a `u32` from the first 4 bytes of the given slice.
+#[cfg(feature = "safe-only")]
+#[inline(always)]
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
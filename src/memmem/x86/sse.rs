@@ -1,6 +1,9 @@
 use core::arch::x86_64::__m128i;
 
-use crate::memmem::{genericsimd, NeedleInfo};
+use crate::{
+    arch::{self, Backend},
+    memmem::{genericsimd, NeedleInfo},
+};
 
 /// An SSE accelerated vectorized substring search routine that only works on
 /// small needles.
@@ -11,7 +14,7 @@ impl Forward {
     /// Create a new "generic simd" forward searcher. If one could not be
     /// created from the given inputs, then None is returned.
     pub(crate) fn new(ninfo: &NeedleInfo, needle: &[u8]) -> Option<Forward> {
-        if !cfg!(memchr_runtime_sse2) {
+        if !cfg!(memchr_runtime_sse2) || !arch::allows(Backend::Sse2) {
             return None;
         }
         genericsimd::Forward::new(ninfo, needle).map(Forward)
@@ -53,6 +56,58 @@ impl Forward {
     }
 }
 
+/// An SSE accelerated vectorized reverse substring search routine that only
+/// works on small needles.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Reverse(genericsimd::Reverse);
+
+impl Reverse {
+    /// Create a new "generic simd" reverse searcher. If one could not be
+    /// created from the given inputs, then None is returned.
+    pub(crate) fn new(ninfo: &NeedleInfo, needle: &[u8]) -> Option<Reverse> {
+        if !cfg!(memchr_runtime_sse2) || !arch::allows(Backend::Sse2) {
+            return None;
+        }
+        genericsimd::Reverse::new(ninfo, needle).map(Reverse)
+    }
+
+    /// Returns the minimum length of haystack that is needed for this searcher
+    /// to work. Passing a haystack with a length smaller than this will cause
+    /// `rfind` to panic.
+    #[inline(always)]
+    pub(crate) fn min_haystack_len(&self) -> usize {
+        self.0.min_haystack_len::<__m128i>()
+    }
+
+    #[inline(always)]
+    pub(crate) fn rfind(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        // SAFETY: sse2 is enabled on all x86_64 targets, so this is always
+        // safe to call.
+        unsafe { self.rfind_impl(haystack, needle) }
+    }
+
+    /// The implementation of rfind marked with the appropriate target
+    /// feature.
+    ///
+    /// # Safety
+    ///
+    /// This is safe to call in all cases since sse2 is guaranteed to be part
+    /// of x86_64. It is marked as unsafe because of the target feature
+    /// attribute.
+    #[target_feature(enable = "sse2")]
+    unsafe fn rfind_impl(
+        &self,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        genericsimd::rev_find::<__m128i>(&self.0, haystack, needle)
+    }
+}
+
 #[cfg(all(test, feature = "std", not(miri)))]
 mod tests {
     use crate::memmem::{prefilter::PrefilterState, NeedleInfo};
@@ -66,6 +121,17 @@ mod tests {
         super::Forward::new(ninfo, needle).unwrap().find(haystack, needle)
     }
 
+    fn rfind(
+        _: &mut PrefilterState,
+        ninfo: &NeedleInfo,
+        haystack: &[u8],
+        needle: &[u8],
+    ) -> Option<usize> {
+        // Reverse prefilter tests expect the end of the match.
+        let rev = super::Reverse::new(ninfo, needle).unwrap();
+        rev.rfind(haystack, needle).map(|i| i + needle.len())
+    }
+
     #[test]
     fn prefilter_permutations() {
         use crate::memmem::prefilter::tests::PrefilterTest;
@@ -86,4 +152,25 @@ mod tests {
             })
         }
     }
+
+    #[test]
+    fn prefilter_permutations_rev() {
+        use crate::memmem::prefilter::tests::PrefilterTest;
+
+        // SAFETY: sse2 is enabled on all x86_64 targets, so this is always
+        // safe to call.
+        unsafe {
+            PrefilterTest::run_all_tests_rev_filter(rfind, |t| {
+                // This substring searcher only works on certain configs, so
+                // filter our tests such that Reverse::new will be guaranteed
+                // to succeed. (And also remove tests with a haystack that is
+                // too small.)
+                let rev = match super::Reverse::new(&t.ninfo, &t.needle) {
+                    None => return false,
+                    Some(rev) => rev,
+                };
+                t.haystack.len() >= rev.min_haystack_len()
+            })
+        }
+    }
 }
@@ -0,0 +1,177 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    arch::{self, x86_64::avx2, x86_64::sse2},
+    tests::memchr::testdata::memchr_tests,
+};
+
+#[test]
+fn memchr_avx2_find() {
+    if !avx2::is_available() {
+        return;
+    }
+    for test in memchr_tests() {
+        test.one(false, |n1, h| avx2::memchr(n1, h).unwrap());
+        test.two(false, |n1, n2, h| avx2::memchr2(n1, n2, h).unwrap());
+        test.three(false, |n1, n2, n3, h| {
+            avx2::memchr3(n1, n2, n3, h).unwrap()
+        });
+        test.one(true, |n1, h| avx2::memrchr(n1, h).unwrap());
+        test.two(true, |n1, n2, h| avx2::memrchr2(n1, n2, h).unwrap());
+        test.three(true, |n1, n2, n3, h| {
+            avx2::memrchr3(n1, n2, n3, h).unwrap()
+        });
+    }
+}
+
+#[test]
+fn memchr_sse2_find() {
+    if !sse2::is_available() {
+        return;
+    }
+    for test in memchr_tests() {
+        test.one(false, |n1, h| sse2::memchr(n1, h).unwrap());
+        test.two(false, |n1, n2, h| sse2::memchr2(n1, n2, h).unwrap());
+        test.three(false, |n1, n2, n3, h| {
+            sse2::memchr3(n1, n2, n3, h).unwrap()
+        });
+        test.one(true, |n1, h| sse2::memrchr(n1, h).unwrap());
+        test.two(true, |n1, n2, h| sse2::memrchr2(n1, n2, h).unwrap());
+        test.three(true, |n1, n2, n3, h| {
+            sse2::memrchr3(n1, n2, n3, h).unwrap()
+        });
+    }
+}
+
+#[test]
+fn memchr_fallback_find() {
+    for test in memchr_tests() {
+        test.one(false, arch::fallback::memchr);
+        test.two(false, arch::fallback::memchr2);
+        test.three(false, arch::fallback::memchr3);
+        test.one(true, arch::fallback::memrchr);
+        test.two(true, arch::fallback::memrchr2);
+        test.three(true, arch::fallback::memrchr3);
+    }
+}
+
+#[test]
+fn memchr_naive_find() {
+    for test in memchr_tests() {
+        test.one(false, arch::naive::memchr);
+        test.two(false, arch::naive::memchr2);
+        test.three(false, arch::naive::memchr3);
+        test.one(true, arch::naive::memrchr);
+        test.two(true, arch::naive::memrchr2);
+        test.three(true, arch::naive::memrchr3);
+    }
+}
+
+#[test]
+fn memchr_libc_find() {
+    if !arch::libc::is_available() {
+        assert_eq!(None, arch::libc::memchr(b'a', b"a"));
+        return;
+    }
+    for test in memchr_tests() {
+        test.one(false, |n1, h| arch::libc::memchr(n1, h).unwrap());
+        if arch::libc::memrchr(b'a', b"a").is_some() {
+            test.one(true, |n1, h| arch::libc::memrchr(n1, h).unwrap());
+        }
+    }
+}
+
+#[test]
+fn unavailable_backends_return_none() {
+    if !avx2::is_available() {
+        assert_eq!(None, avx2::memchr(b'a', b"a"));
+        assert_eq!(None, avx2::memrchr3(b'a', b'b', b'c', b"a"));
+    }
+    if !sse2::is_available() {
+        assert_eq!(None, sse2::memchr(b'a', b"a"));
+        assert_eq!(None, sse2::memrchr3(b'a', b'b', b'c', b"a"));
+    }
+}
+
+#[test]
+fn selected_is_available() {
+    match arch::selected() {
+        arch::Backend::Avx2 => assert!(avx2::is_available()),
+        arch::Backend::Sse2 => assert!(sse2::is_available()),
+        arch::Backend::Libc => assert!(arch::libc::is_available()),
+        arch::Backend::Swar => {
+            panic!("SWAR is only used for substring search")
+        }
+        _ => {}
+    }
+}
+
+#[test]
+fn selected_honors_features() {
+    if cfg!(feature = "no-avx") || cfg!(feature = "force-fallback") {
+        assert!(!avx2::is_available());
+        assert_ne!(arch::Backend::Avx2, arch::selected());
+    }
+    if cfg!(feature = "force-fallback") {
+        assert!(!sse2::is_available());
+        assert!(!arch::libc::is_available());
+        assert_eq!(arch::Backend::Fallback, arch::selected());
+        let finder = crate::memmem::Finder::new("abc");
+        assert_ne!(arch::Backend::Avx2, finder.backend());
+        assert_ne!(arch::Backend::Sse2, finder.backend());
+        assert_ne!(arch::Backend::Portable, finder.backend());
+    }
+    if cfg!(feature = "safe-only") {
+        let finder = crate::memmem::Finder::new("abc");
+        assert_ne!(arch::Backend::Swar, finder.backend());
+    }
+    if cfg!(feature = "libc-memrchr-only") {
+        assert_ne!(arch::Backend::Libc, arch::selected());
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_backends_match_naive(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        let h = &corpus;
+        let expected = [
+            arch::naive::memchr(n1, h),
+            arch::naive::memchr2(n1, n2, h),
+            arch::naive::memchr3(n1, n2, n3, h),
+            arch::naive::memrchr(n1, h),
+            arch::naive::memrchr2(n1, n2, h),
+            arch::naive::memrchr3(n1, n2, n3, h),
+        ];
+        let fallback = [
+            arch::fallback::memchr(n1, h),
+            arch::fallback::memchr2(n1, n2, h),
+            arch::fallback::memchr3(n1, n2, n3, h),
+            arch::fallback::memrchr(n1, h),
+            arch::fallback::memrchr2(n1, n2, h),
+            arch::fallback::memrchr3(n1, n2, n3, h),
+        ];
+        let avx2 = [
+            avx2::memchr(n1, h),
+            avx2::memchr2(n1, n2, h),
+            avx2::memchr3(n1, n2, n3, h),
+            avx2::memrchr(n1, h),
+            avx2::memrchr2(n1, n2, h),
+            avx2::memrchr3(n1, n2, n3, h),
+        ];
+        let sse2 = [
+            sse2::memchr(n1, h),
+            sse2::memchr2(n1, n2, h),
+            sse2::memchr3(n1, n2, n3, h),
+            sse2::memrchr(n1, h),
+            sse2::memrchr2(n1, n2, h),
+            sse2::memrchr3(n1, n2, n3, h),
+        ];
+        (0..expected.len()).all(|i| {
+            fallback[i] == expected[i]
+                && (avx2[i].is_none() || avx2[i] == Some(expected[i]))
+                && (sse2[i].is_none() || sse2[i] == Some(expected[i]))
+        })
+    }
+}
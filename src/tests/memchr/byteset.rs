@@ -0,0 +1,141 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    memchr::byteset::{fallback, naive},
+    tests::memchr::testdata::memchr_tests,
+    ByteSet,
+};
+
+#[test]
+fn byteset_find() {
+    for test in memchr_tests() {
+        test.set(false, |set, haystack| set.find(haystack));
+    }
+}
+
+#[test]
+fn byteset_fallback_find() {
+    for test in memchr_tests() {
+        test.set(false, fallback::find);
+    }
+}
+
+#[test]
+fn byteset_rfind() {
+    for test in memchr_tests() {
+        test.set(true, |set, haystack| set.rfind(haystack));
+    }
+}
+
+#[test]
+fn byteset_fallback_rfind() {
+    for test in memchr_tests() {
+        test.set(true, fallback::rfind);
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+#[test]
+fn byteset_ssse3_find() {
+    use crate::memchr::byteset::x86::ssse3;
+
+    if !is_x86_feature_detected!("ssse3") {
+        return;
+    }
+    for test in memchr_tests() {
+        test.set(false, |set, haystack| unsafe { ssse3::find(set, haystack) });
+        test.set(true, |set, haystack| unsafe { ssse3::rfind(set, haystack) });
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", memchr_runtime_simd))]
+#[test]
+fn byteset_avx2_find() {
+    use crate::memchr::byteset::x86::avx;
+
+    if !is_x86_feature_detected!("avx2") {
+        return;
+    }
+    for test in memchr_tests() {
+        test.set(false, |set, haystack| unsafe { avx::find(set, haystack) });
+        test.set(true, |set, haystack| unsafe { avx::rfind(set, haystack) });
+    }
+}
+
+#[test]
+fn byteset_iter() {
+    for test in memchr_tests() {
+        test.iter_set(false, |set, haystack| set.find_iter(haystack));
+    }
+}
+
+#[test]
+fn byteset_rev_iter() {
+    for test in memchr_tests() {
+        test.iter_set(true, |set, haystack| set.rfind_iter(haystack));
+    }
+}
+
+#[test]
+fn byteset_membership() {
+    let mut set = ByteSet::new(b"\x00\x7F\x80\xFFaz");
+    assert_eq!(6, set.len());
+    for b in 0..=255u8 {
+        assert_eq!(b"\x00\x7F\x80\xFFaz".contains(&b), set.contains(b));
+    }
+    set.remove(b'a');
+    set.remove(b'a');
+    assert!(!set.contains(b'a'));
+    assert_eq!(5, set.len());
+    assert_eq!(
+        vec![0x00, b'z', 0x7F, 0x80, 0xFF],
+        set.iter().collect::<Vec<_>>()
+    );
+
+    let set: ByteSet = (0..=255u8).collect();
+    assert_eq!(256, set.len());
+    assert!(ByteSet::empty().is_empty());
+    assert_eq!(None, ByteSet::empty().find(b"abc"));
+    assert_eq!(None, ByteSet::empty().rfind(b"abc"));
+}
+
+// Sets with more members than what the word-at-a-time fallback supports use a
+// different code path, so make sure we hit both.
+fn set_from(mut bytes: Vec<u8>, big: bool) -> ByteSet {
+    if big {
+        bytes.extend((0..16).map(|i| i * 16));
+    }
+    ByteSet::new(&bytes)
+}
+
+quickcheck! {
+    fn qc_byteset_find_matches_naive(
+        bytes: Vec<u8>, big: bool, corpus: Vec<u8>
+    ) -> bool {
+        let set = set_from(bytes, big);
+        set.find(&corpus) == naive::find(&set, &corpus)
+            && fallback::find(&set, &corpus) == naive::find(&set, &corpus)
+    }
+
+    fn qc_byteset_rfind_matches_naive(
+        bytes: Vec<u8>, big: bool, corpus: Vec<u8>
+    ) -> bool {
+        let set = set_from(bytes, big);
+        set.rfind(&corpus) == naive::rfind(&set, &corpus)
+            && fallback::rfind(&set, &corpus) == naive::rfind(&set, &corpus)
+    }
+
+    fn qc_byteset_iter(bytes: Vec<u8>, corpus: Vec<u8>) -> bool {
+        let set = ByteSet::new(&bytes);
+        let expected: Vec<usize> = corpus
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| set.contains(b))
+            .map(|t| t.0)
+            .collect();
+        let got: Vec<usize> = set.find_iter(&corpus).collect();
+        let mut got_rev: Vec<usize> = set.rfind_iter(&corpus).collect();
+        got_rev.reverse();
+        expected == got && expected == got_rev
+    }
+}
@@ -0,0 +1,124 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    memchr::{fallback, naive},
+    memchr2_count, memchr3_count, memchr_count, memchr_nth,
+};
+
+use super::small_alphabet;
+
+/// Call the given function with haystacks (at a few different alignments) in
+/// which every `step`th byte is cycled through the needles given. All other
+/// bytes are `z`.
+fn spaced<F: FnMut(&[u8])>(needles: &[u8], lens: &[usize], mut f: F) {
+    let mut buf = vec![];
+    for &len in lens {
+        for step in &[1, 2, 3, 7, 64] {
+            for align in 0..3 {
+                buf.clear();
+                buf.extend((0..align + len).map(|i| {
+                    if i % step == 0 {
+                        needles[(i / step) % needles.len()]
+                    } else {
+                        b'z'
+                    }
+                }));
+                f(&buf[align..]);
+            }
+        }
+    }
+}
+
+fn lens() -> Vec<usize> {
+    // Include a couple of long haystacks, so that the vector counters need
+    // to be flushed before they overflow.
+    (0..200).chain(vec![5_000, 20_001]).collect()
+}
+
+#[test]
+fn memchr1_count() {
+    spaced(b"\n", &lens(), |haystack| {
+        let expected = naive::memchr_count(b'\n', haystack);
+        assert_eq!(expected, memchr_count(b'\n', haystack));
+        assert_eq!(expected, fallback::memchr_count(b'\n', haystack));
+    });
+}
+
+#[test]
+fn memchr2_count_all() {
+    spaced(b"\r\n", &lens(), |haystack| {
+        let expected = naive::memchr2_count(b'\r', b'\n', haystack);
+        assert_eq!(expected, memchr2_count(b'\r', b'\n', haystack));
+        assert_eq!(expected, fallback::memchr2_count(b'\r', b'\n', haystack));
+    });
+}
+
+#[test]
+fn memchr3_count_all() {
+    spaced(b",;\t", &lens(), |haystack| {
+        let expected = naive::memchr3_count(b',', b';', b'\t', haystack);
+        assert_eq!(expected, memchr3_count(b',', b';', b'\t', haystack));
+        assert_eq!(
+            expected,
+            fallback::memchr3_count(b',', b';', b'\t', haystack)
+        );
+    });
+}
+
+#[test]
+fn memchr1_nth() {
+    spaced(b"\n", &(0..200).collect::<Vec<usize>>(), |haystack| {
+        let count = naive::memchr_count(b'\n', haystack);
+        for n in 0..=count {
+            let expected = naive::memchr_nth(n, b'\n', haystack);
+            assert_eq!(expected, memchr_nth(n, b'\n', haystack));
+            assert_eq!(expected, fallback::memchr_nth(n, b'\n', haystack));
+        }
+    });
+}
+
+#[test]
+fn memchr1_nth_long() {
+    spaced(b"\n", &[5_000, 20_001], |haystack| {
+        let count = naive::memchr_count(b'\n', haystack);
+        for &n in &[0, 1, count / 3, count / 2, count - 1, count, count + 1] {
+            let expected = naive::memchr_nth(n, b'\n', haystack);
+            assert_eq!(expected, memchr_nth(n, b'\n', haystack));
+            assert_eq!(expected, fallback::memchr_nth(n, b'\n', haystack));
+        }
+    });
+}
+
+quickcheck! {
+    fn qc_memchr1_count_matches_naive(n1: u8, corpus: Vec<u8>) -> bool {
+        memchr_count(n1, &corpus) == naive::memchr_count(n1, &corpus)
+            && fallback::memchr_count(n1, &corpus)
+                == naive::memchr_count(n1, &corpus)
+    }
+
+    fn qc_memchr2_count_matches_naive(
+        n1: u8, n2: u8, corpus: Vec<u8>
+    ) -> bool {
+        memchr2_count(n1, n2, &corpus)
+            == naive::memchr2_count(n1, n2, &corpus)
+            && fallback::memchr2_count(n1, n2, &corpus)
+                == naive::memchr2_count(n1, n2, &corpus)
+    }
+
+    fn qc_memchr3_count_matches_naive(
+        n1: u8, n2: u8, n3: u8, corpus: Vec<u8>
+    ) -> bool {
+        memchr3_count(n1, n2, n3, &corpus)
+            == naive::memchr3_count(n1, n2, n3, &corpus)
+            && fallback::memchr3_count(n1, n2, n3, &corpus)
+                == naive::memchr3_count(n1, n2, n3, &corpus)
+    }
+
+    fn qc_memchr1_nth_matches_naive(n: u8, n1: u8, corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(&[0, 1, 2, 3], corpus);
+        let (n, n1) = (n as usize % 64, n1 % 4);
+        memchr_nth(n, n1, &corpus) == naive::memchr_nth(n, n1, &corpus)
+            && fallback::memchr_nth(n, n1, &corpus)
+                == naive::memchr_nth(n, n1, &corpus)
+    }
+}
@@ -0,0 +1,98 @@
+use quickcheck::quickcheck;
+
+use crate::{lines, lines_with, LineTerminator};
+
+use super::small_alphabet;
+
+/// A simple line splitter to compare the `Lines` iterator against.
+fn naive_lines(haystack: &[u8], terminator: LineTerminator) -> Vec<&[u8]> {
+    let mut lines = vec![];
+    let mut start = 0;
+    for (i, &b) in haystack.iter().enumerate() {
+        if b == b'\n' {
+            lines.push(&haystack[start..i + 1]);
+            start = i + 1;
+        }
+    }
+    if start < haystack.len() {
+        lines.push(&haystack[start..]);
+    }
+    for line in lines.iter_mut() {
+        if terminator == LineTerminator::Keep || !line.ends_with(b"\n") {
+            continue;
+        }
+        *line = &line[..line.len() - 1];
+        if terminator == LineTerminator::Crlf && line.ends_with(b"\r") {
+            *line = &line[..line.len() - 1];
+        }
+    }
+    lines
+}
+
+fn collect(haystack: &[u8], terminator: LineTerminator) -> Vec<&[u8]> {
+    lines_with(haystack, terminator).collect()
+}
+
+fn collect_rev(haystack: &[u8], terminator: LineTerminator) -> Vec<&[u8]> {
+    let mut lines: Vec<&[u8]> =
+        lines_with(haystack, terminator).rev().collect();
+    lines.reverse();
+    lines
+}
+
+#[test]
+fn lines_edge_cases() {
+    let empty: Vec<&[u8]> = vec![];
+    assert_eq!(empty, lines(b"").collect::<Vec<&[u8]>>());
+    assert_eq!(vec![b""], lines(b"\n").collect::<Vec<&[u8]>>());
+    assert_eq!(vec![b""], lines(b"\r\n").collect::<Vec<&[u8]>>());
+    assert_eq!(vec![b"\r"], lines(b"\r").collect::<Vec<&[u8]>>());
+    assert_eq!(vec![b"a"], lines(b"a").collect::<Vec<&[u8]>>());
+    assert_eq!(vec![b"a"], lines(b"a\n").rev().collect::<Vec<&[u8]>>());
+    assert_eq!(
+        vec![&b"a\r"[..], b"b"],
+        lines(b"a\r\r\nb").collect::<Vec<&[u8]>>()
+    );
+    assert_eq!(
+        vec![b"\r\n", b"\r\n"],
+        collect(b"\r\n\r\n", LineTerminator::Keep)
+    );
+    assert_eq!(vec![b"\r", b"\r"], collect(b"\r\n\r\n", LineTerminator::Lf));
+}
+
+#[test]
+fn lines_both_ends() {
+    let mut it = lines(b"a\nb\r\nc\nd");
+    assert_eq!(Some(&b"a"[..]), it.next());
+    assert_eq!(Some(&b"d"[..]), it.next_back());
+    assert_eq!(Some(&b"c"[..]), it.next_back());
+    assert_eq!(Some(&b"b"[..]), it.next());
+    assert_eq!(None, it.next());
+    assert_eq!(None, it.next_back());
+}
+
+#[test]
+fn lines_matches_str_lines() {
+    let haystack = "foo\r\nbar\n\r\nbaz\rquux\n\n";
+    let expected: Vec<&[u8]> =
+        haystack.lines().map(|l| l.as_bytes()).collect();
+    assert_eq!(expected, collect(haystack.as_bytes(), LineTerminator::Crlf));
+}
+
+quickcheck! {
+    fn qc_lines_matches_naive(corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"a\r\n", corpus);
+        let terms =
+            [LineTerminator::Lf, LineTerminator::Crlf, LineTerminator::Keep];
+        terms.iter().all(|&term| {
+            let expected = naive_lines(&corpus, term);
+            expected == collect(&corpus, term)
+                && expected == collect_rev(&corpus, term)
+        })
+    }
+
+    fn qc_lines_keep_roundtrips(corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"a\r\n", corpus);
+        collect(&corpus, LineTerminator::Keep).concat() == corpus
+    }
+}
@@ -1,7 +1,34 @@
 #[cfg(all(feature = "std", not(miri)))]
+mod arch;
+#[cfg(all(feature = "std", not(miri)))]
+mod byteset;
+#[cfg(all(feature = "std", not(miri)))]
+mod count;
+#[cfg(all(feature = "std", not(miri)))]
 mod iter;
 #[cfg(all(feature = "std", not(miri)))]
+mod lines;
+#[cfg(all(feature = "std", not(miri)))]
 mod memchr;
+#[cfg(all(feature = "std", not(miri)))]
+mod nocase;
+#[cfg(all(feature = "std", not(miri)))]
+mod not;
+#[cfg(all(feature = "std", memchr_portable_simd, not(miri)))]
+mod portable;
+#[cfg(all(feature = "std", not(miri)))]
+mod replace;
 mod simple;
 #[cfg(all(feature = "std", not(miri)))]
+mod split;
+#[cfg(all(feature = "std", not(miri)))]
 mod testdata;
+#[cfg(all(feature = "std", not(miri)))]
+mod which;
+
+// Random data rarely contains the bytes a test searches for, so quickcheck
+// corpora are mapped onto a small alphabet in which they're common.
+#[cfg(all(feature = "std", not(miri)))]
+fn small_alphabet(alphabet: &[u8], corpus: Vec<u8>) -> Vec<u8> {
+    corpus.into_iter().map(|b| alphabet[b as usize % alphabet.len()]).collect()
+}
@@ -0,0 +1,106 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    memchr::{fallback, naive},
+    memchr_ascii_nocase, memchr_ascii_nocase_iter, memrchr_ascii_nocase,
+    memrchr_ascii_nocase_iter,
+};
+
+/// Call the given function with every haystack (at a few different
+/// alignments) that consists entirely of filler bytes except for at most one
+/// position holding `odd`, along with the position of the odd byte out (if
+/// any).
+///
+/// The filler bytes are all a bit or two away from 'x', so that a search
+/// that folds more than the ASCII case bit would find them.
+fn runs<F: FnMut(&[u8], Option<usize>)>(odd: u8, mut f: F) {
+    let filler = b"8\xF8yYz";
+    let mut buf = vec![];
+    for len in 0..200 {
+        for align in 0..3 {
+            buf.clear();
+            buf.extend(filler.iter().cycle().take(align + len));
+            f(&buf[align..], None);
+            for pos in 0..len {
+                let old = buf[align + pos];
+                buf[align + pos] = odd;
+                f(&buf[align..], Some(pos));
+                buf[align + pos] = old;
+            }
+        }
+    }
+}
+
+#[test]
+fn memchr_ascii_nocase_find() {
+    for &odd in [b'x', b'X'].iter() {
+        runs(odd, |haystack, pos| {
+            for &needle in [b'x', b'X'].iter() {
+                assert_eq!(pos, memchr_ascii_nocase(needle, haystack));
+                assert_eq!(pos, memrchr_ascii_nocase(needle, haystack));
+            }
+            assert_eq!(pos, fallback::memchr_ascii_nocase(b'x', haystack));
+            assert_eq!(pos, fallback::memrchr_ascii_nocase(b'x', haystack));
+        });
+    }
+}
+
+#[test]
+fn memchr_ascii_nocase_non_letters() {
+    // Bytes that aren't ASCII letters only ever match themselves.
+    assert_eq!(None, memchr_ascii_nocase(b'@', b"```"));
+    assert_eq!(Some(1), memchr_ascii_nocase(b'@', b"`@`"));
+    assert_eq!(None, memrchr_ascii_nocase(b'`', b"@@@"));
+    assert_eq!(Some(1), memrchr_ascii_nocase(b'`', b"@`@"));
+    assert_eq!(None, memchr_ascii_nocase(b'\xC0', b"\xE0\xE0"));
+    assert_eq!(None, memchr_ascii_nocase(b'[', b"{{{"));
+    assert_eq!(Some(0), memchr_ascii_nocase(b'1', b"1"));
+}
+
+#[test]
+fn memchr_ascii_nocase_iter_both_ends() {
+    let haystack = b"aAbBa.A";
+    let mut it = memchr_ascii_nocase_iter(b'A', haystack);
+    assert_eq!(Some(0), it.next());
+    assert_eq!(Some(6), it.next_back());
+    assert_eq!(Some(1), it.next());
+    assert_eq!(Some(4), it.next_back());
+    assert_eq!(None, it.next());
+    assert_eq!(
+        vec![6, 4, 1, 0],
+        memrchr_ascii_nocase_iter(b'a', haystack).collect::<Vec<usize>>()
+    );
+}
+
+quickcheck! {
+    fn qc_memchr_ascii_nocase_matches_naive(
+        n1: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        memchr_ascii_nocase(n1, &corpus)
+            == naive::memchr_ascii_nocase(n1, &corpus)
+            && memrchr_ascii_nocase(n1, &corpus)
+                == naive::memrchr_ascii_nocase(n1, &corpus)
+    }
+
+    fn qc_memchr_ascii_nocase_letters_matches_naive(
+        n1: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        // Pick a letter and make it (along with bytes that differ from it in
+        // the ASCII case bit only) common in the corpus.
+        let n1 = b'a' + n1 % 26;
+        let corpus: Vec<u8> = corpus
+            .into_iter()
+            .map(|b| if b % 4 == 0 { b } else { n1 ^ (b & 0xE0) })
+            .collect();
+        memchr_ascii_nocase(n1, &corpus)
+            == naive::memchr_ascii_nocase(n1, &corpus)
+            && memrchr_ascii_nocase(n1, &corpus)
+                == naive::memrchr_ascii_nocase(n1, &corpus)
+            && fallback::memchr_ascii_nocase(n1, &corpus)
+                == naive::memchr_ascii_nocase(n1, &corpus)
+            && fallback::memrchr_ascii_nocase(n1, &corpus)
+                == naive::memrchr_ascii_nocase(n1, &corpus)
+    }
+}
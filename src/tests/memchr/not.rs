@@ -0,0 +1,122 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    memchr::{fallback, naive},
+    memchr2_not, memchr3_not, memchr_not, memchr_not_iter, memrchr2_not,
+    memrchr3_not, memrchr_not, memrchr_not_iter,
+};
+
+/// Call the given function with every haystack (at a few different
+/// alignments) that consists entirely of needle bytes except for at most one
+/// position, along with the position of the odd byte out (if any).
+///
+/// The needle bytes given are cycled through to build each haystack, so that
+/// searches for multiple needles see all of their needles.
+fn runs<F: FnMut(&[u8], Option<usize>)>(needles: &[u8], mut f: F) {
+    let mut buf = vec![];
+    for len in 0..200 {
+        for align in 0..3 {
+            buf.clear();
+            buf.extend(needles.iter().cycle().take(align + len));
+            f(&buf[align..], None);
+            for pos in 0..len {
+                buf[align + pos] = b'z';
+                f(&buf[align..], Some(pos));
+                buf[align + pos] = needles[(align + pos) % needles.len()];
+            }
+        }
+    }
+}
+
+#[test]
+fn memchr1_not_find() {
+    runs(b" ", |haystack, pos| {
+        assert_eq!(pos, memchr_not(b' ', haystack));
+        assert_eq!(pos, fallback::memchr_not(b' ', haystack));
+        assert_eq!(pos, memrchr_not(b' ', haystack));
+        assert_eq!(pos, fallback::memrchr_not(b' ', haystack));
+    });
+}
+
+#[test]
+fn memchr2_not_find() {
+    runs(b" \t", |haystack, pos| {
+        assert_eq!(pos, memchr2_not(b' ', b'\t', haystack));
+        assert_eq!(pos, fallback::memchr2_not(b' ', b'\t', haystack));
+        assert_eq!(pos, memrchr2_not(b' ', b'\t', haystack));
+        assert_eq!(pos, fallback::memrchr2_not(b' ', b'\t', haystack));
+    });
+}
+
+#[test]
+fn memchr3_not_find() {
+    runs(b" \t\n", |haystack, pos| {
+        assert_eq!(pos, memchr3_not(b' ', b'\t', b'\n', haystack));
+        assert_eq!(pos, fallback::memchr3_not(b' ', b'\t', b'\n', haystack));
+        assert_eq!(pos, memrchr3_not(b' ', b'\t', b'\n', haystack));
+        assert_eq!(pos, fallback::memrchr3_not(b' ', b'\t', b'\n', haystack));
+    });
+}
+
+#[test]
+fn memchr_not_iter_both_ends() {
+    let haystack = b"\x00\x00a\x00\x00\x00b\x00c\x00";
+    let mut it = memchr_not_iter(0, haystack);
+    assert_eq!(Some(2), it.next());
+    assert_eq!(Some(8), it.next_back());
+    assert_eq!(Some(6), it.next());
+    assert_eq!(None, it.next_back());
+    assert_eq!(
+        vec![8, 6, 2],
+        memrchr_not_iter(0, haystack).collect::<Vec<usize>>()
+    );
+}
+
+// Random data very rarely contains long runs of the same byte, so we replace
+// most of the bytes in the corpus with needles.
+fn mostly(needles: &[u8], corpus: Vec<u8>) -> Vec<u8> {
+    corpus
+        .into_iter()
+        .enumerate()
+        .map(|(i, b)| if b < 250 { needles[i % needles.len()] } else { b })
+        .collect()
+}
+
+quickcheck! {
+    fn qc_memchr1_not_matches_naive(n1: u8, corpus: Vec<u8>) -> bool {
+        let corpus = mostly(&[n1], corpus);
+        memchr_not(n1, &corpus) == naive::memchr_not(n1, &corpus)
+            && memrchr_not(n1, &corpus) == naive::memrchr_not(n1, &corpus)
+            && fallback::memchr_not(n1, &corpus)
+                == naive::memchr_not(n1, &corpus)
+            && fallback::memrchr_not(n1, &corpus)
+                == naive::memrchr_not(n1, &corpus)
+    }
+
+    fn qc_memchr2_not_matches_naive(
+        n1: u8, n2: u8, corpus: Vec<u8>
+    ) -> bool {
+        let corpus = mostly(&[n1, n2], corpus);
+        memchr2_not(n1, n2, &corpus) == naive::memchr2_not(n1, n2, &corpus)
+            && memrchr2_not(n1, n2, &corpus)
+                == naive::memrchr2_not(n1, n2, &corpus)
+            && fallback::memchr2_not(n1, n2, &corpus)
+                == naive::memchr2_not(n1, n2, &corpus)
+            && fallback::memrchr2_not(n1, n2, &corpus)
+                == naive::memrchr2_not(n1, n2, &corpus)
+    }
+
+    fn qc_memchr3_not_matches_naive(
+        n1: u8, n2: u8, n3: u8, corpus: Vec<u8>
+    ) -> bool {
+        let corpus = mostly(&[n1, n2, n3], corpus);
+        memchr3_not(n1, n2, n3, &corpus)
+            == naive::memchr3_not(n1, n2, n3, &corpus)
+            && memrchr3_not(n1, n2, n3, &corpus)
+                == naive::memrchr3_not(n1, n2, n3, &corpus)
+            && fallback::memchr3_not(n1, n2, n3, &corpus)
+                == naive::memchr3_not(n1, n2, n3, &corpus)
+            && fallback::memrchr3_not(n1, n2, n3, &corpus)
+                == naive::memrchr3_not(n1, n2, n3, &corpus)
+    }
+}
@@ -0,0 +1,164 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    memchr::{fallback, portable},
+    tests::memchr::testdata::memchr_tests,
+};
+
+#[test]
+fn memchr1_portable_find() {
+    for test in memchr_tests() {
+        test.one(false, portable::memchr);
+    }
+}
+
+#[test]
+fn memchr2_portable_find() {
+    for test in memchr_tests() {
+        test.two(false, portable::memchr2);
+    }
+}
+
+#[test]
+fn memchr3_portable_find() {
+    for test in memchr_tests() {
+        test.three(false, portable::memchr3);
+    }
+}
+
+#[test]
+fn memrchr1_portable_find() {
+    for test in memchr_tests() {
+        test.one(true, portable::memrchr);
+    }
+}
+
+#[test]
+fn memrchr2_portable_find() {
+    for test in memchr_tests() {
+        test.two(true, portable::memrchr2);
+    }
+}
+
+#[test]
+fn memrchr3_portable_find() {
+    for test in memchr_tests() {
+        test.three(true, portable::memrchr3);
+    }
+}
+
+quickcheck! {
+    fn qc_memchr1_portable_matches_fallback(n1: u8, corpus: Vec<u8>) -> bool {
+        portable::memchr(n1, &corpus) == fallback::memchr(n1, &corpus)
+    }
+}
+
+quickcheck! {
+    fn qc_memchr2_portable_matches_fallback(
+        n1: u8, n2: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        portable::memchr2(n1, n2, &corpus)
+            == fallback::memchr2(n1, n2, &corpus)
+    }
+}
+
+quickcheck! {
+    fn qc_memchr3_portable_matches_fallback(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        portable::memchr3(n1, n2, n3, &corpus)
+            == fallback::memchr3(n1, n2, n3, &corpus)
+    }
+}
+
+quickcheck! {
+    fn qc_memrchr1_portable_matches_fallback(n1: u8, corpus: Vec<u8>) -> bool {
+        portable::memrchr(n1, &corpus) == fallback::memrchr(n1, &corpus)
+    }
+}
+
+quickcheck! {
+    fn qc_memrchr2_portable_matches_fallback(
+        n1: u8, n2: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        portable::memrchr2(n1, n2, &corpus)
+            == fallback::memrchr2(n1, n2, &corpus)
+    }
+}
+
+quickcheck! {
+    fn qc_memrchr3_portable_matches_fallback(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        portable::memrchr3(n1, n2, n3, &corpus)
+            == fallback::memrchr3(n1, n2, n3, &corpus)
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_not_portable_matches_fallback(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        let c = &corpus;
+        portable::memchr_not(n1, c) == fallback::memchr_not(n1, c)
+            && portable::memchr2_not(n1, n2, c)
+                == fallback::memchr2_not(n1, n2, c)
+            && portable::memchr3_not(n1, n2, n3, c)
+                == fallback::memchr3_not(n1, n2, n3, c)
+            && portable::memrchr_not(n1, c) == fallback::memrchr_not(n1, c)
+            && portable::memrchr2_not(n1, n2, c)
+                == fallback::memrchr2_not(n1, n2, c)
+            && portable::memrchr3_not(n1, n2, n3, c)
+                == fallback::memrchr3_not(n1, n2, n3, c)
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_which_portable_matches_fallback(
+        n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        let c = &corpus;
+        portable::memchr2_which(n1, n2, c)
+            == fallback::memchr2_which(n1, n2, c)
+            && portable::memchr3_which(n1, n2, n3, c)
+                == fallback::memchr3_which(n1, n2, n3, c)
+            && portable::memrchr2_which(n1, n2, c)
+                == fallback::memrchr2_which(n1, n2, c)
+            && portable::memrchr3_which(n1, n2, n3, c)
+                == fallback::memrchr3_which(n1, n2, n3, c)
+    }
+}
+
+quickcheck! {
+    fn qc_memchr_count_portable_matches_fallback(
+        n: u8, n1: u8, n2: u8, n3: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        let (n, c) = (n as usize, &corpus);
+        portable::memchr_count(n1, c) == fallback::memchr_count(n1, c)
+            && portable::memchr2_count(n1, n2, c)
+                == fallback::memchr2_count(n1, n2, c)
+            && portable::memchr3_count(n1, n2, n3, c)
+                == fallback::memchr3_count(n1, n2, n3, c)
+            && portable::memchr_nth(n, n1, c)
+                == fallback::memchr_nth(n, n1, c)
+    }
+}
+
+quickcheck! {
+    fn qc_replace_portable_matches_fallback(
+        n1: u8, n2: u8, n3: u8, to: u8,
+        corpus: Vec<u8>
+    ) -> bool {
+        let (mut got, mut expected) = (corpus.clone(), corpus);
+        portable::replace3(n1, n2, n3, to, &mut got)
+            == fallback::replace3(n1, n2, n3, to, &mut expected)
+            && got == expected
+    }
+}
@@ -0,0 +1,144 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    memchr::{fallback, naive},
+    replace, replace2, replace3,
+};
+
+use super::small_alphabet;
+
+/// Run the given replacement routine and its naive counterpart on copies of
+/// the given haystack (at a few different alignments), and check that they
+/// agree on both the result and the number of bytes replaced.
+fn check<F, G>(haystack: &[u8], mut got: F, mut expected: G)
+where
+    F: FnMut(&mut [u8]) -> usize,
+    G: FnMut(&mut [u8]) -> usize,
+{
+    for align in 0..3 {
+        let mut buf1 = vec![b'z'; align];
+        buf1.extend_from_slice(haystack);
+        let mut buf2 = buf1.clone();
+        let count1 = got(&mut buf1[align..]);
+        let count2 = expected(&mut buf2[align..]);
+        assert_eq!(count2, count1, "haystack: {:?}", haystack);
+        assert_eq!(buf2, buf1, "haystack: {:?}", haystack);
+    }
+}
+
+/// Build haystacks of every length up to a limit, in which every `step`th
+/// byte is cycled through the needles given.
+fn spaced(needles: &[u8]) -> Vec<Vec<u8>> {
+    let mut haystacks = vec![];
+    for len in (0..200).chain(vec![5_000]) {
+        for step in &[1, 2, 3, 7, 64] {
+            haystacks.push(
+                (0..len)
+                    .map(|i| {
+                        if i % step == 0 {
+                            needles[(i / step) % needles.len()]
+                        } else {
+                            b'z'
+                        }
+                    })
+                    .collect(),
+            );
+        }
+    }
+    haystacks
+}
+
+#[test]
+fn replace1_all() {
+    for haystack in spaced(b"\x00") {
+        check(
+            &haystack,
+            |h| replace(h, b'\x00', b'\n'),
+            |h| naive::replace(b'\x00', b'\n', h),
+        );
+        check(
+            &haystack,
+            |h| fallback::replace(b'\x00', b'\n', h),
+            |h| naive::replace(b'\x00', b'\n', h),
+        );
+    }
+}
+
+#[test]
+fn replace2_all() {
+    for haystack in spaced(b"\t\x0B") {
+        check(
+            &haystack,
+            |h| replace2(h, b'\t', b'\x0B', b' '),
+            |h| naive::replace2(b'\t', b'\x0B', b' ', h),
+        );
+        check(
+            &haystack,
+            |h| fallback::replace2(b'\t', b'\x0B', b' ', h),
+            |h| naive::replace2(b'\t', b'\x0B', b' ', h),
+        );
+    }
+}
+
+#[test]
+fn replace3_all() {
+    for haystack in spaced(b",;\t") {
+        check(
+            &haystack,
+            |h| replace3(h, b',', b';', b'\t', b'|'),
+            |h| naive::replace3(b',', b';', b'\t', b'|', h),
+        );
+        check(
+            &haystack,
+            |h| fallback::replace3(b',', b';', b'\t', b'|', h),
+            |h| naive::replace3(b',', b';', b'\t', b'|', h),
+        );
+    }
+}
+
+#[test]
+fn replace_with_needle() {
+    // Replacing a needle with another needle must not count any byte twice.
+    for haystack in spaced(b"ab") {
+        check(
+            &haystack,
+            |h| replace2(h, b'a', b'b', b'a'),
+            |h| naive::replace2(b'a', b'b', b'a', h),
+        );
+        check(
+            &haystack,
+            |h| replace(h, b'a', b'a'),
+            |h| naive::replace(b'a', b'a', h),
+        );
+    }
+}
+
+quickcheck! {
+    fn qc_replace_matches_naive(
+        n1: u8, n2: u8, n3: u8, to: u8, corpus: Vec<u8>
+    ) -> bool {
+        let corpus = small_alphabet(&[0, 1, 2, 3, 4, 5, 6, 7], corpus);
+        let (n1, n2, n3, to) = (n1 % 8, n2 % 8, n3 % 8, to % 8);
+        check(
+            &corpus,
+            |h| replace(h, n1, to),
+            |h| naive::replace(n1, to, h),
+        );
+        check(
+            &corpus,
+            |h| replace2(h, n1, n2, to),
+            |h| naive::replace2(n1, n2, to, h),
+        );
+        check(
+            &corpus,
+            |h| replace3(h, n1, n2, n3, to),
+            |h| naive::replace3(n1, n2, n3, to, h),
+        );
+        check(
+            &corpus,
+            |h| fallback::replace3(n1, n2, n3, to, h),
+            |h| naive::replace3(n1, n2, n3, to, h),
+        );
+        true
+    }
+}
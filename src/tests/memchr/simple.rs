@@ -4,7 +4,10 @@
 //
 // These tests are also run when the 'std' feature is not enabled.
 
-use crate::{memchr, memchr2, memchr3, memrchr, memrchr2, memrchr3};
+use crate::{
+    memchr, memchr2, memchr2_not, memchr3, memchr3_not, memchr_not, memrchr,
+    memrchr2, memrchr2_not, memrchr3, memrchr3_not, memrchr_not, ByteSet,
+};
 
 #[test]
 fn simple() {
@@ -20,4 +23,16 @@ fn simple() {
     assert_eq!(memrchr2(b'z', b'y', b"abcda"), None);
     assert_eq!(memrchr3(b'a', b'z', b'b', b"abcda"), Some(4));
     assert_eq!(memrchr3(b'z', b'y', b'x', b"abcda"), None);
+    assert_eq!(memchr_not(b'a', b"aabaa"), Some(2));
+    assert_eq!(memchr_not(b'a', b"aaaaa"), None);
+    assert_eq!(memchr2_not(b'a', b'b', b"abcba"), Some(2));
+    assert_eq!(memchr3_not(b'a', b'b', b'c', b"abcba"), None);
+    assert_eq!(memrchr_not(b'a', b"abaaa"), Some(1));
+    assert_eq!(memrchr_not(b'a', b"aaaaa"), None);
+    assert_eq!(memrchr2_not(b'a', b'b', b"acbba"), Some(1));
+    assert_eq!(memrchr3_not(b'a', b'b', b'c', b"abcba"), None);
+    assert_eq!(ByteSet::new(b"dcxyz").find(b"abcda"), Some(2));
+    assert_eq!(ByteSet::new(b"wxyz").find(b"abcda"), None);
+    assert_eq!(ByteSet::new(b"bcxyz").rfind(b"abcda"), Some(2));
+    assert_eq!(ByteSet::new(b"wxyz").rfind(b"abcda"), None);
 }
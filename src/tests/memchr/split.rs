@@ -0,0 +1,148 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    memchr2_rsplit, memchr2_split, memchr2_split_inclusive, memchr2_splitn,
+    memchr3_rsplit, memchr3_split, memchr3_split_inclusive, memchr3_splitn,
+    memchr_rsplit, memchr_split, memchr_split_inclusive, memchr_splitn,
+};
+
+use super::small_alphabet;
+
+/// Collect the given iterator by alternately taking subslices from the
+/// front and the back, and then put them back in order.
+fn alternate<'a, I>(mut it: I) -> Vec<&'a [u8]>
+where
+    I: DoubleEndedIterator<Item = &'a [u8]>,
+{
+    let (mut front, mut back) = (vec![], vec![]);
+    loop {
+        match it.next() {
+            None => break,
+            Some(piece) => front.push(piece),
+        }
+        match it.next_back() {
+            None => break,
+            Some(piece) => back.push(piece),
+        }
+    }
+    back.reverse();
+    front.extend(back);
+    front
+}
+
+#[test]
+fn split_edge_cases() {
+    let empty: Vec<&[u8]> = vec![];
+    assert_eq!(vec![b""], memchr_split(b',', b"").collect::<Vec<_>>());
+    assert_eq!(vec![b"", b""], memchr_split(b',', b",").collect::<Vec<_>>());
+    assert_eq!(vec![b"", b""], memchr_rsplit(b',', b",").collect::<Vec<_>>());
+    assert_eq!(empty, memchr_split_inclusive(b',', b"").collect::<Vec<_>>());
+    assert_eq!(
+        vec![b","],
+        memchr_split_inclusive(b',', b",").rev().collect::<Vec<_>>()
+    );
+    assert_eq!(empty, memchr_splitn(0, b',', b"a,b").collect::<Vec<_>>());
+    assert_eq!(
+        vec![b"a,b"],
+        memchr_splitn(1, b',', b"a,b").collect::<Vec<_>>()
+    );
+    assert_eq!(vec![b""], memchr_splitn(5, b',', b"").collect::<Vec<_>>());
+}
+
+#[test]
+fn split_both_ends() {
+    let mut it = memchr_split(b',', b"a,b,c,d");
+    assert_eq!(Some(&b"a"[..]), it.next());
+    assert_eq!(Some(&b"d"[..]), it.next_back());
+    assert_eq!(Some(&b"b"[..]), it.next());
+    assert_eq!(Some(&b"c"[..]), it.next_back());
+    assert_eq!(None, it.next());
+    assert_eq!(None, it.next_back());
+
+    let mut it = memchr_split_inclusive(b',', b"a,b,c,");
+    assert_eq!(Some(&b"c,"[..]), it.next_back());
+    assert_eq!(Some(&b"a,"[..]), it.next());
+    assert_eq!(Some(&b"b,"[..]), it.next_back());
+    assert_eq!(None, it.next());
+    assert_eq!(None, it.next_back());
+}
+
+quickcheck! {
+    fn qc_split1_matches_std(corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"ab,;\t", corpus);
+        let expected: Vec<&[u8]> = corpus.split(|&b| b == b',').collect();
+        let mut rexpected = expected.clone();
+        rexpected.reverse();
+        expected == memchr_split(b',', &corpus).collect::<Vec<_>>()
+            && expected == alternate(memchr_split(b',', &corpus))
+            && rexpected == memchr_rsplit(b',', &corpus).collect::<Vec<_>>()
+    }
+
+    fn qc_split2_matches_std(corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"ab,;\t", corpus);
+        let expected: Vec<&[u8]> =
+            corpus.split(|&b| b == b',' || b == b';').collect();
+        let mut rexpected = expected.clone();
+        rexpected.reverse();
+        expected == memchr2_split(b',', b';', &corpus).collect::<Vec<_>>()
+            && expected == alternate(memchr2_split(b',', b';', &corpus))
+            && rexpected
+                == memchr2_rsplit(b',', b';', &corpus).collect::<Vec<_>>()
+    }
+
+    fn qc_split3_matches_std(corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"ab,;\t", corpus);
+        let expected: Vec<&[u8]> = corpus
+            .split(|&b| b == b',' || b == b';' || b == b'\t')
+            .collect();
+        let mut rexpected = expected.clone();
+        rexpected.reverse();
+        let split = || memchr3_split(b',', b';', b'\t', &corpus);
+        expected == split().collect::<Vec<_>>()
+            && expected == alternate(split())
+            && rexpected
+                == memchr3_rsplit(b',', b';', b'\t', &corpus)
+                    .collect::<Vec<_>>()
+    }
+
+    fn qc_splitn_matches_std(n: u8, corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"ab,;\t", corpus);
+        let n = n as usize % 8;
+        let expected1: Vec<&[u8]> =
+            corpus.splitn(n, |&b| b == b',').collect();
+        let expected2: Vec<&[u8]> =
+            corpus.splitn(n, |&b| b == b',' || b == b';').collect();
+        let expected3: Vec<&[u8]> = corpus
+            .splitn(n, |&b| b == b',' || b == b';' || b == b'\t')
+            .collect();
+        expected1 == memchr_splitn(n, b',', &corpus).collect::<Vec<_>>()
+            && expected2
+                == memchr2_splitn(n, b',', b';', &corpus).collect::<Vec<_>>()
+            && expected3
+                == memchr3_splitn(n, b',', b';', b'\t', &corpus)
+                    .collect::<Vec<_>>()
+    }
+
+    fn qc_split_inclusive_matches_std(corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"ab,;\t", corpus);
+        let expected1: Vec<&[u8]> =
+            corpus.split_inclusive(|&b| b == b',').collect();
+        let expected2: Vec<&[u8]> =
+            corpus.split_inclusive(|&b| b == b',' || b == b';').collect();
+        let expected3: Vec<&[u8]> = corpus
+            .split_inclusive(|&b| b == b',' || b == b';' || b == b'\t')
+            .collect();
+        let split1 = || memchr_split_inclusive(b',', &corpus);
+        let split2 = || memchr2_split_inclusive(b',', b';', &corpus);
+        let split3 = || memchr3_split_inclusive(b',', b';', b'\t', &corpus);
+        let mut rexpected1 = expected1.clone();
+        rexpected1.reverse();
+        expected1 == split1().collect::<Vec<_>>()
+            && expected1 == alternate(split1())
+            && rexpected1 == split1().rev().collect::<Vec<_>>()
+            && expected2 == split2().collect::<Vec<_>>()
+            && expected2 == alternate(split2())
+            && expected3 == split3().collect::<Vec<_>>()
+            && expected3 == alternate(split3())
+    }
+}
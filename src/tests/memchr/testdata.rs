@@ -1,5 +1,7 @@
 use std::iter::repeat;
 
+use crate::memchr::ByteSet;
+
 /// Create a sequence of tests that should be run by memchr implementations.
 pub fn memchr_tests() -> Vec<MemchrTest> {
     let mut tests = Vec::new();
@@ -226,6 +228,28 @@ impl MemchrTest {
         }
     }
 
+    /// Like `one`, `two` and `three`, but uses all of the needles in this test
+    /// (of which there may be any number) as a single byte set.
+    pub fn set<F: Fn(&ByteSet, &[u8]) -> Option<usize>>(
+        &self,
+        reverse: bool,
+        f: F,
+    ) {
+        let set = ByteSet::new(&self.needles);
+        for align in 0..130 {
+            let corpus = self.corpus(align);
+            assert_eq!(
+                self.positions(align, reverse).get(0).cloned(),
+                f(&set, corpus.as_bytes()),
+                "search for {:?} failed in: {:?} (len: {}, alignment: {})",
+                set,
+                corpus,
+                corpus.len(),
+                align
+            );
+        }
+    }
+
     pub fn iter_one<'a, I, F>(&'a self, reverse: bool, f: F)
     where
         F: FnOnce(u8, &'a [u8]) -> I,
@@ -256,6 +280,15 @@ impl MemchrTest {
         }
     }
 
+    pub fn iter_set<'a, I, F>(&'a self, reverse: bool, f: F)
+    where
+        F: FnOnce(ByteSet, &'a [u8]) -> I,
+        I: Iterator<Item = usize>,
+    {
+        let set = ByteSet::new(&self.needles);
+        self.iter(reverse, f(set, self.corpus.as_bytes()));
+    }
+
     /// Test that the positions yielded by the given iterator match the
     /// positions in this test. If reverse is true, then reverse the positions
     /// before comparing them.
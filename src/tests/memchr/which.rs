@@ -0,0 +1,117 @@
+use quickcheck::quickcheck;
+
+use crate::{
+    memchr::{fallback, naive},
+    memchr2_which, memchr2_which_iter, memchr3_which, memchr3_which_iter,
+    memrchr2_which, memrchr2_which_iter, memrchr3_which, memrchr3_which_iter,
+};
+
+use super::small_alphabet;
+
+/// Call the given function with every haystack (at a few different
+/// alignments) that consists of `z` bytes except for one or two positions,
+/// each of which holds one of the given needles.
+fn sprinkled<F: FnMut(&[u8])>(needles: &[u8], mut f: F) {
+    let mut buf = vec![];
+    for len in 0..100 {
+        for align in 0..3 {
+            buf.clear();
+            buf.resize(align + len, b'z');
+            f(&buf[align..]);
+            for pos1 in 0..len {
+                for &n1 in needles {
+                    buf[align + pos1] = n1;
+                    f(&buf[align..]);
+                    for pos2 in (pos1 + 1..len).step_by(7) {
+                        for &n2 in needles {
+                            buf[align + pos2] = n2;
+                            f(&buf[align..]);
+                            buf[align + pos2] = b'z';
+                        }
+                    }
+                    buf[align + pos1] = b'z';
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn memchr2_which_find() {
+    sprinkled(b"ab", |haystack| {
+        let expected = naive::memchr2_which(b'a', b'b', haystack);
+        assert_eq!(expected, memchr2_which(b'a', b'b', haystack));
+        assert_eq!(expected, fallback::memchr2_which(b'a', b'b', haystack));
+        let expected = naive::memrchr2_which(b'a', b'b', haystack);
+        assert_eq!(expected, memrchr2_which(b'a', b'b', haystack));
+        assert_eq!(expected, fallback::memrchr2_which(b'a', b'b', haystack));
+    });
+}
+
+#[test]
+fn memchr3_which_find() {
+    sprinkled(b"abc", |haystack| {
+        let expected = naive::memchr3_which(b'a', b'b', b'c', haystack);
+        assert_eq!(expected, memchr3_which(b'a', b'b', b'c', haystack));
+        let expected = naive::memrchr3_which(b'a', b'b', b'c', haystack);
+        assert_eq!(expected, memrchr3_which(b'a', b'b', b'c', haystack));
+    });
+}
+
+#[test]
+fn memchr_which_iter_both_ends() {
+    let haystack = b"a\"b\\c\"d\ne";
+    let mut it = memchr3_which_iter(b'"', b'\\', b'\n', haystack);
+    assert_eq!(Some((1, b'"')), it.next());
+    assert_eq!(Some((7, b'\n')), it.next_back());
+    assert_eq!(Some((3, b'\\')), it.next());
+    assert_eq!(Some((5, b'"')), it.next_back());
+    assert_eq!(None, it.next());
+    assert_eq!(None, it.next_back());
+
+    let got: Vec<(usize, u8)> =
+        memrchr2_which_iter(b'"', b'\\', haystack).collect();
+    assert_eq!(got, vec![(5, b'"'), (3, b'\\'), (1, b'"')]);
+}
+
+quickcheck! {
+    fn qc_memchr2_which_iter_matches_naive(corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"abcdefgh", corpus);
+        let expected: Vec<(usize, u8)> = corpus
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| b == b'a' || b == b'b')
+            .map(|(i, &b)| (i, b))
+            .collect();
+        let mut rexpected = expected.clone();
+        rexpected.reverse();
+        expected == memchr2_which_iter(b'a', b'b', &corpus).collect::<Vec<_>>()
+            && rexpected
+                == memrchr2_which_iter(b'a', b'b', &corpus)
+                    .collect::<Vec<_>>()
+    }
+
+    fn qc_memchr3_which_iter_matches_naive(corpus: Vec<u8>) -> bool {
+        let corpus = small_alphabet(b"abcdefgh", corpus);
+        let expected: Vec<(usize, u8)> = corpus
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| b == b'a' || b == b'b' || b == b'c')
+            .map(|(i, &b)| (i, b))
+            .collect();
+        let mut rexpected = expected.clone();
+        rexpected.reverse();
+        expected
+            == memchr3_which_iter(b'a', b'b', b'c', &corpus)
+                .collect::<Vec<_>>()
+            && rexpected
+                == memrchr3_which_iter(b'a', b'b', b'c', &corpus)
+                    .collect::<Vec<_>>()
+    }
+
+    fn qc_memchr_which_same_needles(n: u8, corpus: Vec<u8>) -> bool {
+        memchr2_which(n, n, &corpus) == naive::memchr2_which(n, n, &corpus)
+            && memrchr3_which(n, n, n, &corpus)
+                == naive::memrchr3_which(n, n, n, &corpus)
+    }
+}
@@ -0,0 +1,458 @@
+/// A trait for describing vector operations used by vectorized searchers.
+///
+/// The trait is highly constrained to low level vector operations needed. In
+/// general, it was invented mostly to be generic over x86's __m128i and
+/// __m256i types. It's used to implement both memchr (see
+/// src/memchr/genericsimd.rs) and substring search (see
+/// src/memmem/genericsimd.rs), so adding support for a new vector type only
+/// requires implementing this trait.
+///
+/// Throughout, a vector is treated as a sequence of bytes, where the result
+/// of `movemask` has bit `i` set if and only if the most significant bit of
+/// the byte at position `i` is set. Since `cmpeq` sets every bit in a byte
+/// that compares equal, this means `movemask` can be used to find the
+/// position of matching bytes.
+///
+/// # Safety
+///
+/// All methods are not safe since they are intended to be implemented using
+/// vendor intrinsics, which are also not safe. Callers must ensure that the
+/// appropriate target features are enabled in the calling function, and that
+/// the current CPU supports them. All implementations should avoid marking the
+/// routines with #[target_feature] and instead mark them as #[inline(always)]
+/// to ensure they get appropriately inlined. (inline(always) cannot be used
+/// with target_feature.)
+#[cfg_attr(
+    not(all(
+        not(miri),
+        any(
+            all(target_arch = "x86_64", memchr_runtime_simd),
+            memchr_portable_simd
+        )
+    )),
+    allow(dead_code)
+)]
+pub(crate) trait Vector: Copy + core::fmt::Debug {
+    /// _mm_set1_epi8 or _mm256_set1_epi8
+    unsafe fn splat(byte: u8) -> Self;
+    /// _mm_load_si128 or _mm256_load_si256
+    ///
+    /// The pointer given must be aligned to the size of the vector.
+    unsafe fn load_aligned(data: *const u8) -> Self;
+    /// _mm_loadu_si128 or _mm256_loadu_si256
+    unsafe fn load_unaligned(data: *const u8) -> Self;
+    /// _mm_movemask_epi8 or _mm256_movemask_epi8
+    unsafe fn movemask(self) -> u32;
+    /// _mm_cmpeq_epi8 or _mm256_cmpeq_epi8
+    unsafe fn cmpeq(self, vector2: Self) -> Self;
+    /// _mm_and_si128 or _mm256_and_si256
+    unsafe fn and(self, vector2: Self) -> Self;
+    /// _mm_or_si128 or _mm256_or_si256
+    unsafe fn or(self, vector2: Self) -> Self;
+    /// _mm_andnot_si128 or _mm256_andnot_si256
+    ///
+    /// Note that it's `self` that is negated, and not `vector2`.
+    unsafe fn andnot(self, vector2: Self) -> Self;
+    /// _mm_sub_epi8 or _mm256_sub_epi8
+    ///
+    /// Each lane wraps around on overflow independently of the others.
+    unsafe fn sub(self, vector2: Self) -> Self;
+    /// _mm_storeu_si128 or _mm256_storeu_si256
+    unsafe fn store_unaligned(self, data: *mut u8);
+    /// Return the sum of every lane in this vector, where each lane is
+    /// treated as an unsigned 8-bit integer. (This is what _mm_sad_epu8
+    /// against a zero vector computes, before the partial sums are added.)
+    unsafe fn sum_bytes(self) -> usize;
+
+    /// Returns true if and only if `movemask` would return a non-zero value
+    /// for this vector.
+    #[inline(always)]
+    unsafe fn movemask_will_have_non_zero(self) -> bool {
+        self.movemask() != 0
+    }
+}
+
+/// An extension of `Vector` for vectors whose bytes can be shuffled, which
+/// permits using a vector as a table with 16 entries. It's used by the Teddy
+/// multi-substring searcher (see src/memmem/teddy.rs).
+///
+/// # Safety
+///
+/// The same requirements as for `Vector` apply. Note that for `__m128i`,
+/// this requires the ssse3 target feature and not just sse2.
+#[cfg_attr(
+    not(all(
+        feature = "std",
+        not(miri),
+        any(
+            all(target_arch = "x86_64", memchr_runtime_simd),
+            memchr_portable_simd
+        )
+    )),
+    allow(dead_code)
+)]
+pub(crate) trait ShuffleVector: Vector {
+    /// _mm_shuffle_epi8 or _mm256_shuffle_epi8
+    ///
+    /// Each lane of `indices` must be less than 16. For vectors bigger than
+    /// 16 bytes, each 16 byte half of `self` is a separate table, which is
+    /// indexed by the lanes in the same half of `indices`.
+    unsafe fn shuffle_bytes(self, indices: Self) -> Self;
+    /// Return a vector whose lanes are the high 4 bits of each lane in this
+    /// vector. That is, each lane is shifted right by 4 bits.
+    unsafe fn high_nibbles(self) -> Self;
+}
+
+mod swar {
+    use super::Vector;
+
+    const LO: u64 = 0x0101010101010101;
+    const HI: u64 = 0x8080808080808080;
+
+    // A "vector" of 8 bytes packed into a u64. This works on every target,
+    // and while it's not as fast as real vector instructions, it still
+    // permits examining 8 bytes at a time in the vectorized searchers.
+    //
+    // Bytes are always loaded in little endian order, such that the byte at
+    // the lowest address corresponds to the least significant 8 bits. This
+    // makes the result of movemask line up with the vector instructions on
+    // x86, regardless of the endianness of the target.
+    impl Vector for u64 {
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> u64 {
+            LO * (byte as u64)
+        }
+
+        #[inline(always)]
+        unsafe fn load_aligned(data: *const u8) -> u64 {
+            u64::from_le((data as *const u64).read())
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> u64 {
+            u64::from_le((data as *const u64).read_unaligned())
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u32 {
+            // Move the most significant bit of each byte to its least
+            // significant bit. The multiplication then gathers each of those
+            // bits into the most significant byte, with the bit from byte i
+            // ending up at bit i of that byte.
+            let bits = (self & HI) >> 7;
+            (bits.wrapping_mul(0x0102040810204080) >> 56) as u32
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> u64 {
+            // A byte in x is zero if and only if the corresponding bytes are
+            // equal. This is the same exact zero byte detection used by
+            // zero_bytes in src/memchr/fallback.rs.
+            let x = self ^ vector2;
+            let nonzero = ((x & !HI) + !HI) | x;
+            let zero = !nonzero & HI;
+            // Like _mm_cmpeq_epi8, set every bit in each matching byte.
+            (zero >> 7) * 0xFF
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> u64 {
+            self & vector2
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> u64 {
+            self | vector2
+        }
+
+        #[inline(always)]
+        unsafe fn andnot(self, vector2: Self) -> u64 {
+            !self & vector2
+        }
+
+        #[inline(always)]
+        unsafe fn sub(self, vector2: Self) -> u64 {
+            // Subtract the low 7 bits of each byte with the most significant
+            // bit of each byte in self set, so that no byte ever borrows from
+            // its neighbor. Then fix up the most significant bit of each byte.
+            ((self | HI) - (vector2 & !HI)) ^ ((self ^ !vector2) & HI)
+        }
+
+        #[inline(always)]
+        unsafe fn store_unaligned(self, data: *mut u8) {
+            (data as *mut u64).write_unaligned(self.to_le())
+        }
+
+        #[inline(always)]
+        unsafe fn sum_bytes(self) -> usize {
+            // Add neighboring bytes into 16-bit lanes, which can't overflow.
+            // The multiplication then adds every 16-bit lane into the most
+            // significant one.
+            const LO16: u64 = 0x00FF00FF00FF00FF;
+            let pairs = (self & LO16) + ((self >> 8) & LO16);
+            (pairs.wrapping_mul(0x0001000100010001) >> 48) as usize
+        }
+
+        #[inline(always)]
+        unsafe fn movemask_will_have_non_zero(self) -> bool {
+            self & HI != 0
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86sse {
+    use super::{ShuffleVector, Vector};
+    use core::arch::x86_64::*;
+
+    impl Vector for __m128i {
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> __m128i {
+            _mm_set1_epi8(byte as i8)
+        }
+
+        #[inline(always)]
+        unsafe fn load_aligned(data: *const u8) -> __m128i {
+            _mm_load_si128(data as *const __m128i)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> __m128i {
+            _mm_loadu_si128(data as *const __m128i)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u32 {
+            _mm_movemask_epi8(self) as u32
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> __m128i {
+            _mm_cmpeq_epi8(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> __m128i {
+            _mm_and_si128(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> __m128i {
+            _mm_or_si128(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn andnot(self, vector2: Self) -> __m128i {
+            _mm_andnot_si128(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn sub(self, vector2: Self) -> __m128i {
+            _mm_sub_epi8(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn store_unaligned(self, data: *mut u8) {
+            _mm_storeu_si128(data as *mut __m128i, self)
+        }
+
+        #[inline(always)]
+        unsafe fn sum_bytes(self) -> usize {
+            let sums = _mm_sad_epu8(self, _mm_setzero_si128());
+            let lo = _mm_cvtsi128_si64(sums) as usize;
+            let hi =
+                _mm_cvtsi128_si64(_mm_unpackhi_epi64(sums, sums)) as usize;
+            lo + hi
+        }
+    }
+
+    impl ShuffleVector for __m128i {
+        #[inline(always)]
+        unsafe fn shuffle_bytes(self, indices: Self) -> __m128i {
+            _mm_shuffle_epi8(self, indices)
+        }
+
+        #[inline(always)]
+        unsafe fn high_nibbles(self) -> __m128i {
+            // There is no 8-bit shift, so shift 16-bit lanes instead and
+            // clear the bits shifted in from the neighboring byte.
+            _mm_and_si128(_mm_srli_epi16(self, 4), _mm_set1_epi8(0xF))
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86avx {
+    use super::{ShuffleVector, Vector};
+    use core::arch::x86_64::*;
+
+    impl Vector for __m256i {
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> __m256i {
+            _mm256_set1_epi8(byte as i8)
+        }
+
+        #[inline(always)]
+        unsafe fn load_aligned(data: *const u8) -> __m256i {
+            _mm256_load_si256(data as *const __m256i)
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> __m256i {
+            _mm256_loadu_si256(data as *const __m256i)
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u32 {
+            _mm256_movemask_epi8(self) as u32
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> __m256i {
+            _mm256_cmpeq_epi8(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> __m256i {
+            _mm256_and_si256(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> __m256i {
+            _mm256_or_si256(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn andnot(self, vector2: Self) -> __m256i {
+            _mm256_andnot_si256(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn sub(self, vector2: Self) -> __m256i {
+            _mm256_sub_epi8(self, vector2)
+        }
+
+        #[inline(always)]
+        unsafe fn store_unaligned(self, data: *mut u8) {
+            _mm256_storeu_si256(data as *mut __m256i, self)
+        }
+
+        #[inline(always)]
+        unsafe fn sum_bytes(self) -> usize {
+            let sums = _mm256_sad_epu8(self, _mm256_setzero_si256());
+            let sums = _mm_add_epi64(
+                _mm256_castsi256_si128(sums),
+                _mm256_extracti128_si256(sums, 1),
+            );
+            let lo = _mm_cvtsi128_si64(sums) as usize;
+            let hi =
+                _mm_cvtsi128_si64(_mm_unpackhi_epi64(sums, sums)) as usize;
+            lo + hi
+        }
+    }
+
+    impl ShuffleVector for __m256i {
+        #[inline(always)]
+        unsafe fn shuffle_bytes(self, indices: Self) -> __m256i {
+            _mm256_shuffle_epi8(self, indices)
+        }
+
+        #[inline(always)]
+        unsafe fn high_nibbles(self) -> __m256i {
+            // See the __m128i implementation.
+            _mm256_and_si256(_mm256_srli_epi16(self, 4), _mm256_set1_epi8(0xF))
+        }
+    }
+}
+
+#[cfg(memchr_portable_simd)]
+mod portable {
+    use super::{ShuffleVector, Vector};
+    use core::simd::{
+        cmp::SimdPartialEq,
+        num::{SimdInt, SimdUint},
+        u8x16,
+    };
+
+    impl Vector for u8x16 {
+        #[inline(always)]
+        unsafe fn splat(byte: u8) -> u8x16 {
+            u8x16::splat(byte)
+        }
+
+        #[inline(always)]
+        unsafe fn load_aligned(data: *const u8) -> u8x16 {
+            data.cast::<u8x16>().read()
+        }
+
+        #[inline(always)]
+        unsafe fn load_unaligned(data: *const u8) -> u8x16 {
+            data.cast::<u8x16>().read_unaligned()
+        }
+
+        #[inline(always)]
+        unsafe fn movemask(self) -> u32 {
+            // Like _mm_movemask_epi8, this collects the most significant bit
+            // of each lane, and not just whether the lane is all ones.
+            let high = u8x16::splat(0x80);
+            (self & high).simd_eq(high).to_bitmask() as u32
+        }
+
+        #[inline(always)]
+        unsafe fn cmpeq(self, vector2: Self) -> u8x16 {
+            self.simd_eq(vector2).to_simd().cast()
+        }
+
+        #[inline(always)]
+        unsafe fn and(self, vector2: Self) -> u8x16 {
+            self & vector2
+        }
+
+        #[inline(always)]
+        unsafe fn or(self, vector2: Self) -> u8x16 {
+            self | vector2
+        }
+
+        #[inline(always)]
+        unsafe fn andnot(self, vector2: Self) -> u8x16 {
+            !self & vector2
+        }
+
+        #[inline(always)]
+        unsafe fn sub(self, vector2: Self) -> u8x16 {
+            // Arithmetic on core::simd vectors always wraps.
+            self - vector2
+        }
+
+        #[inline(always)]
+        unsafe fn store_unaligned(self, data: *mut u8) {
+            data.cast::<u8x16>().write_unaligned(self)
+        }
+
+        #[inline(always)]
+        unsafe fn sum_bytes(self) -> usize {
+            // Widen each lane first, so that the sum can't overflow.
+            self.cast::<u16>().reduce_sum() as usize
+        }
+
+        #[inline(always)]
+        unsafe fn movemask_will_have_non_zero(self) -> bool {
+            // OR'ing all of the lanes together and checking the most
+            // significant bit of the result is usually cheaper than
+            // computing a bitmask.
+            self.reduce_or() & 0x80 != 0
+        }
+    }
+
+    impl ShuffleVector for u8x16 {
+        #[inline(always)]
+        unsafe fn shuffle_bytes(self, indices: Self) -> u8x16 {
+            self.swizzle_dyn(indices)
+        }
+
+        #[inline(always)]
+        unsafe fn high_nibbles(self) -> u8x16 {
+            self >> u8x16::splat(4)
+        }
+    }
+}
@@ -0,0 +1,92 @@
+// Forcing a backend changes global state for the whole process, so this test
+// lives in its own test binary. Otherwise, it would change the backend out
+// from under every other test running in parallel with it.
+
+use memchr::{
+    arch::{self, naive, Backend},
+    memchr, memchr2, memchr3, memrchr, memrchr2, memrchr3, ByteSet,
+};
+
+/// Restores the automatic choice of backend when dropped, so that a failing
+/// assertion doesn't leave a backend forced.
+struct Unforce;
+
+impl Drop for Unforce {
+    fn drop(&mut self) {
+        arch::force(None);
+    }
+}
+
+/// Returns haystacks of many lengths, each with the needle bytes `a`, `b`
+/// and `c` placed at a few different offsets, such that every routine is
+/// exercised on both its vector and scalar code paths.
+fn haystacks() -> Vec<Vec<u8>> {
+    let mut haystacks = vec![];
+    for len in 0..300 {
+        haystacks.push(vec![b'z'; len]);
+        for &(pos, byte) in [(0, b'a'), (len / 2, b'b'), (len / 3, b'c')]
+            .iter()
+            .filter(|&&(pos, _)| pos < len)
+        {
+            let mut haystack = vec![b'z'; len];
+            haystack[pos] = byte;
+            haystack[len - 1 - pos] = byte;
+            haystacks.push(haystack);
+        }
+    }
+    haystacks
+}
+
+#[test]
+fn force_changes_selected_backend() {
+    let _unforce = Unforce;
+    let backends = [
+        Backend::Avx2,
+        Backend::Sse2,
+        Backend::Portable,
+        Backend::Swar,
+        Backend::Libc,
+        Backend::Fallback,
+        Backend::Naive,
+    ];
+    let set = ByteSet::new(b"abc");
+    for &backend in backends.iter() {
+        let before = arch::selected();
+        if !arch::force(Some(backend)) {
+            assert_eq!(before, arch::selected());
+            continue;
+        }
+        assert_eq!(backend, arch::selected());
+        // The naive routines never change with the forced backend, so they
+        // tell us what every other routine should report.
+        for h in haystacks() {
+            let h = &h;
+            let expected = [
+                naive::memchr(b'a', h),
+                naive::memchr2(b'a', b'b', h),
+                naive::memchr3(b'a', b'b', b'c', h),
+                naive::memrchr(b'a', h),
+                naive::memrchr2(b'a', b'b', h),
+                naive::memrchr3(b'a', b'b', b'c', h),
+                naive::memchr3(b'a', b'b', b'c', h),
+                naive::memrchr3(b'a', b'b', b'c', h),
+            ];
+            let got = [
+                memchr(b'a', h),
+                memchr2(b'a', b'b', h),
+                memchr3(b'a', b'b', b'c', h),
+                memrchr(b'a', h),
+                memrchr2(b'a', b'b', h),
+                memrchr3(b'a', b'b', b'c', h),
+                set.find(h),
+                set.rfind(h),
+            ];
+            assert_eq!(
+                expected, got,
+                "backend: {:?}, haystack: {:?}",
+                backend, h
+            );
+        }
+    }
+    assert!(arch::force(None));
+}